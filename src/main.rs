@@ -1,33 +1,176 @@
 use anyhow::{anyhow, Context, Result};
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell};
 use hidapi::{HidApi, HidDevice};
 use rich_rust::markup;
 use rich_rust::prelude::*;
 use rich_rust::r#box::ROUNDED;
 use rusb::{Device, GlobalContext};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use std::time::Duration;
 
 /// Pedal configuration stored on disk (device EEPROM is write-only)
-#[derive(Clone)]
+#[derive(Clone, Default)]
 struct PedalConfig {
     left: String,
     middle: String,
     right: String,
+    /// Optional label and notes per pedal, indexed by `PedalPosition::index`
+    details: [PedalDetails; 3],
+    /// The physical pedal this configuration was last programmed onto
+    device: Option<DeviceBinding>,
+    /// SET_REPORT layout the device accepted the last time it was programmed
+    last_method: Option<String>,
+    /// Platform `primary`/`secondary` resolve for, when one was chosen with --target-os
+    target_os: Option<TargetOs>,
+}
+
+/// Human-facing description of what a pedal is for
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+struct PedalDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+}
+
+/// Identifies a specific physical pedal (most Savant Elites have no serial string)
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeviceBinding {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    serial: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unit_id: Option<u8>,
+}
+
+/// Current on-disk config schema version
+const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+/// Versioned on-disk representation of a [`PedalConfig`]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    version: u32,
+    pedals: ConfigFilePedals,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    device: Option<DeviceBinding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target_os: Option<TargetOs>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFilePedals {
+    left: ConfigFilePedal,
+    middle: ConfigFilePedal,
+    right: ConfigFilePedal,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFilePedal {
+    action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+}
+
+/// Only the `version` field, so newer files are reported as such rather than as schema errors
+#[derive(Deserialize)]
+struct ConfigFileVersion {
+    version: Option<u32>,
 }
 
 impl PedalConfig {
     fn config_path() -> PathBuf {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("savant-elite");
-        config_dir.join("pedals.conf")
+        savant_config_dir().join("pedals.conf")
+    }
+
+    /// The saved action for one pedal
+    fn get(&self, position: PedalPosition) -> &str {
+        match position {
+            PedalPosition::Left => &self.left,
+            PedalPosition::Middle => &self.middle,
+            PedalPosition::Right => &self.right,
+        }
+    }
+
+    /// Whether `content` uses the pre-versioning `left=`/`middle=`/`right=` format
+    fn is_legacy(content: &str) -> bool {
+        !content.trim_start().starts_with('{')
     }
 
+    /// Parse either on-disk format
     fn parse(content: &str) -> Option<Self> {
+        if Self::is_legacy(content) {
+            Self::parse_legacy(content)
+        } else {
+            Self::parse_versioned(content).ok()
+        }
+    }
+
+    /// Parse a versioned JSON config, rejecting unknown fields and newer schema versions
+    fn parse_versioned(content: &str) -> Result<Self> {
+        let probe: ConfigFileVersion =
+            serde_json::from_str(content).context("Config is not valid JSON")?;
+        match probe.version {
+            Some(CONFIG_SCHEMA_VERSION) => {}
+            Some(v) => {
+                return Err(anyhow!(
+                    "Unsupported config version {} (this savant supports version {})",
+                    v,
+                    CONFIG_SCHEMA_VERSION
+                ))
+            }
+            None => return Err(anyhow!("Missing required field: version")),
+        }
+
+        Ok(Self::from_file(serde_json::from_str(content)?))
+    }
+
+    fn from_file(file: ConfigFile) -> Self {
+        let [left, middle, right] = [file.pedals.left, file.pedals.middle, file.pedals.right];
+        Self {
+            details: [&left, &middle, &right].map(|p| PedalDetails {
+                label: p.label.clone(),
+                notes: p.notes.clone(),
+            }),
+            left: left.action,
+            middle: middle.action,
+            right: right.action,
+            device: file.device,
+            last_method: file.last_method,
+            target_os: file.target_os,
+        }
+    }
+
+    fn to_file(&self) -> ConfigFile {
+        let pedal = |action: &str, details: &PedalDetails| ConfigFilePedal {
+            action: action.to_string(),
+            label: details.label.clone(),
+            notes: details.notes.clone(),
+        };
+        ConfigFile {
+            version: CONFIG_SCHEMA_VERSION,
+            pedals: ConfigFilePedals {
+                left: pedal(&self.left, &self.details[0]),
+                middle: pedal(&self.middle, &self.details[1]),
+                right: pedal(&self.right, &self.details[2]),
+            },
+            device: self.device.clone(),
+            last_method: self.last_method.clone(),
+            target_os: self.target_os,
+        }
+    }
+
+    /// Parse the legacy key=value format (unknown keys ignored, all three pedals required)
+    fn parse_legacy(content: &str) -> Option<Self> {
         let mut left = String::new();
         let mut middle = String::new();
         let mut right = String::new();
@@ -58,6 +201,7 @@ impl PedalConfig {
                 left,
                 middle,
                 right,
+                ..Default::default()
             })
         } else {
             None
@@ -69,12 +213,83 @@ impl PedalConfig {
         Self::parse(&content)
     }
 
+    /// Load the live config, upgrading a legacy file to the versioned format in place.
+    /// The original is kept next to it as `pedals.conf.legacy`.
     fn load() -> Option<Self> {
-        Self::load_from(&Self::config_path())
+        let path = Self::config_path();
+        let content = fs::read_to_string(&path).ok()?;
+        let config = Self::parse(&content)?;
+        if Self::is_legacy(&content) {
+            // Best-effort: skip the upgrade if another writer holds the lock right now
+            if let Ok(_lock) = lock_config_dir(Duration::ZERO) {
+                let legacy_path = path.with_extension("conf.legacy");
+                if write_atomic(&legacy_path, &content).is_ok() {
+                    let _ = config.save_to(&path);
+                }
+            }
+        }
+        Some(config)
+    }
+
+    /// The --target-os setting recorded in the live config, read without upgrading the file
+    fn saved_target_os() -> Option<TargetOs> {
+        let content = fs::read_to_string(Self::config_path()).ok()?;
+        Self::parse(&content)?.target_os
+    }
+
+    /// Recover the pedal actions from a config that no longer parses as a whole, e.g. a
+    /// versioned file with an unknown field or a hand-edited legacy file with stray lines.
+    /// Returns `None` unless all three pedals are present and valid key actions.
+    fn salvage(content: &str) -> Option<Self> {
+        let mut actions: [Option<String>; 3] = Default::default();
+        let mut target_os = None;
+
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+            if value["version"]
+                .as_u64()
+                .is_some_and(|v| v > CONFIG_SCHEMA_VERSION as u64)
+            {
+                return None; // Written by a newer savant; don't downgrade it
+            }
+            for position in PedalPosition::ALL {
+                let pedal = &value["pedals"][position.name()];
+                actions[position.index()] = pedal["action"]
+                    .as_str()
+                    .or_else(|| pedal.as_str())
+                    .or_else(|| value[position.name()].as_str())
+                    .map(str::to_string);
+            }
+            target_os = serde_json::from_value(value["target_os"].clone()).ok();
+        } else {
+            for (key, value) in content.lines().filter_map(|line| line.split_once('=')) {
+                if let Some(position) = PedalPosition::from_name(key.trim()) {
+                    actions[position.index()] = Some(value.trim().to_string());
+                }
+            }
+        }
+
+        let [Some(left), Some(middle), Some(right)] = actions else {
+            return None;
+        };
+        let os = target_os.unwrap_or_else(TargetOs::current);
+        if [&left, &middle, &right]
+            .iter()
+            .any(|action| KeyAction::parse_for(action, os).is_err())
+        {
+            return None;
+        }
+        Some(Self {
+            left,
+            middle,
+            right,
+            target_os,
+            ..Default::default()
+        })
     }
 
     fn serialize(&self) -> Result<String> {
-        // Validate no newlines in values (would corrupt config file format)
+        // Validate no newlines in values (never valid in a key action, and older
+        // key=value readers would mis-parse them)
         for (name, val) in [
             ("left", &self.left),
             ("middle", &self.middle),
@@ -88,56 +303,71 @@ impl PedalConfig {
             }
         }
 
-        Ok(format!(
-            "left={}\nmiddle={}\nright={}\n",
-            self.left, self.middle, self.right
-        ))
+        Ok(serde_json::to_string_pretty(&self.to_file())? + "\n")
     }
 
+    /// Write this config to `path` atomically. Callers writing into the config directory
+    /// should hold [`lock_config_dir`].
     fn save_to(&self, path: &std::path::Path) -> Result<()> {
         let content = self.serialize()?;
-
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        fs::write(path, content)?;
-        Ok(())
+        write_atomic(path, &content)
     }
 
-    fn save(&self) -> Result<()> {
+    /// Replace the live config, first snapshotting the old one into history along with
+    /// `meta`, which describes the operation doing the replacing.
+    fn save(&self, meta: &HistoryMeta) -> Result<()> {
+        // Reject an unwritable config before it can displace anything into history
+        self.serialize()?;
+        let _lock = lock_config_dir(CONFIG_LOCK_WAIT)?;
         // Backup current config before overwriting (if it exists)
-        Self::backup_current_config();
+        Self::backup_current_config(meta);
         self.save_to(&Self::config_path())
     }
 
     /// Get the history directory path for config backups
     fn history_dir() -> PathBuf {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("savant-elite");
-        config_dir.join("history")
+        savant_config_dir().join("history")
     }
 
-    /// Backup current config to history directory with timestamp
-    fn backup_current_config() {
+    /// Snapshot the current config into the history directory. Entries are named by UTC
+    /// timestamp with millisecond precision plus a counter, so they never overwrite each other.
+    fn backup_current_config(meta: &HistoryMeta) {
         let config_path = Self::config_path();
-        if !config_path.exists() {
+        let Ok(content) = fs::read_to_string(&config_path) else {
             return; // Nothing to backup
-        }
+        };
 
         let history_dir = Self::history_dir();
         if fs::create_dir_all(&history_dir).is_err() {
             return; // Can't create history dir, skip backup silently
         }
 
-        // Generate timestamp-based filename
-        let now = chrono::Local::now();
-        let backup_name = format!("{}.conf", now.format("%Y-%m-%d_%H%M%S"));
-        let backup_path = history_dir.join(&backup_name);
+        // Keep the snapshot even if it no longer parses, so nothing is lost
+        let config = match Self::parse(&content) {
+            Some(config) => HistoryConfig::Versioned(Box::new(config.to_file())),
+            None => HistoryConfig::Raw(content),
+        };
+
+        let now = chrono::Utc::now();
+        let entry = HistoryFile {
+            timestamp: now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            meta: meta.clone(),
+            config,
+        };
+        let Ok(json) = serde_json::to_string_pretty(&entry) else {
+            return;
+        };
+
+        let stem = now.format("%Y%m%dT%H%M%S%.3fZ").to_string();
+        let backup_path = (0..)
+            .map(|n| match n {
+                0 => history_dir.join(format!("{}.json", stem)),
+                n => history_dir.join(format!("{}-{}.json", stem, n)),
+            })
+            .find(|path| !path.exists())
+            .unwrap_or_else(|| history_dir.join(format!("{}.json", stem)));
 
-        // Copy current config to backup
-        if fs::copy(&config_path, &backup_path).is_ok() {
+        if write_atomic(&backup_path, &(json + "\n")).is_ok() {
             // Prune old backups after successful backup
             Self::prune_old_backups();
         }
@@ -150,58 +380,30 @@ impl PedalConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(10);
 
-        let history_dir = Self::history_dir();
-        if !history_dir.exists() {
-            return;
+        // Remove oldest backups if we have too many (list is newest first)
+        for entry in Self::list_backups().iter().skip(max_backups) {
+            let _ = fs::remove_file(&entry.path);
         }
+    }
 
-        // Collect backup files
-        let mut backups: Vec<PathBuf> = fs::read_dir(&history_dir)
+    /// List every history entry, newest first. Legacy `.conf` backups (no metadata) are
+    /// included, and files whose name or content can't be parsed fall back to their
+    /// modification time rather than being dropped.
+    fn list_backups() -> Vec<HistoryEntry> {
+        let history_dir = Self::history_dir();
+        let mut backups: Vec<HistoryEntry> = fs::read_dir(&history_dir)
             .into_iter()
             .flatten()
             .filter_map(|e| e.ok())
             .map(|e| e.path())
-            .filter(|p| p.extension().is_some_and(|ext| ext == "conf"))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|ext| ext == "conf" || ext == "json")
+            })
+            .map(|path| HistoryEntry::read(&path))
             .collect();
 
-        // Sort by filename (timestamp format ensures chronological order)
-        backups.sort();
-
-        // Remove oldest backups if we have too many
-        if backups.len() > max_backups {
-            let to_remove = backups.len() - max_backups;
-            for backup in backups.iter().take(to_remove) {
-                let _ = fs::remove_file(backup);
-            }
-        }
-    }
-
-    /// List all backup files with timestamps and config summaries
-    fn list_backups() -> Vec<(PathBuf, chrono::NaiveDateTime, Option<Self>)> {
-        let history_dir = Self::history_dir();
-        if !history_dir.exists() {
-            return Vec::new();
-        }
-
-        let mut backups: Vec<(PathBuf, chrono::NaiveDateTime, Option<Self>)> =
-            fs::read_dir(&history_dir)
-                .into_iter()
-                .flatten()
-                .filter_map(|e| e.ok())
-                .map(|e| e.path())
-                .filter(|p| p.extension().is_some_and(|ext| ext == "conf"))
-                .filter_map(|path| {
-                    // Parse timestamp from filename (YYYY-MM-DD_HHMMSS.conf)
-                    let stem = path.file_stem()?.to_str()?;
-                    let datetime =
-                        chrono::NaiveDateTime::parse_from_str(stem, "%Y-%m-%d_%H%M%S").ok()?;
-                    let config = Self::load_from(&path);
-                    Some((path, datetime, config))
-                })
-                .collect();
-
-        // Sort by timestamp, newest first
-        backups.sort_by_key(|b| std::cmp::Reverse(b.1));
+        backups.sort_by(HistoryEntry::newest_first);
         backups
     }
 
@@ -223,12 +425,211 @@ impl PedalConfig {
             ));
         }
 
-        let (path, _, config) = &backups[index - 1];
+        let entry = &backups[index - 1];
 
-        config
+        entry
+            .config
             .clone()
-            .ok_or_else(|| anyhow!("Failed to parse backup file: {}", path.display()))
+            .ok_or_else(|| anyhow!("Failed to parse backup file: {}", entry.path.display()))
+    }
+}
+
+/// Describes the operation that replaced a history snapshot
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct HistoryMeta {
+    /// The savant command that replaced the snapshot (e.g. "program", "config load work")
+    command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    device: Option<HistoryDevice>,
+    /// SET_REPORT layout the device accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    /// Whether every pedal and the EEPROM save succeeded (absent if the device wasn't touched)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    success: Option<bool>,
+    /// Set when Ctrl+C stopped the operation before every pedal was written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    interrupted: Option<bool>,
+    /// USB transfer retries spent under the retry policy (absent if none were needed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retries: Option<u32>,
+}
+
+impl HistoryMeta {
+    fn command(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct HistoryDevice {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    serial: Option<String>,
+    /// bcdDevice from the USB device descriptor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    revision: Option<String>,
+}
+
+/// On-disk history entry: the replaced config plus when and how it was replaced
+#[derive(Serialize, Deserialize)]
+struct HistoryFile {
+    /// UTC, RFC 3339 / ISO-8601
+    timestamp: String,
+    #[serde(flatten)]
+    meta: HistoryMeta,
+    config: HistoryConfig,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum HistoryConfig {
+    Versioned(Box<ConfigFile>),
+    /// Raw file contents, kept when the replaced config couldn't be parsed
+    Raw(String),
+}
+
+/// A history entry as listed by `config history`
+struct HistoryEntry {
+    path: PathBuf,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    /// `None` for legacy `.conf` backups and unreadable entries
+    meta: Option<HistoryMeta>,
+    config: Option<PedalConfig>,
+}
+
+impl HistoryEntry {
+    /// Order by timestamp, newest first. Entries saved in the same millisecond are told apart
+    /// by the counter `backup_current_config` appends to the name (`<stem>-N.json`, where a
+    /// higher N is newer and no suffix is the first).
+    fn newest_first(a: &Self, b: &Self) -> std::cmp::Ordering {
+        b.timestamp
+            .cmp(&a.timestamp)
+            .then(b.sequence().cmp(&a.sequence()))
+            .then(b.path.cmp(&a.path))
+    }
+
+    /// The same-millisecond counter from the file name (0 without one)
+    fn sequence(&self) -> u64 {
+        self.path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit_once("Z-"))
+            .and_then(|(_, n)| n.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn read(path: &std::path::Path) -> Self {
+        let modified = || {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .map(chrono::DateTime::<chrono::Utc>::from)
+                .unwrap_or_default()
+        };
+
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let file = fs::read_to_string(path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<HistoryFile>(&content).ok());
+            let Some(file) = file else {
+                return Self {
+                    path: path.to_path_buf(),
+                    timestamp: modified(),
+                    meta: None,
+                    config: None,
+                };
+            };
+            let config = match file.config {
+                HistoryConfig::Versioned(config) => Some(PedalConfig::from_file(*config)),
+                HistoryConfig::Raw(raw) => PedalConfig::parse(&raw),
+            };
+            return Self {
+                path: path.to_path_buf(),
+                timestamp: chrono::DateTime::parse_from_rfc3339(&file.timestamp)
+                    .map(|t| t.to_utc())
+                    .unwrap_or_else(|_| modified()),
+                meta: Some(file.meta),
+                config,
+            };
+        }
+
+        // Legacy backup named YYYY-MM-DD_HHMMSS.conf in local time
+        let timestamp = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| chrono::NaiveDateTime::parse_from_str(stem, "%Y-%m-%d_%H%M%S").ok())
+            .and_then(|naive| naive.and_local_timezone(chrono::Local).single())
+            .map(|t| t.to_utc())
+            .unwrap_or_else(modified);
+        Self {
+            path: path.to_path_buf(),
+            timestamp,
+            meta: None,
+            config: PedalConfig::load_from(path),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum HistoryStep {
+    Undo,
+    Redo,
+}
+
+impl HistoryStep {
+    fn command(self) -> &'static str {
+        match self {
+            HistoryStep::Undo => "undo",
+            HistoryStep::Redo => "redo",
+        }
+    }
+}
+
+/// Whether `program` should save its result to the config file (backing up the old one
+/// into history). An interrupted run whose EEPROM save was skipped leaves the pedal on the
+/// saved config, so a history entry would just duplicate it and make the next undo a no-op.
+fn program_updates_config(interrupted: bool, eeprom_skipped: bool) -> bool {
+    !(interrupted && eeprom_skipped)
+}
+
+/// Find the history entry (index into the newest-first list) that `step` should program,
+/// given the command that replaced each entry.
+///
+/// Entries replaced by `undo` form the redo stack; every other entry (including those
+/// replaced by `redo`) forms the undo stack. Walking from newest to oldest, each `undo`
+/// entry means one older undo-stack entry has already been used, and each `redo` entry
+/// means one older redo-stack entry has. Redo is only possible until something other than
+/// undo/redo changes the config.
+fn history_step_target<'a>(
+    commands: impl IntoIterator<Item = Option<&'a str>>,
+    step: HistoryStep,
+) -> Option<usize> {
+    let (mut used_undo, mut used_redo) = (0usize, 0usize);
+    for (i, command) in commands.into_iter().enumerate() {
+        if command == Some("undo") {
+            if used_redo > 0 {
+                used_redo -= 1;
+            } else if step == HistoryStep::Redo {
+                return Some(i);
+            }
+            used_undo += 1;
+        } else {
+            if step == HistoryStep::Redo && command != Some("redo") {
+                return None;
+            }
+            if used_undo > 0 {
+                used_undo -= 1;
+            } else if step == HistoryStep::Undo {
+                return Some(i);
+            }
+            if command == Some("redo") {
+                used_redo += 1;
+            }
+        }
     }
+    None
 }
 
 // JSON output structures for --json flag
@@ -265,6 +666,8 @@ struct JsonDeviceInfo {
     pid: Option<String>,
     path: Option<String>,
     serial: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit_id: Option<u8>,
     interfaces: Vec<JsonDeviceInterface>,
 }
 
@@ -273,6 +676,8 @@ struct JsonStatusDevice {
     mode: String,
     pid: String,
     location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit_id: Option<u8>,
 }
 
 #[derive(Serialize)]
@@ -283,20 +688,83 @@ struct JsonStatusOutput {
     ready_to_program: bool,
 }
 
+// JSON output for unit-id command
+#[derive(Serialize)]
+struct JsonUnitIdOutput {
+    devices: Vec<JsonStatusDevice>,
+}
+
+#[derive(Serialize)]
+struct JsonModeSwitchOutput {
+    success: bool,
+    from_mode: String,
+    to_mode: String,
+    already_in_mode: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    reenumerated: bool,
+    location: String,
+}
+
+#[derive(Serialize)]
+struct JsonUnitIdSetOutput {
+    success: bool,
+    unit_id: u8,
+    method: String,
+    eeprom_saved: bool,
+    verified: Option<bool>,
+    /// USB transfer retries spent under the retry policy
+    retries: u32,
+}
+
+#[derive(Serialize)]
+struct JsonProgramOutput {
+    success: bool,
+    /// --dry-run: nothing was sent, and statuses describe what would happen
+    dry_run: bool,
+    /// Read-back showed every requested pedal already held its action, so nothing was written
+    unchanged: bool,
+    pedals: Vec<JsonProgramPedal>,
+    /// saved, unchanged (nothing to save), skipped (after Ctrl+C) or failed; planned in a
+    /// dry run
+    eeprom: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reboot_verified: Option<bool>,
+    interrupted: bool,
+    retries: u32,
+}
+
+#[derive(Serialize)]
+struct JsonProgramPedal {
+    pedal: String,
+    action: String,
+    /// written, unchanged, failed or not_sent; planned or unchanged in a dry run
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    /// GET_KEY_MACRO read-back after the write (absent if the firmware doesn't answer)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verified: Option<bool>,
+}
+
 // Built-in preset configurations for common use cases
 #[derive(Clone)]
 struct Preset {
     name: &'static str,
     description: &'static str,
+    tags: &'static [&'static str],
     left: &'static str,
     middle: &'static str,
     right: &'static str,
+    /// Left/middle/right on macOS, for shortcuts `primary`/`secondary` can't express
+    macos: Option<[&'static str; 3]>,
 }
 
 impl Preset {
     const fn new(
         name: &'static str,
         description: &'static str,
+        tags: &'static [&'static str],
         left: &'static str,
         middle: &'static str,
         right: &'static str,
@@ -304,9 +772,26 @@ impl Preset {
         Self {
             name,
             description,
+            tags,
             left,
             middle,
             right,
+            macos: None,
+        }
+    }
+
+    const fn on_macos(self, left: &'static str, middle: &'static str, right: &'static str) -> Self {
+        Self {
+            macos: Some([left, middle, right]),
+            ..self
+        }
+    }
+
+    /// Left/middle/right actions as programmed for `os`
+    fn actions(&self, os: TargetOs) -> [&'static str; 3] {
+        match (os, self.macos) {
+            (TargetOs::Macos, Some(actions)) => actions,
+            _ => [self.left, self.middle, self.right],
         }
     }
 }
@@ -316,110 +801,662 @@ const PRESETS: &[Preset] = &[
     Preset::new(
         "copy-paste",
         "Copy/Select/Paste workflow - the most universally useful configuration",
-        "cmd+c",
-        "cmd+a",
-        "cmd+v",
+        &["editing"],
+        "primary+c",
+        "primary+a",
+        "primary+v",
     ),
     Preset::new(
         "undo-redo",
         "Undo/Select/Redo workflow for editing",
-        "cmd+z",
-        "cmd+a",
-        "shift+cmd+z",
+        &["editing"],
+        "primary+z",
+        "primary+a",
+        "primary+shift+z",
     ),
+    // Back/forward have no common spelling: Alt+Left/Right on Linux and Windows, Cmd+[/] on macOS
     Preset::new(
         "browser",
         "Browser navigation - back/new tab/forward",
-        "cmd+[",
-        "cmd+t",
-        "cmd+]",
-    ),
+        &["browser", "navigation"],
+        "alt+left",
+        "primary+t",
+        "alt+right",
+    )
+    .on_macos("cmd+[", "primary+t", "cmd+]"),
     Preset::new(
         "zoom",
         "Zoom video calls - mute/video/leave (macOS shortcuts)",
+        &["meetings", "macos"],
         "cmd+shift+a",
         "cmd+shift+v",
         "cmd+w",
     ),
+    Preset::new(
+        FACTORY_PRESET,
+        "Kinesis factory defaults - Ctrl+Alt+4/5/6 as shipped",
+        &["defaults"],
+        "ctrl+alt+4",
+        "ctrl+alt+5",
+        "ctrl+alt+6",
+    ),
 ];
 
+/// Name of the preset holding the factory defaults, used by `savant reset --factory`
+const FACTORY_PRESET: &str = "factory";
+
 fn find_preset(name: &str) -> Option<&'static Preset> {
     let name_lower = name.to_lowercase();
     PRESETS.iter().find(|p| p.name == name_lower)
 }
 
-// JSON output for presets
-#[derive(Serialize)]
-struct JsonPreset {
+/// Where a preset was defined. Later layers shadow earlier ones by name.
+#[derive(Clone)]
+enum PresetSource {
+    BuiltIn,
+    System(PathBuf),
+    User(PathBuf),
+}
+
+impl PresetSource {
+    fn label(&self) -> &'static str {
+        match self {
+            PresetSource::BuiltIn => "built-in",
+            PresetSource::System(_) => "system",
+            PresetSource::User(_) => "user",
+        }
+    }
+
+    fn path(&self) -> Option<&std::path::Path> {
+        match self {
+            PresetSource::BuiltIn => None,
+            PresetSource::System(path) | PresetSource::User(path) => Some(path),
+        }
+    }
+}
+
+/// A preset from any layer, as shown by `preset --list` and applied by `preset NAME`
+#[derive(Clone)]
+struct PresetEntry {
     name: String,
     description: String,
+    tags: Vec<String>,
     left: String,
     middle: String,
     right: String,
+    source: PresetSource,
 }
 
-#[derive(Serialize)]
-struct JsonPresetListOutput {
-    presets: Vec<JsonPreset>,
+impl PresetEntry {
+    fn built_in(preset: &Preset, os: TargetOs) -> Self {
+        let [left, middle, right] = preset.actions(os);
+        Self {
+            name: preset.name.to_string(),
+            description: preset.description.to_string(),
+            tags: preset.tags.iter().map(|t| t.to_string()).collect(),
+            left: left.to_string(),
+            middle: middle.to_string(),
+            right: right.to_string(),
+            source: PresetSource::BuiltIn,
+        }
+    }
 }
 
-// JSON output for config profiles
-#[derive(Serialize)]
-struct JsonProfile {
-    name: String,
+/// On-disk preset definition (one preset per `.json` file)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PresetFile {
+    /// Defaults to the file name without `.json`
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
     left: String,
     middle: String,
     right: String,
 }
 
-#[derive(Serialize)]
-struct JsonProfileListOutput {
-    profiles: Vec<JsonProfile>,
-    profiles_dir: String,
+/// User preset directory
+fn presets_dir() -> PathBuf {
+    savant_config_dir().join("presets")
 }
 
-#[derive(Serialize)]
-struct JsonProfileSaveOutput {
-    success: bool,
-    name: String,
-    path: String,
+/// Read-only, system-wide preset directory shared by every user on the machine
+fn system_presets_dir() -> PathBuf {
+    std::env::var_os("SAVANT_PRESET_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/etc/savant-elite/presets"))
 }
 
-#[derive(Serialize)]
-struct JsonProfileDeleteOutput {
-    success: bool,
-    name: String,
-    path: String,
+/// Parse and validate a preset file, collecting every problem rather than stopping at the first
+fn parse_preset_file(
+    path: &std::path::Path,
+    source: PresetSource,
+) -> std::result::Result<PresetEntry, Vec<String>> {
+    let content = fs::read_to_string(path).map_err(|e| vec![format!("Cannot read file: {}", e)])?;
+    let file: PresetFile =
+        serde_json::from_str(&content).map_err(|e| vec![format!("Invalid preset file: {}", e)])?;
+
+    let mut errors = Vec::new();
+    let name = file.name.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    if let Err(e) = validate_name("Preset", &name) {
+        errors.push(e.to_string());
+    } else if name == "check" {
+        errors.push("Preset name 'check' is reserved for `savant preset check`".to_string());
+    }
+    for (position, action) in
+        PedalPosition::ALL
+            .into_iter()
+            .zip([&file.left, &file.middle, &file.right])
+    {
+        if let Err(e) = KeyAction::from_string(action) {
+            errors.push(format!("{}: {}", position.name(), e.summary()));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(PresetEntry {
+        name: name.to_lowercase(),
+        description: file.description,
+        tags: file.tags,
+        left: file.left,
+        middle: file.middle,
+        right: file.right,
+        source,
+    })
 }
 
-// JSON output for config check command
-#[derive(Serialize, Clone)]
-struct JsonConfigCheckError {
-    line: Option<usize>,
-    field: Option<String>,
-    value: Option<String>,
-    error: String,
+/// List the `.json` files of a preset directory in name order (empty if it doesn't exist)
+fn preset_files(dir: &std::path::Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
 }
 
-#[derive(Serialize)]
-struct JsonConfigCheckParsedKey {
-    action: String,
-    modifier_hex: String,
-    key_hex: String,
+/// Built-in, system and user presets merged by name, plus any files that failed to load
+struct PresetCatalog {
+    presets: Vec<PresetEntry>,
+    problems: Vec<(PathBuf, Vec<String>)>,
 }
 
-#[derive(Serialize)]
-struct JsonConfigCheckOutput {
-    valid: bool,
-    file: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    left: Option<JsonConfigCheckParsedKey>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+impl PresetCatalog {
+    /// Built-in presets are resolved for `os`, the platform the pedal is programmed for
+    fn load(os: TargetOs) -> Self {
+        Self::load_from(&system_presets_dir(), &presets_dir(), os)
+    }
+
+    fn load_from(system_dir: &std::path::Path, user_dir: &std::path::Path, os: TargetOs) -> Self {
+        let mut catalog = Self {
+            presets: PRESETS
+                .iter()
+                .map(|preset| PresetEntry::built_in(preset, os))
+                .collect(),
+            problems: Vec::new(),
+        };
+
+        let system: fn(PathBuf) -> PresetSource = PresetSource::System;
+        for (dir, source) in [(system_dir, system), (user_dir, PresetSource::User)] {
+            let mut seen: Vec<(String, PathBuf)> = Vec::new();
+            for path in preset_files(dir) {
+                let preset = match parse_preset_file(&path, source(path.clone())) {
+                    Ok(preset) => preset,
+                    Err(errors) => {
+                        catalog.problems.push((path, errors));
+                        continue;
+                    }
+                };
+                if let Some((_, other)) = seen.iter().find(|(name, _)| *name == preset.name) {
+                    let error = format!(
+                        "Duplicate preset name '{}' (also defined in {})",
+                        preset.name,
+                        other.display()
+                    );
+                    catalog.problems.push((path, vec![error]));
+                    continue;
+                }
+                seen.push((preset.name.clone(), path));
+                match catalog.presets.iter_mut().find(|p| p.name == preset.name) {
+                    Some(existing) => *existing = preset,
+                    None => catalog.presets.push(preset),
+                }
+            }
+        }
+        catalog
+    }
+
+    fn find(&self, name: &str) -> Option<&PresetEntry> {
+        let name_lower = name.to_lowercase();
+        self.presets.iter().find(|p| p.name == name_lower)
+    }
+}
+
+// JSON output for presets
+#[derive(Serialize)]
+struct JsonPreset {
+    name: String,
+    description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    left: String,
+    middle: String,
+    right: String,
+    /// "built-in", "system" or "user"
+    source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+}
+
+impl JsonPreset {
+    fn new(preset: &PresetEntry) -> Self {
+        Self {
+            name: preset.name.clone(),
+            description: preset.description.clone(),
+            tags: preset.tags.clone(),
+            left: preset.left.clone(),
+            middle: preset.middle.clone(),
+            right: preset.right.clone(),
+            source: preset.source.label().to_string(),
+            path: preset.source.path().map(|p| p.display().to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonPresetListOutput {
+    presets: Vec<JsonPreset>,
+}
+
+// JSON output for preset check command
+#[derive(Serialize)]
+struct JsonPresetCheckFile {
+    path: String,
+    /// "system", "user" or "file" (checked by path)
+    source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    valid: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct JsonPresetCheckOutput {
+    valid: bool,
+    files: Vec<JsonPresetCheckFile>,
+    user_dir: String,
+    system_dir: String,
+}
+
+// JSON output for config profiles
+#[derive(Serialize)]
+struct JsonProfile {
+    name: String,
+    left: String,
+    middle: String,
+    right: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    details: Vec<JsonPedalDetails>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<DeviceBinding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_os: Option<TargetOs>,
+    /// "user" or "system"
+    source: String,
+    path: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    shadowed: bool,
+}
+
+#[derive(Serialize)]
+struct JsonPedalDetails {
+    pedal: String,
+    #[serde(flatten)]
+    details: PedalDetails,
+}
+
+impl JsonProfile {
+    fn new(location: &ProfileLocation, config: &PedalConfig) -> Self {
+        Self {
+            name: location.name.clone(),
+            left: config.left.clone(),
+            middle: config.middle.clone(),
+            right: config.right.clone(),
+            details: PedalPosition::ALL
+                .into_iter()
+                .filter(|p| config.details[p.index()] != PedalDetails::default())
+                .map(|p| JsonPedalDetails {
+                    pedal: p.name().to_string(),
+                    details: config.details[p.index()].clone(),
+                })
+                .collect(),
+            device: config.device.clone(),
+            target_os: config.target_os,
+            source: location.source.label().to_string(),
+            path: location.path.display().to_string(),
+            shadowed: location.shadowed,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonProfileListOutput {
+    profiles: Vec<JsonProfile>,
+    profiles_dir: String,
+    system_profiles_dir: String,
+}
+
+#[derive(Serialize)]
+struct JsonProfileSaveOutput {
+    success: bool,
+    name: String,
+    path: String,
+}
+
+#[derive(Serialize)]
+struct JsonProfileDeleteOutput {
+    success: bool,
+    name: String,
+    path: String,
+}
+
+// JSON output for config check command
+#[derive(Serialize, Clone)]
+struct JsonConfigCheckError {
+    line: Option<usize>,
+    field: Option<String>,
+    value: Option<String>,
+    /// 1-based character column in `value` of the component the error is about
+    column: Option<usize>,
+    error: String,
+    /// Closest valid key or modifier name, for typos
+    suggestion: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonConfigCheckParsedKey {
+    action: String,
+    modifier_hex: String,
+    key_hex: String,
+}
+
+#[derive(Serialize)]
+struct JsonConfigCheckOutput {
+    valid: bool,
+    file: String,
+    /// "legacy" for key=value files, "v<N>" for versioned JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    /// Platform `primary`/`secondary` were resolved for in the hex values below
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_os: Option<TargetOs>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    left: Option<JsonConfigCheckParsedKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     middle: Option<JsonConfigCheckParsedKey>,
     #[serde(skip_serializing_if = "Option::is_none")]
     right: Option<JsonConfigCheckParsedKey>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     errors: Vec<JsonConfigCheckError>,
+    /// Shortcut-lint findings; these never make the file invalid
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    lint: Vec<JsonLintFinding>,
+}
+
+#[derive(Serialize)]
+struct JsonLintFinding {
+    code: &'static str,
+    severity: &'static str,
+    pedal: &'static str,
+    message: String,
+}
+
+impl From<&LintFinding> for JsonLintFinding {
+    fn from(finding: &LintFinding) -> Self {
+        Self {
+            code: finding.code.name(),
+            severity: finding.severity.name(),
+            pedal: finding.pedal.name(),
+            message: finding.message.clone(),
+        }
+    }
+}
+
+/// Outcome of validating a config file, shared by the legacy and versioned formats.
+/// `values` and `parsed` are indexed by `PedalPosition::index`.
+#[derive(Default)]
+struct ConfigCheckResult {
+    values: [Option<String>; 3],
+    parsed: [Option<KeyAction>; 3],
+    errors: Vec<JsonConfigCheckError>,
+    /// Non-fatal findings, shown with --verbose
+    warnings: Vec<String>,
+}
+
+impl ConfigCheckResult {
+    /// Record a pedal action and validate it with the key-action parser
+    fn check_action(
+        &mut self,
+        position: PedalPosition,
+        value: &str,
+        line: Option<usize>,
+        os: TargetOs,
+    ) {
+        self.values[position.index()] = Some(value.to_string());
+        match KeyAction::parse_for(value, os) {
+            Ok(action) => self.parsed[position.index()] = Some(action),
+            Err(e) => self.errors.push(JsonConfigCheckError {
+                line,
+                field: Some(position.name().to_string()),
+                value: Some(value.to_string()),
+                column: (e.width > 0).then_some(e.column + 1),
+                error: e.summary(),
+                suggestion: e.suggestion.map(str::to_string),
+            }),
+        }
+    }
+
+    fn error(&mut self, field: Option<&str>, line: Option<usize>, error: String) {
+        self.errors.push(JsonConfigCheckError {
+            line,
+            field: field.map(str::to_string),
+            value: None,
+            column: None,
+            error,
+            suggestion: None,
+        });
+    }
+}
+
+/// Validate a legacy `left=`/`middle=`/`right=` config line by line
+fn check_legacy_config(content: &str, os: TargetOs) -> ConfigCheckResult {
+    let mut result = ConfigCheckResult::default();
+
+    for (line_num, line) in content.lines().enumerate() {
+        let line_display = line_num + 1; // 1-indexed for display
+        let line = line.trim();
+
+        // Skip empty lines
+        if line.is_empty() {
+            continue;
+        }
+
+        // Check for proper key=value format
+        let Some((key, value)) = line.split_once('=') else {
+            result.errors.push(JsonConfigCheckError {
+                line: Some(line_display),
+                field: None,
+                value: Some(line.to_string()),
+                column: None,
+                error: "Invalid syntax: expected 'key=value' format".to_string(),
+                suggestion: None,
+            });
+            continue;
+        };
+
+        let key = key.trim();
+        match PedalPosition::from_name(key) {
+            Some(position) => result.check_action(position, value.trim(), Some(line_display), os),
+            None => {
+                // Unknown key - warning, not error (for future compatibility)
+                result.warnings.push(format!(
+                    "Unknown key '{}' at line {} (ignored)",
+                    key, line_display
+                ));
+            }
+        }
+    }
+
+    for position in PedalPosition::ALL {
+        if result.values[position.index()].is_none() {
+            result.error(
+                Some(position.name()),
+                None,
+                format!("Missing required field: {}", position.name()),
+            );
+        }
+    }
+
+    result
+}
+
+/// Validate a versioned JSON config: the version, every pedal action, then the full schema
+/// (unknown fields, wrong types) once the per-pedal checks pass.
+fn check_versioned_config(content: &str, os: TargetOs) -> ConfigCheckResult {
+    let mut result = ConfigCheckResult::default();
+
+    let value: serde_json::Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            result.error(None, Some(e.line()), format!("Invalid JSON: {}", e));
+            return result;
+        }
+    };
+
+    match value.get("version").map(serde_json::Value::as_u64) {
+        None => result.error(
+            Some("version"),
+            None,
+            "Missing required field: version".to_string(),
+        ),
+        Some(Some(v)) if v == CONFIG_SCHEMA_VERSION as u64 => {}
+        Some(Some(v)) => {
+            result.error(
+                Some("version"),
+                None,
+                format!(
+                    "Unsupported config version {} (this savant supports version {})",
+                    v, CONFIG_SCHEMA_VERSION
+                ),
+            );
+            return result;
+        }
+        Some(None) => result.error(
+            Some("version"),
+            None,
+            "version must be a non-negative integer".to_string(),
+        ),
+    }
+
+    for position in PedalPosition::ALL {
+        let field = format!("pedals.{}.action", position.name());
+        match value["pedals"][position.name()]["action"].as_str() {
+            Some(action) => result.check_action(position, action, None, os),
+            None => result.error(
+                Some(position.name()),
+                None,
+                format!("Missing required field: {}", field),
+            ),
+        }
+    }
+
+    if result.errors.is_empty() {
+        if let Err(e) = serde_json::from_str::<ConfigFile>(content) {
+            result.error(None, Some(e.line()), format!("Schema error: {}", e));
+        }
+    }
+
+    result
+}
+
+// JSON output for config diff command
+#[derive(Serialize)]
+struct JsonConfigDiffPedal {
+    pedal: String,
+    a: String,
+    b: String,
+    changed: bool,
+}
+
+#[derive(Serialize)]
+struct JsonConfigDiffOutput {
+    a: String,
+    b: String,
+    pedals: Vec<JsonConfigDiffPedal>,
+    identical: bool,
+}
+
+/// Compare two configs pedal by pedal. Actions that parse to the same chord (e.g. `cmd+c`
+/// and `command+C`) are not reported as changed.
+fn diff_configs(a: &PedalConfig, b: &PedalConfig) -> Vec<JsonConfigDiffPedal> {
+    PedalPosition::ALL
+        .into_iter()
+        .map(|position| {
+            let (a, b) = (a.get(position), b.get(position));
+            let same = match (KeyAction::from_string(a), KeyAction::from_string(b)) {
+                (Ok(x), Ok(y)) => x.modifiers == y.modifiers && x.key == y.key,
+                _ => a == b,
+            };
+            JsonConfigDiffPedal {
+                pedal: position.name().to_string(),
+                a: a.to_string(),
+                b: b.to_string(),
+                changed: !same,
+            }
+        })
+        .collect()
+}
+
+/// Resolve a `config diff` operand: `current` (the live config), a history number
+/// (`3` or `history:3`, 1 = most recent), or a profile (`work` or `profile:work`).
+fn resolve_config_ref(spec: &str) -> Result<PedalConfig> {
+    if spec == "current" {
+        return PedalConfig::load().ok_or_else(|| {
+            anyhow!(
+                "No current configuration at {}",
+                PedalConfig::config_path().display()
+            )
+        });
+    }
+
+    let history = spec.strip_prefix("history:").unwrap_or(spec);
+    if let Ok(number) = history.parse::<usize>() {
+        return PedalConfig::restore_backup(number);
+    }
+    if spec.starts_with("history:") {
+        return Err(anyhow!("Invalid history number in '{}'", spec));
+    }
+
+    let name = spec.strip_prefix("profile:").unwrap_or(spec);
+    validate_profile_name(name)?;
+    let profile = find_profile(name).ok_or_else(|| anyhow!("Profile '{}' not found", name))?;
+    PedalConfig::load_from(&profile.path)
+        .ok_or_else(|| anyhow!("Failed to parse profile '{}'", name))
 }
 
 // JSON output for doctor command
@@ -441,4467 +1478,11335 @@ struct JsonDoctorOutput {
     arch: String,
     checks: Vec<JsonDoctorCheck>,
     summary: JsonDoctorSummary,
+    /// Present with --fix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fixes: Option<Vec<JsonDoctorFix>>,
+    /// Present with --bundle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle: Option<JsonDoctorBundle>,
 }
 
 #[derive(Serialize)]
-struct JsonDoctorSummary {
-    total: usize,
-    passed: usize,
-    warnings: usize,
-    failed: usize,
-    healthy: bool,
+struct JsonDoctorFix {
+    action: String,
+    path: String,
+    /// Destination of a backup or move
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<String>,
+    reason: String,
+    status: String, // "planned" (--dry-run), "applied", "failed", "skipped"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
-/// Get the profiles directory path
-fn profiles_dir() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("savant-elite")
-        .join("profiles")
+#[derive(Serialize)]
+struct JsonDoctorBundle {
+    path: String,
+    files: Vec<String>,
 }
 
-/// Get the path for a specific profile
-fn profile_path(name: &str) -> PathBuf {
-    profiles_dir().join(format!("{}.conf", name))
+// JSON output for probe (and the probe.json file in a support bundle)
+#[derive(Serialize)]
+struct JsonProbeDevice {
+    vid: String,
+    pid: String,
+    mode: String,
+    path: String,
+    interface: i32,
+    usage_page: String,
+    usage: String,
+    opened: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    commands: Vec<JsonProbeCommand>,
+    feature_reports: Vec<JsonProbeReport>,
 }
 
-/// Validate profile name (alphanumeric, hyphen, underscore only)
-fn validate_profile_name(name: &str) -> Result<()> {
-    if name.is_empty() {
-        return Err(anyhow!("Profile name cannot be empty"));
-    }
-    if name.len() > 64 {
-        return Err(anyhow!("Profile name too long (max 64 characters)"));
-    }
-    if !name
-        .chars()
-        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
-    {
-        return Err(anyhow!(
-            "Profile name can only contain letters, numbers, hyphens, and underscores"
-        ));
-    }
-    // Prevent names that could cause issues
-    if name.starts_with('-') || name.starts_with('_') {
-        return Err(anyhow!(
-            "Profile name cannot start with a hyphen or underscore"
-        ));
-    }
-    Ok(())
+#[derive(Serialize)]
+struct JsonProbeCommand {
+    command: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_written: Option<usize>,
+    /// Hex-encoded reply, `None` if nothing came back
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
-const KINESIS_VID: u16 = 0x05F3;
-const SAVANT_ELITE_PID: u16 = 0x030C; // Normal "play" mode PID
-const PROGRAMMING_PID: u16 = 0x0232; // Programming mode PID (from driver INF)
+#[derive(Serialize)]
+struct JsonProbeReport {
+    report_id: u8,
+    data: String,
+}
 
-// PI Engineering X-keys protocol commands (used by Kinesis Savant Elite)
-// These constants document the full protocol even if not all are currently used.
-#[allow(dead_code)]
-mod xkeys_protocol {
-    // Output report commands (sent to device)
-    pub const CMD_GENERATE_DATA: u8 = 0xB5; // Request device state
-    pub const CMD_SET_LED: u8 = 0xB6; // Set LED state
-    pub const CMD_SET_FLASH_FREQ: u8 = 0xB7; // Set flash frequency
-    pub const CMD_SET_TIMESTAMP: u8 = 0xB8; // Enable/disable timestamp
-    pub const CMD_GET_DESCRIPTOR: u8 = 0xC1; // Request device descriptor
-    pub const CMD_SET_UNIT_ID: u8 = 0xC9; // Set unit ID
-    pub const CMD_SET_PID: u8 = 0xCA; // Change product ID (mode switch)
-    pub const CMD_REBOOT: u8 = 0xCB; // Reboot device
-    pub const CMD_SET_KEY_MACRO: u8 = 0xCC; // Program a key macro
-    pub const CMD_GET_KEY_MACRO: u8 = 0xCD; // Get key macro
-    pub const CMD_SAVE_TO_EEPROM: u8 = 0xCE; // Save to EEPROM
+// USB and HID enumeration recorded in a support bundle
+#[derive(Serialize)]
+struct JsonUsbDevice {
+    bus: u8,
+    address: u8,
+    ports: String,
+    vid: String,
+    pid: String,
+    /// bcdDevice, i.e. the firmware revision for the pedal
+    revision: String,
+    class: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    product: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    serial: Option<String>,
+    configurations: Vec<JsonUsbConfiguration>,
+}
 
-    // Pedal indices
-    pub const PEDAL_LEFT: u8 = 0;
-    pub const PEDAL_MIDDLE: u8 = 1;
-    pub const PEDAL_RIGHT: u8 = 2;
+#[derive(Serialize)]
+struct JsonUsbConfiguration {
+    value: u8,
+    interfaces: Vec<JsonUsbInterface>,
 }
 
-mod usb_constants {
-    pub const HID_REQUEST_TYPE_CLASS_INTERFACE_OUT: u8 = 0x21;
-    pub const HID_REQUEST_TYPE_CLASS_INTERFACE_IN: u8 = 0xA1;
+#[derive(Serialize)]
+struct JsonUsbInterface {
+    number: u8,
+    alt_setting: u8,
+    class: String,
+    subclass: String,
+    protocol: String,
+    endpoints: Vec<JsonUsbEndpoint>,
+}
 
-    pub const HID_SET_REPORT: u8 = 0x09;
-    pub const HID_GET_REPORT: u8 = 0x01;
+#[derive(Serialize)]
+struct JsonUsbEndpoint {
+    address: String,
+    direction: String,
+    transfer_type: String,
+    max_packet_size: u16,
+    interval: u8,
+}
 
-    pub const HID_REPORT_TYPE_INPUT: u16 = 0x0100;
-    pub const HID_REPORT_TYPE_OUTPUT: u16 = 0x0200;
-    pub const HID_REPORT_TYPE_FEATURE: u16 = 0x0300;
+#[derive(Serialize)]
+struct JsonHidDevice {
+    vid: String,
+    pid: String,
+    path: String,
+    interface: i32,
+    usage_page: String,
+    usage: String,
+    release: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    product: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    serial: Option<String>,
+}
 
-    pub const USB_REQUEST_TYPE_VENDOR_OUT: u8 = 0x40;
+/// bcdDevice as "major.minor.sub_minor"
+fn format_revision(version: rusb::Version) -> String {
+    format!(
+        "{}.{}.{}",
+        version.major(),
+        version.minor(),
+        version.sub_minor()
+    )
 }
 
-// USB HID keyboard usage codes
-// See: https://usb.org/sites/default/files/hut1_4.pdf (Section 10)
-// These constants document the full HID spec even if not all are currently used.
-#[allow(dead_code)]
-mod usb_hid {
-    // Modifier keys (byte 0 of keyboard report)
-    pub const MOD_LEFT_CTRL: u8 = 0x01;
-    pub const MOD_LEFT_SHIFT: u8 = 0x02;
-    pub const MOD_LEFT_ALT: u8 = 0x04;
-    pub const MOD_LEFT_GUI: u8 = 0x08; // Command on Mac
-    pub const MOD_RIGHT_CTRL: u8 = 0x10;
-    pub const MOD_RIGHT_SHIFT: u8 = 0x20;
-    pub const MOD_RIGHT_ALT: u8 = 0x40;
-    pub const MOD_RIGHT_GUI: u8 = 0x80;
+/// Every USB device libusb can see, with its full descriptor tree. String descriptors
+/// are only read from the pedal, since opening other devices may need extra permissions.
+fn usb_enumeration(devices: &[Device<GlobalContext>]) -> Vec<JsonUsbDevice> {
+    let mut result = Vec::new();
+    for device in devices {
+        let Ok(desc) = device.device_descriptor() else {
+            continue;
+        };
+        let (mut manufacturer, mut product, mut serial) = (None, None, None);
+        if desc.vendor_id() == KINESIS_VID {
+            if let Ok(handle) = device.open() {
+                manufacturer = handle.read_manufacturer_string_ascii(&desc).ok();
+                product = handle.read_product_string_ascii(&desc).ok();
+                serial = handle.read_serial_number_string_ascii(&desc).ok();
+            }
+        }
 
-    // Common key codes (bytes 2-7 of keyboard report)
-    pub const KEY_A: u8 = 0x04;
-    pub const KEY_B: u8 = 0x05;
-    pub const KEY_C: u8 = 0x06;
-    pub const KEY_D: u8 = 0x07;
-    pub const KEY_E: u8 = 0x08;
-    pub const KEY_F: u8 = 0x09;
-    pub const KEY_G: u8 = 0x0A;
-    pub const KEY_H: u8 = 0x0B;
-    pub const KEY_I: u8 = 0x0C;
-    pub const KEY_J: u8 = 0x0D;
-    pub const KEY_K: u8 = 0x0E;
-    pub const KEY_L: u8 = 0x0F;
-    pub const KEY_M: u8 = 0x10;
-    pub const KEY_N: u8 = 0x11;
-    pub const KEY_O: u8 = 0x12;
-    pub const KEY_P: u8 = 0x13;
-    pub const KEY_Q: u8 = 0x14;
-    pub const KEY_R: u8 = 0x15;
-    pub const KEY_S: u8 = 0x16;
-    pub const KEY_T: u8 = 0x17;
-    pub const KEY_U: u8 = 0x18;
-    pub const KEY_V: u8 = 0x19;
-    pub const KEY_W: u8 = 0x1A;
-    pub const KEY_X: u8 = 0x1B;
-    pub const KEY_Y: u8 = 0x1C;
-    pub const KEY_Z: u8 = 0x1D;
-    pub const KEY_1: u8 = 0x1E;
-    pub const KEY_2: u8 = 0x1F;
-    pub const KEY_3: u8 = 0x20;
-    pub const KEY_4: u8 = 0x21;
-    pub const KEY_5: u8 = 0x22;
-    pub const KEY_6: u8 = 0x23;
-    pub const KEY_7: u8 = 0x24;
-    pub const KEY_8: u8 = 0x25;
-    pub const KEY_9: u8 = 0x26;
-    pub const KEY_0: u8 = 0x27;
-    pub const KEY_ENTER: u8 = 0x28;
-    pub const KEY_ESC: u8 = 0x29;
-    pub const KEY_BACKSPACE: u8 = 0x2A;
-    pub const KEY_TAB: u8 = 0x2B;
-    pub const KEY_SPACE: u8 = 0x2C;
-    pub const KEY_F1: u8 = 0x3A;
-    pub const KEY_F2: u8 = 0x3B;
-    pub const KEY_F3: u8 = 0x3C;
-    pub const KEY_F4: u8 = 0x3D;
-    pub const KEY_F5: u8 = 0x3E;
-    pub const KEY_F6: u8 = 0x3F;
-    pub const KEY_F7: u8 = 0x40;
-    pub const KEY_F8: u8 = 0x41;
-    pub const KEY_F9: u8 = 0x42;
-    pub const KEY_F10: u8 = 0x43;
-    pub const KEY_F11: u8 = 0x44;
-    pub const KEY_F12: u8 = 0x45;
-    pub const KEY_LEFT: u8 = 0x50;
-    pub const KEY_RIGHT: u8 = 0x4F;
-    pub const KEY_UP: u8 = 0x52;
-    pub const KEY_DOWN: u8 = 0x51;
+        let configurations = (0..desc.num_configurations())
+            .filter_map(|i| device.config_descriptor(i).ok())
+            .map(|config| JsonUsbConfiguration {
+                value: config.number(),
+                interfaces: config
+                    .interfaces()
+                    .flat_map(|interface| interface.descriptors())
+                    .map(|alt| JsonUsbInterface {
+                        number: alt.interface_number(),
+                        alt_setting: alt.setting_number(),
+                        class: format!("0x{:02X}", alt.class_code()),
+                        subclass: format!("0x{:02X}", alt.sub_class_code()),
+                        protocol: format!("0x{:02X}", alt.protocol_code()),
+                        endpoints: alt
+                            .endpoint_descriptors()
+                            .map(|ep| JsonUsbEndpoint {
+                                address: format!("0x{:02X}", ep.address()),
+                                direction: match ep.direction() {
+                                    rusb::Direction::In => "in",
+                                    rusb::Direction::Out => "out",
+                                }
+                                .to_string(),
+                                transfer_type: match ep.transfer_type() {
+                                    rusb::TransferType::Control => "control",
+                                    rusb::TransferType::Isochronous => "isochronous",
+                                    rusb::TransferType::Bulk => "bulk",
+                                    rusb::TransferType::Interrupt => "interrupt",
+                                }
+                                .to_string(),
+                                max_packet_size: ep.max_packet_size(),
+                                interval: ep.interval(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
 
-    pub fn key_name(code: u8) -> &'static str {
-        match code {
-            0x00 => "None",
-            0x04 => "A",
-            0x05 => "B",
-            0x06 => "C",
-            0x07 => "D",
-            0x08 => "E",
-            0x09 => "F",
-            0x0A => "G",
-            0x0B => "H",
-            0x0C => "I",
-            0x0D => "J",
-            0x0E => "K",
-            0x0F => "L",
-            0x10 => "M",
-            0x11 => "N",
-            0x12 => "O",
-            0x13 => "P",
-            0x14 => "Q",
-            0x15 => "R",
-            0x16 => "S",
-            0x17 => "T",
-            0x18 => "U",
-            0x19 => "V",
-            0x1A => "W",
-            0x1B => "X",
-            0x1C => "Y",
-            0x1D => "Z",
-            0x1E => "1",
-            0x1F => "2",
-            0x20 => "3",
-            0x21 => "4",
-            0x22 => "5",
-            0x23 => "6",
-            0x24 => "7",
-            0x25 => "8",
-            0x26 => "9",
-            0x27 => "0",
-            0x28 => "Enter",
-            0x29 => "Escape",
-            0x2A => "Backspace",
-            0x2B => "Tab",
-            0x2C => "Space",
-            0x2D => "Minus",
-            0x2E => "Equal",
-            0x2F => "LeftBracket",
-            0x30 => "RightBracket",
-            0x31 => "Backslash",
-            0x33 => "Semicolon",
-            0x34 => "Quote",
-            0x35 => "Grave",
-            0x36 => "Comma",
-            0x37 => "Period",
-            0x38 => "Slash",
-            0x39 => "CapsLock",
-            0x3A => "F1",
-            0x3B => "F2",
-            0x3C => "F3",
-            0x3D => "F4",
-            0x3E => "F5",
-            0x3F => "F6",
-            0x40 => "F7",
-            0x41 => "F8",
-            0x42 => "F9",
-            0x43 => "F10",
-            0x44 => "F11",
-            0x45 => "F12",
-            0x4F => "Right",
-            0x50 => "Left",
-            0x51 => "Down",
-            0x52 => "Up",
-            _ => "Unknown",
-        }
+        result.push(JsonUsbDevice {
+            bus: device.bus_number(),
+            address: device.address(),
+            ports: device
+                .port_numbers()
+                .unwrap_or_default()
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join("."),
+            vid: format!("0x{:04X}", desc.vendor_id()),
+            pid: format!("0x{:04X}", desc.product_id()),
+            revision: format_revision(desc.device_version()),
+            class: format!("0x{:02X}", desc.class_code()),
+            manufacturer,
+            product,
+            serial,
+            configurations,
+        });
     }
+    result
+}
 
-    pub fn modifier_names(mods: u8) -> Vec<&'static str> {
-        let mut names = Vec::new();
-        if mods & MOD_LEFT_CTRL != 0 {
-            names.push("LCtrl");
-        }
-        if mods & MOD_LEFT_SHIFT != 0 {
-            names.push("LShift");
-        }
-        if mods & MOD_LEFT_ALT != 0 {
-            names.push("LAlt");
-        }
-        if mods & MOD_LEFT_GUI != 0 {
-            names.push("LCmd");
-        }
-        if mods & MOD_RIGHT_CTRL != 0 {
-            names.push("RCtrl");
-        }
-        if mods & MOD_RIGHT_SHIFT != 0 {
-            names.push("RShift");
-        }
-        if mods & MOD_RIGHT_ALT != 0 {
-            names.push("RAlt");
-        }
-        if mods & MOD_RIGHT_GUI != 0 {
-            names.push("RCmd");
+/// Every HID interface hidapi can see
+fn hid_enumeration(api: &HidApi) -> Vec<JsonHidDevice> {
+    api.device_list()
+        .map(|info| JsonHidDevice {
+            vid: format!("0x{:04X}", info.vendor_id()),
+            pid: format!("0x{:04X}", info.product_id()),
+            path: info.path().to_string_lossy().into_owned(),
+            interface: info.interface_number(),
+            usage_page: format!("0x{:04X}", info.usage_page()),
+            usage: format!("0x{:04X}", info.usage()),
+            release: format!("0x{:04X}", info.release_number()),
+            manufacturer: info.manufacturer_string().map(str::to_string),
+            product: info.product_string().map(str::to_string),
+            serial: info.serial_number().map(str::to_string),
+        })
+        .collect()
+}
+
+/// Hides who ran savant: the home directory becomes `~` and the login name `<user>`
+struct Redactor {
+    home: Option<String>,
+    user: Option<String>,
+}
+
+impl Redactor {
+    fn current() -> Self {
+        let home = dirs::home_dir().map(|h| h.to_string_lossy().trim_end_matches('/').to_string());
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok()
+            .or_else(|| {
+                home.as_deref()
+                    .and_then(|h| h.rsplit(['/', '\\']).next())
+                    .map(str::to_string)
+            });
+        Self::new(home, user)
+    }
+
+    fn new(home: Option<String>, user: Option<String>) -> Self {
+        Self {
+            // Redacting "/" or a one-letter name would mangle everything else
+            home: home.filter(|h| h.len() > 1),
+            user: user.filter(|u| u.len() > 1),
         }
-        names
     }
 
-    pub fn normalize_boot_keyboard_report(data: &[u8]) -> Option<[u8; 8]> {
-        if data.len() < 8 {
-            return None;
+    fn redact(&self, text: &str) -> String {
+        let mut text = match &self.home {
+            Some(home) => text.replace(home.as_str(), "~"),
+            None => text.to_string(),
+        };
+        if let Some(user) = &self.user {
+            // Whole words only, so "root" doesn't turn "chroot" into "ch<user>"
+            let is_word = |c: char| c.is_alphanumeric() || c == '_';
+            let mut out = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(i) = rest.find(user.as_str()) {
+                let end = i + user.len();
+                let before = rest[..i].chars().next_back();
+                let after = rest[end..].chars().next();
+                out.push_str(&rest[..i]);
+                if before.is_some_and(is_word) || after.is_some_and(is_word) {
+                    out.push_str(user);
+                } else {
+                    out.push_str("<user>");
+                }
+                rest = &rest[end..];
+            }
+            out.push_str(rest);
+            text = out;
         }
+        text
+    }
+}
 
-        // Heuristic: Some HID backends include a leading report-id byte (often 0) and/or pad
-        // input reports out to a larger endpoint size. The Savant Elite keyboard interface is a
-        // standard 8-byte boot keyboard report, so we normalize to the first 8 bytes of the
-        // actual report.
-        let looks_prefixed = data.len() >= 9
-            && data[0] == 0
-            && data[2] == 0
-            && (data[1] != 0 || data[3..9].iter().any(|&b| b != 0));
-        let offset = if looks_prefixed { 1 } else { 0 };
-        if data.len() < offset + 8 {
-            return None;
+#[derive(Serialize)]
+struct JsonDoctorSummary {
+    total: usize,
+    passed: usize,
+    warnings: usize,
+    failed: usize,
+    healthy: bool,
+}
+
+/// One change `doctor --fix` makes to the filesystem
+#[derive(Clone, Debug, PartialEq)]
+enum DoctorFixAction {
+    CreateDir,
+    /// Copy the file at `path` to the given backup path before it's replaced
+    Backup(PathBuf),
+    Write(String),
+    /// Move the file at `path` aside to the given path
+    Move(PathBuf),
+    Remove,
+}
+
+impl DoctorFixAction {
+    fn name(&self) -> &'static str {
+        match self {
+            DoctorFixAction::CreateDir => "create_dir",
+            DoctorFixAction::Backup(_) => "backup",
+            DoctorFixAction::Write(_) => "write",
+            DoctorFixAction::Move(_) => "move",
+            DoctorFixAction::Remove => "remove",
         }
+    }
+}
 
-        let mut report = [0u8; 8];
-        report.copy_from_slice(&data[offset..offset + 8]);
-        Some(report)
+struct DoctorFixStep {
+    path: PathBuf,
+    action: DoctorFixAction,
+    /// Why the change is needed, shown next to it
+    reason: String,
+}
+
+impl DoctorFixStep {
+    fn new(path: PathBuf, action: DoctorFixAction, reason: impl Into<String>) -> Self {
+        Self {
+            path,
+            action,
+            reason: reason.into(),
+        }
     }
 
-    pub fn parse_key_name(name: &str) -> Option<u8> {
-        match name.to_lowercase().as_str() {
-            "a" => Some(KEY_A),
-            "b" => Some(KEY_B),
-            "c" => Some(KEY_C),
-            "d" => Some(KEY_D),
-            "e" => Some(KEY_E),
-            "f" => Some(KEY_F),
-            "g" => Some(KEY_G),
-            "h" => Some(KEY_H),
-            "i" => Some(KEY_I),
-            "j" => Some(KEY_J),
-            "k" => Some(KEY_K),
-            "l" => Some(KEY_L),
-            "m" => Some(KEY_M),
-            "n" => Some(KEY_N),
-            "o" => Some(KEY_O),
-            "p" => Some(KEY_P),
-            "q" => Some(KEY_Q),
-            "r" => Some(KEY_R),
-            "s" => Some(KEY_S),
-            "t" => Some(KEY_T),
-            "u" => Some(KEY_U),
-            "v" => Some(KEY_V),
-            "w" => Some(KEY_W),
-            "x" => Some(KEY_X),
-            "y" => Some(KEY_Y),
-            "z" => Some(KEY_Z),
-            "1" => Some(KEY_1),
-            "2" => Some(KEY_2),
-            "3" => Some(KEY_3),
-            "4" => Some(KEY_4),
-            "5" => Some(KEY_5),
-            "6" => Some(KEY_6),
-            "7" => Some(KEY_7),
-            "8" => Some(KEY_8),
-            "9" => Some(KEY_9),
-            "0" => Some(KEY_0),
-            "enter" | "return" => Some(KEY_ENTER),
-            "esc" | "escape" => Some(KEY_ESC),
-            "backspace" => Some(KEY_BACKSPACE),
-            "tab" => Some(KEY_TAB),
-            "space" => Some(KEY_SPACE),
-            "f1" => Some(KEY_F1),
-            "f2" => Some(KEY_F2),
-            "f3" => Some(KEY_F3),
-            "f4" => Some(KEY_F4),
-            "f5" => Some(KEY_F5),
-            "f6" => Some(KEY_F6),
-            "f7" => Some(KEY_F7),
-            "f8" => Some(KEY_F8),
-            "f9" => Some(KEY_F9),
-            "f10" => Some(KEY_F10),
-            "f11" => Some(KEY_F11),
-            "f12" => Some(KEY_F12),
-            "left" => Some(KEY_LEFT),
-            "right" => Some(KEY_RIGHT),
-            "up" => Some(KEY_UP),
-            "down" => Some(KEY_DOWN),
-            // Punctuation and special keys
-            "minus" | "-" => Some(0x2D),
-            "equal" | "=" => Some(0x2E),
-            "leftbracket" | "[" => Some(0x2F),
-            "rightbracket" | "]" => Some(0x30),
-            "backslash" | "\\" => Some(0x31),
-            "semicolon" | ";" => Some(0x33),
-            "quote" | "'" => Some(0x34),
-            "grave" | "`" => Some(0x35),
-            "comma" | "," => Some(0x36),
-            "period" | "." => Some(0x37),
-            "slash" | "/" => Some(0x38),
-            "capslock" => Some(0x39),
-            _ => None,
+    fn apply(&self) -> Result<()> {
+        match &self.action {
+            DoctorFixAction::CreateDir => fs::create_dir_all(&self.path)?,
+            DoctorFixAction::Backup(to) => {
+                fs::copy(&self.path, to)?;
+            }
+            DoctorFixAction::Write(content) => write_atomic(&self.path, content)?,
+            DoctorFixAction::Move(to) => fs::rename(&self.path, to)?,
+            DoctorFixAction::Remove => fs::remove_file(&self.path)?,
         }
+        Ok(())
     }
 }
 
-#[derive(Parser)]
-#[command(name = "savant")]
-#[command(version)]
-#[command(about = "Kinesis Savant Elite foot pedal programmer for macOS")]
-#[command(
-    long_about = "Native macOS programmer for the discontinued Kinesis Savant Elite USB foot pedal.\n\nProgram your foot pedals directly via USB—no Windows VM, no 32-bit compatibility hacks."
-)]
-struct Cli {
-    /// Enable verbose output for debugging
-    #[arg(short, long, global = true)]
-    verbose: bool,
+/// Work out what `doctor --fix` would change under `config_dir`. Nothing is touched here.
+/// `udev_rule_dirs` is where udev rules are searched for, or `None` where udev doesn't apply.
+fn plan_doctor_fixes(
+    config_dir: &std::path::Path,
+    udev_rule_dirs: Option<&[PathBuf]>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<DoctorFixStep> {
+    let mut steps = Vec::new();
+
+    for dir in [
+        config_dir.to_path_buf(),
+        config_dir.join("profiles"),
+        config_dir.join("history"),
+    ] {
+        if !dir.is_dir() {
+            steps.push(DoctorFixStep::new(
+                dir,
+                DoctorFixAction::CreateDir,
+                "directory is missing",
+            ));
+        }
+    }
 
-    /// Output in JSON format for scripting
-    #[arg(long, global = true)]
-    json: bool,
+    let config_path = config_dir.join("pedals.conf");
+    if let Ok(content) = fs::read_to_string(&config_path) {
+        match PedalConfig::parse(&content) {
+            Some(config) if PedalConfig::is_legacy(&content) => {
+                // Same upgrade `PedalConfig::load` does, for when it couldn't take the lock
+                if let Ok(versioned) = config.serialize() {
+                    steps.push(DoctorFixStep::new(
+                        config_path.clone(),
+                        DoctorFixAction::Backup(config_path.with_extension("conf.legacy")),
+                        "keep the legacy key=value config",
+                    ));
+                    steps.push(DoctorFixStep::new(
+                        config_path,
+                        DoctorFixAction::Write(versioned),
+                        "migrate the legacy config to the versioned format",
+                    ));
+                }
+            }
+            Some(_) => {}
+            None => {
+                let backup = config_path
+                    .with_extension(format!("conf.broken-{}", now.format("%Y%m%dT%H%M%SZ")));
+                match PedalConfig::salvage(&content).and_then(|c| c.serialize().ok()) {
+                    Some(repaired) => {
+                        steps.push(DoctorFixStep::new(
+                            config_path.clone(),
+                            DoctorFixAction::Backup(backup),
+                            "keep the unparseable config",
+                        ));
+                        steps.push(DoctorFixStep::new(
+                            config_path,
+                            DoctorFixAction::Write(repaired),
+                            "rewrite the config from the pedal actions it still contains",
+                        ));
+                    }
+                    None => steps.push(DoctorFixStep::new(
+                        config_path,
+                        DoctorFixAction::Move(backup),
+                        "config can't be parsed or repaired; 'savant program' will create a new one",
+                    )),
+                }
+            }
+        }
+    }
 
-    /// USB operation timeout in milliseconds [default: 500]
-    #[arg(long, global = true, value_name = "MS", value_parser = clap::value_parser!(u64).range(100..=600000))]
-    timeout: Option<u64>,
+    // Raw snapshots of a broken config still have metadata and are kept on purpose; only
+    // entries that can't be read at all are removed
+    let history_dir = config_dir.join("history");
+    let mut corrupt: Vec<PathBuf> = fs::read_dir(&history_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "conf" || ext == "json")
+        })
+        .map(|path| HistoryEntry::read(&path))
+        .filter(|entry| entry.meta.is_none() && entry.config.is_none())
+        .map(|entry| entry.path)
+        .collect();
+    corrupt.sort();
+    for path in corrupt {
+        steps.push(DoctorFixStep::new(
+            path,
+            DoctorFixAction::Remove,
+            "history entry can't be parsed",
+        ));
+    }
 
-    #[command(subcommand)]
-    command: Commands,
+    if let Some(dirs) = udev_rule_dirs {
+        let rules = linux_access::udev_rules(linux_access::DEFAULT_GROUP);
+        let path = config_dir.join(linux_access::RULES_FILE_NAME);
+        let current = fs::read_to_string(&path).ok();
+        if linux_access::find_installed_rule(dirs).is_none() && current.as_ref() != Some(&rules) {
+            steps.push(DoctorFixStep::new(
+                path.clone(),
+                DoctorFixAction::Write(rules),
+                format!(
+                    "no udev rule installed; install it with 'sudo cp {} /etc/udev/rules.d/'",
+                    path.display()
+                ),
+            ));
+        }
+    }
+
+    steps
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Detect and show info about connected Savant Elite pedals
-    Info,
+/// The `savant-elite` directory under the platform config dir
+fn savant_config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("savant-elite")
+}
 
-    /// Monitor foot pedal input in real-time (requires Input Monitoring permission)
-    Monitor {
-        /// Duration in seconds (0 = infinite)
-        #[arg(short, long, default_value = "30")]
-        duration: u64,
-    },
+/// Write `content` to a temp file beside `path`, fsync it, then rename it into place so a
+/// crash or a concurrent reader never sees a truncated file.
+fn write_atomic(path: &std::path::Path, content: &str) -> Result<()> {
+    use std::io::Write;
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file path: {}", path.display()))?
+        .to_string_lossy();
+    let temp_path = parent.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
 
-    /// Program the pedals (requires device in programming mode)
-    Program {
-        /// Left pedal action (e.g., "cmd+c" for copy)
-        #[arg(long, default_value = "cmd+c")]
-        left: String,
+/// An exclusive advisory lock on a lock file, released when dropped
+struct FileLock {
+    _file: fs::File,
+}
 
-        /// Middle pedal action (e.g., "cmd+a" for select all)
-        #[arg(long, default_value = "cmd+a")]
-        middle: String,
+impl FileLock {
+    /// Lock `path`, retrying for up to `wait` while another process holds it
+    fn acquire(path: &std::path::Path, wait: Duration) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
 
-        /// Right pedal action (e.g., "cmd+v" for paste)
-        #[arg(long, default_value = "cmd+v")]
-        right: String,
+        let deadline = std::time::Instant::now() + wait;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(fs::TryLockError::WouldBlock) if std::time::Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                Err(fs::TryLockError::WouldBlock) => {
+                    return Err(anyhow!(
+                        "{} is locked by another savant process",
+                        path.display()
+                    ));
+                }
+                Err(fs::TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+                }
+            }
+        }
+    }
+}
 
-        /// Dry run - don't actually write to device
-        #[arg(long)]
-        dry_run: bool,
+/// How long config writers wait for each other before giving up
+const CONFIG_LOCK_WAIT: Duration = Duration::from_secs(10);
 
-        /// Start monitor mode after programming to test the pedals
-        #[arg(long, short = 'm')]
-        monitor: bool,
-    },
+/// Serialize every writer of the config directory (live config, profiles, history)
+fn lock_config_dir(wait: Duration) -> Result<FileLock> {
+    FileLock::acquire(&savant_config_dir().join(".lock"), wait)
+        .context("Another savant process is updating the configuration; try again shortly")
+}
 
-    /// Check if device is in programming mode
-    Status,
+/// Stop two processes from programming the same physical pedal at once. The lock is keyed
+/// by bus and port chain, which stay stable while the device is plugged in.
+fn lock_device(device: &Device<GlobalContext>) -> Result<FileLock> {
+    let ports = device
+        .port_numbers()
+        .unwrap_or_default()
+        .iter()
+        .map(u8::to_string)
+        .collect::<Vec<_>>()
+        .join(".");
+    let path = savant_config_dir().join("locks").join(format!(
+        "device-{:03}-{}.lock",
+        device.bus_number(),
+        ports
+    ));
+    FileLock::acquire(&path, Duration::ZERO)
+        .context("Another savant process is already talking to this pedal")
+}
 
-    /// Probe device for programming protocol (reverse engineering)
-    Probe,
+/// The `savant-elite` directory under the platform state dir (`~/.local/state` on Linux),
+/// or under the local data dir where the platform has no state dir (macOS, Windows)
+fn savant_state_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("savant-elite")
+}
 
-    /// Send raw HID command to device (expert mode)
-    RawCmd {
-        /// Command byte (hex, e.g., "b5" for generate data)
-        #[arg(long)]
-        cmd: String,
+/// What an operation-log record describes
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum LogEvent {
+    /// A savant invocation, with its arguments
+    Command,
+    /// A pedal found (or not) on the bus
+    Device,
+    /// One USB transfer attempt and its result
+    Transfer,
+    /// The outcome of programming or verifying one pedal
+    Pedal,
+    /// The SAVE_TO_EEPROM outcome
+    Eeprom,
+    /// The pedal vanished mid-operation
+    Disconnect,
+    /// SIGINT/SIGTERM stopped an operation between transfers
+    Interrupt,
+    /// How the invocation ended
+    Result,
+}
 
-        /// Additional data bytes (hex, e.g., "00010203")
-        #[arg(long, default_value = "")]
-        data: String,
+impl LogEvent {
+    fn name(self) -> &'static str {
+        match self {
+            LogEvent::Command => "command",
+            LogEvent::Device => "device",
+            LogEvent::Transfer => "transfer",
+            LogEvent::Pedal => "pedal",
+            LogEvent::Eeprom => "eeprom",
+            LogEvent::Disconnect => "disconnect",
+            LogEvent::Interrupt => "interrupt",
+            LogEvent::Result => "result",
+        }
+    }
+}
 
-        /// Interface number (0=keyboard, 1=mouse)
-        #[arg(long, default_value = "0", value_parser = clap::value_parser!(i32).range(0..=255))]
-        interface: i32,
-    },
+/// One line of the operation log
+#[derive(Serialize, Deserialize)]
+struct LogRecord {
+    /// UTC, RFC 3339 with milliseconds
+    timestamp: String,
+    /// Tells apart records from savant processes running at the same time
+    pid: u32,
+    event: LogEvent,
+    message: String,
+}
 
-    /// List all valid key names and modifiers
-    Keys {
-        /// Output in JSON format for scripting
-        #[arg(long)]
-        json: bool,
-    },
+/// How many rotated files (`savant.log.1` is the newest) are kept next to the live log
+const LOG_ROTATIONS: usize = 3;
 
-    /// Generate shell completion scripts
-    Completions {
-        /// Shell to generate completions for
-        #[arg(value_enum)]
-        shell: Shell,
-    },
+/// Always-on JSON-lines log of every device interaction, kept whether or not --verbose is
+/// set. Rotated by size (`SAVANT_LOG_SIZE` bytes, default 1 MiB). Writing is best-effort:
+/// an unwritable state dir never fails a command.
+struct OperationLog {
+    path: PathBuf,
+    max_bytes: u64,
+}
 
-    /// Apply a preset configuration (built-in pedal mappings for common use cases)
-    Preset {
-        /// Name of the preset to apply (e.g., "copy-paste", "undo-redo", "browser", "zoom")
-        #[arg(value_name = "NAME")]
-        name: Option<String>,
+impl OperationLog {
+    fn new(path: PathBuf) -> Self {
+        let max_bytes = std::env::var("SAVANT_LOG_SIZE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1024 * 1024);
+        Self { path, max_bytes }
+    }
 
-        /// List all available presets
-        #[arg(long, short = 'l')]
-        list: bool,
+    /// `SAVANT_LOG_PATH` if set, else `savant.log` in the state dir
+    fn default_path() -> PathBuf {
+        std::env::var_os("SAVANT_LOG_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| savant_state_dir().join("savant.log"))
+    }
 
-        /// Show details of a specific preset without applying it
-        #[arg(long)]
-        show: bool,
+    fn append(&self, event: LogEvent, message: &str) {
+        use std::io::Write;
 
-        /// Dry run - show what would be programmed without writing to device
-        #[arg(long)]
-        dry_run: bool,
-    },
+        let record = LogRecord {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            pid: std::process::id(),
+            event,
+            message: message.to_string(),
+        };
+        let Ok(line) = serde_json::to_string(&record) else {
+            return;
+        };
+        if fs::metadata(&self.path).is_ok_and(|m| m.len() >= self.max_bytes) {
+            self.rotate();
+        }
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        // One write per record so concurrent appenders don't interleave within a line
+        if let Ok(mut file) = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        {
+            let _ = file.write_all((line + "\n").as_bytes());
+        }
+    }
 
-    /// Manage saved pedal configuration profiles
-    Config {
-        #[command(subcommand)]
-        command: ConfigCommand,
-    },
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        self.path.with_extension(format!("log.{}", n))
+    }
 
-    /// Run system diagnostics to identify configuration issues
-    Doctor,
+    fn rotate(&self) {
+        for n in (1..LOG_ROTATIONS).rev() {
+            let _ = fs::rename(self.rotated_path(n), self.rotated_path(n + 1));
+        }
+        let _ = fs::rename(&self.path, self.rotated_path(1));
+    }
+
+    /// Every readable record, oldest first, across the rotated files. Lines that don't
+    /// parse (e.g. cut short by a full disk) are skipped.
+    fn read(&self) -> Vec<LogRecord> {
+        (1..=LOG_ROTATIONS)
+            .rev()
+            .map(|n| self.rotated_path(n))
+            .chain([self.path.clone()])
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .flat_map(|content| {
+                content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect::<Vec<LogRecord>>()
+            })
+            .collect()
+    }
 }
 
-/// Subcommands for the config command
-#[derive(Subcommand)]
-enum ConfigCommand {
-    /// Save current configuration as a named profile
-    Save {
-        /// Name for the profile (alphanumeric, hyphens, underscores)
-        name: String,
+/// Get the profiles directory path
+fn profiles_dir() -> PathBuf {
+    savant_config_dir().join("profiles")
+}
 
-        /// Overwrite existing profile without prompting
-        #[arg(long, short = 'f')]
-        force: bool,
-    },
+/// Get the path for a specific profile in the user profiles directory
+fn profile_path(name: &str) -> PathBuf {
+    profiles_dir().join(format!("{}.conf", name))
+}
 
-    /// Load and apply a saved profile (programs device)
-    Load {
-        /// Name of the profile to load
-        name: String,
+/// Read-only, centrally provisioned profiles, searched after the user directory
+fn system_profiles_dir() -> PathBuf {
+    std::env::var_os("SAVANT_PROFILE_PATH")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/etc/savant-elite/profiles"))
+}
 
-        /// Preview without programming device
-        #[arg(long)]
-        dry_run: bool,
-    },
+#[derive(Clone, Copy, PartialEq)]
+enum ProfileSource {
+    User,
+    System,
+}
 
-    /// List all saved profiles
-    List,
+impl ProfileSource {
+    fn label(self) -> &'static str {
+        match self {
+            ProfileSource::User => "user",
+            ProfileSource::System => "system",
+        }
+    }
+}
 
-    /// Show contents of a saved profile
-    Show {
-        /// Name of the profile to show
-        name: String,
-    },
+/// A profile file and the store it lives in
+struct ProfileLocation {
+    name: String,
+    path: PathBuf,
+    source: ProfileSource,
+    /// A user profile with the same name hides this one
+    shadowed: bool,
+}
 
-    /// Delete a saved profile
-    Delete {
-        /// Name of the profile to delete
-        name: String,
+/// Find the profile `name` resolves to: the user store wins over the system store
+fn find_profile(name: &str) -> Option<ProfileLocation> {
+    [
+        (profiles_dir(), ProfileSource::User),
+        (system_profiles_dir(), ProfileSource::System),
+    ]
+    .into_iter()
+    .map(|(dir, source)| ProfileLocation {
+        name: name.to_string(),
+        path: dir.join(format!("{}.conf", name)),
+        source,
+        shadowed: false,
+    })
+    .find(|location| location.path.is_file())
+}
 
-        /// Delete without confirmation
-        #[arg(long, short = 'f')]
-        force: bool,
-    },
+/// Every profile in both stores, sorted by name with the user copy first
+fn list_profiles() -> Vec<ProfileLocation> {
+    let mut profiles: Vec<ProfileLocation> = Vec::new();
+    for (dir, source) in [
+        (profiles_dir(), ProfileSource::User),
+        (system_profiles_dir(), ProfileSource::System),
+    ] {
+        let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "conf"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let shadowed = profiles.iter().any(|p| p.name == name);
+            profiles.push(ProfileLocation {
+                name: name.to_string(),
+                path,
+                source,
+                shadowed,
+            });
+        }
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
 
-    /// Validate a configuration file
-    Check {
-        /// Path to config file (defaults to current config)
-        #[arg(value_name = "FILE")]
-        file: Option<String>,
-    },
+/// Validate `name` and load the profile it resolves to (user store first, then system)
+fn load_profile(name: &str) -> Result<(ProfileLocation, PedalConfig)> {
+    validate_profile_name(name)?;
+    let location = find_profile(name).ok_or_else(|| anyhow!("Profile '{}' not found", name))?;
+    let config = PedalConfig::load_from(&location.path)
+        .ok_or_else(|| anyhow!("Failed to parse profile '{}'", name))?;
+    Ok((location, config))
+}
 
-    /// Show configuration history (automatic backups)
-    History,
+/// Check every pedal action with the key-action parser
+fn validate_profile_actions(config: &PedalConfig) -> Result<()> {
+    for position in PedalPosition::ALL {
+        KeyAction::from_string(config.get(position))
+            .with_context(|| format!("Invalid {} pedal action", position.name()))?;
+    }
+    Ok(())
+}
 
-    /// Restore a previous configuration from history
-    Restore {
-        /// Backup number to restore (1 = most recent)
-        number: usize,
+/// Write `config` as the user profile `name` after validating the name and actions.
+/// Refuses to replace an existing user profile unless `force` is set.
+fn write_profile(name: &str, config: &PedalConfig, force: bool) -> Result<PathBuf> {
+    validate_profile_name(name)?;
+    validate_profile_actions(config)?;
 
-        /// Program device immediately after restore
-        #[arg(long)]
-        apply: bool,
-    },
+    let path = profile_path(name);
+    let _lock = lock_config_dir(CONFIG_LOCK_WAIT)?;
+    if path.exists() && !force {
+        return Err(anyhow!(
+            "Profile '{}' already exists. Use --force to overwrite.",
+            name
+        ));
+    }
+    fs::create_dir_all(profiles_dir()).context("Failed to create profiles directory")?;
+    config.save_to(&path).context("Failed to save profile")?;
+    Ok(path)
 }
 
-#[derive(Debug, Clone)]
-struct KeyAction {
-    modifiers: u8,
-    key: u8,
+/// Current format of `config export` bundles
+const BUNDLE_VERSION: u32 = 1;
+
+/// A set of profiles moved between machines with `config export` / `config import`.
+/// `C` is [`ConfigFile`] when writing and raw JSON when reading, so each profile can be
+/// validated on its own.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileBundle<C> {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exported_at: Option<String>,
+    profiles: Vec<BundleProfile<C>>,
 }
 
-impl KeyAction {
-    fn from_string(s: &str) -> Result<Self> {
-        // Validate input is not empty or whitespace-only
-        let s = s.trim();
-        if s.is_empty() {
-            return Err(anyhow!("Key action cannot be empty"));
-        }
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BundleProfile<C> {
+    name: String,
+    config: C,
+}
 
-        // Validate no leading or trailing '+' (would produce empty parts)
-        if s.starts_with('+') || s.ends_with('+') {
-            return Err(anyhow!(
-                "Key action cannot start or end with '+': \"{}\"",
-                s
-            ));
-        }
+/// What to do when an imported profile has the same name as an existing user profile
+#[derive(Clone, Copy, PartialEq)]
+enum ImportConflict {
+    Fail,
+    Rename,
+    Overwrite,
+    Skip,
+}
 
-        // Validate no consecutive '+' characters (e.g., "cmd++c")
-        if s.contains("++") {
-            return Err(anyhow!(
-                "Key action contains empty modifier (consecutive '+'): \"{}\"",
-                s
-            ));
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ImportAction {
+    Create,
+    Rename,
+    Overwrite,
+    Skip,
+}
+
+impl ImportAction {
+    fn label(self) -> &'static str {
+        match self {
+            ImportAction::Create => "created",
+            ImportAction::Rename => "renamed",
+            ImportAction::Overwrite => "overwritten",
+            ImportAction::Skip => "skipped",
         }
+    }
+}
 
-        let parts: Vec<&str> = s.split('+').collect();
-        let mut modifiers = 0u8;
-        let mut key = 0u8;
+struct ImportStep {
+    /// Name the profile is written under
+    name: String,
+    /// Name in the bundle
+    original_name: String,
+    config: PedalConfig,
+    action: ImportAction,
+}
 
-        for (i, part) in parts.iter().enumerate() {
-            let part = part.trim().to_lowercase();
-            if part.is_empty() {
-                // Extra safety check for whitespace-only parts like "cmd + + c"
-                return Err(anyhow!("Key action contains empty component: \"{}\"", s));
+/// Validate every profile in a bundle and decide where each one goes. Nothing is written
+/// here; any problem fails the whole import so a bad bundle can't half-apply.
+fn plan_import(
+    content: &str,
+    exists: impl Fn(&str) -> bool,
+    conflict: ImportConflict,
+) -> Result<Vec<ImportStep>> {
+    let bundle: ProfileBundle<serde_json::Value> =
+        serde_json::from_str(content).context("Not a valid savant profile bundle")?;
+    if bundle.version != BUNDLE_VERSION {
+        return Err(anyhow!(
+            "Unsupported bundle version {} (this savant supports version {})",
+            bundle.version,
+            BUNDLE_VERSION
+        ));
+    }
+
+    let bundled: Vec<&str> = bundle.profiles.iter().map(|p| p.name.as_str()).collect();
+    let mut errors: Vec<String> = Vec::new();
+    let mut steps: Vec<ImportStep> = Vec::new();
+
+    for (i, profile) in bundle.profiles.iter().enumerate() {
+        let name = &profile.name;
+        if let Err(e) = validate_profile_name(name) {
+            errors.push(format!("'{}': {}", name, e));
+            continue;
+        }
+        if bundled[..i].contains(&name.as_str()) {
+            errors.push(format!("'{}': appears more than once in the bundle", name));
+            continue;
+        }
+        let config = match PedalConfig::parse_versioned(&profile.config.to_string()) {
+            Ok(config) => config,
+            Err(e) => {
+                errors.push(format!("'{}': {:#}", name, e));
+                continue;
             }
-            if i == parts.len() - 1 {
-                // Last part is the key
-                key = usb_hid::parse_key_name(&part)
-                    .ok_or_else(|| anyhow!("Unknown key: \"{}\"", part))?;
-            } else {
-                // Modifier
-                match part.as_str() {
-                    "cmd" | "command" | "gui" | "meta" | "super" => {
-                        modifiers |= usb_hid::MOD_LEFT_GUI;
-                    }
-                    "ctrl" | "control" => {
-                        modifiers |= usb_hid::MOD_LEFT_CTRL;
-                    }
-                    "shift" => {
-                        modifiers |= usb_hid::MOD_LEFT_SHIFT;
-                    }
-                    "alt" | "option" | "opt" => {
-                        modifiers |= usb_hid::MOD_LEFT_ALT;
-                    }
-                    _ => return Err(anyhow!("Unknown modifier: \"{}\"", part)),
+        };
+        let invalid: Vec<String> = PedalPosition::ALL
+            .into_iter()
+            .filter_map(|position| {
+                KeyAction::from_string(config.get(position))
+                    .err()
+                    .map(|e| format!("'{}': {} pedal: {}", name, position.name(), e.summary()))
+            })
+            .collect();
+        if !invalid.is_empty() {
+            errors.extend(invalid);
+            continue;
+        }
+
+        let taken = |candidate: &str| {
+            exists(candidate)
+                || bundled.contains(&candidate)
+                || steps.iter().any(|s| s.name == candidate)
+        };
+        let (target, action) = if !exists(name) {
+            (name.clone(), ImportAction::Create)
+        } else {
+            match conflict {
+                ImportConflict::Fail => {
+                    errors.push(format!(
+                        "'{}': a profile with this name already exists (use --rename, --overwrite or --skip)",
+                        name
+                    ));
+                    continue;
+                }
+                ImportConflict::Overwrite => (name.clone(), ImportAction::Overwrite),
+                ImportConflict::Skip => (name.clone(), ImportAction::Skip),
+                ImportConflict::Rename => {
+                    let Some(renamed) = (2..)
+                        .map(|n| format!("{}-{}", name, n))
+                        .take_while(|candidate| candidate.len() <= 64)
+                        .find(|candidate| !taken(candidate))
+                    else {
+                        errors.push(format!("'{}': no free name to rename it to", name));
+                        continue;
+                    };
+                    (renamed, ImportAction::Rename)
                 }
             }
-        }
+        };
+        steps.push(ImportStep {
+            name: target,
+            original_name: name.clone(),
+            config,
+            action,
+        });
+    }
 
-        Ok(Self { modifiers, key })
+    if !errors.is_empty() {
+        return Err(anyhow!(
+            "Bundle has {} problem(s), nothing was imported:\n  - {}",
+            errors.len(),
+            errors.join("\n  - ")
+        ));
     }
+    Ok(steps)
 }
 
-struct SavantElite {
-    console: Console,
-    verbose: bool,
-    json_output: bool,
-    timeout_ms: u64,
+/// Validate profile name (alphanumeric, hyphen, underscore only)
+fn validate_profile_name(name: &str) -> Result<()> {
+    validate_name("Profile", name)
 }
 
-struct UsbInterfaceGuard<'a> {
-    handle: &'a rusb::DeviceHandle<GlobalContext>,
-    interface_num: u8,
-    detached_kernel_driver: bool,
-    claimed: bool,
+/// Validate a profile or preset name; `kind` prefixes the error messages
+fn validate_name(kind: &str, name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(anyhow!("{} name cannot be empty", kind));
+    }
+    if name.len() > 64 {
+        return Err(anyhow!("{} name too long (max 64 characters)", kind));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow!(
+            "{} name can only contain letters, numbers, hyphens, and underscores",
+            kind
+        ));
+    }
+    // Prevent names that could cause issues
+    if name.starts_with('-') || name.starts_with('_') {
+        return Err(anyhow!(
+            "{} name cannot start with a hyphen or underscore",
+            kind
+        ));
+    }
+    Ok(())
 }
 
-impl Drop for UsbInterfaceGuard<'_> {
-    fn drop(&mut self) {
-        if self.claimed {
-            let _ = self.handle.release_interface(self.interface_num);
-        }
+const KINESIS_VID: u16 = 0x05F3;
+const SAVANT_ELITE_PID: u16 = 0x030C; // Normal "play" mode PID
+const PROGRAMMING_PID: u16 = 0x0232; // Programming mode PID (from driver INF)
 
-        if self.detached_kernel_driver {
-            // Best-effort: if we detached the kernel driver, try to restore it.
-            let _ = self.handle.attach_kernel_driver(self.interface_num);
-        }
-    }
-}
+// PI Engineering X-keys protocol commands (used by Kinesis Savant Elite)
+// These constants document the full protocol even if not all are currently used.
+#[allow(dead_code)]
+mod xkeys_protocol {
+    // Output report commands (sent to device)
+    pub const CMD_GENERATE_DATA: u8 = 0xB5; // Request device state
+    pub const CMD_SET_LED: u8 = 0xB6; // Set LED state
+    pub const CMD_SET_FLASH_FREQ: u8 = 0xB7; // Set flash frequency
+    pub const CMD_SET_TIMESTAMP: u8 = 0xB8; // Enable/disable timestamp
+    pub const CMD_GET_DESCRIPTOR: u8 = 0xC1; // Request device descriptor
+    pub const CMD_SET_UNIT_ID: u8 = 0xC9; // Set unit ID
+    pub const CMD_SET_PID: u8 = 0xCA; // Change product ID (mode switch)
+    pub const CMD_REBOOT: u8 = 0xCB; // Reboot device
+    pub const CMD_SET_KEY_MACRO: u8 = 0xCC; // Program a key macro
+    pub const CMD_GET_KEY_MACRO: u8 = 0xCD; // Get key macro
+    pub const CMD_SAVE_TO_EEPROM: u8 = 0xCE; // Save to EEPROM
 
-/// Check if a USB device is still connected by verifying it appears in the device list.
-/// Returns true if the device with the given bus/address is still present.
-fn is_device_still_connected(bus_number: u8, device_address: u8) -> bool {
-    match rusb::devices() {
-        Ok(devices) => devices
-            .iter()
-            .any(|d| d.bus_number() == bus_number && d.address() == device_address),
-        Err(_) => false,
-    }
-}
+    // Pedal indices
+    pub const PEDAL_LEFT: u8 = 0;
+    pub const PEDAL_MIDDLE: u8 = 1;
+    pub const PEDAL_RIGHT: u8 = 2;
 
-/// Default USB operation timeout in milliseconds
-const DEFAULT_USB_TIMEOUT_MS: u64 = 500;
+    // Byte 1 of an X-keys input report has this bit set when the report was produced in
+    // response to CMD_GENERATE_DATA (as opposed to a key press). Bit 0 carries the program
+    // switch state; no other bits are used in a generated-data report.
+    pub const GENERATED_DATA_FLAG: u8 = 0x02;
+    pub const PROGRAM_SWITCH_FLAG: u8 = 0x01;
 
-impl SavantElite {
-    fn new(verbose: bool, json_output: bool, timeout_ms: u64) -> Result<Self> {
-        Ok(Self {
-            console: Console::new(),
-            verbose,
-            json_output,
-            timeout_ms,
-        })
+    /// Whether a SET_REPORT layout from `send_xkeys_command` puts a report-id byte (0) in
+    /// front of the command. The device answers GET_REPORT in the same layout.
+    pub fn layout_has_report_id(layout: &str) -> bool {
+        layout.ends_with("-prefix")
     }
 
-    /// Print verbose output to stderr if verbose mode is enabled
-    fn verbose(&self, msg: &str) {
-        if self.verbose {
-            eprintln!("[verbose] {}", msg);
+    /// Extract the unit ID from an X-keys input report produced by CMD_GENERATE_DATA.
+    ///
+    /// X-keys devices echo their unit ID as the first byte of every input report, followed
+    /// by the generated-data flags. With a report-id layout everything is shifted by one.
+    /// Only the position implied by `report_id` is checked, so ordinary keyboard reports
+    /// (which never carry the flag byte) are rejected.
+    pub fn parse_unit_id_report(data: &[u8], report_id: bool) -> Option<u8> {
+        let data = if report_id {
+            match data.split_first() {
+                Some((0, rest)) => rest,
+                _ => return None,
+            }
+        } else {
+            data
+        };
+        let (&unit_id, rest) = data.split_first()?;
+        let flags = *rest.first()?;
+        let known = GENERATED_DATA_FLAG | PROGRAM_SWITCH_FLAG;
+        (flags & GENERATED_DATA_FLAG != 0 && flags & !known == 0).then_some(unit_id)
+    }
+
+    /// Extract `(modifiers, key)` from a GET_KEY_MACRO response for `pedal`.
+    ///
+    /// Response formats vary by firmware: `[cmd, pedal, mod, key, ...]` or, with a report-id
+    /// byte, `[0, cmd, pedal, mod, key, ...]`. Only a response echoing CMD_GET_KEY_MACRO and
+    /// the pedal index is accepted: `program` skips writes on a match, so a stray input
+    /// report must never look like one.
+    pub fn parse_key_macro_report(data: &[u8], pedal: u8) -> Option<(u8, u8)> {
+        let data = match data {
+            [0, CMD_GET_KEY_MACRO, rest @ ..] => rest,
+            [CMD_GET_KEY_MACRO, rest @ ..] => rest,
+            _ => return None,
+        };
+        match data {
+            [p, modifiers, key, ..] if *p == pedal => Some((*modifiers, *key)),
+            _ => None,
         }
     }
+}
 
-    /// Print verbose hex data to stderr if verbose mode is enabled
-    fn verbose_hex(&self, label: &str, data: &[u8]) {
-        if self.verbose {
-            let hex: Vec<String> = data.iter().map(|b| format!("{:02X}", b)).collect();
-            if data.len() <= 16 {
-                eprintln!("[verbose] {}: [{}]", label, hex.join(" "));
-            } else {
-                eprintln!(
-                    "[verbose] {}: [{}...] ({} bytes)",
-                    label,
+mod usb_constants {
+    pub const HID_REQUEST_TYPE_CLASS_INTERFACE_OUT: u8 = 0x21;
+    pub const HID_REQUEST_TYPE_CLASS_INTERFACE_IN: u8 = 0xA1;
+
+    pub const HID_SET_REPORT: u8 = 0x09;
+    pub const HID_GET_REPORT: u8 = 0x01;
+
+    pub const HID_REPORT_TYPE_INPUT: u16 = 0x0100;
+    pub const HID_REPORT_TYPE_OUTPUT: u16 = 0x0200;
+    pub const HID_REPORT_TYPE_FEATURE: u16 = 0x0300;
+
+    pub const USB_REQUEST_TYPE_VENDOR_OUT: u8 = 0x40;
+
+    pub const USB_CLASS_HID: u8 = 0x03;
+}
+
+// USB HID keyboard usage codes
+// See: https://usb.org/sites/default/files/hut1_4.pdf (Section 10)
+// These constants document the full HID spec even if not all are currently used.
+#[allow(dead_code)]
+mod usb_hid {
+    // Modifier keys (byte 0 of keyboard report)
+    pub const MOD_LEFT_CTRL: u8 = 0x01;
+    pub const MOD_LEFT_SHIFT: u8 = 0x02;
+    pub const MOD_LEFT_ALT: u8 = 0x04;
+    pub const MOD_LEFT_GUI: u8 = 0x08; // Command on Mac
+    pub const MOD_RIGHT_CTRL: u8 = 0x10;
+    pub const MOD_RIGHT_SHIFT: u8 = 0x20;
+    pub const MOD_RIGHT_ALT: u8 = 0x40;
+    pub const MOD_RIGHT_GUI: u8 = 0x80;
+
+    // Common key codes (bytes 2-7 of keyboard report)
+    pub const KEY_A: u8 = 0x04;
+    pub const KEY_B: u8 = 0x05;
+    pub const KEY_C: u8 = 0x06;
+    pub const KEY_D: u8 = 0x07;
+    pub const KEY_E: u8 = 0x08;
+    pub const KEY_F: u8 = 0x09;
+    pub const KEY_G: u8 = 0x0A;
+    pub const KEY_H: u8 = 0x0B;
+    pub const KEY_I: u8 = 0x0C;
+    pub const KEY_J: u8 = 0x0D;
+    pub const KEY_K: u8 = 0x0E;
+    pub const KEY_L: u8 = 0x0F;
+    pub const KEY_M: u8 = 0x10;
+    pub const KEY_N: u8 = 0x11;
+    pub const KEY_O: u8 = 0x12;
+    pub const KEY_P: u8 = 0x13;
+    pub const KEY_Q: u8 = 0x14;
+    pub const KEY_R: u8 = 0x15;
+    pub const KEY_S: u8 = 0x16;
+    pub const KEY_T: u8 = 0x17;
+    pub const KEY_U: u8 = 0x18;
+    pub const KEY_V: u8 = 0x19;
+    pub const KEY_W: u8 = 0x1A;
+    pub const KEY_X: u8 = 0x1B;
+    pub const KEY_Y: u8 = 0x1C;
+    pub const KEY_Z: u8 = 0x1D;
+    pub const KEY_1: u8 = 0x1E;
+    pub const KEY_2: u8 = 0x1F;
+    pub const KEY_3: u8 = 0x20;
+    pub const KEY_4: u8 = 0x21;
+    pub const KEY_5: u8 = 0x22;
+    pub const KEY_6: u8 = 0x23;
+    pub const KEY_7: u8 = 0x24;
+    pub const KEY_8: u8 = 0x25;
+    pub const KEY_9: u8 = 0x26;
+    pub const KEY_0: u8 = 0x27;
+    pub const KEY_ENTER: u8 = 0x28;
+    pub const KEY_ESC: u8 = 0x29;
+    pub const KEY_BACKSPACE: u8 = 0x2A;
+    pub const KEY_TAB: u8 = 0x2B;
+    pub const KEY_SPACE: u8 = 0x2C;
+    pub const KEY_DELETE: u8 = 0x4C;
+    pub const KEY_F1: u8 = 0x3A;
+    pub const KEY_F2: u8 = 0x3B;
+    pub const KEY_F3: u8 = 0x3C;
+    pub const KEY_F4: u8 = 0x3D;
+    pub const KEY_F5: u8 = 0x3E;
+    pub const KEY_F6: u8 = 0x3F;
+    pub const KEY_F7: u8 = 0x40;
+    pub const KEY_F8: u8 = 0x41;
+    pub const KEY_F9: u8 = 0x42;
+    pub const KEY_F10: u8 = 0x43;
+    pub const KEY_F11: u8 = 0x44;
+    pub const KEY_F12: u8 = 0x45;
+    pub const KEY_LEFT: u8 = 0x50;
+    pub const KEY_RIGHT: u8 = 0x4F;
+    pub const KEY_UP: u8 = 0x52;
+    pub const KEY_DOWN: u8 = 0x51;
+
+    pub fn key_name(code: u8) -> &'static str {
+        match code {
+            0x00 => "None",
+            0x04 => "A",
+            0x05 => "B",
+            0x06 => "C",
+            0x07 => "D",
+            0x08 => "E",
+            0x09 => "F",
+            0x0A => "G",
+            0x0B => "H",
+            0x0C => "I",
+            0x0D => "J",
+            0x0E => "K",
+            0x0F => "L",
+            0x10 => "M",
+            0x11 => "N",
+            0x12 => "O",
+            0x13 => "P",
+            0x14 => "Q",
+            0x15 => "R",
+            0x16 => "S",
+            0x17 => "T",
+            0x18 => "U",
+            0x19 => "V",
+            0x1A => "W",
+            0x1B => "X",
+            0x1C => "Y",
+            0x1D => "Z",
+            0x1E => "1",
+            0x1F => "2",
+            0x20 => "3",
+            0x21 => "4",
+            0x22 => "5",
+            0x23 => "6",
+            0x24 => "7",
+            0x25 => "8",
+            0x26 => "9",
+            0x27 => "0",
+            0x28 => "Enter",
+            0x29 => "Escape",
+            0x2A => "Backspace",
+            0x2B => "Tab",
+            0x2C => "Space",
+            0x2D => "Minus",
+            0x2E => "Equal",
+            0x2F => "LeftBracket",
+            0x30 => "RightBracket",
+            0x31 => "Backslash",
+            0x33 => "Semicolon",
+            0x34 => "Quote",
+            0x35 => "Grave",
+            0x36 => "Comma",
+            0x37 => "Period",
+            0x38 => "Slash",
+            0x39 => "CapsLock",
+            0x3A => "F1",
+            0x3B => "F2",
+            0x3C => "F3",
+            0x3D => "F4",
+            0x3E => "F5",
+            0x3F => "F6",
+            0x40 => "F7",
+            0x41 => "F8",
+            0x42 => "F9",
+            0x43 => "F10",
+            0x44 => "F11",
+            0x45 => "F12",
+            0x4C => "Delete",
+            0x4F => "Right",
+            0x50 => "Left",
+            0x51 => "Down",
+            0x52 => "Up",
+            _ => "Unknown",
+        }
+    }
+
+    pub fn modifier_names(mods: u8) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if mods & MOD_LEFT_CTRL != 0 {
+            names.push("LCtrl");
+        }
+        if mods & MOD_LEFT_SHIFT != 0 {
+            names.push("LShift");
+        }
+        if mods & MOD_LEFT_ALT != 0 {
+            names.push("LAlt");
+        }
+        if mods & MOD_LEFT_GUI != 0 {
+            names.push("LCmd");
+        }
+        if mods & MOD_RIGHT_CTRL != 0 {
+            names.push("RCtrl");
+        }
+        if mods & MOD_RIGHT_SHIFT != 0 {
+            names.push("RShift");
+        }
+        if mods & MOD_RIGHT_ALT != 0 {
+            names.push("RAlt");
+        }
+        if mods & MOD_RIGHT_GUI != 0 {
+            names.push("RCmd");
+        }
+        names
+    }
+
+    pub fn normalize_boot_keyboard_report(data: &[u8]) -> Option<[u8; 8]> {
+        if data.len() < 8 {
+            return None;
+        }
+
+        // Heuristic: Some HID backends include a leading report-id byte (often 0) and/or pad
+        // input reports out to a larger endpoint size. The Savant Elite keyboard interface is a
+        // standard 8-byte boot keyboard report, so we normalize to the first 8 bytes of the
+        // actual report.
+        let looks_prefixed = data.len() >= 9
+            && data[0] == 0
+            && data[2] == 0
+            && (data[1] != 0 || data[3..9].iter().any(|&b| b != 0));
+        let offset = if looks_prefixed { 1 } else { 0 };
+        if data.len() < offset + 8 {
+            return None;
+        }
+
+        let mut report = [0u8; 8];
+        report.copy_from_slice(&data[offset..offset + 8]);
+        Some(report)
+    }
+
+    /// Every name `parse_modifier_name` accepts
+    pub const MODIFIER_NAMES: &[&str] = &[
+        "cmd", "command", "gui", "meta", "super", "ctrl", "control", "shift", "alt", "option",
+        "opt",
+    ];
+
+    /// Parse a modifier name into its left-hand modifier bit (case-insensitive)
+    pub fn parse_modifier_name(name: &str) -> Option<u8> {
+        match name.to_lowercase().as_str() {
+            "cmd" | "command" | "gui" | "meta" | "super" => Some(MOD_LEFT_GUI),
+            "ctrl" | "control" => Some(MOD_LEFT_CTRL),
+            "shift" => Some(MOD_LEFT_SHIFT),
+            "alt" | "option" | "opt" => Some(MOD_LEFT_ALT),
+            _ => None,
+        }
+    }
+
+    /// Every name `parse_key_name` accepts
+    pub const KEY_NAMES: &[&str] = &[
+        "a",
+        "b",
+        "c",
+        "d",
+        "e",
+        "f",
+        "g",
+        "h",
+        "i",
+        "j",
+        "k",
+        "l",
+        "m",
+        "n",
+        "o",
+        "p",
+        "q",
+        "r",
+        "s",
+        "t",
+        "u",
+        "v",
+        "w",
+        "x",
+        "y",
+        "z",
+        "1",
+        "2",
+        "3",
+        "4",
+        "5",
+        "6",
+        "7",
+        "8",
+        "9",
+        "0",
+        "enter",
+        "return",
+        "esc",
+        "escape",
+        "backspace",
+        "delete",
+        "del",
+        "tab",
+        "space",
+        "f1",
+        "f2",
+        "f3",
+        "f4",
+        "f5",
+        "f6",
+        "f7",
+        "f8",
+        "f9",
+        "f10",
+        "f11",
+        "f12",
+        "left",
+        "right",
+        "up",
+        "down",
+        "minus",
+        "-",
+        "equal",
+        "=",
+        "leftbracket",
+        "[",
+        "rightbracket",
+        "]",
+        "backslash",
+        "\\",
+        "semicolon",
+        ";",
+        "quote",
+        "'",
+        "grave",
+        "`",
+        "comma",
+        ",",
+        "period",
+        ".",
+        "slash",
+        "/",
+        "capslock",
+    ];
+
+    pub fn parse_key_name(name: &str) -> Option<u8> {
+        match name.to_lowercase().as_str() {
+            "a" => Some(KEY_A),
+            "b" => Some(KEY_B),
+            "c" => Some(KEY_C),
+            "d" => Some(KEY_D),
+            "e" => Some(KEY_E),
+            "f" => Some(KEY_F),
+            "g" => Some(KEY_G),
+            "h" => Some(KEY_H),
+            "i" => Some(KEY_I),
+            "j" => Some(KEY_J),
+            "k" => Some(KEY_K),
+            "l" => Some(KEY_L),
+            "m" => Some(KEY_M),
+            "n" => Some(KEY_N),
+            "o" => Some(KEY_O),
+            "p" => Some(KEY_P),
+            "q" => Some(KEY_Q),
+            "r" => Some(KEY_R),
+            "s" => Some(KEY_S),
+            "t" => Some(KEY_T),
+            "u" => Some(KEY_U),
+            "v" => Some(KEY_V),
+            "w" => Some(KEY_W),
+            "x" => Some(KEY_X),
+            "y" => Some(KEY_Y),
+            "z" => Some(KEY_Z),
+            "1" => Some(KEY_1),
+            "2" => Some(KEY_2),
+            "3" => Some(KEY_3),
+            "4" => Some(KEY_4),
+            "5" => Some(KEY_5),
+            "6" => Some(KEY_6),
+            "7" => Some(KEY_7),
+            "8" => Some(KEY_8),
+            "9" => Some(KEY_9),
+            "0" => Some(KEY_0),
+            "enter" | "return" => Some(KEY_ENTER),
+            "esc" | "escape" => Some(KEY_ESC),
+            "backspace" => Some(KEY_BACKSPACE),
+            "delete" | "del" => Some(KEY_DELETE),
+            "tab" => Some(KEY_TAB),
+            "space" => Some(KEY_SPACE),
+            "f1" => Some(KEY_F1),
+            "f2" => Some(KEY_F2),
+            "f3" => Some(KEY_F3),
+            "f4" => Some(KEY_F4),
+            "f5" => Some(KEY_F5),
+            "f6" => Some(KEY_F6),
+            "f7" => Some(KEY_F7),
+            "f8" => Some(KEY_F8),
+            "f9" => Some(KEY_F9),
+            "f10" => Some(KEY_F10),
+            "f11" => Some(KEY_F11),
+            "f12" => Some(KEY_F12),
+            "left" => Some(KEY_LEFT),
+            "right" => Some(KEY_RIGHT),
+            "up" => Some(KEY_UP),
+            "down" => Some(KEY_DOWN),
+            // Punctuation and special keys
+            "minus" | "-" => Some(0x2D),
+            "equal" | "=" => Some(0x2E),
+            "leftbracket" | "[" => Some(0x2F),
+            "rightbracket" | "]" => Some(0x30),
+            "backslash" | "\\" => Some(0x31),
+            "semicolon" | ";" => Some(0x33),
+            "quote" | "'" => Some(0x34),
+            "grave" | "`" => Some(0x35),
+            "comma" | "," => Some(0x36),
+            "period" | "." => Some(0x37),
+            "slash" | "/" => Some(0x38),
+            "capslock" => Some(0x39),
+            _ => None,
+        }
+    }
+}
+
+/// Device-node access on Linux: the udev rule savant ships and the sysfs/procfs lookups
+/// `doctor` uses to explain permission problems. Paths are parameters so tests can use
+/// fixture trees.
+mod linux_access {
+    use super::{KINESIS_VID, PROGRAMMING_PID, SAVANT_ELITE_PID};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    pub const RULES_FILE_NAME: &str = "70-savant-elite.rules";
+
+    /// Where udev reads rules from, highest priority first
+    pub const RULES_DIRS: &[&str] = &[
+        "/etc/udev/rules.d",
+        "/run/udev/rules.d",
+        "/usr/lib/udev/rules.d",
+        "/lib/udev/rules.d",
+    ];
+
+    /// Group the generated rule grants access to unless `--group` says otherwise
+    pub const DEFAULT_GROUP: &str = "plugdev";
+
+    /// Groups that commonly grant access to USB and input devices
+    pub const ACCESS_GROUPS: &[&str] = &[DEFAULT_GROUP, "input"];
+
+    /// udev rule giving `group` (and, through `uaccess`, whoever is logged in at the seat)
+    /// access to the usbfs and hidraw nodes of the pedal in both modes
+    pub fn udev_rules(group: &str) -> String {
+        let mut rules = format!(
+            "# Kinesis Savant Elite foot pedal, generated by `savant udev-rules`\n\
+             # Install with:\n\
+             #   savant udev-rules | sudo tee /etc/udev/rules.d/{}\n\
+             #   sudo udevadm control --reload-rules && sudo udevadm trigger\n",
+            RULES_FILE_NAME
+        );
+        for (pid, mode) in [(SAVANT_ELITE_PID, "play"), (PROGRAMMING_PID, "programming")] {
+            rules.push_str(&format!("\n# {} mode\n", mode));
+            for subsystem in ["usb", "hidraw"] {
+                rules.push_str(&format!(
+                    "SUBSYSTEM==\"{}\", ATTRS{{idVendor}}==\"{:04x}\", ATTRS{{idProduct}}==\"{:04x}\", MODE=\"0660\", GROUP=\"{}\", TAG+=\"uaccess\"\n",
+                    subsystem, KINESIS_VID, pid, group
+                ));
+            }
+        }
+        rules
+    }
+
+    /// Active (uncommented) rule lines matching the Kinesis vendor ID and one of the pedal's
+    /// product IDs. Rules for other Kinesis devices, such as keyboards, don't count.
+    fn pedal_rule_lines(content: &str) -> impl Iterator<Item = &str> {
+        let vendor = format!("{{idvendor}}==\"{:04x}\"", KINESIS_VID);
+        let products = [SAVANT_ELITE_PID, PROGRAMMING_PID]
+            .map(|pid| format!("{{idproduct}}==\"{:04x}\"", pid));
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter(move |line| {
+                let line = line.to_lowercase();
+                line.contains(&vendor) && products.iter().any(|product| line.contains(product))
+            })
+    }
+
+    /// Whether a rules file has an active line for the pedal
+    pub fn rules_match_pedal(content: &str) -> bool {
+        pedal_rule_lines(content).next().is_some()
+    }
+
+    /// The `GROUP` the first pedal rule in a rules file assigns, if any
+    pub fn rule_group(content: &str) -> Option<String> {
+        pedal_rule_lines(content).find_map(|line| {
+            line.split(',').find_map(|key| {
+                let value = key.trim().strip_prefix("GROUP")?;
+                let value = value
+                    .strip_prefix(":=")
+                    .or_else(|| value.strip_prefix('='))?;
+                let group = value.trim().trim_matches('"');
+                (!group.is_empty()).then(|| group.to_string())
+            })
+        })
+    }
+
+    /// The first rules file in `dirs` that matches the pedal
+    pub fn find_installed_rule(dirs: &[PathBuf]) -> Option<PathBuf> {
+        dirs.iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flat_map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rules"))
+            .find(|path| fs::read_to_string(path).is_ok_and(|content| rules_match_pedal(&content)))
+    }
+
+    /// Whether a vendor/product pair is the pedal in either mode. The vendor ID alone isn't
+    /// enough: it is shared by every P.I. Engineering X-keys device.
+    pub fn is_pedal(vendor: u16, product: u16) -> bool {
+        vendor == KINESIS_VID && (product == SAVANT_ELITE_PID || product == PROGRAMMING_PID)
+    }
+
+    /// Vendor and product ID from the `HID_ID=BUS:VENDOR:PRODUCT` line of a hidraw uevent
+    pub fn parse_hid_id(uevent: &str) -> Option<(u16, u16)> {
+        let id = uevent
+            .lines()
+            .find_map(|line| line.strip_prefix("HID_ID="))?;
+        let mut parts = id.split(':').skip(1);
+        let vendor = u32::from_str_radix(parts.next()?, 16).ok()?;
+        let product = u32::from_str_radix(parts.next()?, 16).ok()?;
+        Some((u16::try_from(vendor).ok()?, u16::try_from(product).ok()?))
+    }
+
+    /// `/dev/hidrawN` nodes that belong to a Kinesis pedal, from `/sys/class/hidraw`
+    pub fn hidraw_nodes(sys_class_hidraw: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(sys_class_hidraw) else {
+            return Vec::new();
+        };
+        let mut nodes: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .filter(|entry| {
+                fs::read_to_string(entry.path().join("device/uevent"))
+                    .ok()
+                    .and_then(|uevent| parse_hid_id(&uevent))
+                    .is_some_and(|(vendor, product)| is_pedal(vendor, product))
+            })
+            .map(|entry| Path::new("/dev").join(entry.file_name()))
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// A pedal interface as seen in `/sys/bus/usb/devices`
+    pub struct InterfaceDriver {
+        /// sysfs name, e.g. `1-2:1.0`
+        pub interface: String,
+        pub product_id: u16,
+        pub driver: Option<String>,
+    }
+
+    /// Kernel drivers bound to each interface of every connected pedal
+    pub fn interface_drivers(sys_bus_usb_devices: &Path) -> Vec<InterfaceDriver> {
+        let read_hex = |path: PathBuf| {
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|value| u16::from_str_radix(value.trim(), 16).ok())
+        };
+        let Ok(entries) = fs::read_dir(sys_bus_usb_devices) else {
+            return Vec::new();
+        };
+        let mut drivers = Vec::new();
+        for entry in entries.filter_map(|e| e.ok()) {
+            let device = entry.path();
+            let (Some(vendor_id), Some(product_id)) = (
+                read_hex(device.join("idVendor")),
+                read_hex(device.join("idProduct")),
+            ) else {
+                continue;
+            };
+            if !is_pedal(vendor_id, product_id) {
+                continue;
+            }
+            let device_name = entry.file_name().to_string_lossy().to_string();
+            let Ok(children) = fs::read_dir(&device) else {
+                continue;
+            };
+            for child in children.filter_map(|e| e.ok()) {
+                let interface = child.file_name().to_string_lossy().to_string();
+                // Interfaces are named `<device>:<config>.<interface>`
+                if !interface.starts_with(&format!("{}:", device_name)) {
+                    continue;
+                }
+                let driver = fs::read_link(child.path().join("driver"))
+                    .ok()
+                    .and_then(|target| target.file_name().map(|n| n.to_string_lossy().to_string()));
+                drivers.push(InterfaceDriver {
+                    interface,
+                    product_id,
+                    driver,
+                });
+            }
+        }
+        drivers.sort_by(|a, b| a.interface.cmp(&b.interface));
+        drivers
+    }
+
+    /// Real user ID from the `Uid:` line of `/proc/self/status`
+    pub fn process_uid(status: &str) -> Option<u32> {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("Uid:"))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    }
+
+    /// Supplementary group IDs from the `Groups:` line of `/proc/self/status`
+    pub fn process_groups(status: &str) -> Vec<u32> {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("Groups:"))
+            .map(|ids| {
+                ids.split_whitespace()
+                    .filter_map(|id| id.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Names of the groups in `gids`, looked up in an `/etc/group`-format file
+    pub fn group_names(gids: &[u32], group_file: &str) -> Vec<String> {
+        group_file
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let gid: u32 = fields.nth(1)?.parse().ok()?;
+                gids.contains(&gid).then(|| name.to_string())
+            })
+            .collect()
+    }
+}
+
+/// Just enough of the ustar format to write the `doctor --bundle` archive: regular files
+/// only, no compression. Readable by `tar` on macOS and Linux.
+mod tar_archive {
+    const BLOCK: usize = 512;
+
+    use anyhow::{anyhow, Result};
+
+    /// Append one regular file. `path` uses `/` separators and must fit the ustar
+    /// name/prefix fields (100 + 155 bytes); longer paths are an error.
+    pub fn append(archive: &mut Vec<u8>, path: &str, data: &[u8], mtime: u64) -> Result<()> {
+        let mut header = [0u8; BLOCK];
+        let (prefix, name) = split_path(path)
+            .ok_or_else(|| anyhow!("'{}' is too long for a tar archive entry", path))?;
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        octal(&mut header[100..108], 0o644);
+        octal(&mut header[108..116], 0);
+        octal(&mut header[116..124], 0);
+        octal(&mut header[124..136], data.len() as u64);
+        octal(&mut header[136..148], mtime);
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+        // The checksum is computed with its own field filled with spaces
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len().next_multiple_of(BLOCK), 0);
+        Ok(())
+    }
+
+    /// Two empty blocks mark the end of the archive
+    pub fn finish(archive: &mut Vec<u8>) {
+        archive.resize(archive.len() + 2 * BLOCK, 0);
+    }
+
+    /// Zero-padded octal with a trailing NUL, filling `field`
+    fn octal(field: &mut [u8], value: u64) {
+        let digits = field.len() - 1;
+        field[..digits].copy_from_slice(format!("{:0width$o}", value, width = digits).as_bytes());
+        field[digits] = 0;
+    }
+
+    /// Split a long path at a `/` so the tail fits the 100-byte name field and the head
+    /// the 155-byte prefix field. `None` if there's no such `/`.
+    fn split_path(path: &str) -> Option<(&str, &str)> {
+        if path.len() <= 100 {
+            return Some(("", path));
+        }
+        path.char_indices()
+            .filter(|&(i, c)| c == '/' && i <= 155 && path.len() - i - 1 <= 100)
+            .map(|(i, _)| (&path[..i], &path[i + 1..]))
+            .next()
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "savant")]
+#[command(version)]
+#[command(about = "Kinesis Savant Elite foot pedal programmer for macOS")]
+#[command(
+    long_about = "Native macOS programmer for the discontinued Kinesis Savant Elite USB foot pedal.\n\nProgram your foot pedals directly via USB—no Windows VM, no 32-bit compatibility hacks."
+)]
+struct Cli {
+    /// Enable verbose output for debugging
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Output in JSON format for scripting
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// USB operation timeout in milliseconds [default: 500]
+    #[arg(long, global = true, value_name = "MS", value_parser = clap::value_parser!(u64).range(100..=600000))]
+    timeout: Option<u64>,
+
+    /// Extra attempts for a USB transfer that fails with a retryable error; 0 disables retries
+    /// [default: 2]
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(0..=10))]
+    retries: Option<u32>,
+
+    /// Delay before the first retry, doubled for each later one [default: 20]
+    #[arg(long, global = true, value_name = "MS", value_parser = clap::value_parser!(u64).range(0..=10000))]
+    retry_backoff: Option<u64>,
+
+    /// Error kind worth retrying; repeatable, replaces the default set
+    /// [default: timeout, busy, io, interrupted]
+    #[arg(long, global = true, value_enum, value_name = "KIND")]
+    retry_on: Vec<RetryKind>,
+
+    /// Only operate on the pedal with this X-keys unit ID (see `savant unit-id`)
+    #[arg(long, global = true, value_name = "ID")]
+    unit: Option<u8>,
+
+    /// Don't report (or block on) this shortcut-lint check; repeatable
+    #[arg(long, global = true, value_enum, value_name = "CHECK")]
+    allow: Vec<LintCode>,
+
+    /// Platform the pedal is used on; decides what `primary`/`secondary` mean and which
+    /// shortcuts are linted. Remembered by `program` [default: saved setting, else this OS]
+    #[arg(long, global = true, value_enum, value_name = "OS")]
+    target_os: Option<TargetOs>,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Detect and show info about connected Savant Elite pedals
+    Info,
+
+    /// Monitor foot pedal input in real-time (requires Input Monitoring permission)
+    Monitor {
+        /// Duration in seconds (0 = infinite)
+        #[arg(short, long, default_value = "30")]
+        duration: u64,
+    },
+
+    /// Program the pedals (requires device in programming mode)
+    ///
+    /// Only the pedals you name are written; the others keep their current keys. With no
+    /// pedal options at all, every pedal gets the primary+c / primary+a / primary+v defaults
+    /// (copy / select all / paste; `primary` is Cmd on macOS and Ctrl elsewhere).
+    Program {
+        /// Left pedal action (e.g., "cmd+c" for copy)
+        #[arg(long)]
+        left: Option<String>,
+
+        /// Middle pedal action (e.g., "cmd+a" for select all)
+        #[arg(long)]
+        middle: Option<String>,
+
+        /// Right pedal action (e.g., "cmd+v" for paste)
+        #[arg(long)]
+        right: Option<String>,
+
+        /// Only write these pedals (repeatable); ones without an action use the saved config
+        #[arg(long, value_enum, value_name = "PEDAL")]
+        only: Vec<PedalPosition>,
+
+        /// Dry run - don't actually write to device
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Start monitor mode after programming to test the pedals
+        #[arg(long, short = 'm')]
+        monitor: bool,
+
+        /// Reboot the pedal after saving and read it back to confirm the EEPROM write
+        #[arg(long)]
+        reboot: bool,
+
+        /// After Ctrl+C, save the pedals written so far to EEPROM, skip the save, or ask
+        #[arg(long, value_enum, value_name = "ACTION", default_value = "ask")]
+        on_interrupt: OnInterrupt,
+
+        /// USB interface to claim and send reports to [default: 0; interrupt writes try every
+        /// HID interface]
+        #[arg(long, value_name = "N")]
+        interface: Option<u8>,
+
+        /// Pipe for the X-keys reports: control SET_REPORT, interrupt OUT writes, or both
+        #[arg(long, value_enum, value_name = "TRANSPORT", default_value = "auto")]
+        transport: Transport,
+
+        /// Write every pedal and save to EEPROM even if the pedal already holds these keys
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Check if device is in programming mode
+    Status,
+
+    /// Probe device for programming protocol (reverse engineering)
+    Probe,
+
+    /// Send raw HID command to device (expert mode)
+    RawCmd {
+        /// Command byte (hex, e.g., "b5" for generate data)
+        #[arg(long)]
+        cmd: String,
+
+        /// Additional data bytes (hex, e.g., "00010203")
+        #[arg(long, default_value = "")]
+        data: String,
+
+        /// Interface number (0=keyboard, 1=mouse)
+        #[arg(long, default_value = "0", value_parser = clap::value_parser!(i32).range(0..=255))]
+        interface: i32,
+    },
+
+    /// List all valid key names and modifiers
+    Keys {
+        /// Output in JSON format for scripting
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print a udev rule that gives your user access to the pedal on Linux
+    ///
+    /// Covers both the play-mode (0x030C) and programming-mode (0x0232) product IDs, for
+    /// the usbfs and hidraw device nodes.
+    UdevRules {
+        /// Group given read/write access to the device nodes
+        #[arg(long, default_value = linux_access::DEFAULT_GROUP)]
+        group: String,
+
+        /// Write the rule to FILE instead of stdout
+        #[arg(long, short = 'o', value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Generate shell completion scripts
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Apply a preset configuration (built-in or from the user/system presets directories)
+    #[command(args_conflicts_with_subcommands = true)]
+    Preset {
+        #[command(subcommand)]
+        command: Option<PresetCommand>,
+
+        /// Name of the preset to apply (e.g., "copy-paste", "undo-redo", "browser", "zoom")
+        #[arg(value_name = "NAME")]
+        name: Option<String>,
+
+        /// List all available presets
+        #[arg(long, short = 'l')]
+        list: bool,
+
+        /// Show details of a specific preset without applying it
+        #[arg(long)]
+        show: bool,
+
+        /// Dry run - show what would be programmed without writing to device
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write every pedal and save to EEPROM even if the pedal already holds these keys
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Manage saved pedal configuration profiles
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Run system diagnostics to identify configuration issues
+    Doctor {
+        /// Repair what can be repaired safely: missing directories, a broken or legacy
+        /// pedals.conf (the original is backed up), unreadable history entries, and the
+        /// udev rule (written to the config directory for you to install)
+        #[arg(long)]
+        fix: bool,
+
+        /// With --fix, list the changes without making them
+        #[arg(long, requires = "fix")]
+        dry_run: bool,
+
+        /// Collect diagnostics, USB/HID enumeration, probe results and config files into a
+        /// support bundle (a .tar archive, or a directory for any other path)
+        #[arg(long, value_name = "PATH")]
+        bundle: Option<String>,
+    },
+
+    /// Read or assign the X-keys unit ID used to tell identical pedals apart
+    UnitId {
+        #[command(subcommand)]
+        command: UnitIdCommand,
+    },
+
+    /// Restore the pedals to their factory defaults (requires device in programming mode)
+    Reset {
+        /// Program the Kinesis factory defaults: Ctrl+Alt+4 / Ctrl+Alt+5 / Ctrl+Alt+6
+        #[arg(long, required = true)]
+        factory: bool,
+
+        /// Dry run - show what would be programmed without writing to device
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Reprogram the configuration that was in place before the last change
+    Undo {
+        /// Dry run - show what would be programmed without writing to device
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Reprogram the configuration replaced by the last `savant undo`
+    Redo {
+        /// Dry run - show what would be programmed without writing to device
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Switch between play and programming mode without the paperclip (experimental)
+    Mode {
+        /// Mode to switch the pedal into
+        #[arg(value_enum)]
+        mode: PedalMode,
+
+        /// Acknowledge that this sends undocumented PID-switch and reboot commands
+        #[arg(long)]
+        experimental: bool,
+
+        /// Don't ask for confirmation before sending the commands (for scripts)
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Show the operation log: every command, device found, USB transfer and EEPROM save
+    Log {
+        /// Only show the last N records
+        #[arg(long, short = 'n', value_name = "N")]
+        tail: Option<usize>,
+    },
+}
+
+/// One of the three pedals, as selected by `program --only`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PedalPosition {
+    Left,
+    Middle,
+    Right,
+}
+
+impl PedalPosition {
+    const ALL: [Self; 3] = [Self::Left, Self::Middle, Self::Right];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    /// Pedal index as used by SET_KEY_MACRO / GET_KEY_MACRO
+    fn index(self) -> usize {
+        let idx = match self {
+            Self::Left => xkeys_protocol::PEDAL_LEFT,
+            Self::Middle => xkeys_protocol::PEDAL_MIDDLE,
+            Self::Right => xkeys_protocol::PEDAL_RIGHT,
+        };
+        idx as usize
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Middle => "middle",
+            Self::Right => "right",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Left => "Left",
+            Self::Middle => "Middle",
+            Self::Right => "Right",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Self::Left => "#e74c3c",
+            Self::Middle => "#f39c12",
+            Self::Right => "#2ecc71",
+        }
+    }
+
+    fn table_label(self) -> &'static str {
+        match self {
+            Self::Left => "[bold #e74c3c]◀ LEFT[/]",
+            Self::Middle => "[bold #f39c12]● MIDDLE[/]",
+            Self::Right => "[bold #2ecc71]▶ RIGHT[/]",
+        }
+    }
+}
+
+/// Actions programmed by a bare `savant program` (copy / select all / paste)
+const DEFAULT_PEDAL_ACTIONS: [&str; 3] = ["primary+c", "primary+a", "primary+v"];
+
+/// Work out which pedals `program` should write from the command line.
+///
+/// Explicit `--left/--middle/--right` values always win. `--only` restricts the write to the
+/// listed pedals, filling any without an explicit action from the saved config. Without
+/// `--only`, just the pedals given explicitly are written; with nothing at all, every pedal
+/// gets [`DEFAULT_PEDAL_ACTIONS`]. `None` means "leave this pedal untouched".
+fn resolve_program_pedals(
+    explicit: [Option<String>; 3],
+    only: &[PedalPosition],
+    saved: Option<&PedalConfig>,
+) -> Result<[Option<String>; 3]> {
+    if only.is_empty() {
+        if explicit.iter().all(Option::is_none) {
+            return Ok(DEFAULT_PEDAL_ACTIONS.map(|a| Some(a.to_string())));
+        }
+        return Ok(explicit);
+    }
+
+    for position in PedalPosition::ALL {
+        if explicit[position.index()].is_some() && !only.contains(&position) {
+            return Err(anyhow!(
+                "--{} was given but the {} pedal is not selected by --only",
+                position.name(),
+                position.name()
+            ));
+        }
+    }
+
+    let mut resolved: [Option<String>; 3] = [None, None, None];
+    for (position, value) in PedalPosition::ALL.into_iter().zip(explicit) {
+        resolved[position.index()] = match (value, only.contains(&position)) {
+            (Some(value), _) => Some(value),
+            (None, true) => Some(saved.map(|c| c.get(position).to_string()).ok_or_else(|| {
+                anyhow!(
+                    "No action for the {} pedal: pass --{} or save a configuration first",
+                    position.name(),
+                    position.name()
+                )
+            })?),
+            (None, false) => None,
+        };
+    }
+    Ok(resolved)
+}
+
+/// Operating mode of the pedal, normally selected with the recessed switch underneath
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PedalMode {
+    /// Normal keyboard operation (PID 0x030C)
+    Play,
+    /// Programming mode (PID 0x0232)
+    Program,
+}
+
+impl PedalMode {
+    fn from_pid(pid: u16) -> Option<Self> {
+        match pid {
+            SAVANT_ELITE_PID => Some(Self::Play),
+            PROGRAMMING_PID => Some(Self::Program),
+            _ => None,
+        }
+    }
+
+    fn pid(self) -> u16 {
+        match self {
+            Self::Play => SAVANT_ELITE_PID,
+            Self::Program => PROGRAMMING_PID,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Play => "play",
+            Self::Program => "program",
+        }
+    }
+}
+
+/// Subcommands for the preset command
+#[derive(Subcommand)]
+enum PresetCommand {
+    /// Validate preset files in the user and system preset directories
+    Check {
+        /// Path to a preset file (defaults to every installed preset file)
+        #[arg(value_name = "FILE")]
+        file: Option<String>,
+    },
+}
+
+/// Subcommands for the unit-id command
+#[derive(Subcommand)]
+enum UnitIdCommand {
+    /// Show the unit ID of every connected pedal
+    Get,
+
+    /// Assign a unit ID to the pedal (requires device in programming mode)
+    Set {
+        /// Unit ID to store on the device (0-255)
+        id: u8,
+    },
+}
+
+/// Subcommands for the config command
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Save current configuration as a named profile
+    Save {
+        /// Name for the profile (alphanumeric, hyphens, underscores)
+        name: String,
+
+        /// Overwrite existing profile without prompting
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+
+    /// Load and apply a saved profile (programs device)
+    Load {
+        /// Name of the profile to load
+        name: String,
+
+        /// Preview without programming device
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write every pedal and save to EEPROM even if the pedal already holds these keys
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// List all saved profiles
+    List,
+
+    /// Show contents of a saved profile
+    Show {
+        /// Name of the profile to show
+        name: String,
+    },
+
+    /// Delete a saved profile
+    Delete {
+        /// Name of the profile to delete
+        name: String,
+
+        /// Delete without confirmation
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+
+    /// Validate a configuration file
+    Check {
+        /// Path to config file (defaults to current config)
+        #[arg(value_name = "FILE")]
+        file: Option<String>,
+    },
+
+    /// Show configuration history (automatic backups)
+    History,
+
+    /// Restore a previous configuration from history
+    Restore {
+        /// Backup number to restore (1 = most recent)
+        number: usize,
+
+        /// Program device immediately after restore
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Compare two configurations pedal by pedal
+    ///
+    /// Each side is `current` (the live config), a history number (`3` or `history:3`),
+    /// or a profile name (`work` or `profile:work`).
+    Diff {
+        /// First configuration
+        a: String,
+
+        /// Second configuration
+        b: String,
+    },
+
+    /// Create a profile without programming the device first
+    New {
+        /// Name for the profile (alphanumeric, hyphens, underscores)
+        name: String,
+
+        /// Key action for left pedal
+        #[arg(long, short = 'l')]
+        left: String,
+
+        /// Key action for middle pedal
+        #[arg(long, short = 'm')]
+        middle: String,
+
+        /// Key action for right pedal
+        #[arg(long, short = 'r')]
+        right: String,
+
+        /// Overwrite an existing profile
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+
+    /// Change one pedal of a saved profile
+    Set {
+        /// Name of the profile to change
+        name: String,
+
+        /// Pedal to change
+        #[arg(value_enum)]
+        pedal: PedalPosition,
+
+        /// New key action (e.g., "cmd+z")
+        action: String,
+    },
+
+    /// Copy a profile under a new name
+    Copy {
+        /// Profile to copy
+        from: String,
+
+        /// Name for the copy
+        to: String,
+
+        /// Overwrite an existing profile
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+
+    /// Rename a saved profile
+    Rename {
+        /// Current profile name
+        from: String,
+
+        /// New profile name
+        to: String,
+
+        /// Overwrite an existing profile
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+
+    /// Edit a profile in $VISUAL / $EDITOR, re-validating until it is valid
+    Edit {
+        /// Name of the profile to edit
+        name: String,
+    },
+
+    /// Export profiles to a bundle file for use on another machine
+    Export {
+        /// Profiles to export (defaults to every profile)
+        #[arg(value_name = "NAME")]
+        names: Vec<String>,
+
+        /// Write the bundle to FILE instead of stdout
+        #[arg(long, short = 'o', value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Import profiles from a bundle created by `config export`
+    Import {
+        /// Bundle file to import
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Import clashing profiles under a new name (NAME-2, NAME-3, ...)
+        #[arg(long, conflicts_with_all = ["overwrite", "skip"])]
+        rename: bool,
+
+        /// Replace existing profiles with the bundled ones
+        #[arg(long, conflicts_with = "skip")]
+        overwrite: bool,
+
+        /// Keep existing profiles and leave the bundled ones out
+        #[arg(long)]
+        skip: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct KeyAction {
+    modifiers: u8,
+    key: u8,
+}
+
+/// Modifiers that resolve per target OS (see [`TargetOs::primary_modifier`])
+const PORTABLE_MODIFIER_NAMES: &[&str] = &["primary", "secondary"];
+
+impl KeyAction {
+    /// Parse for the OS savant is running on. Whether an action is valid doesn't depend on the
+    /// OS, so this is enough for validation; use [`KeyAction::parse_for`] to get the bytes to send.
+    fn from_string(s: &str) -> Result<Self, KeyActionError> {
+        Self::parse_for(s, TargetOs::current())
+    }
+
+    /// Parse an action, resolving `primary` and `secondary` for `os`
+    fn parse_for(s: &str, os: TargetOs) -> Result<Self, KeyActionError> {
+        let modifier = |name: &str| match name {
+            "primary" => Some(os.primary_modifier()),
+            "secondary" => Some(os.secondary_modifier()),
+            _ => usb_hid::parse_modifier_name(name),
+        };
+
+        // Validate input is not empty or whitespace-only
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(KeyActionError::new(
+                s,
+                0..0,
+                "Key action cannot be empty".to_string(),
+            ));
+        }
+        // Byte offset of `trimmed` in `s`, so errors point into the string as given
+        let base = s.len() - s.trim_start().len();
+
+        // Validate no leading or trailing '+' (would produce empty parts)
+        if trimmed.starts_with('+') || trimmed.ends_with('+') {
+            let at = if trimmed.starts_with('+') {
+                base
+            } else {
+                base + trimmed.len() - 1
+            };
+            return Err(KeyActionError::new(
+                s,
+                at..at + 1,
+                format!("Key action cannot start or end with '+': \"{}\"", trimmed),
+            ));
+        }
+
+        // Validate no consecutive '+' characters (e.g., "cmd++c")
+        if let Some(at) = trimmed.find("++") {
+            let at = base + at + 1;
+            return Err(KeyActionError::new(
+                s,
+                at..at + 1,
+                format!(
+                    "Key action contains empty modifier (consecutive '+'): \"{}\"",
+                    trimmed
+                ),
+            ));
+        }
+
+        let parts: Vec<&str> = trimmed.split('+').collect();
+        let mut modifiers = 0u8;
+        let mut key = 0u8;
+        let mut offset = base;
+
+        for (i, raw) in parts.iter().enumerate() {
+            let raw_range = offset..offset + raw.len();
+            offset += raw.len() + 1;
+            let part = raw.trim();
+            if part.is_empty() {
+                // Extra safety check for whitespace-only parts like "cmd + + c"
+                return Err(KeyActionError::new(
+                    s,
+                    raw_range,
+                    format!("Key action contains empty component: \"{}\"", trimmed),
+                ));
+            }
+            let part_start = raw_range.start + raw.len() - raw.trim_start().len();
+            let range = part_start..part_start + part.len();
+            let part = part.to_lowercase();
+
+            if i == parts.len() - 1 {
+                // Last part is the key
+                match usb_hid::parse_key_name(&part) {
+                    Some(code) => key = code,
+                    None if modifier(&part).is_some() => {
+                        return Err(KeyActionError::new(
+                            s,
+                            range,
+                            format!(
+                                "Unknown key: \"{}\" is a modifier and needs a key after it, e.g. \"{}+a\"",
+                                part, part
+                            ),
+                        ));
+                    }
+                    None => {
+                        return Err(KeyActionError::new(
+                            s,
+                            range,
+                            format!("Unknown key: \"{}\"", part),
+                        )
+                        .suggest(&part, usb_hid::KEY_NAMES));
+                    }
+                }
+            } else {
+                // Modifier
+                match modifier(&part) {
+                    Some(bit) => modifiers |= bit,
+                    None if usb_hid::parse_key_name(&part).is_some() => {
+                        return Err(KeyActionError::new(
+                            s,
+                            range,
+                            format!(
+                                "\"{}\" is a key, not a modifier; the key must come last",
+                                part
+                            ),
+                        ));
+                    }
+                    None => {
+                        return Err(KeyActionError::new(
+                            s,
+                            range,
+                            format!("Unknown modifier: \"{}\"", part),
+                        )
+                        .suggest(
+                            &part,
+                            &[usb_hid::MODIFIER_NAMES, PORTABLE_MODIFIER_NAMES].concat(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(Self { modifiers, key })
+    }
+}
+
+/// A key-action parse error that knows which component of the input it is about
+#[derive(Debug)]
+struct KeyActionError {
+    input: String,
+    /// Character offset of the offending component in `input`
+    column: usize,
+    /// Width of the offending component in characters (0 when there is nothing to point at)
+    width: usize,
+    message: String,
+    suggestion: Option<&'static str>,
+}
+
+impl KeyActionError {
+    /// `range` is a byte range into `input`
+    fn new(input: &str, range: std::ops::Range<usize>, message: String) -> Self {
+        Self {
+            input: input.to_string(),
+            column: input[..range.start].chars().count(),
+            width: input[range].chars().count(),
+            message,
+            suggestion: None,
+        }
+    }
+
+    fn suggest(mut self, word: &str, candidates: &[&'static str]) -> Self {
+        self.suggestion = closest_name(word, candidates);
+        self
+    }
+
+    /// The message and suggestion on one line, without the caret
+    fn summary(&self) -> String {
+        match self.suggestion {
+            Some(suggestion) => format!("{} (did you mean \"{}\"?)", self.message, suggestion),
+            None => self.message.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for KeyActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.summary())?;
+        if self.width > 0 {
+            write!(
+                f,
+                "\n  {}\n  {}{}",
+                self.input,
+                " ".repeat(self.column),
+                "^".repeat(self.width)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for KeyActionError {}
+
+/// The candidate closest to `word` by edit distance (counting a swap of neighbouring letters as
+/// one edit), if it is close enough to be a plausible typo
+fn closest_name(word: &str, candidates: &[&'static str]) -> Option<&'static str> {
+    let word: Vec<char> = word.chars().collect();
+    let max_distance = if word.len() <= 4 { 1 } else { 2 };
+    candidates
+        .iter()
+        .map(|candidate| {
+            let candidate_chars: Vec<char> = candidate.chars().collect();
+            (edit_distance(&word, &candidate_chars), *candidate)
+        })
+        .filter(|&(distance, _)| distance <= max_distance && distance < word.len())
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Optimal string alignment distance between two words
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Operating system the pedal's shortcuts are meant for
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TargetOs {
+    Macos,
+    Linux,
+    Windows,
+}
+
+impl TargetOs {
+    fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            TargetOs::Macos
+        } else if cfg!(target_os = "windows") {
+            TargetOs::Windows
+        } else {
+            TargetOs::Linux
+        }
+    }
+
+    /// What the `primary` modifier means here: Cmd on macOS, Ctrl elsewhere
+    fn primary_modifier(self) -> u8 {
+        match self {
+            TargetOs::Macos => usb_hid::MOD_LEFT_GUI,
+            TargetOs::Linux | TargetOs::Windows => usb_hid::MOD_LEFT_CTRL,
+        }
+    }
+
+    /// What the `secondary` modifier means here: Ctrl on macOS, Win/Super elsewhere
+    fn secondary_modifier(self) -> u8 {
+        match self {
+            TargetOs::Macos => usb_hid::MOD_LEFT_CTRL,
+            TargetOs::Linux | TargetOs::Windows => usb_hid::MOD_LEFT_GUI,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TargetOs::Macos => "macOS",
+            TargetOs::Linux => "Linux",
+            TargetOs::Windows => "Windows",
+        }
+    }
+}
+
+/// Kinds of shortcut-lint finding; also the values accepted by `--allow`
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum)]
+enum LintCode {
+    /// Chords that close, quit or delete things
+    Destructive,
+    /// Chords the operating system intercepts
+    OsReserved,
+    /// Printable keys without a modifier, which fire on every accidental foot rest
+    BareKey,
+    /// The same action on more than one pedal
+    Duplicate,
+    /// Every check
+    All,
+}
+
+impl LintCode {
+    fn name(self) -> &'static str {
+        match self {
+            LintCode::Destructive => "destructive",
+            LintCode::OsReserved => "os-reserved",
+            LintCode::BareKey => "bare-key",
+            LintCode::Duplicate => "duplicate",
+            LintCode::All => "all",
+        }
+    }
+}
+
+/// Errors stop `program` unless allowed; warnings are only reported
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum LintSeverity {
+    Warning,
+    Error,
+}
+
+impl LintSeverity {
+    fn name(self) -> &'static str {
+        match self {
+            LintSeverity::Warning => "warning",
+            LintSeverity::Error => "error",
+        }
+    }
+}
+
+struct LintFinding {
+    code: LintCode,
+    severity: LintSeverity,
+    pedal: PedalPosition,
+    message: String,
+}
+
+/// A chord that is dangerous or reserved on some platforms. Modifiers are left-hand bits;
+/// right-hand modifiers are folded onto them before matching.
+struct LintRule {
+    os: &'static [TargetOs],
+    modifiers: u8,
+    key: u8,
+    code: LintCode,
+    severity: LintSeverity,
+    what: &'static str,
+}
+
+impl LintRule {
+    const fn new(
+        os: &'static [TargetOs],
+        modifiers: u8,
+        key: u8,
+        code: LintCode,
+        severity: LintSeverity,
+        what: &'static str,
+    ) -> Self {
+        Self {
+            os,
+            modifiers,
+            key,
+            code,
+            severity,
+            what,
+        }
+    }
+}
+
+const LINT_CTRL: u8 = usb_hid::MOD_LEFT_CTRL;
+const LINT_SHIFT: u8 = usb_hid::MOD_LEFT_SHIFT;
+const LINT_ALT: u8 = usb_hid::MOD_LEFT_ALT;
+const LINT_GUI: u8 = usb_hid::MOD_LEFT_GUI;
+
+const LINT_RULES: &[LintRule] = {
+    use usb_hid::*;
+    use LintCode::{Destructive, OsReserved};
+    use LintSeverity::{Error, Warning};
+    use TargetOs::{Linux, Macos, Windows};
+    &[
+        // macOS
+        LintRule::new(
+            &[Macos],
+            LINT_GUI,
+            KEY_Q,
+            Destructive,
+            Error,
+            "quits the frontmost application",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_GUI | LINT_SHIFT,
+            KEY_Q,
+            Destructive,
+            Error,
+            "logs you out",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_GUI | LINT_ALT | LINT_SHIFT,
+            KEY_Q,
+            Destructive,
+            Error,
+            "logs you out without confirmation",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_GUI,
+            KEY_W,
+            Destructive,
+            Warning,
+            "closes the current window or tab",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_GUI,
+            KEY_BACKSPACE,
+            Destructive,
+            Warning,
+            "moves the selection to the Trash in Finder",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_CTRL | LINT_GUI,
+            KEY_Q,
+            OsReserved,
+            Warning,
+            "locks the screen",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_GUI | LINT_ALT,
+            KEY_ESC,
+            OsReserved,
+            Warning,
+            "opens the Force Quit dialog",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_GUI,
+            KEY_TAB,
+            OsReserved,
+            Warning,
+            "switches applications",
+        ),
+        LintRule::new(
+            &[Macos],
+            LINT_GUI,
+            KEY_SPACE,
+            OsReserved,
+            Warning,
+            "opens Spotlight",
+        ),
+        // Windows and Linux
+        LintRule::new(
+            &[Windows, Linux],
+            LINT_ALT,
+            KEY_F4,
+            Destructive,
+            Error,
+            "closes the active application",
+        ),
+        LintRule::new(
+            &[Windows, Linux],
+            LINT_CTRL,
+            KEY_W,
+            Destructive,
+            Warning,
+            "closes the current window or tab",
+        ),
+        LintRule::new(
+            &[Windows, Linux],
+            LINT_CTRL | LINT_ALT,
+            KEY_DELETE,
+            OsReserved,
+            Error,
+            "opens the security screen or reboots",
+        ),
+        LintRule::new(
+            &[Windows, Linux],
+            LINT_GUI,
+            KEY_L,
+            OsReserved,
+            Warning,
+            "locks the computer",
+        ),
+        LintRule::new(
+            &[Windows, Linux],
+            LINT_ALT,
+            KEY_TAB,
+            OsReserved,
+            Warning,
+            "switches applications",
+        ),
+        LintRule::new(
+            &[Windows],
+            LINT_CTRL | LINT_SHIFT,
+            KEY_ESC,
+            OsReserved,
+            Warning,
+            "opens Task Manager",
+        ),
+        LintRule::new(
+            &[Windows],
+            LINT_SHIFT,
+            KEY_DELETE,
+            Destructive,
+            Warning,
+            "permanently deletes files in Explorer",
+        ),
+        LintRule::new(
+            &[Linux],
+            LINT_CTRL,
+            KEY_Q,
+            Destructive,
+            Error,
+            "quits the application",
+        ),
+        LintRule::new(
+            &[Linux],
+            LINT_CTRL | LINT_ALT,
+            KEY_BACKSPACE,
+            Destructive,
+            Error,
+            "can kill the graphical session",
+        ),
+    ]
+};
+
+/// Flag destructive or OS-reserved chords for `os`, unmodified printable keys, and the
+/// same action on more than one pedal. `None` pedals are skipped.
+fn lint_pedals(actions: &[Option<KeyAction>; 3], os: TargetOs) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let normalized = |action: &KeyAction| (action.modifiers | action.modifiers >> 4) & 0x0F;
+
+    for position in PedalPosition::ALL {
+        let Some(action) = &actions[position.index()] else {
+            continue;
+        };
+        let modifiers = normalized(action);
+        let mut finding = |code, severity, message: String| {
+            findings.push(LintFinding {
+                code,
+                severity,
+                pedal: position,
+                message,
+            })
+        };
+
+        for rule in LINT_RULES {
+            if rule.os.contains(&os) && rule.modifiers == modifiers && rule.key == action.key {
+                finding(
+                    rule.code,
+                    rule.severity,
+                    format!("{} on {}", rule.what, os.label()),
+                );
+            }
+        }
+        if os == TargetOs::Linux
+            && modifiers == LINT_CTRL | LINT_ALT
+            && (usb_hid::KEY_F1..=usb_hid::KEY_F12).contains(&action.key)
+        {
+            finding(
+                LintCode::OsReserved,
+                LintSeverity::Warning,
+                "switches to a virtual console on Linux".to_string(),
+            );
+        }
+        if modifiers == 0 {
+            match action.key {
+                usb_hid::KEY_BACKSPACE | usb_hid::KEY_DELETE => finding(
+                    LintCode::Destructive,
+                    LintSeverity::Warning,
+                    "deletes text every time the pedal is touched".to_string(),
+                ),
+                // Letters, digits, space and punctuation
+                usb_hid::KEY_A..=usb_hid::KEY_0 | usb_hid::KEY_SPACE..=0x38 => finding(
+                    LintCode::BareKey,
+                    LintSeverity::Warning,
+                    "types a character every time the pedal is touched; add a modifier".to_string(),
+                ),
+                _ => {}
+            }
+        }
+
+        if let Some(other) = PedalPosition::ALL[..position.index()].iter().find(|other| {
+            actions[other.index()]
+                .as_ref()
+                .is_some_and(|o| normalized(o) == modifiers && o.key == action.key)
+        }) {
+            finding(
+                LintCode::Duplicate,
+                LintSeverity::Warning,
+                format!("same action as the {} pedal", other.name()),
+            );
+        }
+    }
+    findings
+}
+
+struct SavantElite {
+    console: Console,
+    verbose: bool,
+    json_output: bool,
+    timeout_ms: u64,
+    unit_filter: Option<u8>,
+    /// Platform chosen with --target-os; see [`SavantElite::target_os`] for the fallbacks
+    target_os: Option<TargetOs>,
+    /// Lint checks silenced with --allow
+    allow: Vec<LintCode>,
+    /// Where device interactions are recorded, see [`OperationLog`]
+    oplog: OperationLog,
+    session: DeviceSession,
+    /// How failed USB transfers are repeated (--retries, --retry-backoff, --retry-on)
+    retry: RetryPolicy,
+    retry_stats: std::cell::Cell<RetryStats>,
+}
+
+struct UsbInterfaceGuard<'a> {
+    handle: &'a rusb::DeviceHandle<GlobalContext>,
+    interface_num: u8,
+    detached_kernel_driver: bool,
+    claimed: bool,
+}
+
+impl Drop for UsbInterfaceGuard<'_> {
+    fn drop(&mut self) {
+        if self.claimed {
+            let _ = self.handle.release_interface(self.interface_num);
+        }
+
+        if self.detached_kernel_driver {
+            // Best-effort: if we detached the kernel driver, try to restore it.
+            let _ = self.handle.attach_kernel_driver(self.interface_num);
+        }
+    }
+}
+
+/// Turns SIGINT/SIGTERM into a flag while `program` writes to the pedal, so an interrupt
+/// lands between transfers and the interface guard still re-attaches the kernel driver. A
+/// second signal exits at once. Outside a guard both signals keep their default action.
+struct InterruptGuard {
+    idle: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    caught: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl InterruptGuard {
+    fn install() -> Self {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        // signal-hook can't restore a default handler, so the handlers are registered once
+        // and switched on and off through `idle`
+        static FLAGS: std::sync::OnceLock<(Arc<AtomicBool>, Arc<AtomicBool>)> =
+            std::sync::OnceLock::new();
+        let (idle, caught) = FLAGS
+            .get_or_init(|| {
+                let idle = Arc::new(AtomicBool::new(true));
+                let caught = Arc::new(AtomicBool::new(false));
+                for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+                    // Order matters: a repeated signal exits, an unguarded one does what it
+                    // normally does, and only then is a guarded one recorded
+                    let _ = signal_hook::flag::register_conditional_shutdown(
+                        signal,
+                        128 + signal,
+                        Arc::clone(&caught),
+                    );
+                    let _ =
+                        signal_hook::flag::register_conditional_default(signal, Arc::clone(&idle));
+                    let _ = signal_hook::flag::register(signal, Arc::clone(&caught));
+                }
+                (idle, caught)
+            })
+            .clone();
+        caught.store(false, Ordering::SeqCst);
+        idle.store(false, Ordering::SeqCst);
+        Self { idle, caught }
+    }
+
+    /// Whether SIGINT or SIGTERM arrived since the guard was installed
+    fn interrupted(&self) -> bool {
+        self.caught.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        self.idle.store(true, std::sync::atomic::Ordering::SeqCst);
+        self.caught
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// USB and HID access shared by everything one savant invocation does. hidapi is
+/// initialised on first use and the libusb device list is cached, so commands that look at
+/// the bus several times (`doctor`, `status`) enumerate it once. Callers that wait for the
+/// bus to change call [`DeviceSession::refresh_usb`] / [`DeviceSession::refresh_hid`].
+#[derive(Default)]
+struct DeviceSession {
+    hid: std::cell::RefCell<Option<HidApi>>,
+    usb: std::cell::RefCell<Option<Vec<Device<GlobalContext>>>>,
+}
+
+impl DeviceSession {
+    /// The shared hidapi context, created on first use
+    fn hid(&self) -> hidapi::HidResult<std::cell::Ref<'_, HidApi>> {
+        if self.hid.borrow().is_none() {
+            *self.hid.borrow_mut() = Some(HidApi::new()?);
+        }
+        Ok(std::cell::Ref::map(self.hid.borrow(), |api| {
+            api.as_ref().expect("initialised above")
+        }))
+    }
+
+    /// Re-read the HID device list (only if hidapi is already initialised)
+    fn refresh_hid(&self) -> hidapi::HidResult<()> {
+        match self.hid.borrow_mut().as_mut() {
+            Some(api) => api.refresh_devices(),
+            None => Ok(()),
+        }
+    }
+
+    /// Every USB device on the bus, from the cached list when there is one
+    fn usb_devices(&self) -> rusb::Result<Vec<Device<GlobalContext>>> {
+        if let Some(devices) = self.usb.borrow().as_ref() {
+            return Ok(devices.clone());
+        }
+        let devices: Vec<_> = rusb::devices()?.iter().collect();
+        *self.usb.borrow_mut() = Some(devices.clone());
+        Ok(devices)
+    }
+
+    /// Drop the cached USB device list so the next lookup re-enumerates the bus
+    fn refresh_usb(&self) {
+        self.usb.borrow_mut().take();
+    }
+
+    /// Start watching `device` for removal. Where libusb supports hotplug, removal is
+    /// reported by a callback; elsewhere [`DeviceSession::still_connected`] re-lists the bus.
+    fn watch(&self, device: &Device<GlobalContext>) -> DisconnectWatch {
+        let gone = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let registration = rusb::has_hotplug()
+            .then(|| {
+                let mut builder = rusb::HotplugBuilder::new();
+                builder.vendor_id(KINESIS_VID);
+                builder
+                    .register(
+                        GlobalContext::default(),
+                        Box::new(DisconnectFlag {
+                            bus_number: device.bus_number(),
+                            address: device.address(),
+                            gone: gone.clone(),
+                        }),
+                    )
+                    .ok()
+            })
+            .flatten();
+        DisconnectWatch {
+            bus_number: device.bus_number(),
+            address: device.address(),
+            gone,
+            registration,
+        }
+    }
+
+    /// Whether the watched device is still on the bus
+    fn still_connected(&self, watch: &DisconnectWatch) -> bool {
+        use rusb::UsbContext;
+        use std::sync::atomic::Ordering;
+
+        if watch.registration.is_some() {
+            // Deliver any pending hotplug callbacks without blocking
+            let _ = GlobalContext::default().handle_events(Some(Duration::ZERO));
+            return !watch.gone.load(Ordering::SeqCst);
+        }
+        self.refresh_usb();
+        self.usb_devices().is_ok_and(|devices| {
+            devices
+                .iter()
+                .any(|d| d.bus_number() == watch.bus_number && d.address() == watch.address)
+        })
+    }
+
+    /// Wait for a device to re-enumerate on the same physical port after a reboot.
+    ///
+    /// A re-enumerated device always gets a fresh address, so a match requires the same bus
+    /// and port chain, the expected PID, and an address different from `old_address`.
+    fn wait_for_reenumeration(
+        &self,
+        bus_number: u8,
+        port_numbers: &[u8],
+        old_address: u8,
+        pid: u16,
+        timeout: Duration,
+    ) -> Option<Device<GlobalContext>> {
+        let deadline = std::time::Instant::now() + timeout;
+        while std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(250));
+            self.refresh_usb();
+            let Ok(devices) = self.usb_devices() else {
+                continue;
+            };
+            let found = devices.into_iter().find(|d| {
+                d.bus_number() == bus_number
+                    && d.address() != old_address
+                    && d.port_numbers().is_ok_and(|p| p == port_numbers)
+                    && d.device_descriptor().is_ok_and(|desc| {
+                        desc.vendor_id() == KINESIS_VID && desc.product_id() == pid
+                    })
+            });
+            if found.is_some() {
+                // Anything cached before the reboot is stale now
+                let _ = self.refresh_hid();
+                return found;
+            }
+        }
+        None
+    }
+}
+
+/// Handle for [`DeviceSession::still_connected`]; unregisters its hotplug callback on drop
+struct DisconnectWatch {
+    bus_number: u8,
+    address: u8,
+    gone: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    registration: Option<rusb::Registration<GlobalContext>>,
+}
+
+/// Hotplug callback that flags when one particular device leaves the bus
+struct DisconnectFlag {
+    bus_number: u8,
+    address: u8,
+    gone: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl rusb::Hotplug<GlobalContext> for DisconnectFlag {
+    fn device_arrived(&mut self, _device: Device<GlobalContext>) {}
+
+    fn device_left(&mut self, device: Device<GlobalContext>) {
+        if device.bus_number() == self.bus_number && device.address() == self.address {
+            self.gone.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+}
+
+/// How long to wait for the pedal to come back after CMD_REBOOT
+const REENUMERATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Per-transfer timeout cap for the unit-ID probe (milliseconds), which is never retried
+const UNIT_ID_PROBE_TIMEOUT_MS: u64 = 100;
+
+/// Format an optional unit ID for tables ("unknown" when the device didn't report one)
+fn format_unit_id(unit_id: Option<u8>) -> String {
+    unit_id.map_or_else(|| "unknown".to_string(), |id| id.to_string())
+}
+
+/// Flags that change how `program` talks to the device
+#[derive(Default, Clone, Copy)]
+struct ProgramOptions<'a> {
+    /// Show what would be sent without touching the device
+    dry_run: bool,
+    /// Wait for play mode and start the monitor afterwards
+    auto_monitor: bool,
+    /// Reboot after SAVE_TO_EEPROM and verify the pedals against the fresh boot
+    reboot: bool,
+    /// Labels and notes to record with the new config (e.g. from a profile). Without them,
+    /// the saved details are kept for every pedal whose action doesn't change.
+    details: Option<&'a [PedalDetails; 3]>,
+    /// Command recorded in history for this change (defaults to "program")
+    command: Option<&'a str>,
+    /// If the pedal is in play mode, wait for it to be switched instead of giving up
+    wait_for_programming: bool,
+    /// What to do about the EEPROM save after Ctrl+C
+    on_interrupt: OnInterrupt,
+    /// Interface to claim and address (defaults to 0)
+    interface: Option<u8>,
+    /// Which USB pipe carries the X-keys reports
+    transport: Transport,
+    /// Write and save even when read-back shows the pedal already matches
+    force: bool,
+}
+
+/// How `program` sends X-keys reports; the values of `--transport`
+#[derive(Clone, Copy, PartialEq, Debug, Default, ValueEnum)]
+enum Transport {
+    /// SET_REPORT and the vendor request first, then interrupt OUT writes on each HID interface
+    #[default]
+    Auto,
+    /// Only control-endpoint SET_REPORT and the vendor request
+    Control,
+    /// Only output reports written to interrupt OUT endpoints
+    Interrupt,
+}
+
+impl Transport {
+    fn name(self) -> &'static str {
+        match self {
+            Transport::Auto => "auto",
+            Transport::Control => "control",
+            Transport::Interrupt => "interrupt",
+        }
+    }
+}
+
+/// An interrupt OUT endpoint on one of the pedal's HID interfaces
+#[derive(Clone, Copy, Debug)]
+struct InterruptOut {
+    interface: u8,
+    address: u8,
+    max_packet_size: u16,
+}
+
+/// Interrupt OUT endpoints on the HID interfaces of `config` (only `interface` if given)
+fn interrupt_out_endpoints(
+    config: &rusb::ConfigDescriptor,
+    interface: Option<u8>,
+) -> Vec<InterruptOut> {
+    config
+        .interfaces()
+        .flat_map(|interface| interface.descriptors())
+        .filter(|desc| {
+            desc.setting_number() == 0 && desc.class_code() == usb_constants::USB_CLASS_HID
+        })
+        .filter(|desc| interface.is_none_or(|wanted| desc.interface_number() == wanted))
+        .flat_map(|desc| {
+            desc.endpoint_descriptors()
+                .filter(|ep| {
+                    ep.direction() == rusb::Direction::Out
+                        && ep.transfer_type() == rusb::TransferType::Interrupt
+                })
+                .map(|ep| InterruptOut {
+                    interface: desc.interface_number(),
+                    address: ep.address(),
+                    max_packet_size: ep.max_packet_size(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The SET_REPORT layouts an X-keys command is tried in, in order, as
+/// `(name, wValue, report)`. Used by `send_xkeys_command` and the unit-ID probe.
+fn xkeys_command_layouts(cmd: u8, payload: &[u8]) -> Vec<(&'static str, u16, Vec<u8>)> {
+    // Short reports are padded to 8 bytes; payloads never exceed a 36-byte report.
+    let payload = &payload[..payload.len().min(34)];
+    let len = (payload.len() + 2).max(8);
+
+    let mut unprefixed = vec![0u8; len];
+    unprefixed[0] = cmd;
+    unprefixed[1..=payload.len()].copy_from_slice(payload);
+
+    let mut prefixed = vec![0u8; len];
+    prefixed[1] = cmd;
+    prefixed[2..payload.len() + 2].copy_from_slice(payload);
+
+    let mut payload_only = vec![0u8; len];
+    payload_only[..payload.len()].copy_from_slice(payload);
+
+    let mut long_prefixed = [0u8; 36];
+    long_prefixed[1] = cmd;
+    long_prefixed[2..payload.len() + 2].copy_from_slice(payload);
+
+    let mut long_unprefixed = [0u8; 36];
+    long_unprefixed[0] = cmd;
+    long_unprefixed[1..=payload.len()].copy_from_slice(payload);
+
+    vec![
+        (
+            "out-rid0-cmd",
+            usb_constants::HID_REPORT_TYPE_OUTPUT,
+            unprefixed.clone(),
+        ),
+        (
+            "out-rid0-prefix",
+            usb_constants::HID_REPORT_TYPE_OUTPUT,
+            prefixed.to_vec(),
+        ),
+        (
+            "out-ridcmd",
+            usb_constants::HID_REPORT_TYPE_OUTPUT | (cmd as u16),
+            unprefixed.clone(),
+        ),
+        (
+            "out-ridcmd-payload",
+            usb_constants::HID_REPORT_TYPE_OUTPUT | (cmd as u16),
+            payload_only.to_vec(),
+        ),
+        (
+            "feat-rid0-cmd",
+            usb_constants::HID_REPORT_TYPE_FEATURE,
+            unprefixed.clone(),
+        ),
+        (
+            "feat-rid0-prefix",
+            usb_constants::HID_REPORT_TYPE_FEATURE,
+            prefixed.to_vec(),
+        ),
+        (
+            "feat-ridcmd",
+            usb_constants::HID_REPORT_TYPE_FEATURE | (cmd as u16),
+            unprefixed.clone(),
+        ),
+        (
+            "feat-ridcmd-payload",
+            usb_constants::HID_REPORT_TYPE_FEATURE | (cmd as u16),
+            payload_only.to_vec(),
+        ),
+        // Longer buffer (36 bytes like PI Engineering)
+        (
+            "36b-out-prefix",
+            usb_constants::HID_REPORT_TYPE_OUTPUT,
+            long_prefixed.to_vec(),
+        ),
+        (
+            "36b-out-cmd",
+            usb_constants::HID_REPORT_TYPE_OUTPUT,
+            long_unprefixed.to_vec(),
+        ),
+        (
+            "36b-feat-prefix",
+            usb_constants::HID_REPORT_TYPE_FEATURE,
+            long_prefixed.to_vec(),
+        ),
+        (
+            "36b-feat-cmd",
+            usb_constants::HID_REPORT_TYPE_FEATURE,
+            long_unprefixed.to_vec(),
+        ),
+    ]
+}
+
+/// An output report for `cmd` as it travels on an interrupt OUT pipe: no report-ID byte (the
+/// host HID stack strips hidapi's leading 0), the command, its payload, then zero padding to
+/// the endpoint's packet size (at least 8 bytes)
+fn interrupt_report(cmd: u8, payload: &[u8], packet_size: usize) -> Vec<u8> {
+    let mut report = vec![0u8; packet_size.max(payload.len() + 1).max(8)];
+    report[0] = cmd;
+    report[1..=payload.len()].copy_from_slice(payload);
+    report
+}
+
+/// What `program` does about the EEPROM save when it is interrupted; the values of
+/// `--on-interrupt`
+#[derive(Clone, Copy, PartialEq, Debug, Default, ValueEnum)]
+enum OnInterrupt {
+    /// Ask on a terminal; skip the save otherwise
+    #[default]
+    Ask,
+    /// Save the pedals written so far to EEPROM
+    Save,
+    /// Leave the EEPROM untouched; the pedals revert to it when unplugged
+    Skip,
+}
+
+/// Default USB operation timeout in milliseconds
+const DEFAULT_USB_TIMEOUT_MS: u64 = 500;
+
+/// `rusb::Error` kinds a USB transfer can be retried on; the values accepted by `--retry-on`
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum)]
+enum RetryKind {
+    /// The transfer timed out
+    Timeout,
+    /// The device stalled the request (also how firmware rejects a wrong report layout)
+    Pipe,
+    /// The device or interface was busy
+    Busy,
+    /// Generic I/O error from the host controller
+    Io,
+    /// The transfer was interrupted by a signal
+    Interrupted,
+    /// The device sent more data than requested
+    Overflow,
+}
+
+impl RetryKind {
+    fn name(self) -> &'static str {
+        match self {
+            RetryKind::Timeout => "timeout",
+            RetryKind::Pipe => "pipe",
+            RetryKind::Busy => "busy",
+            RetryKind::Io => "io",
+            RetryKind::Interrupted => "interrupted",
+            RetryKind::Overflow => "overflow",
+        }
+    }
+
+    fn of(error: &rusb::Error) -> Option<Self> {
+        match error {
+            rusb::Error::Timeout => Some(RetryKind::Timeout),
+            rusb::Error::Pipe => Some(RetryKind::Pipe),
+            rusb::Error::Busy => Some(RetryKind::Busy),
+            rusb::Error::Io => Some(RetryKind::Io),
+            rusb::Error::Interrupted => Some(RetryKind::Interrupted),
+            rusb::Error::Overflow => Some(RetryKind::Overflow),
+            _ => None,
+        }
+    }
+}
+
+/// Default number of extra attempts per USB transfer
+const DEFAULT_USB_RETRIES: u32 = 2;
+
+/// Default delay before the first retry, doubled for each one after it
+const DEFAULT_RETRY_BACKOFF_MS: u64 = 20;
+
+/// How often a failed SET_REPORT, GET_REPORT or EEPROM save is repeated before savant moves
+/// on to the next report layout
+#[derive(Clone, Debug, PartialEq)]
+struct RetryPolicy {
+    /// Extra attempts after the first one
+    retries: u32,
+    /// Delay before the first retry; doubled for each retry after it
+    backoff: Duration,
+    retry_on: Vec<RetryKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_USB_RETRIES,
+            backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+            // Not Pipe: a stall is how the firmware rejects a report layout, so retrying it
+            // would only resend every wrong layout while probing for the right one
+            retry_on: vec![
+                RetryKind::Timeout,
+                RetryKind::Busy,
+                RetryKind::Io,
+                RetryKind::Interrupted,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a transfer that failed with `error` on attempt `attempt` (1-based) should be
+    /// tried again
+    fn should_retry(&self, error: &rusb::Error, attempt: u32) -> bool {
+        attempt <= self.retries && RetryKind::of(error).is_some_and(|k| self.retry_on.contains(&k))
+    }
+
+    /// How long to wait after failed attempt `attempt` (1-based)
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+    }
+
+    fn describe(&self) -> String {
+        if self.retries == 0 || self.retry_on.is_empty() {
+            return "off".to_string();
+        }
+        let kinds: Vec<_> = self.retry_on.iter().map(|k| k.name()).collect();
+        format!(
+            "{} (backoff {}ms, on {})",
+            self.retries,
+            self.backoff.as_millis(),
+            kinds.join(",")
+        )
+    }
+}
+
+/// Retries spent during one command, for the summary and history metadata
+#[derive(Clone, Copy, Default, Debug)]
+struct RetryStats {
+    /// Extra attempts made
+    retries: u32,
+    /// Transfers that failed at first and then succeeded on a retry
+    recovered: u32,
+}
+
+impl SavantElite {
+    fn new(
+        verbose: bool,
+        json_output: bool,
+        timeout_ms: u64,
+        unit_filter: Option<u8>,
+        allow: Vec<LintCode>,
+        target_os: Option<TargetOs>,
+        retry: RetryPolicy,
+    ) -> Result<Self> {
+        Ok(Self {
+            // With --json, stdout carries only JSON; progress from commands that print
+            // while they work (like `program`) goes to stderr
+            console: if json_output {
+                Console::builder().file(Box::new(std::io::stderr())).build()
+            } else {
+                Console::new()
+            },
+            verbose,
+            json_output,
+            timeout_ms,
+            unit_filter,
+            target_os,
+            allow,
+            oplog: OperationLog::new(OperationLog::default_path()),
+            session: DeviceSession::default(),
+            retry,
+            retry_stats: Default::default(),
+        })
+    }
+
+    /// Record `message` in the operation log, and show it with --verbose
+    fn log_event(&self, event: LogEvent, message: &str) {
+        self.verbose(message);
+        self.oplog.append(event, message);
+    }
+
+    /// Run one USB transfer under the retry policy, logging every attempt. `what` names the
+    /// request and report layout. Errors outside --retry-on (e.g. NoDevice) fail at once.
+    fn transfer(
+        &self,
+        what: &str,
+        mut attempt_transfer: impl FnMut() -> rusb::Result<usize>,
+    ) -> rusb::Result<usize> {
+        let mut attempt = 1;
+        loop {
+            let result = attempt_transfer();
+            if attempt == 1 {
+                self.log_transfer(what, &result);
+            } else {
+                self.log_transfer(
+                    &format!("{} (attempt {}/{})", what, attempt, self.retry.retries + 1),
+                    &result,
+                );
+            }
+            let mut stats = self.retry_stats.get();
+            match &result {
+                Err(e) if self.retry.should_retry(e, attempt) => {
+                    let delay = self.retry.delay(attempt);
+                    self.verbose(&format!(
+                        "{}: {}; retrying in {}ms (attempt {}/{})",
+                        what,
+                        e,
+                        delay.as_millis(),
+                        attempt + 1,
+                        self.retry.retries + 1
+                    ));
+                    stats.retries += 1;
+                    self.retry_stats.set(stats);
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                Ok(_) if attempt > 1 => {
+                    self.verbose(&format!("{}: succeeded on attempt {}", what, attempt));
+                    stats.recovered += 1;
+                    self.retry_stats.set(stats);
+                    return result;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Retries spent since `since`, formatted for a human status line (empty if none)
+    fn retry_note(&self, since: RetryStats) -> String {
+        match self.retry_stats.get().retries - since.retries {
+            0 => String::new(),
+            1 => ", 1 retry".to_string(),
+            n => format!(", {} retries", n),
+        }
+    }
+
+    /// Record one USB transfer attempt; `what` names the request and report layout
+    fn log_transfer(&self, what: &str, result: &rusb::Result<usize>) {
+        let outcome = match result {
+            Ok(len) => format!("ok ({} bytes)", len),
+            Err(e) => format!("failed: {}", e),
+        };
+        self.oplog
+            .append(LogEvent::Transfer, &format!("{}: {}", what, outcome));
+    }
+
+    /// The platform shortcuts are meant for: --target-os, else the setting saved in the live
+    /// config, else the OS savant is running on
+    fn target_os(&self) -> TargetOs {
+        self.target_os
+            .or_else(PedalConfig::saved_target_os)
+            .unwrap_or_else(TargetOs::current)
+    }
+
+    /// Lint pedal actions for `os`, dropping checks silenced with --allow
+    fn lint(&self, actions: &[Option<KeyAction>; 3], os: TargetOs) -> Vec<LintFinding> {
+        if self.allow.contains(&LintCode::All) {
+            return Vec::new();
+        }
+        lint_pedals(actions, os)
+            .into_iter()
+            .filter(|finding| !self.allow.contains(&finding.code))
+            .collect()
+    }
+
+    fn print_lint_findings(&self, findings: &[LintFinding], os: TargetOs) {
+        self.console.print(&format!(
+            "  [bold #f39c12]Shortcut check ({}):[/]",
+            os.label()
+        ));
+        for finding in findings {
+            let marker = match finding.severity {
+                LintSeverity::Error => "[bold #e74c3c]✗[/]",
+                LintSeverity::Warning => "[bold #f39c12]⚠[/]",
+            };
+            self.console.print(&format!(
+                "    {} [bold]{}[/]: {} [dim]({})[/]",
+                marker,
+                finding.pedal.label(),
+                finding.message,
+                finding.code.name()
+            ));
+        }
+    }
+
+    /// Print verbose output to stderr if verbose mode is enabled
+    fn verbose(&self, msg: &str) {
+        if self.verbose {
+            eprintln!("[verbose] {}", msg);
+        }
+    }
+
+    /// Print verbose hex data to stderr if verbose mode is enabled
+    fn verbose_hex(&self, label: &str, data: &[u8]) {
+        if self.verbose {
+            let hex: Vec<String> = data.iter().map(|b| format!("{:02X}", b)).collect();
+            if data.len() <= 16 {
+                eprintln!("[verbose] {}: [{}]", label, hex.join(" "));
+            } else {
+                eprintln!(
+                    "[verbose] {}: [{}...] ({} bytes)",
+                    label,
                     hex[..16].join(" "),
                     data.len()
                 );
             }
         }
-    }
+    }
+
+    fn print_banner(&self) {
+        self.console.print("");
+        self.console
+            .print("[bold #ff6b6b]╔══════════════════════════════════════════════════════════╗[/]");
+        self.console.print(
+            "[bold #ff6b6b]║[/]                                                          [bold #ff6b6b]║[/]",
+        );
+        self.console.print(
+            "[bold #ff6b6b]║[/]   [bold #4ecdc4]SAVANT ELITE[/]  -  [bold #ffe66d]Kinesis Foot Pedal Programmer[/]         [bold #ff6b6b]║[/]",
+        );
+        self.console.print(
+            "[bold #ff6b6b]║[/]                                                          [bold #ff6b6b]║[/]",
+        );
+        self.console
+            .print("[bold #ff6b6b]╚══════════════════════════════════════════════════════════╝[/]");
+        self.console.print("");
+    }
+
+    fn print_pedal_visualization(&self, left: &str, middle: &str, right: &str) {
+        // Helper to center text in a fixed width
+        fn center(s: &str, width: usize) -> String {
+            let len = s.chars().count();
+            if len >= width {
+                s.chars().take(width).collect()
+            } else {
+                let pad = width - len;
+                let left_pad = pad / 2;
+                let right_pad = pad - left_pad;
+                format!("{}{}{}", " ".repeat(left_pad), s, " ".repeat(right_pad))
+            }
+        }
+
+        // Format key action for display (e.g., "cmd+c" -> "⌘C")
+        fn format_key(s: &str, os: TargetOs) -> String {
+            let s = s.to_lowercase();
+            let parts: Vec<&str> = s.split('+').collect();
+            let mut result = String::new();
+
+            for (i, part) in parts.iter().enumerate() {
+                let part = part.trim();
+                if i < parts.len() - 1 {
+                    // Modifier
+                    let part = match part {
+                        "primary" | "secondary" => {
+                            let bit = if part == "primary" {
+                                os.primary_modifier()
+                            } else {
+                                os.secondary_modifier()
+                            };
+                            if bit == usb_hid::MOD_LEFT_GUI {
+                                "cmd"
+                            } else {
+                                "ctrl"
+                            }
+                        }
+                        _ => part,
+                    };
+                    match part {
+                        "cmd" | "command" | "gui" | "meta" | "super" => result.push('⌘'),
+                        "ctrl" | "control" => result.push('⌃'),
+                        "shift" => result.push('⇧'),
+                        "alt" | "option" | "opt" => result.push('⌥'),
+                        _ => result.push_str(part),
+                    }
+                } else {
+                    // Key - uppercase for display
+                    result.push_str(&part.to_uppercase());
+                }
+            }
+            result
+        }
+
+        let os = self.target_os();
+        let left_key = format_key(left, os);
+        let middle_key = format_key(middle, os);
+        let right_key = format_key(right, os);
+
+        // Create centered key displays (max 7 chars for the box interior)
+        let left_display = center(&left_key, 7);
+        let middle_display = center(&middle_key, 7);
+        let right_display = center(&right_key, 7);
+
+        self.console.print("");
+        self.console
+            .print("[bold #9b59b6]┌──────────────────────────────────────────────────────────┐[/]");
+        self.console.print(
+            "[bold #9b59b6]│[/]                 [bold white]YOUR PEDAL CONFIGURATION[/]                 [bold #9b59b6]│[/]",
+        );
+        self.console
+            .print("[bold #9b59b6]└──────────────────────────────────────────────────────────┘[/]");
+        self.console.print("");
+
+        // Top of pedals
+        self.console.print(
+            "       [#e74c3c]╭─────────────╮[/]  [#f39c12]╭─────────────╮[/]  [#2ecc71]╭─────────────╮[/]",
+        );
+        self.console.print(
+            "       [#e74c3c]│[/]             [#e74c3c]│[/]  [#f39c12]│[/]             [#f39c12]│[/]  [#2ecc71]│[/]             [#2ecc71]│[/]",
+        );
+
+        // Pedal labels
+        self.console.print(
+            "       [#e74c3c]│[/]  [bold #e74c3c]◀ LEFT[/]    [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]● MIDDLE[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]RIGHT ▶[/]   [#2ecc71]│[/]",
+        );
+
+        self.console.print(
+            "       [#e74c3c]│[/]             [#e74c3c]│[/]  [#f39c12]│[/]             [#f39c12]│[/]  [#2ecc71]│[/]             [#2ecc71]│[/]",
+        );
+
+        // Key box top
+        self.console.print(
+            "       [#e74c3c]│[/]  [bold #e74c3c]┌───────┐[/]  [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]┌───────┐[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]┌───────┐[/]  [#2ecc71]│[/]",
+        );
+
+        // Key values
+        self.console.print(&format!(
+            "       [#e74c3c]│[/]  [bold #e74c3c]│[/][bold white]{}[/][bold #e74c3c]│[/]  [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]│[/][bold white]{}[/][bold #f39c12]│[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]│[/][bold white]{}[/][bold #2ecc71]│[/]  [#2ecc71]│[/]",
+            left_display, middle_display, right_display
+        ));
+
+        // Key box bottom
+        self.console.print(
+            "       [#e74c3c]│[/]  [bold #e74c3c]└───────┘[/]  [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]└───────┘[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]└───────┘[/]  [#2ecc71]│[/]",
+        );
+
+        self.console.print(
+            "       [#e74c3c]│[/]             [#e74c3c]│[/]  [#f39c12]│[/]             [#f39c12]│[/]  [#2ecc71]│[/]             [#2ecc71]│[/]",
+        );
+
+        // Bottom of pedals
+        self.console.print(
+            "       [#e74c3c]╰─────────────╯[/]  [#f39c12]╰─────────────╯[/]  [#2ecc71]╰─────────────╯[/]",
+        );
+
+        // Pedal "stems"
+        self.console.print(
+            "            [#e74c3c]│[/]                  [#f39c12]│[/]                  [#2ecc71]│[/]",
+        );
+        self.console
+            .print("       [dim]═════╧══════════════════╧══════════════════╧═════[/]");
+        self.console.print("");
+    }
+
+    fn find_device(&self) -> Result<()> {
+        self.verbose("Initializing HID API...");
+        let api = self.session.hid().context("Failed to initialize HID API")?;
+        self.verbose("HID API initialized successfully");
+
+        // (mode, vid, pid, path, serial, interface, usage_page, usage)
+        type DeviceInfo = (String, String, String, String, String, i32, u16, u16);
+        let mut found_any = false;
+        let mut devices_info: Vec<DeviceInfo> = Vec::new();
+
+        self.verbose("Enumerating HID devices...");
+        for device in api.device_list() {
+            if device.vendor_id() == KINESIS_VID
+                && (device.product_id() == SAVANT_ELITE_PID
+                    || device.product_id() == PROGRAMMING_PID)
+            {
+                found_any = true;
+                let mode = if device.product_id() == PROGRAMMING_PID {
+                    "PROGRAM".to_string()
+                } else {
+                    "PLAY".to_string()
+                };
+                self.verbose(&format!(
+                    "Found Savant Elite ({} mode): VID={:#06X} PID={:#06X} interface={}",
+                    mode,
+                    device.vendor_id(),
+                    device.product_id(),
+                    device.interface_number()
+                ));
+                devices_info.push((
+                    mode,
+                    format!("0x{:04X}", device.vendor_id()),
+                    format!("0x{:04X}", device.product_id()),
+                    device.path().to_string_lossy().to_string(),
+                    device.serial_number().unwrap_or("N/A").to_string(),
+                    device.interface_number(),
+                    device.usage_page(),
+                    device.usage(),
+                ));
+            }
+        }
+
+        // Unit IDs are only reachable over libusb (control transfers on interface 0)
+        let mut unit_id = None;
+        if found_any {
+            if let Ok(devices) = self.session.usb_devices() {
+                for device in devices {
+                    let is_savant = device.device_descriptor().is_ok_and(|d| {
+                        d.vendor_id() == KINESIS_VID
+                            && (d.product_id() == SAVANT_ELITE_PID
+                                || d.product_id() == PROGRAMMING_PID)
+                    });
+                    if !is_savant {
+                        continue;
+                    }
+                    let id = self.query_unit_id(&device);
+                    if self.unit_filter.is_none_or(|wanted| id == Some(wanted)) {
+                        unit_id = id;
+                        if unit_id.is_some() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(wanted) = self.unit_filter {
+            if unit_id != Some(wanted) {
+                self.verbose(&format!("No device with unit ID {} found", wanted));
+                found_any = false;
+                devices_info.clear();
+            }
+        }
+
+        // Load config
+        self.verbose(&format!(
+            "Loading config from: {}",
+            PedalConfig::config_path().display()
+        ));
+        let config = PedalConfig::load();
+        if let Some(ref cfg) = config {
+            self.verbose(&format!(
+                "Config loaded: left={}, middle={}, right={}",
+                cfg.left, cfg.middle, cfg.right
+            ));
+        } else {
+            self.verbose("No saved config found");
+        }
+
+        // JSON output mode
+        if self.json_output {
+            let mut interfaces: Vec<JsonDeviceInterface> = Vec::new();
+            let mut seen_interfaces = std::collections::HashSet::new();
+
+            for (mode, vid, pid, _path, _serial, iface, usage_page, usage) in &devices_info {
+                if seen_interfaces.insert((*iface, *usage_page, *usage)) {
+                    interfaces.push(JsonDeviceInterface {
+                        mode: mode.to_lowercase(),
+                        vid: vid.clone(),
+                        pid: pid.clone(),
+                        interface: *iface,
+                        usage_page: format!("0x{:04X}", usage_page),
+                        usage: format!("0x{:04X}", usage),
+                    });
+                }
+            }
+
+            let (path, serial) = devices_info.first().map_or((None, None), |d| {
+                let serial = if d.4.is_empty() || d.4 == "N/A" {
+                    None
+                } else {
+                    Some(d.4.clone())
+                };
+                (Some(d.3.clone()), serial)
+            });
+
+            let mode = devices_info.first().map(|d| d.0.to_lowercase());
+            let pid = devices_info.first().map(|d| d.2.clone());
+
+            let json_config = config.map(|cfg| JsonConfig {
+                source: "file".to_string(),
+                path: PedalConfig::config_path().display().to_string(),
+                left: cfg.left,
+                middle: cfg.middle,
+                right: cfg.right,
+            });
+
+            let output = JsonInfoOutput {
+                device: JsonDeviceInfo {
+                    detected: found_any,
+                    mode,
+                    vid: format!("0x{:04X}", KINESIS_VID),
+                    pid,
+                    path,
+                    serial,
+                    unit_id,
+                    interfaces,
+                },
+                config: json_config,
+            };
+
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+
+        // Human-readable output
+        self.print_banner();
+
+        if found_any {
+            self.console.print(
+                "[bold #3498db]┌──────────────────────────────────────────────────────────┐[/]",
+            );
+            self.console.print(
+                "[bold #3498db]│[/]  [bold #2ecc71]✓[/] [bold white]DEVICE DETECTED[/]                                       [bold #3498db]│[/]",
+            );
+            self.console.print(
+                "[bold #3498db]└──────────────────────────────────────────────────────────┘[/]",
+            );
+            self.console.print("");
+
+            // Create a table for device info
+            let mut table = Table::new()
+                .box_style(&ROUNDED)
+                .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
+                .border_style(Style::parse("#3498db").unwrap_or_default())
+                .with_column(Column::new("Mode"))
+                .with_column(Column::new("VID"))
+                .with_column(Column::new("PID"))
+                .with_column(Column::new("Interface"))
+                .with_column(Column::new("Usage"));
+
+            // Deduplicate by interface
+            let mut seen_interfaces = std::collections::HashSet::new();
+            for (mode, vid, pid, _path, _serial, iface, usage_page, usage) in &devices_info {
+                if seen_interfaces.insert((*iface, *usage_page, *usage)) {
+                    let mode_styled = if mode == "PROGRAM" {
+                        markup::render_or_plain("[bold #e74c3c]PROGRAM[/]")
+                    } else {
+                        markup::render_or_plain("[bold #2ecc71]PLAY[/]")
+                    };
+                    let usage_str = format!("0x{:04X}:0x{:04X}", usage_page, usage);
+                    table.add_row_cells([
+                        mode_styled,
+                        markup::render_or_plain(vid),
+                        markup::render_or_plain(pid),
+                        markup::render_or_plain(&iface.to_string()),
+                        markup::render_or_plain(&usage_str),
+                    ]);
+                }
+            }
+
+            self.console.print_renderable(&table);
+            self.console.print("");
+
+            // Show USB path
+            if let Some((_, _, _, path, serial, _, _, _)) = devices_info.first() {
+                self.console
+                    .print(&format!("  [dim]Path:[/]   [#95a5a6]{}[/]", path));
+                if !serial.is_empty() && serial != "N/A" {
+                    self.console
+                        .print(&format!("  [dim]Serial:[/] [#95a5a6]{}[/]", serial));
+                }
+            }
+            if let Some(id) = unit_id {
+                self.console
+                    .print(&format!("  [dim]Unit ID:[/] [#95a5a6]{}[/]", id));
+            }
+
+            // Show current pedal configuration from saved config
+            if let Some(cfg) = config {
+                self.print_pedal_visualization(&cfg.left, &cfg.middle, &cfg.right);
+            } else {
+                self.console.print("");
+                self.console.print(
+                    "[bold #f39c12]┌──────────────────────────────────────────────────────────┐[/]",
+                );
+                self.console.print(
+                    "[bold #f39c12]│[/]  [bold white]PEDAL CONFIGURATION UNKNOWN[/]                             [bold #f39c12]│[/]",
+                );
+                self.console.print(
+                    "[bold #f39c12]└──────────────────────────────────────────────────────────┘[/]",
+                );
+                self.console.print("");
+                self.console
+                    .print("  [#95a5a6]Run[/] [bold #3498db]savant program[/] [#95a5a6]to configure your pedals.[/]");
+                self.console.print("");
+                self.console
+                    .print("  [dim]Example:[/] [#95a5a6]savant program --left cmd+c --middle cmd+a --right cmd+v[/]");
+            }
+        } else {
+            self.console.print(
+                "[bold #e74c3c]┌──────────────────────────────────────────────────────────┐[/]",
+            );
+            self.console.print(
+                "[bold #e74c3c]│[/]  [bold #e74c3c]✗[/] [bold white]NO DEVICE FOUND[/]                                       [bold #e74c3c]│[/]",
+            );
+            self.console.print(
+                "[bold #e74c3c]└──────────────────────────────────────────────────────────┘[/]",
+            );
+            self.console.print("");
+            self.console
+                .print("  [#95a5a6]Make sure your Savant Elite is connected via USB.[/]");
+        }
+
+        self.console.print("");
+        Ok(())
+    }
+
+    fn open_keyboard_interface(&self) -> Result<HidDevice> {
+        let api = self.session.hid().context("Failed to initialize HID API")?;
+
+        // Find the keyboard interface (usage page 1, usage 6)
+        self.verbose("Searching for keyboard interface (usage_page=0x01, usage=0x06)...");
+        for device in api.device_list() {
+            if device.vendor_id() == KINESIS_VID
+                && device.product_id() == SAVANT_ELITE_PID
+                && device.usage_page() == 0x01
+                && device.usage() == 0x06
+            {
+                self.verbose(&format!(
+                    "Found keyboard interface at path: {}",
+                    device.path().to_string_lossy()
+                ));
+                match device.open_device(&api) {
+                    Ok(dev) => {
+                        self.verbose("Keyboard interface opened successfully");
+                        return Ok(dev);
+                    }
+                    Err(e) => {
+                        let msg = e.to_string();
+                        if msg.contains("privilege violation") || msg.contains("0xE00002C1") {
+                            return Err(anyhow!(e).context(
+                                "Failed to open device (macOS Input Monitoring permission is required; enable it in System Settings → Privacy & Security → Input Monitoring, then re-run)",
+                            ));
+                        }
+                        return Err(anyhow!(e).context("Failed to open device"));
+                    }
+                }
+            }
+        }
+
+        Err(anyhow!("Savant Elite keyboard interface not found"))
+    }
+
+    fn monitor(&self, duration_secs: u64) -> Result<()> {
+        let device = self.open_keyboard_interface()?;
+
+        self.print_banner();
+
+        self.console.print(
+            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
+        );
+        self.console.print(
+            "[bold #9b59b6]│[/]  [bold #f39c12]👁[/]  [bold white]LIVE MONITOR MODE[/]                                          [bold #9b59b6]│[/]",
+        );
+        self.console.print(
+            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
+        );
+        self.console.print("");
+        self.console
+            .print("  [#95a5a6]Press pedals to see what keys they send.[/]");
+        self.console
+            .print("  [#95a5a6]Press[/] [bold #e74c3c]Ctrl+C[/] [#95a5a6]to stop.[/]");
+        if duration_secs > 0 {
+            self.console.print(&format!(
+                "  [#95a5a6]Auto-stop in[/] [bold #f39c12]{}[/] [#95a5a6]seconds.[/]",
+                duration_secs
+            ));
+        }
+        self.console.print("");
+        self.console.print(
+            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
+        );
+
+        self.verbose("Setting non-blocking mode on HID device");
+        device.set_blocking_mode(false)?;
+
+        let mut buf = [0u8; 64];
+        let mut last_report = [0u8; 8];
+        let start = std::time::Instant::now();
+        self.verbose("Starting monitor loop...");
+
+        loop {
+            if duration_secs > 0 && start.elapsed().as_secs() >= duration_secs {
+                self.console.print("");
+                self.console.print(
+                    "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
+                );
+                self.console
+                    .print("[bold #2ecc71]✓[/] [#95a5a6]Monitoring complete.[/]");
+                break;
+            }
+
+            match device.read_timeout(&mut buf, 100) {
+                Ok(len) if len > 0 => {
+                    self.verbose_hex("Raw HID read", &buf[..len]);
+                    let Some(report) = usb_hid::normalize_boot_keyboard_report(&buf[..len]) else {
+                        self.verbose("  -> Could not normalize to boot keyboard report");
+                        continue;
+                    };
+
+                    if report != last_report {
+                        last_report = report;
+                        self.verbose_hex("Normalized report", &report);
+
+                        let modifiers = report[0];
+                        let keys: Vec<u8> =
+                            report[2..8].iter().filter(|&&k| k != 0).copied().collect();
+
+                        if modifiers != 0 || !keys.is_empty() {
+                            let mod_names = usb_hid::modifier_names(modifiers);
+                            let key_names: Vec<&str> =
+                                keys.iter().map(|&k| usb_hid::key_name(k)).collect();
+
+                            let combo = if !mod_names.is_empty() && !key_names.is_empty() {
+                                format!("{}+{}", mod_names.join("+"), key_names.join("+"))
+                            } else if !mod_names.is_empty() {
+                                mod_names.join("+")
+                            } else {
+                                key_names.join("+")
+                            };
+
+                            self.console.print(&format!(
+                                "  [bold #2ecc71]▶[/] [bold #f1c40f]PRESS[/]   [bold white]{}[/]  [dim #7f8c8d]({})[/]",
+                                combo,
+                                hex::encode(report)
+                            ));
+                        } else {
+                            self.console.print(&format!(
+                                "  [dim #e74c3c]◀[/] [dim #95a5a6]RELEASE[/] [dim #7f8c8d]({})[/]",
+                                hex::encode(report)
+                            ));
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    if e.to_string().contains("timeout") {
+                        continue;
+                    }
+                    return Err(anyhow!("Read error: {}", e));
+                }
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        self.console.print("");
+        Ok(())
+    }
+
+    fn status(&self) -> Result<()> {
+        // Check via libusb first (more reliable for programming mode)
+        let mut found_play_usb = false;
+        let mut found_program_usb = false;
+        let mut libusb_error: Option<anyhow::Error> = None;
+        // (mode, pid, location/info, unit id)
+        let mut device_details: Vec<(String, String, String, Option<u8>)> = Vec::new();
+
+        match self.session.usb_devices() {
+            Ok(devices) => {
+                for device in devices {
+                    let desc = match device.device_descriptor() {
+                        Ok(desc) => desc,
+                        Err(_) => continue,
+                    };
+                    if desc.vendor_id() == KINESIS_VID {
+                        self.log_event(
+                            LogEvent::Device,
+                            &format!(
+                                "Found 0x{:04X}:0x{:04X} at Bus {:03} Device {:03}",
+                                desc.vendor_id(),
+                                desc.product_id(),
+                                device.bus_number(),
+                                device.address()
+                            ),
+                        );
+                        if PedalMode::from_pid(desc.product_id()).is_none() {
+                            continue;
+                        }
+                        let unit_id = self.query_unit_id(&device);
+                        if self.unit_filter.is_some() && unit_id != self.unit_filter {
+                            continue;
+                        }
+                        match desc.product_id() {
+                            SAVANT_ELITE_PID => {
+                                found_play_usb = true;
+                                device_details.push((
+                                    "PLAY".to_string(),
+                                    format!("0x{:04X}", SAVANT_ELITE_PID),
+                                    format!(
+                                        "Bus {:03} Device {:03}",
+                                        device.bus_number(),
+                                        device.address()
+                                    ),
+                                    unit_id,
+                                ));
+                            }
+                            PROGRAMMING_PID => {
+                                found_program_usb = true;
+                                let mut product = "Savant Elite".to_string();
+                                let mut mfr = "Kinesis".to_string();
+
+                                if let Ok(handle) = device.open() {
+                                    if let Ok(langs) =
+                                        handle.read_languages(Duration::from_millis(100))
+                                    {
+                                        if let Some(lang) = langs.first() {
+                                            if let Ok(p) = handle.read_product_string(
+                                                *lang,
+                                                &desc,
+                                                Duration::from_millis(100),
+                                            ) {
+                                                product = p;
+                                            }
+                                            if let Ok(m) = handle.read_manufacturer_string(
+                                                *lang,
+                                                &desc,
+                                                Duration::from_millis(100),
+                                            ) {
+                                                mfr = m;
+                                            }
+                                        }
+                                    }
+                                }
+                                device_details.push((
+                                    "PROGRAM".to_string(),
+                                    format!("0x{:04X}", PROGRAMMING_PID),
+                                    format!("{mfr} - {product}"),
+                                    unit_id,
+                                ));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                libusb_error =
+                    Some(anyhow!(e).context("Failed to enumerate USB devices via libusb"));
+            }
+        }
+
+        // Also check HID (for play mode with interfaces)
+        let api = self.session.hid().context("Failed to initialize HID API")?;
+        let mut found_play_hid = false;
+        let mut found_program_hid = false;
+
+        for device_info in api.device_list() {
+            // hidapi cannot read unit IDs, so it can't satisfy a --unit selector
+            if device_info.vendor_id() != KINESIS_VID || self.unit_filter.is_some() {
+                continue;
+            }
+
+            match device_info.product_id() {
+                SAVANT_ELITE_PID if !found_play_usb && !found_play_hid => {
+                    found_play_hid = true;
+                    device_details.push((
+                        "PLAY".to_string(),
+                        format!("0x{:04X}", SAVANT_ELITE_PID),
+                        format!("hidapi: {}", device_info.path().to_string_lossy()),
+                        None,
+                    ));
+                }
+                PROGRAMMING_PID if !found_program_usb && !found_program_hid => {
+                    found_program_hid = true;
+                    device_details.push((
+                        "PROGRAM".to_string(),
+                        format!("0x{:04X}", PROGRAMMING_PID),
+                        format!("hidapi: {}", device_info.path().to_string_lossy()),
+                        None,
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let found_play = found_play_usb || found_play_hid;
+        let found_program = found_program_usb || found_program_hid;
+
+        // JSON output mode
+        if self.json_output {
+            let mode = if found_program {
+                Some("program".to_string())
+            } else if found_play {
+                Some("play".to_string())
+            } else {
+                None
+            };
+
+            let devices: Vec<JsonStatusDevice> = device_details
+                .iter()
+                .map(|(m, pid, loc, unit_id)| JsonStatusDevice {
+                    mode: m.to_lowercase(),
+                    pid: pid.clone(),
+                    location: loc.clone(),
+                    unit_id: *unit_id,
+                })
+                .collect();
+
+            let output = JsonStatusOutput {
+                detected: found_play || found_program,
+                mode,
+                devices,
+                ready_to_program: found_program,
+            };
+
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+
+        // Human-readable output
+        self.print_banner();
+
+        self.console.print(
+            "[bold #1abc9c]┌─────────────────────────────────────────────────────────────────┐[/]",
+        );
+        self.console.print(
+            "[bold #1abc9c]│[/]  [bold #f39c12]🔍[/] [bold white]DEVICE STATUS CHECK[/]                                        [bold #1abc9c]│[/]",
+        );
+        self.console.print(
+            "[bold #1abc9c]└─────────────────────────────────────────────────────────────────┘[/]",
+        );
+        self.console.print("");
+
+        if !found_play && !found_program {
+            self.console.print(
+                "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
+            );
+            self.console.print(
+                "  [bold #e74c3c]│[/]  [bold #e74c3c]✗[/]  [bold white]No Savant Elite device found[/]                           [bold #e74c3c]│[/]",
+            );
+            self.console.print(
+                "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
+            );
+            self.console.print("");
+            self.console.print("  [bold #f39c12]Troubleshooting:[/]");
+            self.console
+                .print("    [#95a5a6]1.[/] Make sure the device is connected via USB");
+            self.console
+                .print("    [#95a5a6]2.[/] Try unplugging and replugging the cable");
+        } else if found_play && !found_program {
+            self.console.print(
+                "  [bold #2ecc71]╭────────────────────────────────────────────────────────────╮[/]",
+            );
+            self.console.print(
+                "  [bold #2ecc71]│[/]  [bold #2ecc71]●[/]  [bold white]Device is in[/] [bold #2ecc71]PLAY[/] [bold white]mode[/]                                 [bold #2ecc71]│[/]",
+            );
+            self.console.print(
+                "  [bold #2ecc71]╰────────────────────────────────────────────────────────────╯[/]",
+            );
+            self.console.print("");
+
+            // Show table of details
+            let mut table = Table::new()
+                .box_style(&ROUNDED)
+                .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
+                .border_style(Style::parse("#3498db").unwrap_or_default())
+                .with_column(Column::new("Mode"))
+                .with_column(Column::new("PID"))
+                .with_column(Column::new("Location"))
+                .with_column(Column::new("Unit ID"));
+
+            for (mode, pid, location, unit_id) in &device_details {
+                let mode_styled = if mode == "PROGRAM" {
+                    markup::render_or_plain(&format!("[bold #e74c3c]{}[/]", mode))
+                } else {
+                    markup::render_or_plain(&format!("[bold #2ecc71]{}[/]", mode))
+                };
+                table.add_row_cells([
+                    mode_styled,
+                    markup::render_or_plain(pid),
+                    markup::render_or_plain(location),
+                    markup::render_or_plain(&format_unit_id(*unit_id)),
+                ]);
+            }
+            self.console.print_renderable(&table);
+            self.console.print("");
+
+            self.console
+                .print("  [bold #f39c12]To program the device, switch to PROGRAMMING mode:[/]");
+            self.console.print("");
+            self.console
+                .print("    [bold #3498db]1.[/] Flip the pedal over");
+            self.console
+                .print("    [bold #3498db]2.[/] Find the recessed switch near the Kinesis sticker");
+            self.console.print(
+                "    [bold #3498db]3.[/] Use a paperclip to flip it from [#2ecc71]Play[/] → [#e74c3c]Program[/]",
+            );
+            self.console
+                .print("    [bold #3498db]4.[/] Unplug and replug the USB cable");
+            self.console
+                .print("    [bold #3498db]5.[/] Run [bold #f1c40f]savant status[/] to verify");
+        } else if found_program {
+            self.console.print(
+                "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
+            );
+            self.console.print(
+                "  [bold #e74c3c]│[/]  [bold #e74c3c]◉[/]  [bold white]Device is in[/] [bold #e74c3c]PROGRAMMING[/] [bold white]mode[/]                          [bold #e74c3c]│[/]",
+            );
+            self.console.print(
+                "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
+            );
+            self.console.print("");
+
+            // Show table of details
+            let mut table = Table::new()
+                .box_style(&ROUNDED)
+                .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
+                .border_style(Style::parse("#e74c3c").unwrap_or_default())
+                .with_column(Column::new("Mode"))
+                .with_column(Column::new("PID"))
+                .with_column(Column::new("Info"))
+                .with_column(Column::new("Unit ID"));
+
+            for (mode, pid, info, unit_id) in &device_details {
+                let mode_styled = if mode == "PROGRAM" {
+                    markup::render_or_plain(&format!("[bold #e74c3c]{}[/]", mode))
+                } else {
+                    markup::render_or_plain(&format!("[bold #2ecc71]{}[/]", mode))
+                };
+                table.add_row_cells([
+                    mode_styled,
+                    markup::render_or_plain(pid),
+                    markup::render_or_plain(info),
+                    markup::render_or_plain(&format_unit_id(*unit_id)),
+                ]);
+            }
+            self.console.print_renderable(&table);
+            self.console.print("");
+
+            self.console
+                .print("  [bold #2ecc71]✓[/] [bold white]Ready to program![/]");
+            self.console.print("");
+            self.console.print("  [#95a5a6]Example command:[/]");
+            self.console.print(
+                "    [bold #f1c40f]savant program --left 'cmd+c' --middle 'cmd+a' --right 'cmd+v'[/]",
+            );
+        }
+
+        if let Some(e) = libusb_error {
+            self.console.print("");
+            self.console.print(&format!(
+                "  [bold #f39c12]⚠[/] [#f39c12]Note:[/] [#95a5a6]libusb scan failed[/] [dim]({})[/]",
+                e
+            ));
+            self.console.print(
+                "  [#95a5a6]If you need to program or reliably detect programming mode, try running with sudo.[/]",
+            );
+        }
+
+        self.console.print("");
+        Ok(())
+    }
+
+    fn probe(&self) -> Result<()> {
+        let api = self.session.hid()?;
+
+        if self.json_output {
+            let devices = self.probe_devices(&api, false);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({ "devices": devices }))?
+            );
+            return Ok(());
+        }
+
+        self.print_banner();
+
+        self.console.print(
+            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
+        );
+        self.console.print(
+            "[bold #9b59b6]│[/]  [bold #f39c12]🔬[/] [bold white]PROTOCOL PROBE[/] [dim](Reverse Engineering Mode)[/]                  [bold #9b59b6]│[/]",
+        );
+        self.console.print(
+            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
+        );
+        self.console.print("");
+
+        self.console
+            .print("  [bold #3498db]Scanning for Kinesis devices...[/]");
+        self.console.print("");
+
+        for device in self.probe_devices(&api, false) {
+            let mode_indicator = if device.mode == "programming" {
+                "[bold #e74c3c]★ PROGRAMMING MODE[/]"
+            } else {
+                "[bold #2ecc71]● PLAY MODE[/]"
+            };
+
+            self.console.print(&format!(
+                "  [bold #f1c40f]►[/] Device [bold white]VID={} PID={}[/]  {}",
+                device.vid, device.pid, mode_indicator
+            ));
+            self.console
+                .print(&format!("    [dim]Path:[/] [#7f8c8d]{}[/]", device.path));
+            self.console.print(&format!(
+                "    [dim]Interface:[/] [#7f8c8d]{}[/]  [dim]Usage:[/] [#7f8c8d]{}:{}[/]",
+                device.interface, device.usage_page, device.usage
+            ));
+
+            if let Some(e) = &device.error {
+                self.console
+                    .print(&format!("    [bold #e74c3c]✗[/] Failed to open: {}", e));
+                self.console.print("");
+                continue;
+            }
+            self.console
+                .print("    [bold #2ecc71]✓[/] Opened successfully");
+
+            for (command, color) in device
+                .commands
+                .iter()
+                .zip(["#3498db", "#9b59b6", "#1abc9c"])
+            {
+                match (&command.bytes_written, &command.error) {
+                    (Some(n), _) => {
+                        self.console.print(&format!(
+                            "    [{}]→[/] {} [dim]({})[/]: {} bytes",
+                            color, command.name, command.command, n
+                        ));
+                        match &command.response {
+                            Some(response) => self.console.print(&format!(
+                                "      [bold #2ecc71]←[/] [#7f8c8d]{}[/]",
+                                response
+                            )),
+                            None => self.console.print("      [dim]← No response[/]"),
+                        }
+                    }
+                    (None, error) => {
+                        self.console.print(&format!(
+                            "    [#e74c3c]✗[/] {} failed: [dim]{}[/]",
+                            command.name,
+                            error.as_deref().unwrap_or_default()
+                        ));
+                    }
+                }
+            }
+
+            self.console
+                .print("    [bold #f39c12]Checking feature reports...[/]");
+            for report in &device.feature_reports {
+                self.console.print(&format!(
+                    "      [#2ecc71]Report {}:[/] [#7f8c8d]{}[/]",
+                    report.report_id, report.data
+                ));
+            }
+            self.console.print("");
+        }
+
+        self.console.print(
+            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
+        );
+        self.console.print("[bold #f39c12]Protocol Notes:[/]");
+        self.console.print(
+            "  [#95a5a6]•[/] Programming mode uses PID [bold]0x0232[/] (vs normal [bold]0x030C[/])",
+        );
+        self.console.print("");
+        self.console
+            .print("[bold #f39c12]To Enter Programming Mode:[/]");
+        self.console
+            .print("  [bold #3498db]1.[/] Flip the pedal over");
+        self.console
+            .print("  [bold #3498db]2.[/] Look for a recessed switch near the Kinesis sticker");
+        self.console.print(
+            "  [bold #3498db]3.[/] Use a paperclip to flip it from [#2ecc71]Play[/] → [#e74c3c]Program[/]",
+        );
+        self.console.print(
+            "  [bold #3498db]4.[/] Unplug and replug USB, then run [bold #f1c40f]savant status[/]",
+        );
+        self.console.print("");
+
+        Ok(())
+    }
+
+    /// Open every Kinesis HID interface and try the PI Engineering X-keys commands and
+    /// feature reports 0-9 on it, recording what comes back. With `pedals_only`, other X-keys
+    /// products sharing the vendor ID are left alone (for probes the user didn't ask for).
+    fn probe_devices(&self, api: &HidApi, pedals_only: bool) -> Vec<JsonProbeDevice> {
+        let mut devices = Vec::new();
+        for device_info in api.device_list().filter(|d| {
+            d.vendor_id() == KINESIS_VID
+                && (!pedals_only || PedalMode::from_pid(d.product_id()).is_some())
+        }) {
+            let pid = device_info.product_id();
+            let mut probe = JsonProbeDevice {
+                vid: format!("0x{:04X}", device_info.vendor_id()),
+                pid: format!("0x{:04X}", pid),
+                mode: if pid == PROGRAMMING_PID {
+                    "programming"
+                } else {
+                    "play"
+                }
+                .to_string(),
+                path: device_info.path().to_string_lossy().into_owned(),
+                interface: device_info.interface_number(),
+                usage_page: format!("0x{:04X}", device_info.usage_page()),
+                usage: format!("0x{:04X}", device_info.usage()),
+                opened: false,
+                error: None,
+                commands: Vec::new(),
+                feature_reports: Vec::new(),
+            };
+
+            let device = match device_info.open_device(api) {
+                Ok(device) => device,
+                Err(e) => {
+                    probe.error = Some(e.to_string());
+                    devices.push(probe);
+                    continue;
+                }
+            };
+            probe.opened = true;
+            self.verbose(&format!(
+                "Probing {} interface {}",
+                probe.pid, probe.interface
+            ));
+
+            // Try PI Engineering X-keys commands
+            for (cmd, name) in [
+                (0xB5, "Generate Data"),
+                (0xC1, "Get Descriptor"),
+                (0xCD, "Get Key Macro"),
+            ] {
+                let mut cmd_buf = [0u8; 36];
+                cmd_buf[0] = 0;
+                cmd_buf[1] = cmd;
+
+                let mut command = JsonProbeCommand {
+                    command: format!("0x{:02X}", cmd),
+                    name: name.to_string(),
+                    bytes_written: None,
+                    response: None,
+                    error: None,
+                };
+                match device.write(&cmd_buf) {
+                    Ok(n) => {
+                        command.bytes_written = Some(n);
+                        std::thread::sleep(Duration::from_millis(50));
+                        let mut response = [0u8; 64];
+                        if let Ok(len) = device.read_timeout(&mut response, 200) {
+                            if len > 0 {
+                                command.response = Some(hex::encode(&response[..len]));
+                            }
+                        }
+                    }
+                    Err(e) => command.error = Some(e.to_string()),
+                }
+                probe.commands.push(command);
+            }
+
+            // Try feature reports
+            for report_id in 0..10u8 {
+                let mut buf = [0u8; 65];
+                buf[0] = report_id;
+                if let Ok(len) = device.get_feature_report(&mut buf) {
+                    if len > 0 {
+                        probe.feature_reports.push(JsonProbeReport {
+                            report_id,
+                            data: hex::encode(&buf[..len]),
+                        });
+                    }
+                }
+            }
+            devices.push(probe);
+        }
+        devices
+    }
+
+    /// Attempt to verify pedal programming by reading back the macro using GET_KEY_MACRO (0xCD).
+    /// Returns Ok(true) if verified, Ok(false) if mismatch, Err if verification not supported.
+    fn verify_pedal_programming(
+        &self,
+        handle: &rusb::DeviceHandle<GlobalContext>,
+        interface_num: u8,
+        pedal_idx: u8,
+        expected_modifiers: u8,
+        expected_key: u8,
+    ) -> Result<bool> {
+        let mut response = [0u8; 64];
+
+        // Try different w_value formats for GET_REPORT with GET_KEY_MACRO
+        // The report ID may be 0, the command byte, or include pedal index
+        for w_value in [
+            // Feature report with CMD as report ID
+            usb_constants::HID_REPORT_TYPE_FEATURE | (xkeys_protocol::CMD_GET_KEY_MACRO as u16),
+            // Feature report with report ID 0
+            usb_constants::HID_REPORT_TYPE_FEATURE,
+            // Input report with CMD as report ID
+            usb_constants::HID_REPORT_TYPE_INPUT | (xkeys_protocol::CMD_GET_KEY_MACRO as u16),
+            // Input report with report ID 0
+            usb_constants::HID_REPORT_TYPE_INPUT,
+        ] {
+            // GET_REPORT request: bmRequestType=0xA1 (device-to-host, class, interface)
+            let result = self.transfer(
+                &format!(
+                    "GET_REPORT GET_KEY_MACRO pedal {} wValue=0x{:04X}",
+                    pedal_idx, w_value
+                ),
+                || {
+                    handle.read_control(
+                        usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_IN,
+                        usb_constants::HID_GET_REPORT,
+                        w_value,
+                        interface_num as u16,
+                        &mut response,
+                        Duration::from_millis(self.timeout_ms),
+                    )
+                },
+            );
+
+            if let Ok(len) = result {
+                let Some((read_mod, read_key)) =
+                    xkeys_protocol::parse_key_macro_report(&response[..len], pedal_idx)
+                else {
+                    // Unknown format or too short, can't verify
+                    continue;
+                };
+
+                return Ok(read_mod == expected_modifiers && read_key == expected_key);
+            }
+        }
+
+        // Verification not supported or no valid response
+        Err(anyhow!("GET_KEY_MACRO not supported by device firmware"))
+    }
+
+    /// Reboot the device after SAVE_TO_EEPROM, wait for it to re-enumerate on the same port
+    /// (bus, port chain, old address), and read each pedal back with GET_KEY_MACRO.
+    ///
+    /// Returns false only when something positively went wrong (no reboot, no re-enumeration,
+    /// or a read-back mismatch); firmware without GET_KEY_MACRO support is reported but not
+    /// treated as a failure.
+    fn reboot_and_verify(
+        &self,
+        handle: &rusb::DeviceHandle<GlobalContext>,
+        interface_num: u8,
+        (bus_number, port_numbers, address): (u8, &[u8], u8),
+        expected: &[(u8, &KeyAction, &str)],
+    ) -> bool {
+        self.console
+            .print("  [bold #f1c40f]▸[/] Rebooting to confirm EEPROM contents...");
+
+        // The device may reset before acknowledging, so only re-enumeration counts as success.
+        if self
+            .send_xkeys_command(handle, interface_num, xkeys_protocol::CMD_REBOOT, &[])
+            .is_none()
+        {
+            self.verbose("REBOOT was not acknowledged (device may already be resetting)");
+        }
+
+        let Some(device) = self.session.wait_for_reenumeration(
+            bus_number,
+            port_numbers,
+            address,
+            PROGRAMMING_PID,
+            REENUMERATION_TIMEOUT,
+        ) else {
+            self.console.print(&format!(
+                "    [bold #f39c12]⚠[/] [#f39c12]Device did not re-enumerate within {}s (firmware may ignore REBOOT)[/]",
+                REENUMERATION_TIMEOUT.as_secs()
+            ));
+            return false;
+        };
+        self.console.print(&format!(
+            "    [bold #2ecc71]✓[/] [#95a5a6]Re-enumerated as Bus {:03} Device {:03}[/]",
+            device.bus_number(),
+            device.address()
+        ));
+
+        let handle = match device.open() {
+            Ok(handle) => handle,
+            Err(e) => {
+                self.console.print(&format!(
+                    "    [bold #f39c12]⚠[/] [#f39c12]Could not reopen device after reboot: {}[/]",
+                    e
+                ));
+                return false;
+            }
+        };
+        let _interface_guard = match self.claim_interface(&handle, interface_num) {
+            Ok(guard) => guard,
+            Err(e) => {
+                self.console
+                    .print(&format!("    [bold #f39c12]⚠[/] [#f39c12]{}[/]", e));
+                return false;
+            }
+        };
+
+        let mut mismatches: Vec<&str> = Vec::new();
+        let mut verified = 0;
+        for &(pedal_idx, action, name) in expected {
+            match self.verify_pedal_programming(
+                &handle,
+                interface_num,
+                pedal_idx,
+                action.modifiers,
+                action.key,
+            ) {
+                Ok(true) => verified += 1,
+                Ok(false) => mismatches.push(name),
+                Err(e) => self.verbose(&format!("{} pedal read-back unavailable: {}", name, e)),
+            }
+        }
+
+        if !mismatches.is_empty() {
+            self.console.print(&format!(
+                "    [bold #e74c3c]✗[/] [#e74c3c]After reboot these pedals read back differently:[/] [bold]{}[/]",
+                mismatches.join(", ")
+            ));
+            false
+        } else if verified == 0 {
+            self.console.print(
+                "    [dim]Firmware does not support GET_KEY_MACRO; persistence could not be read back.[/]",
+            );
+            true
+        } else {
+            self.console.print(&format!(
+                "    [bold #2ecc71]✓[/] [#95a5a6]{} of {} pedals confirmed from EEPROM after reboot[/]",
+                verified,
+                expected.len()
+            ));
+            true
+        }
+    }
+
+    /// Send a generic X-keys command via HID SET_REPORT, trying the same report layouts as
+    /// SAVE_TO_EEPROM. Returns the name of the layout the device accepted, if any.
+    fn send_xkeys_command(
+        &self,
+        handle: &rusb::DeviceHandle<GlobalContext>,
+        interface_num: u8,
+        cmd: u8,
+        payload: &[u8],
+    ) -> Option<&'static str> {
+        for (fmt_name, w_value, data) in xkeys_command_layouts(cmd, payload) {
+            let result = self.transfer(&format!("SET_REPORT 0x{:02X} {}", cmd, fmt_name), || {
+                handle.write_control(
+                    usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
+                    usb_constants::HID_SET_REPORT,
+                    w_value,
+                    interface_num as u16,
+                    &data,
+                    Duration::from_millis(self.timeout_ms),
+                )
+            });
+            if result.is_ok() {
+                self.verbose(&format!("Command 0x{:02X} accepted ({})", cmd, fmt_name));
+                return Some(fmt_name);
+            }
+        }
+
+        self.verbose(&format!("Command 0x{:02X} rejected in every format", cmd));
+        None
+    }
+
+    /// Write an X-keys command as an output report to each interrupt OUT endpoint in turn, for
+    /// firmware that only takes commands on the data pipe. Returns the layout name on success.
+    fn send_interrupt_command(
+        &self,
+        handle: &rusb::DeviceHandle<GlobalContext>,
+        endpoints: &[InterruptOut],
+        cmd: u8,
+        payload: &[u8],
+    ) -> Option<&'static str> {
+        for ep in endpoints {
+            let report = interrupt_report(cmd, payload, ep.max_packet_size as usize);
+            let result = self.transfer(
+                &format!(
+                    "interrupt OUT 0x{:02X} endpoint 0x{:02X} (interface {})",
+                    cmd, ep.address, ep.interface
+                ),
+                || {
+                    handle.write_interrupt(
+                        ep.address,
+                        &report,
+                        Duration::from_millis(self.timeout_ms),
+                    )
+                },
+            );
+            if result.is_ok() {
+                self.verbose(&format!(
+                    "Command 0x{:02X} written to interrupt endpoint 0x{:02X}",
+                    cmd, ep.address
+                ));
+                return Some("interrupt");
+            }
+        }
+        None
+    }
+
+    /// Read the X-keys unit ID by sending GENERATE_DATA (0xB5) and fetching the resulting
+    /// input report with GET_REPORT. Best-effort: returns None if the firmware doesn't answer.
+    fn read_unit_id(
+        &self,
+        handle: &rusb::DeviceHandle<GlobalContext>,
+        interface_num: u8,
+    ) -> Option<u8> {
+        // Read-only commands (status, info) run this on every pedal, so it gets one attempt
+        // per transfer with a short timeout, and gives up on the first timeout: firmware that
+        // times out rather than stalling would otherwise cost seconds per pedal.
+        let timeout = Duration::from_millis(self.timeout_ms.min(UNIT_ID_PROBE_TIMEOUT_MS));
+        let mut accepted = None;
+        for (fmt_name, w_value, data) in
+            xkeys_command_layouts(xkeys_protocol::CMD_GENERATE_DATA, &[])
+        {
+            let result = handle.write_control(
+                usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
+                usb_constants::HID_SET_REPORT,
+                w_value,
+                interface_num as u16,
+                &data,
+                timeout,
+            );
+            self.log_transfer(&format!("SET_REPORT GENERATE_DATA {}", fmt_name), &result);
+            match result {
+                Ok(_) => {
+                    accepted = Some(fmt_name);
+                    break;
+                }
+                Err(rusb::Error::Timeout) => {
+                    self.verbose("GENERATE_DATA timed out; not probing other layouts");
+                    return None;
+                }
+                Err(_) => {}
+            }
+        }
+        let report_id = xkeys_protocol::layout_has_report_id(accepted?);
+        std::thread::sleep(Duration::from_millis(20));
+
+        let mut response = [0u8; 64];
+        for w_value in [
+            usb_constants::HID_REPORT_TYPE_INPUT,
+            usb_constants::HID_REPORT_TYPE_FEATURE,
+        ] {
+            let result = handle.read_control(
+                usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_IN,
+                usb_constants::HID_GET_REPORT,
+                w_value,
+                interface_num as u16,
+                &mut response,
+                timeout,
+            );
+            self.log_transfer(
+                &format!("GET_REPORT GENERATE_DATA wValue=0x{:04X}", w_value),
+                &result,
+            );
+            if let Ok(len) = result {
+                self.verbose_hex("GENERATE_DATA response", &response[..len]);
+                if let Some(id) = xkeys_protocol::parse_unit_id_report(&response[..len], report_id)
+                {
+                    return Some(id);
+                }
+            }
+        }
+        None
+    }
+
+    /// Open a device just long enough to read its unit ID (no interface is claimed).
+    /// Only call this for Savant Elite PIDs: the probe writes X-keys commands, which other
+    /// Kinesis devices on the bus must never see.
+    fn query_unit_id(&self, device: &Device<GlobalContext>) -> Option<u8> {
+        let handle = device.open().ok()?;
+        let unit_id = self.read_unit_id(&handle, 0);
+        self.verbose(&format!(
+            "Bus {:03} Device {:03}: unit ID {}",
+            device.bus_number(),
+            device.address(),
+            format_unit_id(unit_id)
+        ));
+        unit_id
+    }
+
+    /// Whether a device passes the global `--unit` selector (always true without one).
+    fn matches_unit_filter(&self, device: &Device<GlobalContext>) -> bool {
+        match self.unit_filter {
+            Some(wanted) => self.query_unit_id(device) == Some(wanted),
+            None => true,
+        }
+    }
+
+    /// Show the paperclip steps and poll for up to 60s until the pedal re-enumerates in
+    /// programming mode. Returns `None` on timeout.
+    fn wait_for_programming_device(&self) -> Result<Option<Device<GlobalContext>>> {
+        self.console
+            .print("  [bold #f39c12]Switch the pedal to programming mode to continue:[/]");
+        self.print_mode_switch_steps(PedalMode::Program);
+        self.console.print("");
+        self.console.print(
+            "  [#95a5a6]Waiting for programming mode...[/] [dim](60s timeout, Ctrl+C to cancel)[/]",
+        );
+        self.console.print("");
+
+        let wait_start = std::time::Instant::now();
+        let timeout = Duration::from_secs(60);
+        let mut last_reminder = wait_start;
+
+        loop {
+            self.session.refresh_usb();
+            if let (Some(device), _) = self.find_programming_device()? {
+                self.console
+                    .print("  [bold #2ecc71]✓[/] Device detected in programming mode!");
+                self.console.print("");
+                return Ok(Some(device));
+            }
+
+            if wait_start.elapsed() > timeout {
+                self.console.print(
+                    "  [bold #e74c3c]Timeout![/] Device not detected in programming mode after 60s.",
+                );
+                self.console.print("");
+                return Ok(None);
+            }
+
+            // Reminder every 15 seconds
+            if last_reminder.elapsed() > Duration::from_secs(15) {
+                let remaining = timeout.saturating_sub(wait_start.elapsed()).as_secs();
+                self.console.print(&format!(
+                    "  [dim]Still waiting... {}s remaining (switch to Program mode and replug USB)[/]",
+                    remaining
+                ));
+                last_reminder = std::time::Instant::now();
+            }
+
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    /// Locate a Savant Elite in programming mode via libusb, honoring `--unit`.
+    /// Also reports whether a play-mode device was seen, for better error messages.
+    fn find_programming_device(&self) -> Result<(Option<Device<GlobalContext>>, bool)> {
+        self.verbose("Scanning for Savant Elite device via libusb...");
+        let mut play_mode_found = false;
+
+        let devices = self
+            .session
+            .usb_devices()
+            .context("Failed to list USB devices via libusb (try running with sudo)")?;
+        for device in devices {
+            let desc = match device.device_descriptor() {
+                Ok(desc) => desc,
+                Err(_) => continue,
+            };
+            if desc.vendor_id() != KINESIS_VID
+                || PedalMode::from_pid(desc.product_id()).is_none()
+                || !self.matches_unit_filter(&device)
+            {
+                continue;
+            }
+            self.log_event(
+                LogEvent::Device,
+                &format!(
+                    "Found 0x{:04X}:0x{:04X} at Bus {:03} Device {:03}",
+                    desc.vendor_id(),
+                    desc.product_id(),
+                    device.bus_number(),
+                    device.address()
+                ),
+            );
+            match desc.product_id() {
+                PROGRAMMING_PID => return Ok((Some(device), play_mode_found)),
+                SAVANT_ELITE_PID => play_mode_found = true,
+                _ => {}
+            }
+        }
+
+        self.log_event(
+            LogEvent::Device,
+            if play_mode_found {
+                "No pedal in programming mode (found one in play mode)"
+            } else {
+                "No Savant Elite found"
+            },
+        );
+        Ok((None, play_mode_found))
+    }
+
+    /// Detach any kernel driver and claim `interface_num`. The returned guard releases the
+    /// interface and re-attaches the driver when dropped.
+    fn claim_interface<'a>(
+        &self,
+        handle: &'a rusb::DeviceHandle<GlobalContext>,
+        interface_num: u8,
+    ) -> Result<UsbInterfaceGuard<'a>> {
+        let mut detached_kernel_driver = false;
+        if handle.kernel_driver_active(interface_num).unwrap_or(false) {
+            if !self.json_output {
+                self.console.print(&format!(
+                    "  [#f39c12]→[/] Detaching kernel driver from interface {}...",
+                    interface_num
+                ));
+            }
+            handle
+                .detach_kernel_driver(interface_num)
+                .context("Failed to detach kernel driver - try running with sudo")?;
+            detached_kernel_driver = true;
+        }
+
+        let mut interface_guard = UsbInterfaceGuard {
+            handle,
+            interface_num,
+            detached_kernel_driver,
+            claimed: false,
+        };
+
+        handle
+            .claim_interface(interface_num)
+            .context("Failed to claim interface - do you have permission?")?;
+        interface_guard.claimed = true;
+
+        Ok(interface_guard)
+    }
+
+    fn raw_cmd(&self, cmd: &str, data: &str, interface: i32) -> Result<()> {
+        self.console.print("");
+        self.console.print(
+            "[bold #e74c3c]┌─────────────────────────────────────────────────────────────────┐[/]",
+        );
+        self.console.print(
+            "[bold #e74c3c]│[/]  [bold #f39c12]⚠[/]  [bold white]RAW COMMAND MODE[/] [dim](Expert Only)[/]                             [bold #e74c3c]│[/]",
+        );
+        self.console.print(
+            "[bold #e74c3c]└─────────────────────────────────────────────────────────────────┘[/]",
+        );
+        self.console.print("");
+
+        let cmd_byte =
+            u8::from_str_radix(cmd, 16).context("Invalid command byte (use hex, e.g., 'b5')")?;
+
+        let data_bytes: Vec<u8> = if data.is_empty() {
+            vec![]
+        } else {
+            hex::decode(data).context("Invalid data bytes (use hex)")?
+        };
+
+        // Validate data length (buffer is 36 bytes: 1 report ID + 1 command + 34 data)
+        const MAX_RAW_DATA_LEN: usize = 34;
+        if data_bytes.len() > MAX_RAW_DATA_LEN {
+            return Err(anyhow!(
+                "Data too long: {} bytes exceeds maximum {} bytes",
+                data_bytes.len(),
+                MAX_RAW_DATA_LEN
+            ));
+        }
+
+        let api = self.session.hid().context("Failed to initialize HID API")?;
+
+        let mut found = false;
+        for device_info in api.device_list() {
+            if device_info.vendor_id() == KINESIS_VID
+                && (device_info.product_id() == SAVANT_ELITE_PID
+                    || device_info.product_id() == PROGRAMMING_PID)
+                && device_info.interface_number() == interface
+            {
+                found = true;
+                let device = device_info
+                    .open_device(&api)
+                    .context("Failed to open device")?;
+
+                let mut cmd_buf = [0u8; 36];
+                cmd_buf[0] = 0;
+                cmd_buf[1] = cmd_byte;
+                for (i, b) in data_bytes.iter().enumerate() {
+                    if i + 2 < cmd_buf.len() {
+                        cmd_buf[i + 2] = *b;
+                    }
+                }
+
+                self.console.print(&format!(
+                    "  [bold #3498db]→[/] Sending: [bold #f1c40f]{}[/]",
+                    hex::encode(&cmd_buf[..8])
+                ));
+
+                let written = device.write(&cmd_buf);
+                self.oplog.append(
+                    LogEvent::Transfer,
+                    &format!(
+                        "HID write 0x{:02X} interface {}: {}",
+                        cmd_byte,
+                        interface,
+                        match &written {
+                            Ok(n) => format!("ok ({} bytes)", n),
+                            Err(e) => format!("failed: {}", e),
+                        }
+                    ),
+                );
+                let n = written.context("Write error")?;
+                self.console
+                    .print(&format!("  [bold #2ecc71]✓[/] Sent [bold]{}[/] bytes", n));
+
+                std::thread::sleep(Duration::from_millis(100));
+                let mut response = [0u8; 64];
+                match device.read_timeout(&mut response, 500) {
+                    Ok(len) if len > 0 => {
+                        self.console.print(&format!(
+                            "  [bold #2ecc71]←[/] Response ([bold]{}[/] bytes): [#7f8c8d]{}[/]",
+                            len,
+                            hex::encode(&response[..len])
+                        ));
+                    }
+                    Ok(_) => {
+                        self.console.print("  [dim]← No response[/]");
+                    }
+                    Err(e) => {
+                        self.console
+                            .print(&format!("  [#e74c3c]✗[/] Read error: {}", e));
+                    }
+                }
+
+                break;
+            }
+        }
+
+        if !found {
+            return Err(anyhow!(
+                "No Savant Elite device found for interface {} (try `savant info` or `savant status`)",
+                interface
+            ));
+        }
+
+        self.console.print("");
+        Ok(())
+    }
+
+    /// Decide whether the pedals sent before Ctrl+C are saved to EEPROM (`--on-interrupt`)
+    fn save_after_interrupt(&self, on_interrupt: OnInterrupt, written: usize) -> Result<bool> {
+        use std::io::{BufRead, IsTerminal};
+
+        match on_interrupt {
+            OnInterrupt::Save => Ok(true),
+            OnInterrupt::Skip => Ok(false),
+            OnInterrupt::Ask if self.json_output || !std::io::stdin().is_terminal() => {
+                self.console.print(
+                    "  [dim]Not a terminal, so not asking; skipping the EEPROM save (see --on-interrupt).[/]",
+                );
+                Ok(false)
+            }
+            OnInterrupt::Ask => {
+                self.console.print(&format!(
+                    "  Save the {} pedal(s) written so far to EEPROM? (y/N) ",
+                    written
+                ));
+                let mut answer = String::new();
+                std::io::stdin().lock().read_line(&mut answer)?;
+                Ok(matches!(
+                    answer.trim().to_ascii_lowercase().as_str(),
+                    "y" | "yes"
+                ))
+            }
+        }
+    }
+
+    /// With `--json`, print what a dry run of `program` would do: requested pedals are
+    /// "planned", the rest "unchanged"
+    fn print_dry_run_json(
+        &self,
+        actions: &[Option<KeyAction>; 3],
+        display: &[String; 3],
+    ) -> Result<()> {
+        if !self.json_output {
+            return Ok(());
+        }
+        let pedals = PedalPosition::ALL
+            .into_iter()
+            .map(|position| JsonProgramPedal {
+                pedal: position.name().to_string(),
+                action: display[position.index()].clone(),
+                status: if actions[position.index()].is_some() {
+                    "planned"
+                } else {
+                    "unchanged"
+                }
+                .to_string(),
+                method: None,
+                verified: None,
+            })
+            .collect();
+        let output = JsonProgramOutput {
+            success: true,
+            dry_run: true,
+            unchanged: actions.iter().all(Option::is_none),
+            pedals,
+            eeprom: "planned".to_string(),
+            reboot_verified: None,
+            interrupted: false,
+            retries: 0,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        Ok(())
+    }
+
+    /// Program the requested pedals. A `None` action leaves that pedal untouched on the device;
+    /// its saved value (if any) is still shown and kept in the on-disk record.
+    fn program(
+        &self,
+        left: Option<&str>,
+        middle: Option<&str>,
+        right: Option<&str>,
+        options: ProgramOptions,
+    ) -> Result<()> {
+        let ProgramOptions {
+            dry_run,
+            auto_monitor,
+            reboot,
+            details,
+            command,
+            wait_for_programming,
+            on_interrupt,
+            interface,
+            transport,
+            force,
+        } = options;
+        self.print_banner();
+
+        self.console.print(
+            "[bold #2ecc71]┌─────────────────────────────────────────────────────────────────┐[/]",
+        );
+        self.console.print(
+            "[bold #2ecc71]│[/]  [bold #f39c12]⚡[/] [bold white]PEDAL PROGRAMMING[/]                                           [bold #2ecc71]│[/]",
+        );
+        self.console.print(
+            "[bold #2ecc71]└─────────────────────────────────────────────────────────────────┘[/]",
+        );
+        self.console.print("");
+
+        let os = self.target_os();
+        self.verbose(&format!("Resolving shortcuts for {}", os.label()));
+        self.verbose(&format!(
+            "Transport: {}, interface {}",
+            transport.name(),
+            interface.map_or_else(
+                || "0 (any HID interface for interrupt writes)".to_string(),
+                |n| n.to_string()
+            )
+        ));
+
+        // Validate key actions upfront (before any device operations)
+        // This ensures we fail fast on invalid input, even if no device is connected
+        let requested = [left, middle, right];
+        let mut actions: [Option<KeyAction>; 3] = [None, None, None];
+        for (position, value) in PedalPosition::ALL.into_iter().zip(requested) {
+            let Some(value) = value else {
+                self.verbose(&format!(
+                    "{} pedal not requested; leaving it unchanged",
+                    position.label()
+                ));
+                continue;
+            };
+            self.verbose(&format!(
+                "Parsing {} pedal action: '{}'",
+                position.name(),
+                value
+            ));
+            let action = KeyAction::parse_for(value, os)?;
+            self.verbose(&format!(
+                "  -> modifiers=0x{:02X}, key=0x{:02X}",
+                action.modifiers, action.key
+            ));
+            actions[position.index()] = Some(action);
+        }
+
+        // Untouched pedals keep whatever the saved config says they hold
+        let saved = PedalConfig::load();
+        let mut record: [Option<String>; 3] = PedalPosition::ALL.map(|position| {
+            requested[position.index()]
+                .map(str::to_string)
+                .or_else(|| saved.as_ref().map(|c| c.get(position).to_string()))
+        });
+        let display = record
+            .clone()
+            .map(|value| value.unwrap_or_else(|| "?".to_string()));
+        let (left, middle, right) = (
+            display[0].as_str(),
+            display[1].as_str(),
+            display[2].as_str(),
+        );
+
+        let untouched: Vec<&str> = PedalPosition::ALL
+            .into_iter()
+            .filter(|p| actions[p.index()].is_none())
+            .map(PedalPosition::label)
+            .collect();
+        if !untouched.is_empty() {
+            self.console.print(&format!(
+                "  [#95a5a6]Leaving unchanged on the device:[/] [bold]{}[/]",
+                untouched.join(", ")
+            ));
+            self.console.print("");
+        }
+
+        // Lint the pedals as they will end up, but only report on untouched pedals when
+        // they now clash with a pedal being written
+        let effective = PedalPosition::ALL.map(|position| {
+            actions[position.index()].clone().or_else(|| {
+                record[position.index()]
+                    .as_deref()
+                    .and_then(|value| KeyAction::parse_for(value, os).ok())
+            })
+        });
+        let findings: Vec<LintFinding> = self
+            .lint(&effective, os)
+            .into_iter()
+            .filter(|f| actions[f.pedal.index()].is_some() || f.code == LintCode::Duplicate)
+            .collect();
+        if !findings.is_empty() {
+            self.print_lint_findings(&findings, os);
+            self.console.print("");
+        }
+        let blocking: Vec<&LintFinding> = findings
+            .iter()
+            .filter(|f| f.severity == LintSeverity::Error)
+            .collect();
+        if !blocking.is_empty() {
+            let mut codes: Vec<&str> = blocking.iter().map(|f| f.code.name()).collect();
+            codes.dedup();
+            self.console.print(&format!(
+                "  [#95a5a6]Nothing was written. If you really want this, add[/] [bold]{}[/]",
+                codes
+                    .iter()
+                    .map(|code| format!("--allow {}", code))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ));
+            self.console.print("");
+            return Err(anyhow!(
+                "Refusing to program: {}",
+                blocking
+                    .iter()
+                    .map(|f| format!("{} pedal {}", f.pedal.name(), f.message))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ));
+        }
+
+        // Check if device is in programming mode using libusb
+        let (mut programming_device, play_mode_found) = self.find_programming_device()?;
+        if programming_device.is_none() && play_mode_found && wait_for_programming && !dry_run {
+            programming_device = self.wait_for_programming_device()?;
+        }
+
+        let Some(device) = programming_device else {
+            // Show what would be programmed (preview)
+            self.print_pedal_visualization(left, middle, right);
+
+            if play_mode_found {
+                self.console.print(
+                    "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
+                );
+                self.console.print(
+                    "  [bold #e74c3c]│[/]  [bold #e74c3c]✗[/]  [bold white]Device is in PLAY mode, not PROGRAMMING mode[/]          [bold #e74c3c]│[/]",
+                );
+                self.console.print(
+                    "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
+                );
+                self.console.print("");
+                self.console
+                    .print("  [bold #f39c12]To program these keys, enter programming mode:[/]");
+                self.print_mode_switch_steps(PedalMode::Program);
+                self.console
+                    .print("    [bold #3498db]5.[/] Run [bold #f1c40f]savant program[/] again");
+            } else {
+                self.console.print(
+                    "  [bold #e74c3c]✗[/] Savant Elite not found. Make sure it's connected.",
+                );
+            }
+            self.console.print("");
+            if dry_run {
+                self.print_dry_run_json(&actions, &display)?;
+            }
+            return Ok(());
+        };
+
+        // Capture device location for disconnect detection
+        let watch = self.session.watch(&device);
+        let device_bus = device.bus_number();
+        let device_addr = device.address();
+        let device_ports = device.port_numbers().unwrap_or_default();
+
+        // Show configuration table
+        self.console
+            .print("  [bold #f39c12]Configuration to program:[/]");
+        self.console.print("");
+
+        let mut config_table = Table::new()
+            .box_style(&ROUNDED)
+            .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
+            .border_style(Style::parse("#3498db").unwrap_or_default())
+            .with_column(Column::new("Pedal"))
+            .with_column(Column::new("Action"))
+            .with_column(Column::new("Modifier"))
+            .with_column(Column::new("Key Code"));
+
+        for (position, action) in PedalPosition::ALL.into_iter().zip(&actions) {
+            let (action_text, modifier, key) = match action {
+                Some(action) => (
+                    display[position.index()].clone(),
+                    format!("0x{:02X}", action.modifiers),
+                    format!("0x{:02X}", action.key),
+                ),
+                None => (
+                    format!("[dim]{} (unchanged)[/]", display[position.index()]),
+                    "-".to_string(),
+                    "-".to_string(),
+                ),
+            };
+            config_table.add_row_cells([
+                markup::render_or_plain(position.table_label()),
+                markup::render_or_plain(&action_text),
+                markup::render_or_plain(&modifier),
+                markup::render_or_plain(&key),
+            ]);
+        }
+
+        self.console.print_renderable(&config_table);
+        self.console.print("");
+
+        if dry_run {
+            // Show visual pedal configuration preview
+            self.print_pedal_visualization(left, middle, right);
+
+            self.console.print(
+                "  [bold #f39c12]╭────────────────────────────────────────────────────────────╮[/]",
+            );
+            self.console.print(
+                "  [bold #f39c12]│[/]  [bold #f39c12]⚠[/]  [bold white]DRY RUN - No changes will be made[/]                     [bold #f39c12]│[/]",
+            );
+            self.console.print(
+                "  [bold #f39c12]╰────────────────────────────────────────────────────────────╯[/]",
+            );
+            self.console.print("");
+            self.console.print(&format!(
+                "  [#95a5a6]Would send the following commands:[/] [dim](transport: {}, interface {})[/]",
+                transport.name(),
+                interface.unwrap_or(0)
+            ));
+            for (position, action) in PedalPosition::ALL.into_iter().zip(&actions) {
+                match action {
+                    Some(action) => self.console.print(&format!(
+                        "    [#3498db]→[/] SET_KEY_MACRO (0xCC) for pedal {}: mod=0x{:02X}, key=0x{:02X}",
+                        position.index(),
+                        action.modifiers,
+                        action.key
+                    )),
+                    None => self.console.print(&format!(
+                        "    [dim]  (pedal {} left unchanged)[/]",
+                        position.index()
+                    )),
+                }
+            }
+            self.console
+                .print("    [#3498db]→[/] SAVE_TO_EEPROM (0xCE)");
+            if reboot {
+                self.console.print(
+                    "    [#3498db]→[/] REBOOT (0xCB), then GET_KEY_MACRO (0xCD) read-back after re-enumeration",
+                );
+            }
+            self.console.print("");
+            self.print_dry_run_json(&actions, &display)?;
+            return Ok(());
+        }
+
+        // Pre-programming warning about keeping device connected
+        self.console.print(
+            "  [bold #f39c12]⚠[/]  [#f39c12]Keep the device connected during programming.[/]",
+        );
+        self.console.print(
+            "     [dim]Unplugging mid-operation may leave pedals in a partial state (in RAM, not saved).[/]",
+        );
+        self.console.print("");
+
+        // Open device
+        let _device_lock = lock_device(&device)?;
+        let interrupt = InterruptGuard::install();
+        self.verbose("Opening USB device...");
+        let handle = device
+            .open()
+            .context("Failed to open device (try running with sudo)")?;
+        self.verbose("USB device opened successfully");
+
+        // Get device config to find endpoints
+        let config = device
+            .active_config_descriptor()
+            .or_else(|_| device.config_descriptor(0))
+            .context("Failed to read USB configuration descriptor")?;
+        self.console.print(&format!(
+            "  [#95a5a6]Device has[/] [bold]{}[/] [#95a5a6]interface(s)[/]",
+            config.num_interfaces()
+        ));
+
+        // Claim the interface control requests are addressed to (0 unless --interface)
+        let interface_num = interface.unwrap_or(0);
+        if !config.interfaces().any(|i| i.number() == interface_num) {
+            return Err(anyhow!("The device has no interface {}", interface_num));
+        }
+        let _interface_guard = self.claim_interface(&handle, interface_num)?;
+        let mut claimed = vec![interface_num];
+
+        // Interrupt OUT endpoints need their interface claimed too; in auto mode one that
+        // can't be claimed is just left out
+        let mut interrupt_outs = match transport {
+            Transport::Control => Vec::new(),
+            _ => interrupt_out_endpoints(&config, interface),
+        };
+        let mut interrupt_guards = Vec::new();
+        interrupt_outs.retain(|ep| {
+            if claimed.contains(&ep.interface) {
+                return true;
+            }
+            match self.claim_interface(&handle, ep.interface) {
+                Ok(guard) => {
+                    interrupt_guards.push(guard);
+                    claimed.push(ep.interface);
+                    true
+                }
+                Err(e) => {
+                    self.verbose(&format!(
+                        "Not using interrupt endpoint 0x{:02X}: {:#}",
+                        ep.address, e
+                    ));
+                    false
+                }
+            }
+        });
+        if transport == Transport::Interrupt && interrupt_outs.is_empty() {
+            return Err(anyhow!(
+                "No usable interrupt OUT endpoint on {}; try --transport control",
+                match interface {
+                    Some(n) => format!("interface {}", n),
+                    None => "the device's HID interfaces".to_string(),
+                }
+            ));
+        }
 
-    fn print_banner(&self) {
+        self.console.print(&format!(
+            "  [bold #2ecc71]✓[/] Claimed interface [bold]{}[/] [dim](transport: {})[/]",
+            claimed
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            transport.name()
+        ));
         self.console.print("");
+
+        // Log endpoint information for debugging
         self.console
-            .print("[bold #ff6b6b]╔══════════════════════════════════════════════════════════╗[/]");
-        self.console.print(
-            "[bold #ff6b6b]║[/]                                                          [bold #ff6b6b]║[/]",
-        );
-        self.console.print(
-            "[bold #ff6b6b]║[/]   [bold #4ecdc4]SAVANT ELITE[/]  -  [bold #ffe66d]Kinesis Foot Pedal Programmer[/]         [bold #ff6b6b]║[/]",
-        );
+            .print("  [bold #9b59b6]Endpoint Information:[/]");
+        for interface in config.interfaces() {
+            for desc in interface.descriptors() {
+                self.console.print(&format!(
+                    "    [dim]Interface {}:[/] class={} subclass={} protocol={}",
+                    desc.interface_number(),
+                    desc.class_code(),
+                    desc.sub_class_code(),
+                    desc.protocol_code()
+                ));
+                for ep in desc.endpoint_descriptors() {
+                    let dir = match ep.direction() {
+                        rusb::Direction::Out => "[#e74c3c]OUT[/]",
+                        rusb::Direction::In => "[#2ecc71]IN[/]",
+                    };
+                    let transfer = match ep.transfer_type() {
+                        rusb::TransferType::Control => "Control",
+                        rusb::TransferType::Isochronous => "Isochronous",
+                        rusb::TransferType::Bulk => "Bulk",
+                        rusb::TransferType::Interrupt => "Interrupt",
+                    };
+                    self.console.print(&format!(
+                        "      [#7f8c8d]Endpoint 0x{:02X}:[/] {} {} [dim](max: {})[/]",
+                        ep.address(),
+                        dir,
+                        transfer,
+                        ep.max_packet_size()
+                    ));
+                }
+            }
+        }
+        self.console.print("");
+
+        // Program each requested pedal using HID SET_REPORT
+        let pedals: Vec<(u8, &KeyAction, &str, &str)> = PedalPosition::ALL
+            .into_iter()
+            .zip(&actions)
+            .filter_map(|(position, action)| {
+                Some((
+                    position.index() as u8,
+                    action.as_ref()?,
+                    position.label(),
+                    position.color(),
+                ))
+            })
+            .collect();
+
         self.console.print(
-            "[bold #ff6b6b]║[/]                                                          [bold #ff6b6b]║[/]",
+            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
         );
-        self.console
-            .print("[bold #ff6b6b]╚══════════════════════════════════════════════════════════╝[/]");
         self.console.print("");
-    }
 
-    fn print_pedal_visualization(&self, left: &str, middle: &str, right: &str) {
-        // Helper to center text in a fixed width
-        fn center(s: &str, width: usize) -> String {
-            let len = s.chars().count();
-            if len >= width {
-                s.chars().take(width).collect()
+        let mut pedal_failures: Vec<&str> = Vec::new();
+        let mut last_method: Option<&str> = None;
+        // Pedals a SET_KEY_MACRO was sent for (or found already set) before any interrupt
+        let mut reached = [false; 3];
+        let mut outcomes: Vec<JsonProgramPedal> = Vec::new();
+        // Stop reading pedals first once the firmware shows it can't answer GET_KEY_MACRO
+        let mut readback_supported = true;
+        for &(pedal_idx, action, name, color) in &pedals {
+            if interrupt.interrupted() {
+                break;
+            }
+            reached[pedal_idx as usize] = true;
+            self.console.print(&format!(
+                "  [bold {}]▸[/] Programming [bold white]{}[/] pedal...",
+                color, name
+            ));
+            let outcome =
+                |status: &str, method: Option<&str>, verified: Option<bool>| JsonProgramPedal {
+                    pedal: PedalPosition::ALL[pedal_idx as usize].name().to_string(),
+                    action: display[pedal_idx as usize].clone(),
+                    status: status.to_string(),
+                    method: method.map(str::to_string),
+                    verified,
+                };
+
+            // Read first: a pedal that already holds this key costs no EEPROM write cycle
+            if !force && readback_supported {
+                match self.verify_pedal_programming(
+                    &handle,
+                    interface_num,
+                    pedal_idx,
+                    action.modifiers,
+                    action.key,
+                ) {
+                    Ok(true) => {
+                        self.log_event(
+                            LogEvent::Pedal,
+                            &format!("{} pedal already set; not written", name),
+                        );
+                        self.console.print(
+                            "    [bold #2ecc71]✓[/] [#95a5a6]Already set[/] [dim](read back; not written)[/]",
+                        );
+                        outcomes.push(outcome("unchanged", None, None));
+                        continue;
+                    }
+                    Ok(false) => {}
+                    Err(_) => readback_supported = false,
+                }
+            }
+
+            // Try multiple data formats and transfer methods
+            let mut success = false;
+            let mut success_method = "";
+            let pedal_start = self.retry_stats.get();
+
+            // Format 1: Command as first byte, pedal, mods, key
+            let cmd1 = [
+                xkeys_protocol::CMD_SET_KEY_MACRO,
+                pedal_idx,
+                action.modifiers,
+                action.key,
+                0,
+                0,
+                0,
+                0,
+            ];
+
+            // Format 2: Report ID 0, then command in data
+            let cmd2 = [
+                0u8,
+                xkeys_protocol::CMD_SET_KEY_MACRO,
+                pedal_idx,
+                action.modifiers,
+                action.key,
+                0,
+                0,
+                0,
+            ];
+
+            // Format 3: Report ID conveys the command, payload is pedal+mod+key.
+            let cmd3_payload = [pedal_idx, action.modifiers, action.key, 0, 0, 0, 0, 0];
+
+            // Try SET_REPORT with multiple (report-id, layout) combinations.
+            //
+            // Different firmware revisions appear to expect one of:
+            // - report_id = 0 with the command as the first data byte (no leading report-id byte),
+            // - report_id = 0 with a leading 0 report-id byte (hidapi-style),
+            // - report_id = CMD with either data starting at CMD or with a compact payload.
+            if transport != Transport::Interrupt {
+                for (fmt_name, w_value, data) in [
+                    (
+                        "feat-rid0-cmd",
+                        usb_constants::HID_REPORT_TYPE_FEATURE,
+                        &cmd1[..],
+                    ),
+                    (
+                        "feat-rid0-prefix",
+                        usb_constants::HID_REPORT_TYPE_FEATURE,
+                        &cmd2[..],
+                    ),
+                    (
+                        "feat-ridcmd",
+                        usb_constants::HID_REPORT_TYPE_FEATURE
+                            | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
+                        &cmd1[..],
+                    ),
+                    (
+                        "feat-ridcmd-payload",
+                        usb_constants::HID_REPORT_TYPE_FEATURE
+                            | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
+                        &cmd3_payload[..],
+                    ),
+                    (
+                        "out-rid0-cmd",
+                        usb_constants::HID_REPORT_TYPE_OUTPUT,
+                        &cmd1[..],
+                    ),
+                    (
+                        "out-rid0-prefix",
+                        usb_constants::HID_REPORT_TYPE_OUTPUT,
+                        &cmd2[..],
+                    ),
+                    (
+                        "out-ridcmd",
+                        usb_constants::HID_REPORT_TYPE_OUTPUT
+                            | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
+                        &cmd1[..],
+                    ),
+                    (
+                        "out-ridcmd-payload",
+                        usb_constants::HID_REPORT_TYPE_OUTPUT
+                            | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
+                        &cmd3_payload[..],
+                    ),
+                ] {
+                    let result = self.transfer(
+                        &format!("SET_REPORT SET_KEY_MACRO pedal {} {}", pedal_idx, fmt_name),
+                        || {
+                            handle.write_control(
+                                usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
+                                usb_constants::HID_SET_REPORT,
+                                w_value,
+                                interface_num as u16,
+                                data,
+                                Duration::from_millis(self.timeout_ms),
+                            )
+                        },
+                    );
+                    if result.is_ok() {
+                        success = true;
+                        success_method = fmt_name;
+                        break;
+                    }
+                }
+
+                // Try with longer buffer (36 bytes like PI Engineering)
+                if !success {
+                    let mut long_prefixed = [0u8; 36];
+                    long_prefixed[0] = 0;
+                    long_prefixed[1] = xkeys_protocol::CMD_SET_KEY_MACRO;
+                    long_prefixed[2] = pedal_idx;
+                    long_prefixed[3] = action.modifiers;
+                    long_prefixed[4] = action.key;
+
+                    let mut long_unprefixed = [0u8; 36];
+                    long_unprefixed[0] = xkeys_protocol::CMD_SET_KEY_MACRO;
+                    long_unprefixed[1] = pedal_idx;
+                    long_unprefixed[2] = action.modifiers;
+                    long_unprefixed[3] = action.key;
+
+                    for (fmt_name, w_value, data) in [
+                        (
+                            "36b-out-prefix",
+                            usb_constants::HID_REPORT_TYPE_OUTPUT,
+                            &long_prefixed[..],
+                        ),
+                        (
+                            "36b-out-cmd",
+                            usb_constants::HID_REPORT_TYPE_OUTPUT,
+                            &long_unprefixed[..],
+                        ),
+                        (
+                            "36b-feat-prefix",
+                            usb_constants::HID_REPORT_TYPE_FEATURE,
+                            &long_prefixed[..],
+                        ),
+                        (
+                            "36b-feat-cmd",
+                            usb_constants::HID_REPORT_TYPE_FEATURE,
+                            &long_unprefixed[..],
+                        ),
+                    ] {
+                        let result = self.transfer(
+                            &format!("SET_REPORT SET_KEY_MACRO pedal {} {}", pedal_idx, fmt_name),
+                            || {
+                                handle.write_control(
+                                    usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
+                                    usb_constants::HID_SET_REPORT,
+                                    w_value,
+                                    interface_num as u16,
+                                    data,
+                                    Duration::from_millis(self.timeout_ms),
+                                )
+                            },
+                        );
+                        if result.is_ok() {
+                            success = true;
+                            success_method = fmt_name;
+                            break;
+                        }
+                    }
+                }
+
+                // Try vendor-specific request
+                if !success {
+                    let result =
+                        self.transfer(&format!("vendor SET_KEY_MACRO pedal {}", pedal_idx), || {
+                            handle.write_control(
+                                usb_constants::USB_REQUEST_TYPE_VENDOR_OUT,
+                                xkeys_protocol::CMD_SET_KEY_MACRO,
+                                ((action.key as u16) << 8) | (action.modifiers as u16),
+                                pedal_idx as u16,
+                                &[],
+                                Duration::from_millis(self.timeout_ms),
+                            )
+                        });
+                    if result.is_ok() {
+                        success = true;
+                        success_method = "vendor";
+                    }
+                }
+            }
+
+            // Output reports on the interrupt pipe (empty endpoint list with --transport control)
+            if !success {
+                if let Some(method) = self.send_interrupt_command(
+                    &handle,
+                    &interrupt_outs,
+                    xkeys_protocol::CMD_SET_KEY_MACRO,
+                    &[pedal_idx, action.modifiers, action.key],
+                ) {
+                    success = true;
+                    success_method = method;
+                }
+            }
+
+            if success {
+                last_method = Some(success_method);
+                self.console.print(&format!(
+                    "    [bold #2ecc71]✓[/] [#95a5a6]Success[/] [dim]({}{})[/]",
+                    success_method,
+                    self.retry_note(pedal_start)
+                ));
+
+                // Attempt read-back verification using GET_KEY_MACRO (0xCD)
+                // This is best-effort - some firmware versions may not support it
+                std::thread::sleep(Duration::from_millis(50));
+                let verified = self.verify_pedal_programming(
+                    &handle,
+                    interface_num,
+                    pedal_idx,
+                    action.modifiers,
+                    action.key,
+                );
+                self.oplog.append(
+                    LogEvent::Pedal,
+                    &format!(
+                        "{} pedal programmed ({}), read-back {}",
+                        name,
+                        success_method,
+                        match verified {
+                            Ok(true) => "verified",
+                            Ok(false) => "MISMATCH",
+                            Err(_) => "not supported",
+                        }
+                    ),
+                );
+                match verified {
+                    Ok(true) => {
+                        self.console.print("    [dim]✓ Verified[/]");
+                    }
+                    Ok(false) => {
+                        self.console.print(
+                            "    [bold #f39c12]⚠[/] [#f39c12]Read-back mismatch - verify manually after switching to Play mode[/]",
+                        );
+                    }
+                    Err(_) => {
+                        // Verification not supported or failed - that's OK, just skip silently
+                    }
+                }
+                outcomes.push(outcome("written", Some(success_method), verified.ok()));
             } else {
-                let pad = width - len;
-                let left_pad = pad / 2;
-                let right_pad = pad - left_pad;
-                format!("{}{}{}", " ".repeat(left_pad), s, " ".repeat(right_pad))
+                self.console
+                    .print("    [bold #e74c3c]✗[/] [#e74c3c]Failed[/]");
+                self.log_event(
+                    LogEvent::Pedal,
+                    &format!("{} pedal FAILED: rejected in every format", name),
+                );
+                pedal_failures.push(name);
+                outcomes.push(outcome("failed", None, None));
+            }
+
+            std::thread::sleep(Duration::from_millis(50));
+
+            // Check if device is still connected after programming this pedal
+            if !self.session.still_connected(&watch) {
+                self.log_event(
+                    LogEvent::Disconnect,
+                    &format!("Device disconnected after programming the {} pedal", name),
+                );
+                self.console.print("");
+                self.console.print(
+                    "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
+                );
+                self.console.print(
+                    "  [bold #e74c3c]│[/]  [bold #e74c3c]⚠[/]  [bold white]DEVICE DISCONNECTED![/]                                  [bold #e74c3c]│[/]",
+                );
+                self.console.print(
+                    "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
+                );
+                self.console.print("");
+                self.console.print(
+                    "  [bold #f39c12]WARNING:[/] The device was unplugged during programming.",
+                );
+                self.console.print(
+                    "  [#95a5a6]Pedals programmed so far were stored in RAM only (not saved to EEPROM).[/]",
+                );
+                self.console
+                    .print("  [#95a5a6]Those changes were lost when the device was unplugged.[/]");
+                self.console.print("");
+                self.console
+                    .print("  [bold #f39c12]To complete programming:[/]");
+                self.console.print(
+                    "    [bold #3498db]1.[/] Reconnect the device (keep in Programming mode)",
+                );
+                self.console
+                    .print("    [bold #3498db]2.[/] Run [bold #f1c40f]savant program[/] again");
+                self.console.print("");
+                return Ok(());
             }
         }
 
-        // Format key action for display (e.g., "cmd+c" -> "⌘C")
-        fn format_key(s: &str) -> String {
-            let s = s.to_lowercase();
-            let parts: Vec<&str> = s.split('+').collect();
-            let mut result = String::new();
+        self.console.print("");
 
-            for (i, part) in parts.iter().enumerate() {
-                let part = part.trim();
-                if i < parts.len() - 1 {
-                    // Modifier
-                    match part {
-                        "cmd" | "command" | "gui" | "meta" | "super" => result.push('⌘'),
-                        "ctrl" | "control" => result.push('⌃'),
-                        "shift" => result.push('⇧'),
-                        "alt" | "option" | "opt" => result.push('⌥'),
-                        _ => result.push_str(part),
-                    }
-                } else {
-                    // Key - uppercase for display
-                    result.push_str(&part.to_uppercase());
+        // Ctrl+C stops before the next pedal; the user decides whether what was sent is kept
+        let interrupted = interrupt.interrupted();
+        let mut skip_save = false;
+        if interrupted {
+            let written = outcomes.iter().filter(|o| o.status == "written").count();
+            self.log_event(
+                LogEvent::Interrupt,
+                &format!("Interrupted after {} of {} pedal(s)", written, pedals.len()),
+            );
+            self.console.print(&format!(
+                "  [bold #f39c12]⚠[/]  [bold #f39c12]Interrupted[/] [#95a5a6]after {} of {} pedal(s); the rest were not sent.[/]",
+                written,
+                pedals.len()
+            ));
+            // Pedals never reached still hold what the saved config says
+            for position in PedalPosition::ALL {
+                if !reached[position.index()] {
+                    record[position.index()] = saved.as_ref().map(|c| c.get(position).to_string());
                 }
             }
-            result
+            skip_save = written == 0 || !self.save_after_interrupt(on_interrupt, written)?;
+        }
+
+        // Nothing written and nothing failed: skip the EEPROM write cycle, but only if the
+        // saved config agrees, since read-back sees working memory, which an unsaved earlier
+        // run may have changed
+        let all_set = !force
+            && !interrupted
+            && !outcomes.is_empty()
+            && outcomes.iter().all(|o| o.status == "unchanged");
+        let up_to_date = all_set
+            && saved.as_ref().is_some_and(|saved| {
+                PedalPosition::ALL.into_iter().all(|position| {
+                    actions[position.index()].is_none()
+                        || saved.get(position) == display[position.index()]
+                })
+            });
+        if all_set && !up_to_date {
+            self.console.print(
+                "  [dim]The pedal already holds these keys, but the saved config doesn't say so; saving to EEPROM once.[/]",
+            );
         }
+        skip_save |= up_to_date;
 
-        let left_key = format_key(left);
-        let middle_key = format_key(middle);
-        let right_key = format_key(right);
-
-        // Create centered key displays (max 7 chars for the box interior)
-        let left_display = center(&left_key, 7);
-        let middle_display = center(&middle_key, 7);
-        let right_display = center(&right_key, 7);
-
-        self.console.print("");
-        self.console
-            .print("[bold #9b59b6]┌──────────────────────────────────────────────────────────┐[/]");
-        self.console.print(
-            "[bold #9b59b6]│[/]                 [bold white]YOUR PEDAL CONFIGURATION[/]                 [bold #9b59b6]│[/]",
-        );
-        self.console
-            .print("[bold #9b59b6]└──────────────────────────────────────────────────────────┘[/]");
-        self.console.print("");
-
-        // Top of pedals
-        self.console.print(
-            "       [#e74c3c]╭─────────────╮[/]  [#f39c12]╭─────────────╮[/]  [#2ecc71]╭─────────────╮[/]",
-        );
-        self.console.print(
-            "       [#e74c3c]│[/]             [#e74c3c]│[/]  [#f39c12]│[/]             [#f39c12]│[/]  [#2ecc71]│[/]             [#2ecc71]│[/]",
-        );
-
-        // Pedal labels
-        self.console.print(
-            "       [#e74c3c]│[/]  [bold #e74c3c]◀ LEFT[/]    [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]● MIDDLE[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]RIGHT ▶[/]   [#2ecc71]│[/]",
-        );
-
-        self.console.print(
-            "       [#e74c3c]│[/]             [#e74c3c]│[/]  [#f39c12]│[/]             [#f39c12]│[/]  [#2ecc71]│[/]             [#2ecc71]│[/]",
-        );
-
-        // Key box top
-        self.console.print(
-            "       [#e74c3c]│[/]  [bold #e74c3c]┌───────┐[/]  [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]┌───────┐[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]┌───────┐[/]  [#2ecc71]│[/]",
-        );
+        // Final device presence check before EEPROM save
+        if !skip_save && !self.session.still_connected(&watch) {
+            self.log_event(
+                LogEvent::Disconnect,
+                "Device disconnected before the EEPROM save",
+            );
+            self.console.print(
+                "  [bold #e74c3c]⚠[/]  [bold #e74c3c]Device disconnected before EEPROM save![/]",
+            );
+            self.console.print(
+                "  [#95a5a6]Pedal configurations were sent but NOT saved to permanent storage.[/]",
+            );
+            self.console
+                .print("  [#95a5a6]Reconnect and run [bold #f1c40f]savant program[/] again.[/]");
+            self.console.print("");
+            return Ok(());
+        }
 
-        // Key values
-        self.console.print(&format!(
-            "       [#e74c3c]│[/]  [bold #e74c3c]│[/][bold white]{}[/][bold #e74c3c]│[/]  [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]│[/][bold white]{}[/][bold #f39c12]│[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]│[/][bold white]{}[/][bold #2ecc71]│[/]  [#2ecc71]│[/]",
-            left_display, middle_display, right_display
-        ));
+        // Save to EEPROM
+        let save_start = self.retry_stats.get();
+        let save_success = !skip_save && {
+            self.console
+                .print("  [bold #f1c40f]▸[/] Saving to EEPROM...");
+            (transport != Transport::Interrupt)
+                .then(|| {
+                    self.send_xkeys_command(
+                        &handle,
+                        interface_num,
+                        xkeys_protocol::CMD_SAVE_TO_EEPROM,
+                        &[],
+                    )
+                })
+                .flatten()
+                .or_else(|| {
+                    self.send_interrupt_command(
+                        &handle,
+                        &interrupt_outs,
+                        xkeys_protocol::CMD_SAVE_TO_EEPROM,
+                        &[],
+                    )
+                })
+                .is_some()
+        };
 
-        // Key box bottom
-        self.console.print(
-            "       [#e74c3c]│[/]  [bold #e74c3c]└───────┘[/]  [#e74c3c]│[/]  [#f39c12]│[/]  [bold #f39c12]└───────┘[/]  [#f39c12]│[/]  [#2ecc71]│[/]  [bold #2ecc71]└───────┘[/]  [#2ecc71]│[/]",
-        );
+        if save_success {
+            std::thread::sleep(Duration::from_millis(200));
+            self.log_event(LogEvent::Eeprom, "EEPROM saved");
+            self.console.print(&format!(
+                "    [bold #2ecc71]✓[/] [#95a5a6]EEPROM saved[/]{}",
+                match self.retry_note(save_start).strip_prefix(", ") {
+                    Some(note) => format!(" [dim]({})[/]", note),
+                    None => String::new(),
+                }
+            ));
+        } else if up_to_date {
+            self.log_event(LogEvent::Eeprom, "EEPROM save skipped: nothing changed");
+            self.console
+                .print("  [bold #2ecc71]✓[/] [#95a5a6]Nothing changed; EEPROM save skipped[/]");
+        } else if skip_save {
+            self.log_event(LogEvent::Eeprom, "EEPROM save skipped after interrupt");
+            self.console.print(
+                "  [dim]EEPROM save skipped; the pedals go back to their saved settings when unplugged.[/]",
+            );
+        } else {
+            // Check if failure was due to device disconnect
+            if !self.session.still_connected(&watch) {
+                self.log_event(
+                    LogEvent::Disconnect,
+                    "Device disconnected during the EEPROM save",
+                );
+                self.log_event(LogEvent::Eeprom, "EEPROM save interrupted by disconnect");
+                self.console.print(
+                    "    [bold #e74c3c]⚠[/] [#e74c3c]Device disconnected during EEPROM save![/]",
+                );
+                self.console.print(
+                    "    [#95a5a6]The save was interrupted - settings may or may not have persisted.[/]",
+                );
+                self.console.print(
+                    "    [#95a5a6]Reconnect and test the pedals to verify, or re-run savant program.[/]",
+                );
+            } else {
+                self.log_event(
+                    LogEvent::Eeprom,
+                    "EEPROM save FAILED: rejected in every format",
+                );
+                self.console.print(
+                    "    [bold #f39c12]⚠[/] [#f39c12]Save command may have failed, but programming was done[/]",
+                );
+            }
+        }
 
-        self.console.print(
-            "       [#e74c3c]│[/]             [#e74c3c]│[/]  [#f39c12]│[/]             [#f39c12]│[/]  [#2ecc71]│[/]             [#2ecc71]│[/]",
-        );
+        let eeprom_ok = save_success || up_to_date;
 
-        // Bottom of pedals
-        self.console.print(
-            "       [#e74c3c]╰─────────────╯[/]  [#f39c12]╰─────────────╯[/]  [#2ecc71]╰─────────────╯[/]",
-        );
+        // Optionally prove the save by rebooting and reading the pedals back from EEPROM
+        let mut reboot_ok = true;
+        if reboot && eeprom_ok && !interrupted {
+            let expected: Vec<_> = pedals
+                .iter()
+                .map(|&(pedal_idx, action, name, _)| (pedal_idx, action, name))
+                .collect();
+            reboot_ok = self.reboot_and_verify(
+                &handle,
+                interface_num,
+                (device_bus, &device_ports, device_addr),
+                &expected,
+            );
+        } else if reboot && interrupted {
+            self.console
+                .print("  [dim]Skipping reboot check after the interrupt.[/]");
+        } else if reboot {
+            self.console
+                .print("  [dim]Skipping reboot check because the EEPROM save failed.[/]");
+        }
 
-        // Pedal "stems"
+        self.console.print("");
         self.console.print(
-            "            [#e74c3c]│[/]                  [#f39c12]│[/]                  [#2ecc71]│[/]",
+            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
         );
-        self.console
-            .print("       [dim]═════╧══════════════════╧══════════════════╧═════[/]");
         self.console.print("");
-    }
-
-    fn find_device(&self) -> Result<()> {
-        self.verbose("Initializing HID API...");
-        let api = HidApi::new().context("Failed to initialize HID API")?;
-        self.verbose("HID API initialized successfully");
-
-        // (mode, vid, pid, path, serial, interface, usage_page, usage)
-        type DeviceInfo = (String, String, String, String, String, i32, u16, u16);
-        let mut found_any = false;
-        let mut devices_info: Vec<DeviceInfo> = Vec::new();
-
-        self.verbose("Enumerating HID devices...");
-        for device in api.device_list() {
-            if device.vendor_id() == KINESIS_VID
-                && (device.product_id() == SAVANT_ELITE_PID
-                    || device.product_id() == PROGRAMMING_PID)
-            {
-                found_any = true;
-                let mode = if device.product_id() == PROGRAMMING_PID {
-                    "PROGRAM".to_string()
-                } else {
-                    "PLAY".to_string()
-                };
-                self.verbose(&format!(
-                    "Found Savant Elite ({} mode): VID={:#06X} PID={:#06X} interface={}",
-                    mode,
-                    device.vendor_id(),
-                    device.product_id(),
-                    device.interface_number()
-                ));
-                devices_info.push((
-                    mode,
-                    format!("0x{:04X}", device.vendor_id()),
-                    format!("0x{:04X}", device.product_id()),
-                    device.path().to_string_lossy().to_string(),
-                    device.serial_number().unwrap_or("N/A").to_string(),
-                    device.interface_number(),
-                    device.usage_page(),
-                    device.usage(),
+        // Always save config to preserve user's intent (even on partial success)
+        // This helps with `savant info` display and retry attempts
+        if !program_updates_config(interrupted, skip_save) {
+            // The EEPROM still holds the saved config; the interrupt is in the operation log
+            self.console.print(
+                "  [dim]Note: Nothing was saved to the pedal, so the config file and history were left alone (see `savant log`).[/]",
+            );
+        } else if let [Some(left), Some(middle), Some(right)] = record {
+            let values = [&left, &middle, &right];
+            let details = PedalPosition::ALL.map(|position| {
+                let i = position.index();
+                match (details, &saved) {
+                    (Some(details), _) if !interrupted || reached[i] => details[i].clone(),
+                    (_, Some(saved)) if saved.get(position) == values[i].as_str() => {
+                        saved.details[i].clone()
+                    }
+                    _ => PedalDetails::default(),
+                }
+            });
+            let descriptor = device.device_descriptor().ok();
+            let serial = descriptor
+                .as_ref()
+                .and_then(|desc| handle.read_serial_number_string_ascii(desc).ok())
+                .filter(|serial| !serial.is_empty());
+            let revision = descriptor
+                .as_ref()
+                .map(|desc| format_revision(desc.device_version()));
+            let unit_id = self
+                .unit_filter
+                .or_else(|| self.read_unit_id(&handle, interface_num));
+            let config = PedalConfig {
+                left,
+                middle,
+                right,
+                details,
+                device: Some(DeviceBinding {
+                    serial: serial.clone(),
+                    unit_id,
+                }),
+                last_method: last_method.map(str::to_string),
+                target_os: self
+                    .target_os
+                    .or_else(|| saved.as_ref().and_then(|c| c.target_os)),
+            };
+            let meta = HistoryMeta {
+                command: command.unwrap_or("program").to_string(),
+                device: Some(HistoryDevice { serial, revision }),
+                method: last_method.map(str::to_string),
+                success: Some(pedal_failures.is_empty() && eeprom_ok && reboot_ok && !interrupted),
+                retries: Some(self.retry_stats.get().retries).filter(|&n| n > 0),
+                interrupted: interrupted.then_some(true),
+            };
+            if let Err(e) = config.save(&meta) {
+                self.console.print(&format!(
+                    "  [dim]Note: Could not save config to disk: {}[/]",
+                    e
                 ));
             }
-        }
-
-        // Load config
-        self.verbose(&format!(
-            "Loading config from: {}",
-            PedalConfig::config_path().display()
-        ));
-        let config = PedalConfig::load();
-        if let Some(ref cfg) = config {
-            self.verbose(&format!(
-                "Config loaded: left={}, middle={}, right={}",
-                cfg.left, cfg.middle, cfg.right
-            ));
         } else {
-            self.verbose("No saved config found");
+            self.console.print(
+                "  [dim]Note: No saved configuration for the untouched pedals, so the config file was not updated.[/]",
+            );
         }
 
-        // JSON output mode
         if self.json_output {
-            let mut interfaces: Vec<JsonDeviceInterface> = Vec::new();
-            let mut seen_interfaces = std::collections::HashSet::new();
-
-            for (mode, vid, pid, _path, _serial, iface, usage_page, usage) in &devices_info {
-                if seen_interfaces.insert((*iface, *usage_page, *usage)) {
-                    interfaces.push(JsonDeviceInterface {
-                        mode: mode.to_lowercase(),
-                        vid: vid.clone(),
-                        pid: pid.clone(),
-                        interface: *iface,
-                        usage_page: format!("0x{:04X}", usage_page),
-                        usage: format!("0x{:04X}", usage),
+            for &(pedal_idx, _, _, _) in &pedals {
+                if !reached[pedal_idx as usize] {
+                    outcomes.push(JsonProgramPedal {
+                        pedal: PedalPosition::ALL[pedal_idx as usize].name().to_string(),
+                        action: display[pedal_idx as usize].clone(),
+                        status: "not_sent".to_string(),
+                        method: None,
+                        verified: None,
                     });
                 }
             }
-
-            let (path, serial) = devices_info.first().map_or((None, None), |d| {
-                let serial = if d.4.is_empty() || d.4 == "N/A" {
-                    None
+            let output = JsonProgramOutput {
+                success: pedal_failures.is_empty() && eeprom_ok && reboot_ok && !interrupted,
+                dry_run: false,
+                unchanged: up_to_date,
+                pedals: outcomes,
+                eeprom: if save_success {
+                    "saved"
+                } else if up_to_date {
+                    "unchanged"
+                } else if skip_save {
+                    "skipped"
                 } else {
-                    Some(d.4.clone())
-                };
-                (Some(d.3.clone()), serial)
-            });
-
-            let mode = devices_info.first().map(|d| d.0.to_lowercase());
-            let pid = devices_info.first().map(|d| d.2.clone());
-
-            let json_config = config.map(|cfg| JsonConfig {
-                source: "file".to_string(),
-                path: PedalConfig::config_path().display().to_string(),
-                left: cfg.left,
-                middle: cfg.middle,
-                right: cfg.right,
-            });
-
-            let output = JsonInfoOutput {
-                device: JsonDeviceInfo {
-                    detected: found_any,
-                    mode,
-                    vid: format!("0x{:04X}", KINESIS_VID),
-                    pid,
-                    path,
-                    serial,
-                    interfaces,
-                },
-                config: json_config,
+                    "failed"
+                }
+                .to_string(),
+                reboot_verified: (reboot && eeprom_ok && !interrupted).then_some(reboot_ok),
+                interrupted,
+                retries: self.retry_stats.get().retries,
             };
-
             println!("{}", serde_json::to_string_pretty(&output)?);
-            return Ok(());
         }
 
-        // Human-readable output
-        self.print_banner();
-
-        if found_any {
+        if interrupted {
             self.console.print(
-                "[bold #3498db]┌──────────────────────────────────────────────────────────┐[/]",
+                "  [bold #f39c12]╭────────────────────────────────────────────────────────────╮[/]",
             );
             self.console.print(
-                "[bold #3498db]│[/]  [bold #2ecc71]✓[/] [bold white]DEVICE DETECTED[/]                                       [bold #3498db]│[/]",
+                "  [bold #f39c12]│[/]  [bold #f39c12]⚠[/]  [bold white]PROGRAMMING INTERRUPTED[/]                               [bold #f39c12]│[/]",
             );
             self.console.print(
-                "[bold #3498db]└──────────────────────────────────────────────────────────┘[/]",
+                "  [bold #f39c12]╰────────────────────────────────────────────────────────────╯[/]",
             );
-            self.console.print("");
-
-            // Create a table for device info
-            let mut table = Table::new()
-                .box_style(&ROUNDED)
-                .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
-                .border_style(Style::parse("#3498db").unwrap_or_default())
-                .with_column(Column::new("Mode"))
-                .with_column(Column::new("VID"))
-                .with_column(Column::new("PID"))
-                .with_column(Column::new("Interface"))
-                .with_column(Column::new("Usage"));
-
-            // Deduplicate by interface
-            let mut seen_interfaces = std::collections::HashSet::new();
-            for (mode, vid, pid, _path, _serial, iface, usage_page, usage) in &devices_info {
-                if seen_interfaces.insert((*iface, *usage_page, *usage)) {
-                    let mode_styled = if mode == "PROGRAM" {
-                        markup::render_or_plain("[bold #e74c3c]PROGRAM[/]")
-                    } else {
-                        markup::render_or_plain("[bold #2ecc71]PLAY[/]")
-                    };
-                    let usage_str = format!("0x{:04X}:0x{:04X}", usage_page, usage);
-                    table.add_row_cells([
-                        mode_styled,
-                        markup::render_or_plain(vid),
-                        markup::render_or_plain(pid),
-                        markup::render_or_plain(&iface.to_string()),
-                        markup::render_or_plain(&usage_str),
-                    ]);
-                }
+            if save_success {
+                self.console.print(
+                    "  [#95a5a6]The pedals written before the interrupt were saved to EEPROM.[/]",
+                );
+            } else {
+                self.console.print(
+                    "  [#95a5a6]Nothing was saved to EEPROM; replug the pedal to restore its saved settings.[/]",
+                );
             }
-
-            self.console.print_renderable(&table);
+            self.console.print(
+                "  [#95a5a6]Run[/] [bold #f1c40f]savant program[/] [#95a5a6]again to finish.[/]",
+            );
             self.console.print("");
-
-            // Show USB path
-            if let Some((_, _, _, path, serial, _, _, _)) = devices_info.first() {
-                self.console
-                    .print(&format!("  [dim]Path:[/]   [#95a5a6]{}[/]", path));
-                if !serial.is_empty() && serial != "N/A" {
-                    self.console
-                        .print(&format!("  [dim]Serial:[/] [#95a5a6]{}[/]", serial));
-                }
-            }
-
-            // Show current pedal configuration from saved config
-            if let Some(cfg) = config {
-                self.print_pedal_visualization(&cfg.left, &cfg.middle, &cfg.right);
-            } else {
-                self.console.print("");
+            return Err(anyhow!("Programming interrupted"));
+        } else if pedal_failures.is_empty() && eeprom_ok && reboot_ok {
+            self.console.print(
+                "  [bold #2ecc71]╭────────────────────────────────────────────────────────────╮[/]",
+            );
+            self.console.print(
+                "  [bold #2ecc71]│[/]  [bold #2ecc71]✓[/]  [bold white]PROGRAMMING COMPLETE![/]                                  [bold #2ecc71]│[/]",
+            );
+            self.console.print(
+                "  [bold #2ecc71]╰────────────────────────────────────────────────────────────╯[/]",
+            );
+            if up_to_date {
                 self.console.print(
-                    "[bold #f39c12]┌──────────────────────────────────────────────────────────┐[/]",
+                    "  [#95a5a6]The pedal already held these keys, so nothing was written (use --force to rewrite).[/]",
                 );
+            }
+        } else {
+            self.console.print(
+                "  [bold #f39c12]╭────────────────────────────────────────────────────────────╮[/]",
+            );
+            self.console.print(
+                "  [bold #f39c12]│[/]  [bold #f39c12]⚠[/]  [bold white]PROGRAMMING FINISHED WITH WARNINGS[/]                    [bold #f39c12]│[/]",
+            );
+            self.console.print(
+                "  [bold #f39c12]╰────────────────────────────────────────────────────────────╯[/]",
+            );
+            if !pedal_failures.is_empty() {
+                self.console.print(&format!(
+                    "  [#95a5a6]Failed pedals:[/] [bold #e74c3c]{}[/]",
+                    pedal_failures.join(", ")
+                ));
+            }
+            if !eeprom_ok {
                 self.console.print(
-                    "[bold #f39c12]│[/]  [bold white]PEDAL CONFIGURATION UNKNOWN[/]                             [bold #f39c12]│[/]",
+                    "  [#95a5a6]EEPROM save may have failed; changes might not persist after unplug.[/]",
                 );
+            }
+            if !reboot_ok {
                 self.console.print(
-                    "[bold #f39c12]└──────────────────────────────────────────────────────────┘[/]",
+                    "  [#95a5a6]The reboot check could not confirm the settings persisted.[/]",
                 );
-                self.console.print("");
-                self.console
-                    .print("  [#95a5a6]Run[/] [bold #3498db]savant program[/] [#95a5a6]to configure your pedals.[/]");
-                self.console.print("");
-                self.console
-                    .print("  [dim]Example:[/] [#95a5a6]savant program --left cmd+c --middle cmd+a --right cmd+v[/]");
             }
-        } else {
+        }
+        let stats = self.retry_stats.get();
+        if stats.retries > 0 {
+            self.console.print(&format!(
+                "  [dim]USB retries: {} ({} transfer(s) recovered; policy {})[/]",
+                stats.retries,
+                stats.recovered,
+                self.retry.describe()
+            ));
+        }
+        // Show visual pedal configuration
+        self.print_pedal_visualization(left, middle, right);
+
+        self.console
+            .print("  [bold #f39c12]To use the new configuration:[/]");
+        self.console
+            .print("    [bold #3498db]1.[/] Flip the switch back to [bold #2ecc71]Play[/] mode");
+        self.console
+            .print("    [bold #3498db]2.[/] Unplug and replug the USB cable");
+        self.console
+            .print("    [bold #3498db]3.[/] Your pedals should now send the programmed keys!");
+        self.console.print("");
+        // Ctrl+C in the monitor should end it as usual
+        drop(interrupt);
+
+        if auto_monitor {
             self.console.print(
-                "[bold #e74c3c]┌──────────────────────────────────────────────────────────┐[/]",
+                "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
             );
             self.console.print(
-                "[bold #e74c3c]│[/]  [bold #e74c3c]✗[/] [bold white]NO DEVICE FOUND[/]                                       [bold #e74c3c]│[/]",
+                "[bold #9b59b6]│[/]  [bold #f39c12]👁[/]  [bold white]MONITOR MODE[/] [dim](waiting for device in play mode)[/]            [bold #9b59b6]│[/]",
             );
             self.console.print(
-                "[bold #e74c3c]└──────────────────────────────────────────────────────────┘[/]",
+                "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
             );
             self.console.print("");
             self.console
-                .print("  [#95a5a6]Make sure your Savant Elite is connected via USB.[/]");
-        }
+                .print("  [bold #f39c12]1.[/] Switch the pedal to [bold #2ecc71]Play[/] mode");
+            self.console
+                .print("  [bold #f39c12]2.[/] Replug the USB cable");
+            self.console.print("");
+            self.console.print(
+                "  [#95a5a6]Waiting for device...[/] [dim](60s timeout, Ctrl+C to cancel)[/]",
+            );
+            self.console.print("");
 
-        self.console.print("");
-        Ok(())
-    }
+            // Wait for device to appear in play mode (user needs to switch and replug)
+            let wait_start = std::time::Instant::now();
+            let timeout = Duration::from_secs(60);
+            let mut last_reminder = wait_start;
 
-    fn open_keyboard_interface(&self) -> Result<HidDevice> {
-        self.verbose("Initializing HID API for keyboard interface...");
-        let api = HidApi::new().context("Failed to initialize HID API")?;
+            loop {
+                let _ = self.session.refresh_hid();
+                if self.open_keyboard_interface().is_ok() {
+                    break;
+                }
 
-        // Find the keyboard interface (usage page 1, usage 6)
-        self.verbose("Searching for keyboard interface (usage_page=0x01, usage=0x06)...");
-        for device in api.device_list() {
-            if device.vendor_id() == KINESIS_VID
-                && device.product_id() == SAVANT_ELITE_PID
-                && device.usage_page() == 0x01
-                && device.usage() == 0x06
-            {
-                self.verbose(&format!(
-                    "Found keyboard interface at path: {}",
-                    device.path().to_string_lossy()
-                ));
-                match device.open_device(&api) {
-                    Ok(dev) => {
-                        self.verbose("Keyboard interface opened successfully");
-                        return Ok(dev);
-                    }
-                    Err(e) => {
-                        let msg = e.to_string();
-                        if msg.contains("privilege violation") || msg.contains("0xE00002C1") {
-                            return Err(anyhow!(e).context(
-                                "Failed to open device (macOS Input Monitoring permission is required; enable it in System Settings → Privacy & Security → Input Monitoring, then re-run)",
-                            ));
-                        }
-                        return Err(anyhow!(e).context("Failed to open device"));
-                    }
+                if wait_start.elapsed() > timeout {
+                    self.console.print("");
+                    self.console.print(
+                        "  [bold #e74c3c]Timeout![/] Device not detected in play mode after 60s.",
+                    );
+                    self.console.print(
+                        "  Run [bold #f1c40f]savant monitor[/] manually after switching modes.",
+                    );
+                    self.console.print("");
+                    return Ok(());
                 }
-            }
-        }
 
-        Err(anyhow!("Savant Elite keyboard interface not found"))
-    }
+                // Reminder every 15 seconds
+                if last_reminder.elapsed() > Duration::from_secs(15) {
+                    let remaining = timeout.saturating_sub(wait_start.elapsed()).as_secs();
+                    self.console.print(&format!(
+                        "  [dim]Still waiting... {}s remaining (switch to Play mode and replug USB)[/]",
+                        remaining
+                    ));
+                    last_reminder = std::time::Instant::now();
+                }
 
-    fn monitor(&self, duration_secs: u64) -> Result<()> {
-        let device = self.open_keyboard_interface()?;
+                std::thread::sleep(Duration::from_millis(500));
+            }
 
-        self.print_banner();
+            self.console
+                .print("  [bold #2ecc71]✓[/] Device detected in play mode!");
+            self.console.print("");
+            self.console
+                .print("  [#95a5a6]Press pedals to see what keys they send.[/]");
+            self.console.print(
+                "  [#95a5a6]Press[/] [bold #e74c3c]Ctrl+C[/] [#95a5a6]to stop monitoring.[/]",
+            );
+            self.console.print("");
 
-        self.console.print(
-            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
-        self.console.print(
-            "[bold #9b59b6]│[/]  [bold #f39c12]👁[/]  [bold white]LIVE MONITOR MODE[/]                                          [bold #9b59b6]│[/]",
-        );
-        self.console.print(
-            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
-        );
-        self.console.print("");
-        self.console
-            .print("  [#95a5a6]Press pedals to see what keys they send.[/]");
-        self.console
-            .print("  [#95a5a6]Press[/] [bold #e74c3c]Ctrl+C[/] [#95a5a6]to stop.[/]");
-        if duration_secs > 0 {
-            self.console.print(&format!(
-                "  [#95a5a6]Auto-stop in[/] [bold #f39c12]{}[/] [#95a5a6]seconds.[/]",
-                duration_secs
-            ));
+            // Run monitor indefinitely (user presses Ctrl+C to stop)
+            self.monitor(0)?;
         }
-        self.console.print("");
-        self.console.print(
-            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
-        );
 
-        self.verbose("Setting non-blocking mode on HID device");
-        device.set_blocking_mode(false)?;
+        Ok(())
+    }
 
-        let mut buf = [0u8; 64];
-        let mut last_report = [0u8; 8];
-        let start = std::time::Instant::now();
-        self.verbose("Starting monitor loop...");
+    /// List all valid key names and modifiers
+    fn list_keys(&self, json_output: bool) -> Result<()> {
+        // Data structures for key information
+        #[derive(Serialize)]
+        struct ModifierInfo {
+            names: Vec<&'static str>,
+            symbol: &'static str,
+            description: &'static str,
+        }
 
-        loop {
-            if duration_secs > 0 && start.elapsed().as_secs() >= duration_secs {
-                self.console.print("");
-                self.console.print(
-                    "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
-                );
-                self.console
-                    .print("[bold #2ecc71]✓[/] [#95a5a6]Monitoring complete.[/]");
-                break;
-            }
+        #[derive(Serialize)]
+        struct KeysOutput {
+            modifiers: Vec<ModifierInfo>,
+            keys: KeyCategories,
+        }
 
-            match device.read_timeout(&mut buf, 100) {
-                Ok(len) if len > 0 => {
-                    self.verbose_hex("Raw HID read", &buf[..len]);
-                    let Some(report) = usb_hid::normalize_boot_keyboard_report(&buf[..len]) else {
-                        self.verbose("  -> Could not normalize to boot keyboard report");
-                        continue;
-                    };
+        #[derive(Serialize)]
+        struct KeyCategories {
+            letters: Vec<&'static str>,
+            numbers: Vec<&'static str>,
+            function_keys: Vec<&'static str>,
+            special: Vec<KeyAliases>,
+            arrow_keys: Vec<&'static str>,
+            punctuation: Vec<KeyAliases>,
+        }
 
-                    if report != last_report {
-                        last_report = report;
-                        self.verbose_hex("Normalized report", &report);
+        #[derive(Serialize)]
+        struct KeyAliases {
+            names: Vec<&'static str>,
+        }
 
-                        let modifiers = report[0];
-                        let keys: Vec<u8> =
-                            report[2..8].iter().filter(|&&k| k != 0).copied().collect();
+        let modifiers = vec![
+            ModifierInfo {
+                names: vec!["cmd", "command", "gui", "meta", "super"],
+                symbol: "⌘",
+                description: "Command/GUI",
+            },
+            ModifierInfo {
+                names: vec!["ctrl", "control"],
+                symbol: "⌃",
+                description: "Control",
+            },
+            ModifierInfo {
+                names: vec!["alt", "option", "opt"],
+                symbol: "⌥",
+                description: "Option/Alt",
+            },
+            ModifierInfo {
+                names: vec!["shift"],
+                symbol: "⇧",
+                description: "Shift",
+            },
+            ModifierInfo {
+                names: vec!["primary"],
+                symbol: "⌘/⌃",
+                description: "Cmd on macOS, Ctrl elsewhere (see --target-os)",
+            },
+            ModifierInfo {
+                names: vec!["secondary"],
+                symbol: "⌃/⌘",
+                description: "Ctrl on macOS, Win/Super elsewhere",
+            },
+        ];
 
-                        if modifiers != 0 || !keys.is_empty() {
-                            let mod_names = usb_hid::modifier_names(modifiers);
-                            let key_names: Vec<&str> =
-                                keys.iter().map(|&k| usb_hid::key_name(k)).collect();
+        let letters: Vec<&'static str> = vec![
+            "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q",
+            "r", "s", "t", "u", "v", "w", "x", "y", "z",
+        ];
 
-                            let combo = if !mod_names.is_empty() && !key_names.is_empty() {
-                                format!("{}+{}", mod_names.join("+"), key_names.join("+"))
-                            } else if !mod_names.is_empty() {
-                                mod_names.join("+")
-                            } else {
-                                key_names.join("+")
-                            };
+        let numbers: Vec<&'static str> = vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
 
-                            self.console.print(&format!(
-                                "  [bold #2ecc71]▶[/] [bold #f1c40f]PRESS[/]   [bold white]{}[/]  [dim #7f8c8d]({})[/]",
-                                combo,
-                                hex::encode(report)
-                            ));
-                        } else {
-                            self.console.print(&format!(
-                                "  [dim #e74c3c]◀[/] [dim #95a5a6]RELEASE[/] [dim #7f8c8d]({})[/]",
-                                hex::encode(report)
-                            ));
-                        }
-                    }
-                }
-                Ok(_) => {}
-                Err(e) => {
-                    if e.to_string().contains("timeout") {
-                        continue;
-                    }
-                    return Err(anyhow!("Read error: {}", e));
-                }
-            }
+        let function_keys: Vec<&'static str> = vec![
+            "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "f10", "f11", "f12",
+        ];
 
-            std::thread::sleep(Duration::from_millis(10));
-        }
+        let special = vec![
+            KeyAliases {
+                names: vec!["enter", "return"],
+            },
+            KeyAliases {
+                names: vec!["esc", "escape"],
+            },
+            KeyAliases {
+                names: vec!["backspace"],
+            },
+            KeyAliases {
+                names: vec!["delete", "del"],
+            },
+            KeyAliases { names: vec!["tab"] },
+            KeyAliases {
+                names: vec!["space"],
+            },
+            KeyAliases {
+                names: vec!["capslock"],
+            },
+        ];
 
-        self.console.print("");
-        Ok(())
-    }
+        let arrow_keys: Vec<&'static str> = vec!["up", "down", "left", "right"];
 
-    fn status(&self) -> Result<()> {
-        // Check via libusb first (more reliable for programming mode)
-        let mut found_play_usb = false;
-        let mut found_program_usb = false;
-        let mut libusb_error: Option<anyhow::Error> = None;
-        let mut device_details: Vec<(String, String, String)> = Vec::new();
+        let punctuation = vec![
+            KeyAliases {
+                names: vec!["minus", "-"],
+            },
+            KeyAliases {
+                names: vec!["equal", "="],
+            },
+            KeyAliases {
+                names: vec!["leftbracket", "["],
+            },
+            KeyAliases {
+                names: vec!["rightbracket", "]"],
+            },
+            KeyAliases {
+                names: vec!["backslash", "\\"],
+            },
+            KeyAliases {
+                names: vec!["semicolon", ";"],
+            },
+            KeyAliases {
+                names: vec!["quote", "'"],
+            },
+            KeyAliases {
+                names: vec!["grave", "`"],
+            },
+            KeyAliases {
+                names: vec!["comma", ","],
+            },
+            KeyAliases {
+                names: vec!["period", "."],
+            },
+            KeyAliases {
+                names: vec!["slash", "/"],
+            },
+        ];
 
-        match rusb::devices() {
-            Ok(devices) => {
-                for device in devices.iter() {
-                    let desc = match device.device_descriptor() {
-                        Ok(desc) => desc,
-                        Err(_) => continue,
-                    };
-                    if desc.vendor_id() == KINESIS_VID {
-                        match desc.product_id() {
-                            SAVANT_ELITE_PID => {
-                                found_play_usb = true;
-                                device_details.push((
-                                    "PLAY".to_string(),
-                                    format!("0x{:04X}", SAVANT_ELITE_PID),
-                                    format!(
-                                        "Bus {:03} Device {:03}",
-                                        device.bus_number(),
-                                        device.address()
-                                    ),
-                                ));
-                            }
-                            PROGRAMMING_PID => {
-                                found_program_usb = true;
-                                let mut product = "Savant Elite".to_string();
-                                let mut mfr = "Kinesis".to_string();
+        if json_output {
+            let output = KeysOutput {
+                modifiers,
+                keys: KeyCategories {
+                    letters,
+                    numbers,
+                    function_keys,
+                    special,
+                    arrow_keys,
+                    punctuation,
+                },
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            // Human-readable output
+            self.console
+                .print("[bold cyan]MODIFIERS[/] [dim](combine with + before key)[/]");
+            self.console.print("");
+            for m in &modifiers {
+                let names = m.names.join(", ");
+                self.console.print(&format!(
+                    "  [green]{}[/]  →  {} {}",
+                    names, m.symbol, m.description
+                ));
+            }
 
-                                if let Ok(handle) = device.open() {
-                                    if let Ok(langs) =
-                                        handle.read_languages(Duration::from_millis(100))
-                                    {
-                                        if let Some(lang) = langs.first() {
-                                            if let Ok(p) = handle.read_product_string(
-                                                *lang,
-                                                &desc,
-                                                Duration::from_millis(100),
-                                            ) {
-                                                product = p;
-                                            }
-                                            if let Ok(m) = handle.read_manufacturer_string(
-                                                *lang,
-                                                &desc,
-                                                Duration::from_millis(100),
-                                            ) {
-                                                mfr = m;
-                                            }
-                                        }
-                                    }
-                                }
-                                device_details.push((
-                                    "PROGRAM".to_string(),
-                                    format!("0x{:04X}", PROGRAMMING_PID),
-                                    format!("{mfr} - {product}"),
-                                ));
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+            self.console.print("");
+            self.console.print("[bold cyan]LETTERS[/]");
+            self.console
+                .print(&format!("  [green]{}[/]", letters.join(" ")));
+
+            self.console.print("");
+            self.console.print("[bold cyan]NUMBERS[/]");
+            self.console
+                .print(&format!("  [green]{}[/]", numbers.join(" ")));
+
+            self.console.print("");
+            self.console.print("[bold cyan]FUNCTION KEYS[/]");
+            self.console
+                .print(&format!("  [green]{}[/]", function_keys.join(" ")));
+
+            self.console.print("");
+            self.console.print("[bold cyan]SPECIAL KEYS[/]");
+            for k in &special {
+                self.console
+                    .print(&format!("  [green]{}[/]", k.names.join(", ")));
             }
-            Err(e) => {
-                libusb_error =
-                    Some(anyhow!(e).context("Failed to enumerate USB devices via libusb"));
+
+            self.console.print("");
+            self.console.print("[bold cyan]ARROW KEYS[/]");
+            self.console
+                .print(&format!("  [green]{}[/]", arrow_keys.join("  ")));
+
+            self.console.print("");
+            self.console.print("[bold cyan]PUNCTUATION[/]");
+            for k in &punctuation {
+                // Avoid markup interpretation issues with backslash
+                let display = k.names.join(", ");
+                if display.contains('\\') {
+                    // Print without markup for backslash
+                    println!("  {}", display);
+                } else {
+                    self.console.print(&format!("  [green]{}[/]", display));
+                }
             }
+
+            self.console.print("");
+            self.console.print("[bold cyan]EXAMPLES[/]");
+            self.console.print("  [dim]Copy/Paste workflow:[/]");
+            self.console
+                .print("  [yellow]savant program --left cmd+c --middle cmd+a --right cmd+v[/]");
+            self.console.print("");
+            self.console.print("  [dim]Multi-modifier combo:[/]");
+            self.console.print(
+                "  [yellow]savant program --left ctrl+shift+f1 --middle f5 --right enter[/]",
+            );
         }
 
-        // Also check HID (for play mode with interfaces)
-        let api = HidApi::new().context("Failed to initialize HID API")?;
-        let mut found_play_hid = false;
-        let mut found_program_hid = false;
+        Ok(())
+    }
 
-        for device_info in api.device_list() {
-            if device_info.vendor_id() != KINESIS_VID {
-                continue;
+    /// Load every preset layer, noting unreadable preset files in verbose output
+    fn load_presets(&self) -> PresetCatalog {
+        let catalog = PresetCatalog::load(self.target_os());
+        for (path, errors) in &catalog.problems {
+            self.verbose(&format!(
+                "Skipping invalid preset file {}: {}",
+                path.display(),
+                errors.join("; ")
+            ));
+        }
+        catalog
+    }
+
+    fn preset(
+        &self,
+        name: Option<&str>,
+        list: bool,
+        show: bool,
+        dry_run: bool,
+        force: bool,
+    ) -> Result<()> {
+        let catalog = self.load_presets();
+
+        // Handle --list flag
+        if list {
+            return self.list_presets(&catalog);
+        }
+
+        // All other operations require a preset name
+        let Some(preset_name) = name else {
+            if self.json_output {
+                let output = JsonPresetListOutput {
+                    presets: catalog.presets.iter().map(JsonPreset::new).collect(),
+                };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                self.console
+                    .print("[bold red]Error:[/] Missing preset name");
+                self.console.print("");
+                self.console
+                    .print("Usage: [bold yellow]savant preset <NAME>[/]");
+                self.console.print("");
+                self.console
+                    .print("Run [bold yellow]savant preset --list[/] to see available presets.");
             }
+            return Ok(());
+        };
 
-            match device_info.product_id() {
-                SAVANT_ELITE_PID if !found_play_usb && !found_play_hid => {
-                    found_play_hid = true;
-                    device_details.push((
-                        "PLAY".to_string(),
-                        format!("0x{:04X}", SAVANT_ELITE_PID),
-                        format!("hidapi: {}", device_info.path().to_string_lossy()),
-                    ));
-                }
-                PROGRAMMING_PID if !found_program_usb && !found_program_hid => {
-                    found_program_hid = true;
-                    device_details.push((
-                        "PROGRAM".to_string(),
-                        format!("0x{:04X}", PROGRAMMING_PID),
-                        format!("hidapi: {}", device_info.path().to_string_lossy()),
-                    ));
+        // Look up the preset
+        let Some(preset) = catalog.find(preset_name) else {
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": "unknown_preset",
+                    "message": format!("Unknown preset: '{}'", preset_name),
+                    "available": catalog.presets.iter().map(|p| &p.name).collect::<Vec<_>>()
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console.print(&format!(
+                    "[bold red]Error:[/] Unknown preset: '{}'",
+                    preset_name
+                ));
+                self.console.print("");
+                self.console.print("[bold cyan]Available presets:[/]");
+                for p in &catalog.presets {
+                    self.console
+                        .print(&format!("  [yellow]{}[/]  {}", p.name, p.description));
                 }
-                _ => {}
             }
+            return Err(anyhow!("Unknown preset: '{}'", preset_name));
+        };
+
+        // Handle --show flag
+        if show {
+            return self.show_preset(preset);
         }
 
-        let found_play = found_play_usb || found_play_hid;
-        let found_program = found_program_usb || found_program_hid;
+        // Apply the preset (program the device)
+        self.verbose(&format!("Applying preset: {}", preset.name));
+        let command = format!("preset {}", preset.name);
+        self.program(
+            Some(&preset.left),
+            Some(&preset.middle),
+            Some(&preset.right),
+            ProgramOptions {
+                dry_run,
+                command: Some(&command),
+                force,
+                ..Default::default()
+            },
+        )
+    }
 
-        // JSON output mode
+    /// Program the factory defaults through the normal `program` pipeline, which also
+    /// records the previous configuration in history.
+    fn reset_factory(&self, dry_run: bool) -> Result<()> {
+        let preset = find_preset(FACTORY_PRESET)
+            .ok_or_else(|| anyhow!("Built-in '{}' preset is missing", FACTORY_PRESET))?;
+        self.verbose("Restoring factory defaults");
+        self.program(
+            Some(preset.left),
+            Some(preset.middle),
+            Some(preset.right),
+            ProgramOptions {
+                dry_run,
+                command: Some("reset --factory"),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn list_presets(&self, catalog: &PresetCatalog) -> Result<()> {
         if self.json_output {
-            let mode = if found_program {
-                Some("program".to_string())
-            } else if found_play {
-                Some("play".to_string())
-            } else {
-                None
+            let output = JsonPresetListOutput {
+                presets: catalog.presets.iter().map(JsonPreset::new).collect(),
             };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
 
-            let devices: Vec<JsonStatusDevice> = device_details
-                .iter()
-                .map(|(m, pid, loc)| JsonStatusDevice {
-                    mode: m.to_lowercase(),
-                    pid: pid.clone(),
-                    location: loc.clone(),
-                })
-                .collect();
+        self.print_banner();
 
-            let output = JsonStatusOutput {
-                detected: found_play || found_program,
-                mode,
-                devices,
-                ready_to_program: found_program,
+        self.console.print(
+            "[bold #3498db]┌─────────────────────────────────────────────────────────────────┐[/]",
+        );
+        self.console.print(
+            "[bold #3498db]│[/]  [bold white]AVAILABLE PRESETS[/]                                           [bold #3498db]│[/]",
+        );
+        self.console.print(
+            "[bold #3498db]└─────────────────────────────────────────────────────────────────┘[/]",
+        );
+        self.console.print("");
+
+        for preset in &catalog.presets {
+            let source = match preset.source {
+                PresetSource::BuiltIn => String::new(),
+                _ => format!(" [dim]({})[/]", preset.source.label()),
             };
+            self.console
+                .print(&format!("  [bold yellow]{}[/]{}", preset.name, source));
+            if !preset.description.is_empty() {
+                self.console
+                    .print(&format!("    [dim]{}[/]", preset.description));
+            }
+            if !preset.tags.is_empty() {
+                self.console
+                    .print(&format!("    [dim]Tags: {}[/]", preset.tags.join(", ")));
+            }
+            self.console.print(&format!(
+                "    Left: [cyan]{}[/]  Middle: [cyan]{}[/]  Right: [cyan]{}[/]",
+                preset.left, preset.middle, preset.right
+            ));
+            self.console.print("");
+        }
+
+        self.console.print("[bold #2ecc71]Usage:[/]");
+        self.console
+            .print("  [yellow]savant preset copy-paste[/]        Apply the copy-paste preset");
+        self.console
+            .print("  [yellow]savant preset browser --dry-run[/] Preview browser preset");
+        self.console
+            .print("  [yellow]savant preset zoom --show[/]       Show zoom preset details");
+        self.console.print("");
+        self.console.print(&format!(
+            "[dim]Add your own presets as JSON files in {} or {}[/]",
+            presets_dir().display(),
+            system_presets_dir().display()
+        ));
+        if !catalog.problems.is_empty() {
+            self.console.print(&format!(
+                "[bold yellow]{} preset file(s) could not be loaded.[/] Run [bold yellow]savant preset check[/] for details.",
+                catalog.problems.len()
+            ));
+        }
+
+        Ok(())
+    }
 
+    fn show_preset(&self, preset: &PresetEntry) -> Result<()> {
+        if self.json_output {
+            let output = JsonPreset::new(preset);
             println!("{}", serde_json::to_string_pretty(&output)?);
             return Ok(());
         }
 
-        // Human-readable output
         self.print_banner();
 
         self.console.print(
-            "[bold #1abc9c]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
-        self.console.print(
-            "[bold #1abc9c]│[/]  [bold #f39c12]🔍[/] [bold white]DEVICE STATUS CHECK[/]                                        [bold #1abc9c]│[/]",
+            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
         );
+        self.console.print(&format!(
+            "[bold #9b59b6]│[/]  [bold white]PRESET: {}[/]{}[bold #9b59b6]│[/]",
+            preset.name.to_uppercase(),
+            " ".repeat(53_usize.saturating_sub(preset.name.len()))
+        ));
         self.console.print(
-            "[bold #1abc9c]└─────────────────────────────────────────────────────────────────┘[/]",
+            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
         );
         self.console.print("");
 
-        if !found_play && !found_program {
-            self.console.print(
-                "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
-            );
-            self.console.print(
-                "  [bold #e74c3c]│[/]  [bold #e74c3c]✗[/]  [bold white]No Savant Elite device found[/]                           [bold #e74c3c]│[/]",
-            );
-            self.console.print(
-                "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
-            );
-            self.console.print("");
-            self.console.print("  [bold #f39c12]Troubleshooting:[/]");
+        if !preset.description.is_empty() {
             self.console
-                .print("    [#95a5a6]1.[/] Make sure the device is connected via USB");
+                .print(&format!("  [dim]{}[/]", preset.description));
+        }
+        if !preset.tags.is_empty() {
             self.console
-                .print("    [#95a5a6]2.[/] Try unplugging and replugging the cable");
-        } else if found_play && !found_program {
-            self.console.print(
-                "  [bold #2ecc71]╭────────────────────────────────────────────────────────────╮[/]",
-            );
-            self.console.print(
-                "  [bold #2ecc71]│[/]  [bold #2ecc71]●[/]  [bold white]Device is in[/] [bold #2ecc71]PLAY[/] [bold white]mode[/]                                 [bold #2ecc71]│[/]",
-            );
-            self.console.print(
-                "  [bold #2ecc71]╰────────────────────────────────────────────────────────────╯[/]",
-            );
-            self.console.print("");
+                .print(&format!("  [dim]Tags: {}[/]", preset.tags.join(", ")));
+        }
+        match preset.source.path() {
+            Some(path) => self.console.print(&format!(
+                "  [dim]Source: {} ({})[/]",
+                preset.source.label(),
+                path.display()
+            )),
+            None => self.console.print("  [dim]Source: built-in[/]"),
+        }
+        self.console.print("");
+
+        // Show the pedal visualization
+        self.print_pedal_visualization(&preset.left, &preset.middle, &preset.right);
+
+        self.console.print("");
+        self.console.print(&format!(
+            "To apply: [bold yellow]savant preset {}[/]",
+            preset.name
+        ));
+        self.console.print(&format!(
+            "Preview:  [bold yellow]savant preset {} --dry-run[/]",
+            preset.name
+        ));
 
-            // Show table of details
-            let mut table = Table::new()
-                .box_style(&ROUNDED)
-                .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
-                .border_style(Style::parse("#3498db").unwrap_or_default())
-                .with_column(Column::new("Mode"))
-                .with_column(Column::new("PID"))
-                .with_column(Column::new("Location"));
+        Ok(())
+    }
 
-            for (mode, pid, location) in &device_details {
-                let mode_styled = if mode == "PROGRAM" {
-                    markup::render_or_plain(&format!("[bold #e74c3c]{}[/]", mode))
-                } else {
-                    markup::render_or_plain(&format!("[bold #2ecc71]{}[/]", mode))
-                };
-                table.add_row_cells([
-                    mode_styled,
-                    markup::render_or_plain(pid),
-                    markup::render_or_plain(location),
-                ]);
+    /// Validate preset files: the one given, or every file in the system and user
+    /// preset directories (including duplicate names within a directory).
+    fn preset_check(&self, file: Option<&str>) -> Result<()> {
+        let (user_dir, system_dir) = (presets_dir(), system_presets_dir());
+        self.verbose(&format!(
+            "Checking presets in {} and {}",
+            system_dir.display(),
+            user_dir.display()
+        ));
+
+        let mut files: Vec<JsonPresetCheckFile> = Vec::new();
+        if let Some(file) = file {
+            let path = PathBuf::from(file);
+            let result = parse_preset_file(&path, PresetSource::User(path.clone()));
+            files.push(JsonPresetCheckFile {
+                path: path.display().to_string(),
+                source: "file".to_string(),
+                name: result.as_ref().ok().map(|p| p.name.clone()),
+                valid: result.is_ok(),
+                errors: result.err().unwrap_or_default(),
+                warnings: Vec::new(),
+            });
+        } else {
+            let catalog = PresetCatalog::load_from(&system_dir, &user_dir, self.target_os());
+            for (dir, source) in [(&system_dir, "system"), (&user_dir, "user")] {
+                for path in preset_files(dir) {
+                    let problem = catalog.problems.iter().find(|(p, _)| *p == path);
+                    let loaded = catalog
+                        .presets
+                        .iter()
+                        .find(|p| p.source.path() == Some(path.as_path()));
+                    let mut warnings = Vec::new();
+                    if let Some(preset) = loaded {
+                        if find_preset(&preset.name).is_some() {
+                            warnings.push(format!("Shadows the built-in '{}' preset", preset.name));
+                        }
+                    } else if problem.is_none() {
+                        warnings.push("Shadowed by a user preset of the same name".to_string());
+                    }
+                    let name = parse_preset_file(&path, PresetSource::User(path.clone()))
+                        .ok()
+                        .map(|p| p.name);
+                    files.push(JsonPresetCheckFile {
+                        path: path.display().to_string(),
+                        source: source.to_string(),
+                        name,
+                        valid: problem.is_none(),
+                        errors: problem.map(|(_, e)| e.clone()).unwrap_or_default(),
+                        warnings,
+                    });
+                }
             }
-            self.console.print_renderable(&table);
-            self.console.print("");
+        }
 
-            self.console
-                .print("  [bold #f39c12]To program the device, switch to PROGRAMMING mode:[/]");
+        let error_count = files.iter().filter(|f| !f.valid).count();
+
+        if self.json_output {
+            let output = JsonPresetCheckOutput {
+                valid: error_count == 0,
+                files,
+                user_dir: user_dir.display().to_string(),
+                system_dir: system_dir.display().to_string(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else if files.is_empty() {
+            self.console.print("[bold yellow]No preset files found.[/]");
             self.console.print("");
             self.console
-                .print("    [bold #3498db]1.[/] Flip the pedal over");
-            self.console
-                .print("    [bold #3498db]2.[/] Find the recessed switch near the Kinesis sticker");
-            self.console.print(
-                "    [bold #3498db]3.[/] Use a paperclip to flip it from [#2ecc71]Play[/] → [#e74c3c]Program[/]",
-            );
-            self.console
-                .print("    [bold #3498db]4.[/] Unplug and replug the USB cable");
+                .print(&format!("[dim]User presets:   {}[/]", user_dir.display()));
             self.console
-                .print("    [bold #3498db]5.[/] Run [bold #f1c40f]savant status[/] to verify");
-        } else if found_program {
-            self.console.print(
-                "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
-            );
-            self.console.print(
-                "  [bold #e74c3c]│[/]  [bold #e74c3c]◉[/]  [bold white]Device is in[/] [bold #e74c3c]PROGRAMMING[/] [bold white]mode[/]                          [bold #e74c3c]│[/]",
-            );
-            self.console.print(
-                "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
-            );
+                .print(&format!("[dim]System presets: {}[/]", system_dir.display()));
+        } else {
+            for file in &files {
+                let name = file
+                    .name
+                    .as_deref()
+                    .map(|n| format!(" [bold]{}[/]", n))
+                    .unwrap_or_default();
+                if file.valid {
+                    self.console.print(&format!(
+                        "[bold #2ecc71]✓[/]{} [dim]{} ({})[/]",
+                        name, file.path, file.source
+                    ));
+                } else {
+                    self.console.print(&format!(
+                        "[bold red]✗[/] [dim]{} ({})[/]",
+                        file.path, file.source
+                    ));
+                }
+                for error in &file.errors {
+                    self.console
+                        .print(&format!("    [bold red]Error:[/] {}", error));
+                }
+                for warning in &file.warnings {
+                    self.console
+                        .print(&format!("    [bold yellow]Warning:[/] {}", warning));
+                }
+            }
             self.console.print("");
+            if error_count == 0 {
+                self.console.print(&format!(
+                    "[bold #2ecc71]All {} preset file(s) are valid.[/]",
+                    files.len()
+                ));
+            } else {
+                self.console.print(&format!(
+                    "[dim]{} of {} preset file(s) have errors[/]",
+                    error_count,
+                    files.len()
+                ));
+            }
+        }
 
-            // Show table of details
-            let mut table = Table::new()
-                .box_style(&ROUNDED)
-                .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
-                .border_style(Style::parse("#e74c3c").unwrap_or_default())
-                .with_column(Column::new("Mode"))
-                .with_column(Column::new("PID"))
-                .with_column(Column::new("Info"));
+        if error_count == 0 {
+            Ok(())
+        } else {
+            Err(anyhow!("{} preset file(s) have errors", error_count))
+        }
+    }
 
-            for (mode, pid, info) in &device_details {
-                let mode_styled = if mode == "PROGRAM" {
-                    markup::render_or_plain(&format!("[bold #e74c3c]{}[/]", mode))
+    // =========================================================================
+    // Config Profile Commands
+    // =========================================================================
+
+    fn config(&self, command: ConfigCommand) -> Result<()> {
+        match command {
+            ConfigCommand::Save { name, force } => self.config_save(&name, force),
+            ConfigCommand::Load {
+                name,
+                dry_run,
+                force,
+            } => self.config_load(&name, dry_run, force),
+            ConfigCommand::List => self.config_list(),
+            ConfigCommand::Show { name } => self.config_show(&name),
+            ConfigCommand::Delete { name, force } => self.config_delete(&name, force),
+            ConfigCommand::Check { file } => self.config_check(file.as_deref()),
+            ConfigCommand::History => self.config_history(),
+            ConfigCommand::Restore { number, apply } => self.config_restore(number, apply),
+            ConfigCommand::Diff { a, b } => self.config_diff(&a, &b),
+            ConfigCommand::New {
+                name,
+                left,
+                middle,
+                right,
+                force,
+            } => self.config_new(&name, [left, middle, right], force),
+            ConfigCommand::Set {
+                name,
+                pedal,
+                action,
+            } => self.config_set(&name, pedal, &action),
+            ConfigCommand::Copy { from, to, force } => self.config_copy(&from, &to, force),
+            ConfigCommand::Rename { from, to, force } => self.config_rename(&from, &to, force),
+            ConfigCommand::Edit { name } => self.config_edit(&name),
+            ConfigCommand::Export { names, output } => {
+                self.config_export(&names, output.as_deref())
+            }
+            ConfigCommand::Import {
+                file,
+                rename,
+                overwrite,
+                skip,
+            } => {
+                let conflict = if rename {
+                    ImportConflict::Rename
+                } else if overwrite {
+                    ImportConflict::Overwrite
+                } else if skip {
+                    ImportConflict::Skip
                 } else {
-                    markup::render_or_plain(&format!("[bold #2ecc71]{}[/]", mode))
+                    ImportConflict::Fail
                 };
-                table.add_row_cells([
-                    mode_styled,
-                    markup::render_or_plain(pid),
-                    markup::render_or_plain(info),
-                ]);
+                self.config_import(&file, conflict)
             }
-            self.console.print_renderable(&table);
-            self.console.print("");
+        }
+    }
 
-            self.console
-                .print("  [bold #2ecc71]✓[/] [bold white]Ready to program![/]");
-            self.console.print("");
-            self.console.print("  [#95a5a6]Example command:[/]");
-            self.console.print(
-                "    [bold #f1c40f]savant program --left 'cmd+c' --middle 'cmd+a' --right 'cmd+v'[/]",
-            );
+    fn config_save(&self, name: &str, force: bool) -> Result<()> {
+        // Validate profile name
+        validate_profile_name(name)?;
+
+        self.verbose(&format!("Saving profile: {}", name));
+
+        // Check if current config exists
+        let current_config = PedalConfig::load();
+        let Some(config) = current_config else {
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": "no_current_config",
+                    "message": "No current configuration to save. Run 'savant program' first."
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console
+                    .print("[bold red]Error:[/] No current configuration to save.");
+                self.console.print("");
+                self.console
+                    .print("Run [bold yellow]savant program[/] first to create a configuration.");
+            }
+            return Err(anyhow!("No current configuration to save"));
+        };
+
+        let path = profile_path(name);
+
+        // Check if profile already exists
+        if path.exists() && !force {
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": "profile_exists",
+                    "message": format!("Profile '{}' already exists. Use --force to overwrite.", name),
+                    "path": path.display().to_string()
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console.print(&format!(
+                    "[bold red]Error:[/] Profile '{}' already exists.",
+                    name
+                ));
+                self.console.print("");
+                self.console.print(&format!(
+                    "Use [bold yellow]savant config save {} --force[/] to overwrite.",
+                    name
+                ));
+            }
+            return Err(anyhow!("Profile '{}' already exists", name));
         }
 
-        if let Some(e) = libusb_error {
+        // Create profiles directory if needed
+        let dir = profiles_dir();
+        if !dir.exists() {
+            self.verbose(&format!("Creating profiles directory: {}", dir.display()));
+            fs::create_dir_all(&dir).context("Failed to create profiles directory")?;
+        }
+
+        // Save the profile
+        let _lock = lock_config_dir(CONFIG_LOCK_WAIT)?;
+        config.save_to(&path).context("Failed to save profile")?;
+
+        if self.json_output {
+            let output = JsonProfileSaveOutput {
+                success: true,
+                name: name.to_string(),
+                path: path.display().to_string(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            self.console.print(&format!(
+                "[bold #2ecc71]✓[/] Saved profile '[bold yellow]{}[/]'",
+                name
+            ));
+            self.console
+                .print(&format!("  Path: [dim]{}[/]", path.display()));
+            let system_path = system_profiles_dir().join(format!("{}.conf", name));
+            if system_path.is_file() {
+                self.console.print(&format!(
+                    "  [dim]Shadows the system profile {}[/]",
+                    system_path.display()
+                ));
+            }
             self.console.print("");
             self.console.print(&format!(
-                "  [bold #f39c12]⚠[/] [#f39c12]Note:[/] [#95a5a6]libusb scan failed[/] [dim]({})[/]",
-                e
+                "To load: [bold yellow]savant config load {}[/]",
+                name
             ));
-            self.console.print(
-                "  [#95a5a6]If you need to program or reliably detect programming mode, try running with sudo.[/]",
-            );
         }
 
-        self.console.print("");
-        Ok(())
-    }
+        Ok(())
+    }
+
+    fn config_load(&self, name: &str, dry_run: bool, force: bool) -> Result<()> {
+        // Validate profile name
+        validate_profile_name(name)?;
+
+        self.verbose(&format!("Loading profile: {}", name));
+
+        // Check if profile exists
+        let Some(profile) = find_profile(name) else {
+            let path = profile_path(name);
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": "profile_not_found",
+                    "message": format!("Profile '{}' not found.", name),
+                    "path": path.display().to_string()
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console.print(&format!(
+                    "[bold red]Error:[/] Profile '{}' not found.",
+                    name
+                ));
+                self.console.print("");
+                self.console
+                    .print("Run [bold yellow]savant config list[/] to see available profiles.");
+            }
+            return Err(anyhow!("Profile '{}' not found", name));
+        };
+
+        // Load the profile
+        let config = PedalConfig::load_from(&profile.path)
+            .ok_or_else(|| anyhow!("Failed to parse profile '{}'", name))?;
+
+        self.verbose(&format!(
+            "Using {} profile {}",
+            profile.source.label(),
+            profile.path.display()
+        ));
+        self.verbose(&format!(
+            "Profile contents: left={}, middle={}, right={}",
+            config.left, config.middle, config.right
+        ));
+
+        // Program the device using the profile's configuration
+        let command = format!("config load {}", name);
+        self.program(
+            Some(&config.left),
+            Some(&config.middle),
+            Some(&config.right),
+            ProgramOptions {
+                dry_run,
+                details: Some(&config.details),
+                command: Some(&command),
+                force,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn config_list(&self) -> Result<()> {
+        self.verbose("Listing profiles");
+
+        let dir = profiles_dir();
+        let system_dir = system_profiles_dir();
+
+        // Collect profiles from both stores (sorted by name, user copy first)
+        let profiles: Vec<(ProfileLocation, PedalConfig)> = list_profiles()
+            .into_iter()
+            .filter_map(|location| {
+                let config = PedalConfig::load_from(&location.path)?;
+                Some((location, config))
+            })
+            .collect();
+
+        if self.json_output {
+            let output = JsonProfileListOutput {
+                profiles: profiles
+                    .iter()
+                    .map(|(location, config)| JsonProfile::new(location, config))
+                    .collect(),
+                profiles_dir: dir.display().to_string(),
+                system_profiles_dir: system_dir.display().to_string(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
 
-    fn probe(&self) -> Result<()> {
         self.print_banner();
 
         self.console.print(
-            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
+            "[bold #3498db]┌─────────────────────────────────────────────────────────────────┐[/]",
         );
         self.console.print(
-            "[bold #9b59b6]│[/]  [bold #f39c12]🔬[/] [bold white]PROTOCOL PROBE[/] [dim](Reverse Engineering Mode)[/]                  [bold #9b59b6]│[/]",
+            "[bold #3498db]│[/]  [bold white]SAVED PROFILES[/]                                              [bold #3498db]│[/]",
         );
         self.console.print(
-            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
+            "[bold #3498db]└─────────────────────────────────────────────────────────────────┘[/]",
         );
         self.console.print("");
 
-        let api = HidApi::new()?;
+        if profiles.is_empty() {
+            self.console.print("  [dim]No saved profiles yet.[/]");
+            self.console.print("");
+            self.console
+                .print("  Create one with: [bold yellow]savant config save <name>[/]");
+        } else {
+            for (location, config) in &profiles {
+                let source = match (location.source, location.shadowed) {
+                    (ProfileSource::User, _) => String::new(),
+                    (ProfileSource::System, false) => " [dim](system)[/]".to_string(),
+                    (ProfileSource::System, true) => {
+                        " [dim](system, shadowed by your profile)[/]".to_string()
+                    }
+                };
+                self.console
+                    .print(&format!("  [bold yellow]{}[/]{}", location.name, source));
+                self.console.print(&format!(
+                    "    Left: [cyan]{}[/]  Middle: [cyan]{}[/]  Right: [cyan]{}[/]",
+                    config.left, config.middle, config.right
+                ));
+                self.console.print("");
+            }
+        }
 
         self.console
-            .print("  [bold #3498db]Scanning for Kinesis devices...[/]");
-        self.console.print("");
+            .print(&format!("[dim]Profiles directory: {}[/]", dir.display()));
+        self.console.print(&format!(
+            "[dim]System profiles:    {}[/]",
+            system_dir.display()
+        ));
 
-        for device_info in api.device_list() {
-            if device_info.vendor_id() == KINESIS_VID {
-                let pid = device_info.product_id();
-                let mode_indicator = if pid == PROGRAMMING_PID {
-                    "[bold #e74c3c]★ PROGRAMMING MODE[/]"
-                } else {
-                    "[bold #2ecc71]● PLAY MODE[/]"
-                };
+        Ok(())
+    }
 
-                self.console.print(&format!(
-                    "  [bold #f1c40f]►[/] Device [bold white]VID=0x{:04X} PID=0x{:04X}[/]  {}",
-                    device_info.vendor_id(),
-                    pid,
-                    mode_indicator
-                ));
-                self.console.print(&format!(
-                    "    [dim]Path:[/] [#7f8c8d]{}[/]",
-                    device_info.path().to_string_lossy()
-                ));
-                self.console.print(&format!(
-                    "    [dim]Interface:[/] [#7f8c8d]{}[/]  [dim]Usage:[/] [#7f8c8d]0x{:04X}:0x{:04X}[/]",
-                    device_info.interface_number(),
-                    device_info.usage_page(),
-                    device_info.usage()
-                ));
+    fn config_show(&self, name: &str) -> Result<()> {
+        // Validate profile name
+        validate_profile_name(name)?;
 
-                match device_info.open_device(&api) {
-                    Ok(device) => {
-                        self.console
-                            .print("    [bold #2ecc71]✓[/] Opened successfully");
-
-                        // Try PI Engineering X-keys commands
-                        let commands = [
-                            (0xB5, "Generate Data", "#3498db"),
-                            (0xC1, "Get Descriptor", "#9b59b6"),
-                            (0xCD, "Get Key Macro", "#1abc9c"),
-                        ];
-
-                        for (cmd, name, color) in commands {
-                            let mut cmd_buf = [0u8; 36];
-                            cmd_buf[0] = 0;
-                            cmd_buf[1] = cmd;
-
-                            match device.write(&cmd_buf) {
-                                Ok(n) => {
-                                    self.console.print(&format!(
-                                        "    [{}]→[/] {} [dim](0x{:02X})[/]: {} bytes",
-                                        color, name, cmd, n
-                                    ));
-
-                                    std::thread::sleep(Duration::from_millis(50));
-                                    let mut response = [0u8; 64];
-                                    match device.read_timeout(&mut response, 200) {
-                                        Ok(len) if len > 0 => {
-                                            self.console.print(&format!(
-                                                "      [bold #2ecc71]←[/] [#7f8c8d]{}[/]",
-                                                hex::encode(&response[..len])
-                                            ));
-                                        }
-                                        _ => {
-                                            self.console.print("      [dim]← No response[/]");
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    self.console.print(&format!(
-                                        "    [#e74c3c]✗[/] {} failed: [dim]{}[/]",
-                                        name, e
-                                    ));
-                                }
-                            }
-                        }
+        self.verbose(&format!("Showing profile: {}", name));
 
-                        // Try feature reports
-                        self.console
-                            .print("    [bold #f39c12]Checking feature reports...[/]");
-                        for report_id in 0..10u8 {
-                            let mut buf = [0u8; 65];
-                            buf[0] = report_id;
-                            match device.get_feature_report(&mut buf) {
-                                Ok(len) if len > 0 => {
-                                    self.console.print(&format!(
-                                        "      [#2ecc71]Report {}:[/] [#7f8c8d]{}[/]",
-                                        report_id,
-                                        hex::encode(&buf[..len])
-                                    ));
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        self.console
-                            .print(&format!("    [bold #e74c3c]✗[/] Failed to open: {}", e));
-                    }
-                }
+        // Check if profile exists
+        let Some(profile) = find_profile(name) else {
+            let path = profile_path(name);
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": "profile_not_found",
+                    "message": format!("Profile '{}' not found.", name),
+                    "path": path.display().to_string()
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console.print(&format!(
+                    "[bold red]Error:[/] Profile '{}' not found.",
+                    name
+                ));
                 self.console.print("");
+                self.console
+                    .print("Run [bold yellow]savant config list[/] to see available profiles.");
             }
+            return Err(anyhow!("Profile '{}' not found", name));
+        };
+
+        // Load the profile
+        let config = PedalConfig::load_from(&profile.path)
+            .ok_or_else(|| anyhow!("Failed to parse profile '{}'", name))?;
+
+        if self.json_output {
+            let output = JsonProfile::new(&profile, &config);
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
         }
 
+        self.print_banner();
+
         self.console.print(
-            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
+            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
         );
-        self.console.print("[bold #f39c12]Protocol Notes:[/]");
+        self.console.print(&format!(
+            "[bold #9b59b6]│[/]  [bold white]PROFILE: {}[/]{}[bold #9b59b6]│[/]",
+            name.to_uppercase(),
+            " ".repeat(55_usize.saturating_sub(name.len()))
+        ));
         self.console.print(
-            "  [#95a5a6]•[/] Programming mode uses PID [bold]0x0232[/] (vs normal [bold]0x030C[/])",
+            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
         );
         self.console.print("");
-        self.console
-            .print("[bold #f39c12]To Enter Programming Mode:[/]");
-        self.console
-            .print("  [bold #3498db]1.[/] Flip the pedal over");
-        self.console
-            .print("  [bold #3498db]2.[/] Look for a recessed switch near the Kinesis sticker");
-        self.console.print(
-            "  [bold #3498db]3.[/] Use a paperclip to flip it from [#2ecc71]Play[/] → [#e74c3c]Program[/]",
-        );
-        self.console.print(
-            "  [bold #3498db]4.[/] Unplug and replug USB, then run [bold #f1c40f]savant status[/]",
-        );
+
+        // Show the pedal visualization
+        self.print_pedal_visualization(&config.left, &config.middle, &config.right);
+        self.print_config_details(&config);
+        self.console.print(&format!(
+            "  [#95a5a6]Source:[/] {} [dim]({})[/]",
+            profile.source.label(),
+            profile.path.display()
+        ));
+
         self.console.print("");
+        self.console.print(&format!(
+            "To load: [bold yellow]savant config load {}[/]",
+            name
+        ));
+        self.console.print(&format!(
+            "Preview: [bold yellow]savant config load {} --dry-run[/]",
+            name
+        ));
 
         Ok(())
     }
 
-    /// Attempt to verify pedal programming by reading back the macro using GET_KEY_MACRO (0xCD).
-    /// Returns Ok(true) if verified, Ok(false) if mismatch, Err if verification not supported.
-    fn verify_pedal_programming(
-        &self,
-        handle: &rusb::DeviceHandle<GlobalContext>,
-        interface_num: u8,
-        pedal_idx: u8,
-        expected_modifiers: u8,
-        expected_key: u8,
-    ) -> Result<bool> {
-        let mut response = [0u8; 64];
+    /// Print pedal labels/notes and the device binding, if the config has any
+    fn print_config_details(&self, config: &PedalConfig) {
+        for position in PedalPosition::ALL {
+            let PedalDetails { label, notes } = &config.details[position.index()];
+            if label.is_none() && notes.is_none() {
+                continue;
+            }
+            let mut line = format!(
+                "  [bold {}]{}:[/] {}",
+                position.color(),
+                position.label(),
+                label.as_deref().unwrap_or("")
+            );
+            if let Some(notes) = notes {
+                line.push_str(&format!(" [dim]- {}[/]", notes));
+            }
+            self.console.print(&line);
+        }
+        if let Some(device) = &config.device {
+            let mut binding = Vec::new();
+            if let Some(serial) = &device.serial {
+                binding.push(format!("serial {}", serial));
+            }
+            if let Some(unit_id) = device.unit_id {
+                binding.push(format!("unit ID {}", unit_id));
+            }
+            if !binding.is_empty() {
+                self.console.print(&format!(
+                    "  [#95a5a6]Programmed onto:[/] {}",
+                    binding.join(", ")
+                ));
+            }
+        }
+        if let Some(os) = config.target_os {
+            self.console
+                .print(&format!("  [#95a5a6]Target OS:[/] {}", os.label()));
+        }
+    }
 
-        // Try different w_value formats for GET_REPORT with GET_KEY_MACRO
-        // The report ID may be 0, the command byte, or include pedal index
-        for w_value in [
-            // Feature report with CMD as report ID
-            usb_constants::HID_REPORT_TYPE_FEATURE | (xkeys_protocol::CMD_GET_KEY_MACRO as u16),
-            // Feature report with report ID 0
-            usb_constants::HID_REPORT_TYPE_FEATURE,
-            // Input report with CMD as report ID
-            usb_constants::HID_REPORT_TYPE_INPUT | (xkeys_protocol::CMD_GET_KEY_MACRO as u16),
-            // Input report with report ID 0
-            usb_constants::HID_REPORT_TYPE_INPUT,
-        ] {
-            // GET_REPORT request: bmRequestType=0xA1 (device-to-host, class, interface)
-            let result = handle.read_control(
-                usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_IN,
-                usb_constants::HID_GET_REPORT,
-                w_value,
-                interface_num as u16,
-                &mut response,
-                Duration::from_millis(self.timeout_ms),
+    fn config_delete(&self, name: &str, force: bool) -> Result<()> {
+        // Validate profile name
+        validate_profile_name(name)?;
+
+        self.verbose(&format!("Deleting profile: {}", name));
+
+        // Check if profile exists
+        let Some(profile) = find_profile(name) else {
+            let path = profile_path(name);
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": "profile_not_found",
+                    "message": format!("Profile '{}' not found.", name),
+                    "path": path.display().to_string()
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console.print(&format!(
+                    "[bold red]Error:[/] Profile '{}' not found.",
+                    name
+                ));
+            }
+            return Err(anyhow!("Profile '{}' not found", name));
+        };
+        let path = profile.path;
+
+        // System profiles are provisioned centrally and read-only for savant
+        if profile.source == ProfileSource::System {
+            let message = format!(
+                "Profile '{}' is a system profile and cannot be deleted.",
+                name
             );
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": "system_profile",
+                    "message": message,
+                    "path": path.display().to_string()
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console
+                    .print(&format!("[bold red]Error:[/] {}", message));
+                self.console
+                    .print(&format!("  Path: [dim]{}[/]", path.display()));
+                self.console.print("");
+                self.console.print(&format!(
+                    "Use [bold yellow]savant config save {}[/] to shadow it with your own profile.",
+                    name
+                ));
+            }
+            return Err(anyhow!("Profile '{}' is a system profile", name));
+        }
 
-            if let Ok(len) = result {
-                // Response format varies by firmware, try to find mod+key in response
-                // Common formats:
-                // [cmd, pedal, mod, key, ...] or [0, cmd, pedal, mod, key, ...]
-                // or [pedal, mod, key, ...]
-                let (read_mod, read_key) = if len >= 4
-                    && response[0] == xkeys_protocol::CMD_GET_KEY_MACRO
-                    && response[1] == pedal_idx
-                {
-                    // Format: [cmd, pedal, mod, key, ...] - needs 4 bytes
-                    (response[2], response[3])
-                } else if len >= 5
-                    && response[0] == 0
-                    && response[1] == xkeys_protocol::CMD_GET_KEY_MACRO
-                    && response[2] == pedal_idx
-                {
-                    // Format: [0, cmd, pedal, mod, key, ...] - needs 5 bytes
-                    (response[3], response[4])
-                } else if len >= 3 && response[0] == pedal_idx {
-                    // Format: [pedal, mod, key, ...] - needs 3 bytes
-                    (response[1], response[2])
-                } else {
-                    // Unknown format or too short, can't verify
-                    continue;
-                };
+        // In JSON mode or with --force, just delete
+        // Without --force in interactive mode, we'd ideally prompt - but CLI tools
+        // typically use --force for this. We'll just require --force for safety.
+        if !force && !self.json_output {
+            self.console.print(&format!(
+                "[bold #f39c12]Warning:[/] About to delete profile '[bold yellow]{}[/]'",
+                name
+            ));
+            self.console.print("");
+            self.console.print(&format!(
+                "Use [bold yellow]savant config delete {} --force[/] to confirm.",
+                name
+            ));
+            return Ok(());
+        }
 
-                return Ok(read_mod == expected_modifiers && read_key == expected_key);
+        // Delete the profile
+        let _lock = lock_config_dir(CONFIG_LOCK_WAIT)?;
+        fs::remove_file(&path).context("Failed to delete profile")?;
+
+        if self.json_output {
+            let output = JsonProfileDeleteOutput {
+                success: true,
+                name: name.to_string(),
+                path: path.display().to_string(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            self.console.print(&format!(
+                "[bold #2ecc71]✓[/] Deleted profile '[bold yellow]{}[/]'",
+                name
+            ));
+            if let Some(system) = find_profile(name) {
+                self.console.print(&format!(
+                    "  [dim]The system profile of the same name is now used ({})[/]",
+                    system.path.display()
+                ));
             }
         }
 
-        // Verification not supported or no valid response
-        Err(anyhow!("GET_KEY_MACRO not supported by device firmware"))
+        Ok(())
     }
 
-    fn raw_cmd(&self, cmd: &str, data: &str, interface: i32) -> Result<()> {
-        self.console.print("");
-        self.console.print(
-            "[bold #e74c3c]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
-        self.console.print(
-            "[bold #e74c3c]│[/]  [bold #f39c12]⚠[/]  [bold white]RAW COMMAND MODE[/] [dim](Expert Only)[/]                             [bold #e74c3c]│[/]",
-        );
-        self.console.print(
-            "[bold #e74c3c]└─────────────────────────────────────────────────────────────────┘[/]",
-        );
-        self.console.print("");
-
-        let cmd_byte =
-            u8::from_str_radix(cmd, 16).context("Invalid command byte (use hex, e.g., 'b5')")?;
+    fn config_check(&self, file: Option<&str>) -> Result<()> {
+        self.verbose(&format!(
+            "Checking config file: {}",
+            file.unwrap_or("(default)")
+        ));
 
-        let data_bytes: Vec<u8> = if data.is_empty() {
-            vec![]
-        } else {
-            hex::decode(data).context("Invalid data bytes (use hex)")?
+        // Determine which file to check
+        let config_path = match file {
+            Some(path) => std::path::PathBuf::from(path),
+            None => PedalConfig::config_path(),
         };
 
-        // Validate data length (buffer is 36 bytes: 1 report ID + 1 command + 34 data)
-        const MAX_RAW_DATA_LEN: usize = 34;
-        if data_bytes.len() > MAX_RAW_DATA_LEN {
-            return Err(anyhow!(
-                "Data too long: {} bytes exceeds maximum {} bytes",
-                data_bytes.len(),
-                MAX_RAW_DATA_LEN
-            ));
-        }
-
-        let api = HidApi::new().context("Failed to initialize HID API")?;
-
-        let mut found = false;
-        for device_info in api.device_list() {
-            if device_info.vendor_id() == KINESIS_VID
-                && (device_info.product_id() == SAVANT_ELITE_PID
-                    || device_info.product_id() == PROGRAMMING_PID)
-                && device_info.interface_number() == interface
-            {
-                found = true;
-                let device = device_info
-                    .open_device(&api)
-                    .context("Failed to open device")?;
+        let path_display = config_path.display().to_string();
 
-                let mut cmd_buf = [0u8; 36];
-                cmd_buf[0] = 0;
-                cmd_buf[1] = cmd_byte;
-                for (i, b) in data_bytes.iter().enumerate() {
-                    if i + 2 < cmd_buf.len() {
-                        cmd_buf[i + 2] = *b;
-                    }
-                }
+        // Check if file exists
+        if !config_path.exists() {
+            let error = JsonConfigCheckError {
+                line: None,
+                field: None,
+                value: None,
+                column: None,
+                error: "File not found".to_string(),
+                suggestion: None,
+            };
 
+            if self.json_output {
+                let output = JsonConfigCheckOutput {
+                    valid: false,
+                    file: path_display.clone(),
+                    format: None,
+                    target_os: None,
+                    left: None,
+                    middle: None,
+                    right: None,
+                    errors: vec![error],
+                    lint: Vec::new(),
+                };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                self.console.print("[bold red]✗[/] Configuration invalid");
+                self.console.print("");
                 self.console.print(&format!(
-                    "  [bold #3498db]→[/] Sending: [bold #f1c40f]{}[/]",
-                    hex::encode(&cmd_buf[..8])
+                    "  [bold red]Error:[/] File not found: {}",
+                    path_display
                 ));
-
-                let n = device.write(&cmd_buf).context("Write error")?;
+                self.console.print("");
                 self.console
-                    .print(&format!("  [bold #2ecc71]✓[/] Sent [bold]{}[/] bytes", n));
-
-                std::thread::sleep(Duration::from_millis(100));
-                let mut response = [0u8; 64];
-                match device.read_timeout(&mut response, 500) {
-                    Ok(len) if len > 0 => {
-                        self.console.print(&format!(
-                            "  [bold #2ecc71]←[/] Response ([bold]{}[/] bytes): [#7f8c8d]{}[/]",
-                            len,
-                            hex::encode(&response[..len])
-                        ));
-                    }
-                    Ok(_) => {
-                        self.console.print("  [dim]← No response[/]");
-                    }
-                    Err(e) => {
-                        self.console
-                            .print(&format!("  [#e74c3c]✗[/] Read error: {}", e));
-                    }
-                }
-
-                break;
+                    .print("  [dim]Run 'savant program' to create a configuration.[/]");
             }
+            return Err(anyhow!("Config file not found: {}", path_display));
         }
 
-        if !found {
-            return Err(anyhow!(
-                "No Savant Elite device found for interface {} (try `savant info` or `savant status`)",
-                interface
-            ));
-        }
+        // Read file content
+        let content = match fs::read_to_string(&config_path) {
+            Ok(c) => c,
+            Err(e) => {
+                let error = JsonConfigCheckError {
+                    line: None,
+                    field: None,
+                    value: None,
+                    column: None,
+                    error: format!("Cannot read file: {}", e),
+                    suggestion: None,
+                };
 
-        self.console.print("");
-        Ok(())
-    }
+                if self.json_output {
+                    let output = JsonConfigCheckOutput {
+                        valid: false,
+                        file: path_display.clone(),
+                        format: None,
+                        target_os: None,
+                        left: None,
+                        middle: None,
+                        right: None,
+                        errors: vec![error],
+                        lint: Vec::new(),
+                    };
+                    println!("{}", serde_json::to_string_pretty(&output)?);
+                } else {
+                    self.console.print("[bold red]✗[/] Configuration invalid");
+                    self.console.print("");
+                    self.console
+                        .print(&format!("  [bold red]Error:[/] Cannot read file: {}", e));
+                }
+                return Err(anyhow!("Cannot read config file: {}", e));
+            }
+        };
 
-    fn program(
-        &self,
-        left: &str,
-        middle: &str,
-        right: &str,
-        dry_run: bool,
-        auto_monitor: bool,
-    ) -> Result<()> {
-        self.print_banner();
+        // A target OS recorded in the file itself beats the live setting
+        let os = self
+            .target_os
+            .or_else(|| PedalConfig::parse(&content).and_then(|c| c.target_os))
+            .or_else(PedalConfig::saved_target_os)
+            .unwrap_or_else(TargetOs::current);
 
-        self.console.print(
-            "[bold #2ecc71]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
-        self.console.print(
-            "[bold #2ecc71]│[/]  [bold #f39c12]⚡[/] [bold white]PEDAL PROGRAMMING[/]                                           [bold #2ecc71]│[/]",
-        );
-        self.console.print(
-            "[bold #2ecc71]└─────────────────────────────────────────────────────────────────┘[/]",
-        );
-        self.console.print("");
+        // Parse and validate config
+        let legacy = PedalConfig::is_legacy(&content);
+        let ConfigCheckResult {
+            values,
+            parsed,
+            errors,
+            warnings,
+        } = if legacy {
+            check_legacy_config(&content, os)
+        } else {
+            check_versioned_config(&content, os)
+        };
+        for warning in &warnings {
+            self.verbose(warning);
+        }
 
-        // Validate key actions upfront (before any device operations)
-        // This ensures we fail fast on invalid input, even if no device is connected
-        self.verbose(&format!("Parsing left pedal action: '{}'", left));
-        let left_action = KeyAction::from_string(left)?;
-        self.verbose(&format!(
-            "  -> modifiers=0x{:02X}, key=0x{:02X}",
-            left_action.modifiers, left_action.key
-        ));
+        let lint = self.lint(&parsed, os);
 
-        self.verbose(&format!("Parsing middle pedal action: '{}'", middle));
-        let middle_action = KeyAction::from_string(middle)?;
-        self.verbose(&format!(
-            "  -> modifiers=0x{:02X}, key=0x{:02X}",
-            middle_action.modifiers, middle_action.key
-        ));
+        // Build output
+        let is_valid = errors.is_empty();
+        let error_count = errors.len();
+        let format = if legacy {
+            "legacy".to_string()
+        } else {
+            format!("v{}", CONFIG_SCHEMA_VERSION)
+        };
 
-        self.verbose(&format!("Parsing right pedal action: '{}'", right));
-        let right_action = KeyAction::from_string(right)?;
-        self.verbose(&format!(
-            "  -> modifiers=0x{:02X}, key=0x{:02X}",
-            right_action.modifiers, right_action.key
-        ));
+        if self.json_output {
+            let [left, middle, right] = PedalPosition::ALL.map(|position| {
+                let i = position.index();
+                parsed[i].as_ref().map(|a| JsonConfigCheckParsedKey {
+                    action: values[i].clone().unwrap_or_default(),
+                    modifier_hex: format!("0x{:02X}", a.modifiers),
+                    key_hex: format!("0x{:02X}", a.key),
+                })
+            });
+            let output = JsonConfigCheckOutput {
+                valid: is_valid,
+                file: path_display,
+                format: Some(format),
+                target_os: Some(os),
+                left,
+                middle,
+                right,
+                errors,
+                lint: lint.iter().map(JsonLintFinding::from).collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else if is_valid {
+            self.console.print("[bold #2ecc71]✓[/] Configuration valid");
+            self.console.print("");
 
-        // Check if device is in programming mode using libusb
-        self.verbose("Scanning for Savant Elite device via libusb...");
-        let mut programming_device: Option<Device<GlobalContext>> = None;
-        let mut play_mode_found = false;
+            if self.verbose {
+                self.console
+                    .print(&format!("  [dim]File:[/] {}", path_display));
+                self.console.print(&format!("  [dim]Format:[/] {}", format));
+                self.console.print("");
+            }
 
-        let devices = rusb::devices()
-            .context("Failed to list USB devices via libusb (try running with sudo)")?;
-        for device in devices.iter() {
-            let desc = match device.device_descriptor() {
-                Ok(desc) => desc,
-                Err(_) => continue,
-            };
-            if desc.vendor_id() == KINESIS_VID {
-                match desc.product_id() {
-                    PROGRAMMING_PID => {
-                        programming_device = Some(device);
-                        break;
-                    }
-                    SAVANT_ELITE_PID => {
-                        play_mode_found = true;
+            // Show parsed configuration
+            for (position, heading) in PedalPosition::ALL.into_iter().zip([
+                "[bold #e74c3c]Left:[/]  ",
+                "[bold #f39c12]Middle:[/]",
+                "[bold #2ecc71]Right:[/] ",
+            ]) {
+                let i = position.index();
+                if let (Some(value), Some(action)) = (&values[i], &parsed[i]) {
+                    self.console.print(&format!("  {} {}", heading, value));
+                    if self.verbose {
+                        self.console.print(&format!(
+                            "          [dim]Parsed: modifier=0x{:02X}, key=0x{:02X}[/]",
+                            action.modifiers, action.key
+                        ));
                     }
-                    _ => {}
                 }
             }
-        }
 
-        let Some(device) = programming_device else {
-            // Show what would be programmed (preview)
-            self.print_pedal_visualization(left, middle, right);
+            if !lint.is_empty() {
+                self.console.print("");
+                self.print_lint_findings(&lint, os);
+            }
 
-            if play_mode_found {
-                self.console.print(
-                    "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
-                );
-                self.console.print(
-                    "  [bold #e74c3c]│[/]  [bold #e74c3c]✗[/]  [bold white]Device is in PLAY mode, not PROGRAMMING mode[/]          [bold #e74c3c]│[/]",
-                );
-                self.console.print(
-                    "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
-                );
+            if legacy {
                 self.console.print("");
-                self.console
-                    .print("  [bold #f39c12]To program these keys, enter programming mode:[/]");
-                self.console
-                    .print("    [bold #3498db]1.[/] Flip the pedal over");
-                self.console.print(
-                    "    [bold #3498db]2.[/] Find the recessed switch near the Kinesis sticker",
-                );
-                self.console.print(
-                    "    [bold #3498db]3.[/] Use a paperclip to flip it from [#2ecc71]Play[/] → [#e74c3c]Program[/]",
-                );
-                self.console
-                    .print("    [bold #3498db]4.[/] Unplug and replug the USB cable");
-                self.console
-                    .print("    [bold #3498db]5.[/] Run [bold #f1c40f]savant program[/] again");
-            } else {
-                self.console.print(
-                    "  [bold #e74c3c]✗[/] Savant Elite not found. Make sure it's connected.",
-                );
+                self.console.print(&format!(
+                    "  [dim]Legacy key=value format; the live config is upgraded to version {} the next time savant loads it.[/]",
+                    CONFIG_SCHEMA_VERSION
+                ));
             }
+        } else {
+            self.console.print("[bold red]✗[/] Configuration invalid");
             self.console.print("");
-            return Ok(());
-        };
 
-        // Capture device location for disconnect detection
-        let device_bus = device.bus_number();
-        let device_addr = device.address();
+            for error in &errors {
+                let mut msg = String::new();
+                if let Some(line) = error.line {
+                    msg.push_str(&format!("Line {}: ", line));
+                }
+                // Only show "field=value" when we have both field and value
+                // For missing field errors, the error message already explains what's missing
+                if let (Some(ref field), Some(ref value)) = (&error.field, &error.value) {
+                    msg.push_str(&format!("{}={}", field, value));
+                }
+                if !msg.is_empty() {
+                    self.console.print(&format!("  [dim]{}[/]", msg));
+                }
+                // Point at the offending component of the value
+                if let (Some(column), Some(value)) = (error.column, &error.value) {
+                    let component = value
+                        .chars()
+                        .skip(column - 1)
+                        .take_while(|&c| c != '+')
+                        .count()
+                        .max(1);
+                    self.console.print(&format!(
+                        "  {}[bold red]{}[/]",
+                        " ".repeat(msg.chars().count() - value.chars().count() + column - 1),
+                        "^".repeat(component)
+                    ));
+                }
+                self.console
+                    .print(&format!("  [bold red]Error:[/] {}", error.error));
+                self.console.print("");
+            }
 
-        // Show configuration table
-        self.console
-            .print("  [bold #f39c12]Configuration to program:[/]");
-        self.console.print("");
+            self.console
+                .print(&format!("[dim]{} error(s) found[/]", errors.len()));
+            self.console.print("");
+            self.console
+                .print("Run [bold yellow]savant keys[/] for a complete list of valid key names.");
+        }
 
-        let mut config_table = Table::new()
-            .box_style(&ROUNDED)
-            .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
-            .border_style(Style::parse("#3498db").unwrap_or_default())
-            .with_column(Column::new("Pedal"))
-            .with_column(Column::new("Action"))
-            .with_column(Column::new("Modifier"))
-            .with_column(Column::new("Key Code"));
+        if is_valid {
+            Ok(())
+        } else {
+            Err(anyhow!("Configuration has {} error(s)", error_count))
+        }
+    }
 
-        let left_mod = format!("0x{:02X}", left_action.modifiers);
-        let left_key = format!("0x{:02X}", left_action.key);
-        let middle_mod = format!("0x{:02X}", middle_action.modifiers);
-        let middle_key = format!("0x{:02X}", middle_action.key);
-        let right_mod = format!("0x{:02X}", right_action.modifiers);
-        let right_key = format!("0x{:02X}", right_action.key);
-
-        config_table.add_row_cells([
-            markup::render_or_plain("[bold #e74c3c]◀ LEFT[/]"),
-            markup::render_or_plain(left),
-            markup::render_or_plain(&left_mod),
-            markup::render_or_plain(&left_key),
-        ]);
-        config_table.add_row_cells([
-            markup::render_or_plain("[bold #f39c12]● MIDDLE[/]"),
-            markup::render_or_plain(middle),
-            markup::render_or_plain(&middle_mod),
-            markup::render_or_plain(&middle_key),
-        ]);
-        config_table.add_row_cells([
-            markup::render_or_plain("[bold #2ecc71]▶ RIGHT[/]"),
-            markup::render_or_plain(right),
-            markup::render_or_plain(&right_mod),
-            markup::render_or_plain(&right_key),
-        ]);
+    fn config_history(&self) -> Result<()> {
+        self.verbose("Listing config history");
 
-        self.console.print_renderable(&config_table);
-        self.console.print("");
+        let backups = PedalConfig::list_backups();
 
-        if dry_run {
-            // Show visual pedal configuration preview
-            self.print_pedal_visualization(left, middle, right);
+        if self.json_output {
+            let history: Vec<serde_json::Value> = backups
+                .iter()
+                .enumerate()
+                .map(|(i, backup)| {
+                    let mut entry = serde_json::json!({
+                        "number": i + 1,
+                        "timestamp": backup
+                            .timestamp
+                            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                        "path": backup.path.display().to_string(),
+                    });
+                    if let Some(cfg) = &backup.config {
+                        entry["left"] = serde_json::json!(cfg.left);
+                        entry["middle"] = serde_json::json!(cfg.middle);
+                        entry["right"] = serde_json::json!(cfg.right);
+                    }
+                    if let Some(meta) = &backup.meta {
+                        entry["command"] = serde_json::json!(meta.command);
+                        entry["device"] = serde_json::json!(meta.device);
+                        entry["method"] = serde_json::json!(meta.method);
+                        entry["success"] = serde_json::json!(meta.success);
+                        entry["retries"] = serde_json::json!(meta.retries.unwrap_or(0));
+                        entry["interrupted"] = serde_json::json!(meta.interrupted.unwrap_or(false));
+                    }
+                    entry
+                })
+                .collect();
 
-            self.console.print(
-                "  [bold #f39c12]╭────────────────────────────────────────────────────────────╮[/]",
-            );
-            self.console.print(
-                "  [bold #f39c12]│[/]  [bold #f39c12]⚠[/]  [bold white]DRY RUN - No changes will be made[/]                     [bold #f39c12]│[/]",
-            );
-            self.console.print(
-                "  [bold #f39c12]╰────────────────────────────────────────────────────────────╯[/]",
-            );
+            let output = serde_json::json!({
+                "history": history,
+                "count": backups.len(),
+                "history_dir": PedalConfig::history_dir().display().to_string(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+
+        if backups.is_empty() {
+            self.console
+                .print("[bold yellow]No configuration history found.[/]");
             self.console.print("");
             self.console
-                .print("  [#95a5a6]Would send the following commands:[/]");
-            self.console.print(&format!(
-                "    [#3498db]→[/] SET_KEY_MACRO (0xCC) for pedal 0: mod=0x{:02X}, key=0x{:02X}",
-                left_action.modifiers, left_action.key
-            ));
-            self.console.print(&format!(
-                "    [#3498db]→[/] SET_KEY_MACRO (0xCC) for pedal 1: mod=0x{:02X}, key=0x{:02X}",
-                middle_action.modifiers, middle_action.key
-            ));
-            self.console.print(&format!(
-                "    [#3498db]→[/] SET_KEY_MACRO (0xCC) for pedal 2: mod=0x{:02X}, key=0x{:02X}",
-                right_action.modifiers, right_action.key
-            ));
+                .print("[dim]History is created automatically when you program the device[/]");
             self.console
-                .print("    [#3498db]→[/] SAVE_TO_EEPROM (0xCE)");
-            self.console.print("");
+                .print("[dim]or load a profile. Each change creates a backup.[/]");
             return Ok(());
         }
 
-        // Pre-programming warning about keeping device connected
-        self.console.print(
-            "  [bold #f39c12]⚠[/]  [#f39c12]Keep the device connected during programming.[/]",
-        );
-        self.console.print(
-            "     [dim]Unplugging mid-operation may leave pedals in a partial state (in RAM, not saved).[/]",
-        );
+        self.console
+            .print("[bold #3498db]CONFIG HISTORY[/] [dim](most recent first)[/]");
         self.console.print("");
 
-        // Open device
-        self.verbose("Opening USB device...");
-        let handle = device
-            .open()
-            .context("Failed to open device (try running with sudo)")?;
-        self.verbose("USB device opened successfully");
+        for (i, backup) in backups.iter().enumerate() {
+            let num = format!("{:>3}.", i + 1);
+            let timestamp = backup
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string();
 
-        // Get device config to find endpoints
-        let config = device
-            .active_config_descriptor()
-            .or_else(|_| device.config_descriptor(0))
-            .context("Failed to read USB configuration descriptor")?;
-        self.console.print(&format!(
-            "  [#95a5a6]Device has[/] [bold]{}[/] [#95a5a6]interface(s)[/]",
-            config.num_interfaces()
-        ));
+            let summary = if let Some(cfg) = &backup.config {
+                format!("{}, {}, {}", cfg.left, cfg.middle, cfg.right)
+            } else {
+                "[dim]<unable to parse>[/]".to_string()
+            };
 
-        // Try to claim interface 0
-        let interface_num = 0;
-        let mut detached_kernel_driver = false;
-        if handle.kernel_driver_active(interface_num).unwrap_or(false) {
             self.console.print(&format!(
-                "  [#f39c12]→[/] Detaching kernel driver from interface {}...",
-                interface_num
+                "  [bold #3498db]{}[/]  [#95a5a6]{}[/]  {}",
+                num, timestamp, summary
             ));
-            handle
-                .detach_kernel_driver(interface_num)
-                .context("Failed to detach kernel driver - try running with sudo")?;
-            detached_kernel_driver = true;
+            if let Some(meta) = &backup.meta {
+                let status = match meta.success {
+                    _ if meta.interrupted == Some(true) => "  [#f39c12]interrupted[/]",
+                    Some(true) => "  [#2ecc71]✓[/]",
+                    Some(false) => "  [#e74c3c]finished with errors[/]",
+                    None => "",
+                };
+                self.console.print(&format!(
+                    "        [dim]replaced by[/] [bold]savant {}[/]{}",
+                    meta.command, status
+                ));
+            }
         }
 
-        let mut interface_guard = UsbInterfaceGuard {
-            handle: &handle,
-            interface_num,
-            detached_kernel_driver,
-            claimed: false,
-        };
+        self.console.print("");
+        self.console
+            .print("[dim]Use 'savant config restore <N>' to restore a previous config.[/]");
+        self.console.print(
+            "[dim]Use 'savant config restore <N> --apply' to restore and program device.[/]",
+        );
 
-        handle
-            .claim_interface(interface_num)
-            .context("Failed to claim interface - do you have permission?")?;
-        interface_guard.claimed = true;
+        Ok(())
+    }
+
+    fn config_restore(&self, number: usize, apply: bool) -> Result<()> {
+        self.verbose(&format!("Restoring backup #{}", number));
+
+        let config = PedalConfig::restore_backup(number)?;
+
+        // JSON output only when NOT applying (program() doesn't support JSON mode)
+        if self.json_output && !apply {
+            let output = serde_json::json!({
+                "restored": true,
+                "backup_number": number,
+                "left": config.left,
+                "middle": config.middle,
+                "right": config.right,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+
+            // Save the restored config
+            config.save(&HistoryMeta::command(format!("config restore {}", number)))?;
+            return Ok(());
+        }
 
         self.console.print(&format!(
-            "  [bold #2ecc71]✓[/] Claimed interface [bold]{}[/]",
-            interface_num
+            "[bold #2ecc71]Restoring configuration from backup #{}...[/]",
+            number
         ));
         self.console.print("");
-
-        // Log endpoint information for debugging
         self.console
-            .print("  [bold #9b59b6]Endpoint Information:[/]");
-        for interface in config.interfaces() {
-            for desc in interface.descriptors() {
-                self.console.print(&format!(
-                    "    [dim]Interface {}:[/] class={} subclass={} protocol={}",
-                    desc.interface_number(),
-                    desc.class_code(),
-                    desc.sub_class_code(),
-                    desc.protocol_code()
-                ));
-                for ep in desc.endpoint_descriptors() {
-                    let dir = match ep.direction() {
-                        rusb::Direction::Out => "[#e74c3c]OUT[/]",
-                        rusb::Direction::In => "[#2ecc71]IN[/]",
-                    };
-                    let transfer = match ep.transfer_type() {
-                        rusb::TransferType::Control => "Control",
-                        rusb::TransferType::Isochronous => "Isochronous",
-                        rusb::TransferType::Bulk => "Bulk",
-                        rusb::TransferType::Interrupt => "Interrupt",
-                    };
-                    self.console.print(&format!(
-                        "      [#7f8c8d]Endpoint 0x{:02X}:[/] {} {} [dim](max: {})[/]",
-                        ep.address(),
-                        dir,
-                        transfer,
-                        ep.max_packet_size()
-                    ));
-                }
-            }
-        }
-        self.console.print("");
-
-        // Program each pedal using HID SET_REPORT
-        let pedals = [
-            (xkeys_protocol::PEDAL_LEFT, &left_action, "Left", "#e74c3c"),
-            (
-                xkeys_protocol::PEDAL_MIDDLE,
-                &middle_action,
-                "Middle",
-                "#f39c12",
-            ),
-            (
-                xkeys_protocol::PEDAL_RIGHT,
-                &right_action,
-                "Right",
-                "#2ecc71",
-            ),
-        ];
-
-        self.console.print(
-            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
-        );
+            .print(&format!("  [bold]Left:[/]   {}", config.left));
+        self.console
+            .print(&format!("  [bold]Middle:[/] {}", config.middle));
+        self.console
+            .print(&format!("  [bold]Right:[/]  {}", config.right));
         self.console.print("");
 
-        let mut pedal_failures: Vec<&str> = Vec::new();
-        for (pedal_idx, action, name, color) in pedals {
-            self.console.print(&format!(
-                "  [bold {}]▸[/] Programming [bold white]{}[/] pedal...",
-                color, name
-            ));
+        if apply {
+            self.console
+                .print("[bold #3498db]Programming device with restored config...[/]");
+            self.console.print("");
+            self.program(
+                Some(&config.left),
+                Some(&config.middle),
+                Some(&config.right),
+                ProgramOptions {
+                    details: Some(&config.details),
+                    command: Some(&format!("config restore {} --apply", number)),
+                    ..Default::default()
+                },
+            )
+        } else {
+            // Save the restored config
+            config.save(&HistoryMeta::command(format!("config restore {}", number)))?;
+            self.console
+                .print("[bold #2ecc71]✓[/] Config restored to [bold]pedals.conf[/]");
+            self.console.print("");
+            self.console
+                .print("[dim]Run 'savant program' to apply to device, or[/]");
+            self.console.print("[dim]use 'savant config restore <N> --apply' to restore and program in one step.[/]");
+            Ok(())
+        }
+    }
 
-            // Try multiple data formats and transfer methods
-            let mut success = false;
-            let mut success_method = "";
+    fn config_diff(&self, a: &str, b: &str) -> Result<()> {
+        self.verbose(&format!("Comparing {} with {}", a, b));
 
-            // Format 1: Command as first byte, pedal, mods, key
-            let cmd1 = [
-                xkeys_protocol::CMD_SET_KEY_MACRO,
-                pedal_idx,
-                action.modifiers,
-                action.key,
-                0,
-                0,
-                0,
-                0,
-            ];
+        let pedals = diff_configs(&resolve_config_ref(a)?, &resolve_config_ref(b)?);
+        let identical = pedals.iter().all(|p| !p.changed);
 
-            // Format 2: Report ID 0, then command in data
-            let cmd2 = [
-                0u8,
-                xkeys_protocol::CMD_SET_KEY_MACRO,
-                pedal_idx,
-                action.modifiers,
-                action.key,
-                0,
-                0,
-                0,
-            ];
+        if self.json_output {
+            let output = JsonConfigDiffOutput {
+                a: a.to_string(),
+                b: b.to_string(),
+                pedals,
+                identical,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
 
-            // Format 3: Report ID conveys the command, payload is pedal+mod+key.
-            let cmd3_payload = [pedal_idx, action.modifiers, action.key, 0, 0, 0, 0, 0];
+        let mut table = Table::new()
+            .box_style(&ROUNDED)
+            .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
+            .border_style(Style::parse("#3498db").unwrap_or_default())
+            .with_column(Column::new("Pedal"))
+            .with_column(Column::new(a))
+            .with_column(Column::new(b));
 
-            // Try SET_REPORT with multiple (report-id, layout) combinations.
-            //
-            // Different firmware revisions appear to expect one of:
-            // - report_id = 0 with the command as the first data byte (no leading report-id byte),
-            // - report_id = 0 with a leading 0 report-id byte (hidapi-style),
-            // - report_id = CMD with either data starting at CMD or with a compact payload.
-            for (fmt_name, w_value, data) in [
-                (
-                    "feat-rid0-cmd",
-                    usb_constants::HID_REPORT_TYPE_FEATURE,
-                    &cmd1[..],
-                ),
-                (
-                    "feat-rid0-prefix",
-                    usb_constants::HID_REPORT_TYPE_FEATURE,
-                    &cmd2[..],
-                ),
-                (
-                    "feat-ridcmd",
-                    usb_constants::HID_REPORT_TYPE_FEATURE
-                        | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
-                    &cmd1[..],
-                ),
-                (
-                    "feat-ridcmd-payload",
-                    usb_constants::HID_REPORT_TYPE_FEATURE
-                        | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
-                    &cmd3_payload[..],
-                ),
-                (
-                    "out-rid0-cmd",
-                    usb_constants::HID_REPORT_TYPE_OUTPUT,
-                    &cmd1[..],
-                ),
-                (
-                    "out-rid0-prefix",
-                    usb_constants::HID_REPORT_TYPE_OUTPUT,
-                    &cmd2[..],
-                ),
+        for (position, pedal) in PedalPosition::ALL.into_iter().zip(&pedals) {
+            let (left, right) = if pedal.changed {
                 (
-                    "out-ridcmd",
-                    usb_constants::HID_REPORT_TYPE_OUTPUT
-                        | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
-                    &cmd1[..],
-                ),
+                    format!("[#e74c3c]{}[/]", pedal.a),
+                    format!("[#2ecc71]{}[/]", pedal.b),
+                )
+            } else {
                 (
-                    "out-ridcmd-payload",
-                    usb_constants::HID_REPORT_TYPE_OUTPUT
-                        | (xkeys_protocol::CMD_SET_KEY_MACRO as u16),
-                    &cmd3_payload[..],
-                ),
-            ] {
-                let result = handle.write_control(
-                    usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
-                    usb_constants::HID_SET_REPORT,
-                    w_value,
-                    interface_num as u16,
-                    data,
-                    Duration::from_millis(self.timeout_ms),
-                );
-                if result.is_ok() {
-                    success = true;
-                    success_method = fmt_name;
-                    break;
-                }
-            }
+                    format!("[dim]{}[/]", pedal.a),
+                    format!("[dim]{}[/]", pedal.b),
+                )
+            };
+            table.add_row_cells([
+                markup::render_or_plain(position.table_label()),
+                markup::render_or_plain(&left),
+                markup::render_or_plain(&right),
+            ]);
+        }
 
-            // Try with longer buffer (36 bytes like PI Engineering)
-            if !success {
-                let mut long_prefixed = [0u8; 36];
-                long_prefixed[0] = 0;
-                long_prefixed[1] = xkeys_protocol::CMD_SET_KEY_MACRO;
-                long_prefixed[2] = pedal_idx;
-                long_prefixed[3] = action.modifiers;
-                long_prefixed[4] = action.key;
-
-                let mut long_unprefixed = [0u8; 36];
-                long_unprefixed[0] = xkeys_protocol::CMD_SET_KEY_MACRO;
-                long_unprefixed[1] = pedal_idx;
-                long_unprefixed[2] = action.modifiers;
-                long_unprefixed[3] = action.key;
+        self.console.print_renderable(&table);
+        self.console.print("");
+        if identical {
+            self.console
+                .print("[bold #2ecc71]✓[/] The configurations are identical.");
+        } else {
+            let changed = pedals.iter().filter(|p| p.changed).count();
+            self.console
+                .print(&format!("[bold yellow]{} pedal(s) differ.[/]", changed));
+        }
 
-                for (fmt_name, w_value, data) in [
-                    (
-                        "36b-out-prefix",
-                        usb_constants::HID_REPORT_TYPE_OUTPUT,
-                        &long_prefixed[..],
-                    ),
-                    (
-                        "36b-out-cmd",
-                        usb_constants::HID_REPORT_TYPE_OUTPUT,
-                        &long_unprefixed[..],
-                    ),
-                    (
-                        "36b-feat-prefix",
-                        usb_constants::HID_REPORT_TYPE_FEATURE,
-                        &long_prefixed[..],
-                    ),
-                    (
-                        "36b-feat-cmd",
-                        usb_constants::HID_REPORT_TYPE_FEATURE,
-                        &long_unprefixed[..],
-                    ),
-                ] {
-                    let result = handle.write_control(
-                        usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
-                        usb_constants::HID_SET_REPORT,
-                        w_value,
-                        interface_num as u16,
-                        data,
-                        Duration::from_millis(self.timeout_ms),
-                    );
-                    if result.is_ok() {
-                        success = true;
-                        success_method = fmt_name;
-                        break;
-                    }
-                }
-            }
+        Ok(())
+    }
 
-            // Try vendor-specific request
-            if !success {
-                let result = handle.write_control(
-                    usb_constants::USB_REQUEST_TYPE_VENDOR_OUT,
-                    xkeys_protocol::CMD_SET_KEY_MACRO,
-                    ((action.key as u16) << 8) | (action.modifiers as u16),
-                    pedal_idx as u16,
-                    &[],
-                    Duration::from_millis(self.timeout_ms),
-                );
-                if result.is_ok() {
-                    success = true;
-                    success_method = "vendor";
-                }
-            }
+    /// Program the configuration `step` walks back (or forward) to in history. The change
+    /// is recorded as `undo`/`redo` so later steps can find their place.
+    fn history_step(&self, step: HistoryStep, dry_run: bool) -> Result<()> {
+        let command = step.command();
+        self.verbose(&format!("Looking up {} target in history", command));
 
-            if success {
-                self.console.print(&format!(
-                    "    [bold #2ecc71]✓[/] [#95a5a6]Success[/] [dim]({})[/]",
-                    success_method
-                ));
+        let backups = PedalConfig::list_backups();
+        let target = history_step_target(
+            backups
+                .iter()
+                .map(|entry| entry.meta.as_ref().map(|meta| meta.command.as_str())),
+            step,
+        )
+        .ok_or_else(|| match step {
+            HistoryStep::Undo => anyhow!("Nothing to undo: configuration history is empty"),
+            HistoryStep::Redo => anyhow!("Nothing to redo"),
+        })?;
+        let entry = &backups[target];
+        let config = entry
+            .config
+            .clone()
+            .ok_or_else(|| anyhow!("Failed to parse backup file: {}", entry.path.display()))?;
 
-                // Attempt read-back verification using GET_KEY_MACRO (0xCD)
-                // This is best-effort - some firmware versions may not support it
-                std::thread::sleep(Duration::from_millis(50));
-                let verified = self.verify_pedal_programming(
-                    &handle,
-                    interface_num,
-                    pedal_idx,
-                    action.modifiers,
-                    action.key,
-                );
-                match verified {
-                    Ok(true) => {
-                        self.console.print("    [dim]✓ Verified[/]");
-                    }
-                    Ok(false) => {
-                        self.console.print(
-                            "    [bold #f39c12]⚠[/] [#f39c12]Read-back mismatch - verify manually after switching to Play mode[/]",
-                        );
-                    }
-                    Err(_) => {
-                        // Verification not supported or failed - that's OK, just skip silently
-                    }
-                }
-            } else {
-                self.console
-                    .print("    [bold #e74c3c]✗[/] [#e74c3c]Failed[/]");
-                pedal_failures.push(name);
-            }
+        self.verbose(&format!(
+            "{} target is history entry #{} ({})",
+            command,
+            target + 1,
+            entry.path.display()
+        ));
+        self.console.print(&format!(
+            "[bold #3498db]{} to the configuration from {}[/] [dim](history #{})[/]",
+            match step {
+                HistoryStep::Undo => "Reverting",
+                HistoryStep::Redo => "Reapplying",
+            },
+            entry
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            target + 1
+        ));
+        self.console.print("");
 
-            std::thread::sleep(Duration::from_millis(50));
+        self.program(
+            Some(&config.left),
+            Some(&config.middle),
+            Some(&config.right),
+            ProgramOptions {
+                dry_run,
+                details: Some(&config.details),
+                command: Some(command),
+                wait_for_programming: true,
+                ..Default::default()
+            },
+        )
+    }
 
-            // Check if device is still connected after programming this pedal
-            if !is_device_still_connected(device_bus, device_addr) {
-                self.console.print("");
-                self.console.print(
-                    "  [bold #e74c3c]╭────────────────────────────────────────────────────────────╮[/]",
-                );
-                self.console.print(
-                    "  [bold #e74c3c]│[/]  [bold #e74c3c]⚠[/]  [bold white]DEVICE DISCONNECTED![/]                                  [bold #e74c3c]│[/]",
-                );
-                self.console.print(
-                    "  [bold #e74c3c]╰────────────────────────────────────────────────────────────╯[/]",
-                );
-                self.console.print("");
-                self.console.print(
-                    "  [bold #f39c12]WARNING:[/] The device was unplugged during programming.",
-                );
-                self.console.print(
-                    "  [#95a5a6]Pedals programmed so far were stored in RAM only (not saved to EEPROM).[/]",
-                );
-                self.console
-                    .print("  [#95a5a6]Those changes were lost when the device was unplugged.[/]");
-                self.console.print("");
-                self.console
-                    .print("  [bold #f39c12]To complete programming:[/]");
-                self.console.print(
-                    "    [bold #3498db]1.[/] Reconnect the device (keep in Programming mode)",
-                );
-                self.console
-                    .print("    [bold #3498db]2.[/] Run [bold #f1c40f]savant program[/] again");
-                self.console.print("");
-                return Ok(());
-            }
+    /// Report a profile written by new/set/copy/rename/edit
+    fn print_profile_written(&self, verb: &str, name: &str, path: &std::path::Path) -> Result<()> {
+        if self.json_output {
+            let output = JsonProfileSaveOutput {
+                success: true,
+                name: name.to_string(),
+                path: path.display().to_string(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            self.console.print(&format!(
+                "[bold #2ecc71]✓[/] {} profile '[bold yellow]{}[/]'",
+                verb, name
+            ));
+            self.console
+                .print(&format!("  Path: [dim]{}[/]", path.display()));
         }
+        Ok(())
+    }
 
-        self.console.print("");
+    fn config_new(&self, name: &str, actions: [String; 3], force: bool) -> Result<()> {
+        self.verbose(&format!("Creating profile: {}", name));
 
-        // Final device presence check before EEPROM save
-        if !is_device_still_connected(device_bus, device_addr) {
-            self.console.print(
-                "  [bold #e74c3c]⚠[/]  [bold #e74c3c]Device disconnected before EEPROM save![/]",
-            );
-            self.console.print(
-                "  [#95a5a6]Pedal configurations were sent but NOT saved to permanent storage.[/]",
-            );
-            self.console
-                .print("  [#95a5a6]Reconnect and run [bold #f1c40f]savant program[/] again.[/]");
-            self.console.print("");
-            return Ok(());
-        }
+        let [left, middle, right] = actions;
+        let config = PedalConfig {
+            left,
+            middle,
+            right,
+            target_os: self.target_os,
+            ..Default::default()
+        };
+        let path = write_profile(name, &config, force)?;
+        self.print_profile_written("Created", name, &path)
+    }
 
-        // Save to EEPROM
-        self.console
-            .print("  [bold #f1c40f]▸[/] Saving to EEPROM...");
-        let save_cmd = [xkeys_protocol::CMD_SAVE_TO_EEPROM, 0, 0, 0, 0, 0, 0, 0];
-        let save_alt = [0u8, xkeys_protocol::CMD_SAVE_TO_EEPROM, 0, 0, 0, 0, 0, 0];
-        let save_payload = [0u8; 8];
-        let mut save_success = false;
+    fn config_set(&self, name: &str, pedal: PedalPosition, action: &str) -> Result<()> {
+        self.verbose(&format!(
+            "Setting {} pedal of profile {} to {}",
+            pedal.name(),
+            name,
+            action
+        ));
 
-        for (_fmt_name, w_value, data) in [
-            (
-                "out-rid0-cmd",
-                usb_constants::HID_REPORT_TYPE_OUTPUT,
-                &save_cmd[..],
-            ),
-            (
-                "out-rid0-prefix",
-                usb_constants::HID_REPORT_TYPE_OUTPUT,
-                &save_alt[..],
-            ),
-            (
-                "out-ridcmd",
-                usb_constants::HID_REPORT_TYPE_OUTPUT | (xkeys_protocol::CMD_SAVE_TO_EEPROM as u16),
-                &save_cmd[..],
-            ),
-            (
-                "out-ridcmd-payload",
-                usb_constants::HID_REPORT_TYPE_OUTPUT | (xkeys_protocol::CMD_SAVE_TO_EEPROM as u16),
-                &save_payload[..],
-            ),
-            (
-                "feat-rid0-cmd",
-                usb_constants::HID_REPORT_TYPE_FEATURE,
-                &save_cmd[..],
-            ),
-            (
-                "feat-rid0-prefix",
-                usb_constants::HID_REPORT_TYPE_FEATURE,
-                &save_alt[..],
-            ),
-            (
-                "feat-ridcmd",
-                usb_constants::HID_REPORT_TYPE_FEATURE
-                    | (xkeys_protocol::CMD_SAVE_TO_EEPROM as u16),
-                &save_cmd[..],
-            ),
-            (
-                "feat-ridcmd-payload",
-                usb_constants::HID_REPORT_TYPE_FEATURE
-                    | (xkeys_protocol::CMD_SAVE_TO_EEPROM as u16),
-                &save_payload[..],
-            ),
-        ] {
-            let result = handle.write_control(
-                usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
-                usb_constants::HID_SET_REPORT,
-                w_value,
-                interface_num as u16,
-                data,
-                Duration::from_millis(self.timeout_ms),
-            );
-            if result.is_ok() {
-                save_success = true;
-                break;
-            }
+        KeyAction::from_string(action)
+            .with_context(|| format!("Invalid {} pedal action", pedal.name()))?;
+        let (location, mut config) = load_profile(name)?;
+        if location.source == ProfileSource::System {
+            return Err(anyhow!(
+                "Profile '{}' is a system profile; copy it first with 'savant config copy {} <NEW>'",
+                name,
+                name
+            ));
         }
 
-        if !save_success {
-            // Try with longer buffer (36 bytes like PI Engineering)
-            let mut long_prefixed = [0u8; 36];
-            long_prefixed[0] = 0;
-            long_prefixed[1] = xkeys_protocol::CMD_SAVE_TO_EEPROM;
+        *match pedal {
+            PedalPosition::Left => &mut config.left,
+            PedalPosition::Middle => &mut config.middle,
+            PedalPosition::Right => &mut config.right,
+        } = action.to_string();
+        let path = write_profile(name, &config, true)?;
+        self.print_profile_written("Updated", name, &path)
+    }
 
-            let mut long_unprefixed = [0u8; 36];
-            long_unprefixed[0] = xkeys_protocol::CMD_SAVE_TO_EEPROM;
+    fn config_copy(&self, from: &str, to: &str, force: bool) -> Result<()> {
+        self.verbose(&format!("Copying profile {} to {}", from, to));
 
-            for (w_value, data) in [
-                (usb_constants::HID_REPORT_TYPE_OUTPUT, &long_prefixed[..]),
-                (usb_constants::HID_REPORT_TYPE_OUTPUT, &long_unprefixed[..]),
-                (usb_constants::HID_REPORT_TYPE_FEATURE, &long_prefixed[..]),
-                (usb_constants::HID_REPORT_TYPE_FEATURE, &long_unprefixed[..]),
-            ] {
-                let result = handle.write_control(
-                    usb_constants::HID_REQUEST_TYPE_CLASS_INTERFACE_OUT,
-                    usb_constants::HID_SET_REPORT,
-                    w_value,
-                    interface_num as u16,
-                    data,
-                    Duration::from_millis(self.timeout_ms),
-                );
-                if result.is_ok() {
-                    save_success = true;
-                    break;
+        let (_, config) = load_profile(from)?;
+        let path = write_profile(to, &config, force)?;
+        self.print_profile_written("Created", to, &path)
+    }
+
+    fn config_rename(&self, from: &str, to: &str, force: bool) -> Result<()> {
+        self.verbose(&format!("Renaming profile {} to {}", from, to));
+
+        let (location, config) = load_profile(from)?;
+        if location.source == ProfileSource::System {
+            return Err(anyhow!(
+                "Profile '{}' is a system profile and cannot be renamed",
+                from
+            ));
+        }
+        if from == to {
+            return self.print_profile_written("Kept", to, &location.path);
+        }
+
+        let path = write_profile(to, &config, force)?;
+        let _lock = lock_config_dir(CONFIG_LOCK_WAIT)?;
+        fs::remove_file(&location.path).context("Failed to remove the old profile")?;
+        self.print_profile_written("Renamed to", to, &path)
+    }
+
+    fn config_edit(&self, name: &str) -> Result<()> {
+        use std::io::{BufRead, IsTerminal, Write};
+
+        let (location, config) = load_profile(name)?;
+        if location.source == ProfileSource::System {
+            return Err(anyhow!(
+                "Profile '{}' is a system profile; copy it first with 'savant config copy {} <NEW>'",
+                name,
+                name
+            ));
+        }
+
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let mut editor_args = editor.split_whitespace();
+        let program = editor_args
+            .next()
+            .ok_or_else(|| anyhow!("$EDITOR is empty"))?;
+        let editor_args: Vec<&str> = editor_args.collect();
+
+        let original = config.serialize()?;
+        // Created with O_EXCL and owner-only permissions, so a predictable name in a shared
+        // temp dir can't be pre-planted as a symlink
+        let mut scratch_file = tempfile::Builder::new()
+            .prefix(&format!("savant-{}-", name))
+            .suffix(".json")
+            .tempfile()
+            .context("Failed to create a scratch file for the editor")?;
+        scratch_file.write_all(original.as_bytes())?;
+        scratch_file.flush()?;
+        let scratch = scratch_file.path().to_path_buf();
+        self.verbose(&format!("Editing {} with {}", scratch.display(), editor));
+
+        let result = (|| loop {
+            let status = std::process::Command::new(program)
+                .args(&editor_args)
+                .arg(&scratch)
+                .status()
+                .with_context(|| format!("Failed to run editor '{}'", editor))?;
+            if !status.success() {
+                return Err(anyhow!(
+                    "Editor exited with {}; profile left unchanged",
+                    status
+                ));
+            }
+
+            let content = fs::read_to_string(&scratch)?;
+            let check = if PedalConfig::is_legacy(&content) {
+                check_legacy_config(&content, self.target_os())
+            } else {
+                check_versioned_config(&content, self.target_os())
+            };
+            if check.errors.is_empty() {
+                if let Some(config) = PedalConfig::parse(&content) {
+                    return Ok((content == original, config));
                 }
             }
-        }
 
-        if save_success {
-            std::thread::sleep(Duration::from_millis(200));
             self.console
-                .print("    [bold #2ecc71]✓[/] [#95a5a6]EEPROM saved[/]");
-        } else {
-            // Check if failure was due to device disconnect
-            if !is_device_still_connected(device_bus, device_addr) {
-                self.console.print(
-                    "    [bold #e74c3c]⚠[/] [#e74c3c]Device disconnected during EEPROM save![/]",
-                );
-                self.console.print(
-                    "    [#95a5a6]The save was interrupted - settings may or may not have persisted.[/]",
-                );
-                self.console.print(
-                    "    [#95a5a6]Reconnect and test the pedals to verify, or re-run savant program.[/]",
-                );
+                .print("[bold red]✗[/] The profile is not valid:");
+            for error in &check.errors {
+                let line = match (error.line, &error.field) {
+                    (Some(line), _) => format!("line {}: ", line),
+                    (None, Some(field)) => format!("{}: ", field),
+                    (None, None) => String::new(),
+                };
+                self.console
+                    .print(&format!("  [bold red]Error:[/] {}{}", line, error.error));
+            }
+            if !std::io::stdin().is_terminal() {
+                return Err(anyhow!("Profile '{}' is invalid; not saved", name));
+            }
+            self.console.print(
+                "Press [bold]Enter[/] to edit again, or type [bold]q[/] to discard changes: ",
+            );
+            let mut answer = String::new();
+            std::io::stdin().lock().read_line(&mut answer)?;
+            if answer.trim().eq_ignore_ascii_case("q") {
+                return Err(anyhow!("Edit of profile '{}' discarded", name));
+            }
+        })();
+        drop(scratch_file);
+
+        let (unchanged, edited) = result?;
+        if unchanged {
+            self.console.print("[dim]No changes.[/]");
+            return Ok(());
+        }
+        let path = write_profile(name, &edited, true)?;
+        self.print_profile_written("Updated", name, &path)
+    }
+
+    fn config_export(&self, names: &[String], output: Option<&str>) -> Result<()> {
+        self.verbose(&format!(
+            "Exporting profiles: {}",
+            if names.is_empty() {
+                "(all)".to_string()
             } else {
-                self.console.print(
-                    "    [bold #f39c12]⚠[/] [#f39c12]Save command may have failed, but programming was done[/]",
-                );
+                names.join(", ")
             }
+        ));
+
+        let locations: Vec<ProfileLocation> = if names.is_empty() {
+            list_profiles()
+                .into_iter()
+                .filter(|p| !p.shadowed)
+                .collect()
+        } else {
+            names
+                .iter()
+                .map(|name| {
+                    validate_profile_name(name)?;
+                    find_profile(name).ok_or_else(|| anyhow!("Profile '{}' not found", name))
+                })
+                .collect::<Result<_>>()?
+        };
+
+        let mut profiles = Vec::new();
+        for location in &locations {
+            let config = PedalConfig::load_from(&location.path)
+                .ok_or_else(|| anyhow!("Failed to parse profile '{}'", location.name))?;
+            config.serialize()?;
+            profiles.push(BundleProfile {
+                name: location.name.clone(),
+                config: config.to_file(),
+            });
         }
 
-        self.console.print("");
-        self.console.print(
-            "[#3498db]─────────────────────────────────────────────────────────────────────[/]",
-        );
-        self.console.print("");
-        // Always save config to preserve user's intent (even on partial success)
-        // This helps with `savant info` display and retry attempts
-        let config = PedalConfig {
-            left: left.to_string(),
-            middle: middle.to_string(),
-            right: right.to_string(),
+        let bundle = ProfileBundle {
+            version: BUNDLE_VERSION,
+            exported_at: Some(
+                chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            ),
+            profiles,
         };
-        if let Err(e) = config.save() {
+        let content = serde_json::to_string_pretty(&bundle)? + "\n";
+
+        let Some(output) = output else {
+            print!("{}", content);
+            return Ok(());
+        };
+        let path = PathBuf::from(output);
+        write_atomic(&path, &content)
+            .with_context(|| format!("Failed to write bundle to {}", path.display()))?;
+
+        if self.json_output {
+            let output = serde_json::json!({
+                "file": path.display().to_string(),
+                "profiles": bundle.profiles.iter().map(|p| &p.name).collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
             self.console.print(&format!(
-                "  [dim]Note: Could not save config to disk: {}[/]",
-                e
+                "[bold #2ecc71]✓[/] Exported {} profile(s) to [bold]{}[/]",
+                bundle.profiles.len(),
+                path.display()
+            ));
+            for profile in &bundle.profiles {
+                self.console
+                    .print(&format!("  [bold yellow]{}[/]", profile.name));
+            }
+            self.console.print("");
+            self.console.print(&format!(
+                "On the other machine: [bold yellow]savant config import {}[/]",
+                path.display()
             ));
         }
 
-        if pedal_failures.is_empty() && save_success {
-            self.console.print(
-                "  [bold #2ecc71]╭────────────────────────────────────────────────────────────╮[/]",
-            );
-            self.console.print(
-                "  [bold #2ecc71]│[/]  [bold #2ecc71]✓[/]  [bold white]PROGRAMMING COMPLETE![/]                                  [bold #2ecc71]│[/]",
-            );
-            self.console.print(
-                "  [bold #2ecc71]╰────────────────────────────────────────────────────────────╯[/]",
-            );
-        } else {
-            self.console.print(
-                "  [bold #f39c12]╭────────────────────────────────────────────────────────────╮[/]",
-            );
-            self.console.print(
-                "  [bold #f39c12]│[/]  [bold #f39c12]⚠[/]  [bold white]PROGRAMMING FINISHED WITH WARNINGS[/]                    [bold #f39c12]│[/]",
-            );
-            self.console.print(
-                "  [bold #f39c12]╰────────────────────────────────────────────────────────────╯[/]",
-            );
-            if !pedal_failures.is_empty() {
-                self.console.print(&format!(
-                    "  [#95a5a6]Failed pedals:[/] [bold #e74c3c]{}[/]",
-                    pedal_failures.join(", ")
-                ));
-            }
-            if !save_success {
-                self.console.print(
-                    "  [#95a5a6]EEPROM save may have failed; changes might not persist after unplug.[/]",
-                );
+        Ok(())
+    }
+
+    fn config_import(&self, file: &str, conflict: ImportConflict) -> Result<()> {
+        self.verbose(&format!("Importing profiles from {}", file));
+
+        let content =
+            fs::read_to_string(file).with_context(|| format!("Cannot read bundle {}", file))?;
+
+        let _lock = lock_config_dir(CONFIG_LOCK_WAIT)?;
+        let steps = plan_import(&content, |name| profile_path(name).is_file(), conflict)?;
+
+        if steps.iter().any(|s| s.action != ImportAction::Skip) {
+            fs::create_dir_all(profiles_dir()).context("Failed to create profiles directory")?;
+        }
+        for step in &steps {
+            if step.action == ImportAction::Skip {
+                continue;
             }
+            step.config
+                .save_to(&profile_path(&step.name))
+                .with_context(|| format!("Failed to save profile '{}'", step.name))?;
         }
-        // Show visual pedal configuration
-        self.print_pedal_visualization(left, middle, right);
 
-        self.console
-            .print("  [bold #f39c12]To use the new configuration:[/]");
-        self.console
-            .print("    [bold #3498db]1.[/] Flip the switch back to [bold #2ecc71]Play[/] mode");
-        self.console
-            .print("    [bold #3498db]2.[/] Unplug and replug the USB cable");
-        self.console
-            .print("    [bold #3498db]3.[/] Your pedals should now send the programmed keys!");
-        self.console.print("");
+        if self.json_output {
+            let profiles: Vec<serde_json::Value> = steps
+                .iter()
+                .map(|step| {
+                    serde_json::json!({
+                        "name": step.name,
+                        "bundle_name": step.original_name,
+                        "action": step.action.label(),
+                        "path": profile_path(&step.name).display().to_string(),
+                    })
+                })
+                .collect();
+            let output = serde_json::json!({
+                "file": file,
+                "profiles": profiles,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
 
-        if auto_monitor {
-            self.console.print(
-                "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
-            );
-            self.console.print(
-                "[bold #9b59b6]│[/]  [bold #f39c12]👁[/]  [bold white]MONITOR MODE[/] [dim](waiting for device in play mode)[/]            [bold #9b59b6]│[/]",
-            );
-            self.console.print(
-                "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
-            );
-            self.console.print("");
-            self.console
-                .print("  [bold #f39c12]1.[/] Switch the pedal to [bold #2ecc71]Play[/] mode");
+        if steps.is_empty() {
             self.console
-                .print("  [bold #f39c12]2.[/] Replug the USB cable");
-            self.console.print("");
-            self.console.print(
-                "  [#95a5a6]Waiting for device...[/] [dim](60s timeout, Ctrl+C to cancel)[/]",
-            );
-            self.console.print("");
+                .print("[bold yellow]The bundle contains no profiles.[/]");
+            return Ok(());
+        }
+        for step in &steps {
+            let line = match step.action {
+                ImportAction::Create => format!("[bold #2ecc71]+[/] [bold yellow]{}[/]", step.name),
+                ImportAction::Overwrite => format!(
+                    "[bold #f39c12]~[/] [bold yellow]{}[/] [dim](overwritten)[/]",
+                    step.name
+                ),
+                ImportAction::Rename => format!(
+                    "[bold #2ecc71]+[/] [bold yellow]{}[/] [dim](renamed from {})[/]",
+                    step.name, step.original_name
+                ),
+                ImportAction::Skip => format!(
+                    "[dim]-[/] [bold yellow]{}[/] [dim](skipped, already exists)[/]",
+                    step.name
+                ),
+            };
+            self.console.print(&format!("  {}", line));
+        }
+        let written = steps
+            .iter()
+            .filter(|s| s.action != ImportAction::Skip)
+            .count();
+        self.console.print("");
+        self.console.print(&format!(
+            "[bold #2ecc71]✓[/] Imported {} of {} profile(s) into [dim]{}[/]",
+            written,
+            steps.len(),
+            profiles_dir().display()
+        ));
 
-            // Wait for device to appear in play mode (user needs to switch and replug)
-            let wait_start = std::time::Instant::now();
-            let timeout = Duration::from_secs(60);
-            let mut last_reminder = wait_start;
+        Ok(())
+    }
 
-            loop {
-                if self.open_keyboard_interface().is_ok() {
-                    break;
-                }
+    // =========================================================================
+    // Unit ID Commands
+    // =========================================================================
 
-                if wait_start.elapsed() > timeout {
-                    self.console.print("");
-                    self.console.print(
-                        "  [bold #e74c3c]Timeout![/] Device not detected in play mode after 60s.",
-                    );
-                    self.console.print(
-                        "  Run [bold #f1c40f]savant monitor[/] manually after switching modes.",
-                    );
-                    self.console.print("");
-                    return Ok(());
-                }
+    fn unit_id(&self, command: UnitIdCommand) -> Result<()> {
+        match command {
+            UnitIdCommand::Get => self.unit_id_get(),
+            UnitIdCommand::Set { id } => self.unit_id_set(id),
+        }
+    }
 
-                // Reminder every 15 seconds
-                if last_reminder.elapsed() > Duration::from_secs(15) {
-                    let remaining = timeout.saturating_sub(wait_start.elapsed()).as_secs();
-                    self.console.print(&format!(
-                        "  [dim]Still waiting... {}s remaining (switch to Play mode and replug USB)[/]",
-                        remaining
-                    ));
-                    last_reminder = std::time::Instant::now();
-                }
+    fn unit_id_get(&self) -> Result<()> {
+        self.verbose("Reading unit IDs via libusb");
 
-                std::thread::sleep(Duration::from_millis(500));
+        let devices = self
+            .session
+            .usb_devices()
+            .context("Failed to list USB devices via libusb (try running with sudo)")?;
+
+        let mut found: Vec<JsonStatusDevice> = Vec::new();
+        for device in devices {
+            let Ok(desc) = device.device_descriptor() else {
+                continue;
+            };
+            if desc.vendor_id() != KINESIS_VID {
+                continue;
             }
+            let mode = match desc.product_id() {
+                SAVANT_ELITE_PID => "play",
+                PROGRAMMING_PID => "program",
+                _ => continue,
+            };
 
+            let unit_id = self.query_unit_id(&device);
+            if self.unit_filter.is_some() && unit_id != self.unit_filter {
+                continue;
+            }
+
+            found.push(JsonStatusDevice {
+                mode: mode.to_string(),
+                pid: format!("0x{:04X}", desc.product_id()),
+                location: format!(
+                    "Bus {:03} Device {:03}",
+                    device.bus_number(),
+                    device.address()
+                ),
+                unit_id,
+            });
+        }
+
+        if self.json_output {
+            let output = JsonUnitIdOutput { devices: found };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+
+        self.print_banner();
+
+        if found.is_empty() {
             self.console
-                .print("  [bold #2ecc71]✓[/] Device detected in play mode!");
-            self.console.print("");
-            self.console
-                .print("  [#95a5a6]Press pedals to see what keys they send.[/]");
-            self.console.print(
-                "  [#95a5a6]Press[/] [bold #e74c3c]Ctrl+C[/] [#95a5a6]to stop monitoring.[/]",
-            );
+                .print("  [bold #e74c3c]✗[/] No Savant Elite device found");
             self.console.print("");
+            return Ok(());
+        }
+
+        let mut table = Table::new()
+            .box_style(&ROUNDED)
+            .header_style(Style::parse("bold #f1c40f").unwrap_or_default())
+            .border_style(Style::parse("#3498db").unwrap_or_default())
+            .with_column(Column::new("Mode"))
+            .with_column(Column::new("PID"))
+            .with_column(Column::new("Location"))
+            .with_column(Column::new("Unit ID"));
+
+        for device in &found {
+            table.add_row_cells([
+                markup::render_or_plain(&device.mode.to_uppercase()),
+                markup::render_or_plain(&device.pid),
+                markup::render_or_plain(&device.location),
+                markup::render_or_plain(&format_unit_id(device.unit_id)),
+            ]);
+        }
+        self.console.print_renderable(&table);
+        self.console.print("");
 
-            // Run monitor indefinitely (user presses Ctrl+C to stop)
-            self.monitor(0)?;
+        if found.iter().any(|d| d.unit_id.is_none()) {
+            self.console.print(
+                "  [dim]\"unknown\" means the firmware did not answer GENERATE_DATA (0xB5).[/]",
+            );
         }
+        self.console.print(
+            "  [#95a5a6]Assign an ID with[/] [bold #f1c40f]savant unit-id set <N>[/] [#95a5a6](programming mode), then select a pedal with[/] [bold #f1c40f]--unit <N>[/]",
+        );
+        self.console.print("");
 
         Ok(())
     }
 
-    /// List all valid key names and modifiers
-    fn list_keys(&self, json_output: bool) -> Result<()> {
-        // Data structures for key information
-        #[derive(Serialize)]
-        struct ModifierInfo {
-            names: Vec<&'static str>,
-            symbol: &'static str,
-            description: &'static str,
-        }
-
-        #[derive(Serialize)]
-        struct KeysOutput {
-            modifiers: Vec<ModifierInfo>,
-            keys: KeyCategories,
-        }
+    fn unit_id_set(&self, id: u8) -> Result<()> {
+        self.verbose(&format!("Setting unit ID to {}", id));
 
-        #[derive(Serialize)]
-        struct KeyCategories {
-            letters: Vec<&'static str>,
-            numbers: Vec<&'static str>,
-            function_keys: Vec<&'static str>,
-            special: Vec<KeyAliases>,
-            arrow_keys: Vec<&'static str>,
-            punctuation: Vec<KeyAliases>,
-        }
+        let (device, play_mode_found) = self.find_programming_device()?;
+        let Some(device) = device else {
+            let message = if play_mode_found {
+                "Device is in PLAY mode, not PROGRAMMING mode"
+            } else {
+                "No Savant Elite device found in programming mode"
+            };
+            if self.json_output {
+                let err = serde_json::json!({
+                    "error": if play_mode_found { "not_in_programming_mode" } else { "device_not_found" },
+                    "message": message,
+                });
+                println!("{}", serde_json::to_string_pretty(&err)?);
+            } else {
+                self.console
+                    .print(&format!("[bold red]Error:[/] {}", message));
+                self.console.print("");
+                self.console.print(
+                    "Flip the recessed switch to [#e74c3c]Program[/], replug USB, then run [bold yellow]savant status[/] to verify.",
+                );
+            }
+            return Err(anyhow!("{}", message));
+        };
 
-        #[derive(Serialize)]
-        struct KeyAliases {
-            names: Vec<&'static str>,
+        let _device_lock = lock_device(&device)?;
+        let handle = device
+            .open()
+            .context("Failed to open device (try running with sudo)")?;
+        let interface_num = 0;
+        let _interface_guard = self.claim_interface(&handle, interface_num)?;
+
+        let method = self
+            .send_xkeys_command(
+                &handle,
+                interface_num,
+                xkeys_protocol::CMD_SET_UNIT_ID,
+                &[id],
+            )
+            .ok_or_else(|| anyhow!("Device rejected SET_UNIT_ID (0xC9) in every format"))?;
+
+        std::thread::sleep(Duration::from_millis(50));
+        let eeprom_saved = self
+            .send_xkeys_command(
+                &handle,
+                interface_num,
+                xkeys_protocol::CMD_SAVE_TO_EEPROM,
+                &[],
+            )
+            .is_some();
+        if eeprom_saved {
+            std::thread::sleep(Duration::from_millis(200));
         }
-
-        let modifiers = vec![
-            ModifierInfo {
-                names: vec!["cmd", "command", "gui", "meta", "super"],
-                symbol: "⌘",
-                description: "Command/GUI",
-            },
-            ModifierInfo {
-                names: vec!["ctrl", "control"],
-                symbol: "⌃",
-                description: "Control",
-            },
-            ModifierInfo {
-                names: vec!["alt", "option", "opt"],
-                symbol: "⌥",
-                description: "Option/Alt",
-            },
-            ModifierInfo {
-                names: vec!["shift"],
-                symbol: "⇧",
-                description: "Shift",
+        self.log_event(
+            LogEvent::Eeprom,
+            if eeprom_saved {
+                "EEPROM saved (unit ID)"
+            } else {
+                "EEPROM save FAILED (unit ID)"
             },
-        ];
+        );
 
-        let letters: Vec<&'static str> = vec![
-            "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q",
-            "r", "s", "t", "u", "v", "w", "x", "y", "z",
-        ];
+        let verified = self.read_unit_id(&handle, interface_num).map(|r| r == id);
 
-        let numbers: Vec<&'static str> = vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        if self.json_output {
+            let output = JsonUnitIdSetOutput {
+                success: true,
+                unit_id: id,
+                method: method.to_string(),
+                eeprom_saved,
+                verified,
+                retries: self.retry_stats.get().retries,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
 
-        let function_keys: Vec<&'static str> = vec![
-            "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "f10", "f11", "f12",
-        ];
+        self.console.print(&format!(
+            "[bold #2ecc71]✓[/] Unit ID set to [bold yellow]{}[/] [dim]({})[/]",
+            id, method
+        ));
+        if !eeprom_saved {
+            self.console.print(
+                "  [bold #f39c12]⚠[/] [#f39c12]EEPROM save may have failed; the ID might not persist after unplug.[/]",
+            );
+        }
+        match verified {
+            Some(true) => self.console.print("  [dim]✓ Verified[/]"),
+            Some(false) => self.console.print(
+                "  [bold #f39c12]⚠[/] [#f39c12]Read-back mismatch - run 'savant unit-id get' after replugging[/]",
+            ),
+            None => {}
+        }
+        self.console.print("");
+        self.console.print(&format!(
+            "Select this pedal with [bold yellow]--unit {}[/] (e.g. [bold yellow]savant --unit {} program ...[/])",
+            id, id
+        ));
 
-        let special = vec![
-            KeyAliases {
-                names: vec!["enter", "return"],
-            },
-            KeyAliases {
-                names: vec!["esc", "escape"],
-            },
-            KeyAliases {
-                names: vec!["backspace"],
-            },
-            KeyAliases { names: vec!["tab"] },
-            KeyAliases {
-                names: vec!["space"],
-            },
-            KeyAliases {
-                names: vec!["capslock"],
-            },
-        ];
+        Ok(())
+    }
 
-        let arrow_keys: Vec<&'static str> = vec!["up", "down", "left", "right"];
+    // =========================================================================
+    // Mode Command - Experimental Software Mode Switch
+    // =========================================================================
 
-        let punctuation = vec![
-            KeyAliases {
-                names: vec!["minus", "-"],
-            },
-            KeyAliases {
-                names: vec!["equal", "="],
-            },
-            KeyAliases {
-                names: vec!["leftbracket", "["],
-            },
-            KeyAliases {
-                names: vec!["rightbracket", "]"],
-            },
-            KeyAliases {
-                names: vec!["backslash", "\\"],
-            },
-            KeyAliases {
-                names: vec!["semicolon", ";"],
-            },
-            KeyAliases {
-                names: vec!["quote", "'"],
-            },
-            KeyAliases {
-                names: vec!["grave", "`"],
-            },
-            KeyAliases {
-                names: vec!["comma", ","],
-            },
-            KeyAliases {
-                names: vec!["period", "."],
-            },
-            KeyAliases {
-                names: vec!["slash", "/"],
-            },
-        ];
+    /// Print the manual steps for moving the pedal into `mode` with the recessed switch
+    fn print_mode_switch_steps(&self, mode: PedalMode) {
+        let (from, to) = match mode {
+            PedalMode::Program => ("[#2ecc71]Play[/]", "[#e74c3c]Program[/]"),
+            PedalMode::Play => ("[#e74c3c]Program[/]", "[#2ecc71]Play[/]"),
+        };
+        self.console
+            .print("    [bold #3498db]1.[/] Flip the pedal over");
+        self.console
+            .print("    [bold #3498db]2.[/] Find the recessed switch near the Kinesis sticker");
+        self.console.print(&format!(
+            "    [bold #3498db]3.[/] Use a paperclip to flip it from {} → {}",
+            from, to
+        ));
+        self.console
+            .print("    [bold #3498db]4.[/] Unplug and replug the USB cable");
+    }
 
-        if json_output {
-            let output = KeysOutput {
-                modifiers,
-                keys: KeyCategories {
-                    letters,
-                    numbers,
-                    function_keys,
-                    special,
-                    arrow_keys,
-                    punctuation,
-                },
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
+    /// Print a JSON error or the paperclip fallback, then return the error
+    fn mode_switch_error(&self, mode: PedalMode, error: &str, message: &str) -> Result<()> {
+        if self.json_output {
+            let err = serde_json::json!({
+                "error": error,
+                "message": message,
+                "target_mode": mode.name(),
+            });
+            println!("{}", serde_json::to_string_pretty(&err)?);
         } else {
-            // Human-readable output
             self.console
-                .print("[bold cyan]MODIFIERS[/] [dim](combine with + before key)[/]");
+                .print(&format!("  [bold #e74c3c]✗[/] {}", message));
             self.console.print("");
-            for m in &modifiers {
-                let names = m.names.join(", ");
-                self.console.print(&format!(
-                    "  [green]{}[/]  →  {} {}",
-                    names, m.symbol, m.description
-                ));
-            }
-
+            self.console.print(&format!(
+                "  [bold #f39c12]Switch to {} mode manually:[/]",
+                mode.name()
+            ));
+            self.print_mode_switch_steps(mode);
             self.console.print("");
-            self.console.print("[bold cyan]LETTERS[/]");
-            self.console
-                .print(&format!("  [green]{}[/]", letters.join(" ")));
+        }
+        Err(anyhow!("{}", message))
+    }
 
-            self.console.print("");
-            self.console.print("[bold cyan]NUMBERS[/]");
-            self.console
-                .print(&format!("  [green]{}[/]", numbers.join(" ")));
+    /// Send CMD_SET_PID followed by CMD_REBOOT. Returns the format SET_PID was accepted in.
+    fn send_mode_switch(
+        &self,
+        device: &Device<GlobalContext>,
+        mode: PedalMode,
+    ) -> Result<&'static str> {
+        let _device_lock = lock_device(device)?;
+        let handle = device
+            .open()
+            .context("Failed to open device (try running with sudo)")?;
+        let interface_num = 0;
+        let _interface_guard = self.claim_interface(&handle, interface_num)?;
+
+        // The PID-switch payload is undocumented for the Savant Elite; send the target PID
+        // little-endian, as PI Engineering's X-keys firmware does.
+        let method = self
+            .send_xkeys_command(
+                &handle,
+                interface_num,
+                xkeys_protocol::CMD_SET_PID,
+                &mode.pid().to_le_bytes(),
+            )
+            .ok_or_else(|| anyhow!("Device rejected SET_PID (0xCA) in every format"))?;
+        std::thread::sleep(Duration::from_millis(50));
+
+        // The device may drop off the bus before acknowledging, so a failed REBOOT is expected.
+        if self
+            .send_xkeys_command(&handle, interface_num, xkeys_protocol::CMD_REBOOT, &[])
+            .is_none()
+        {
+            self.verbose("REBOOT was not acknowledged (device may already be resetting)");
+        }
+
+        Ok(method)
+    }
 
-            self.console.print("");
-            self.console.print("[bold cyan]FUNCTION KEYS[/]");
-            self.console
-                .print(&format!("  [green]{}[/]", function_keys.join(" ")));
+    fn switch_mode(&self, mode: PedalMode, experimental: bool, yes: bool) -> Result<()> {
+        use std::io::{BufRead, IsTerminal};
 
-            self.console.print("");
-            self.console.print("[bold cyan]SPECIAL KEYS[/]");
-            for k in &special {
-                self.console
-                    .print(&format!("  [green]{}[/]", k.names.join(", ")));
-            }
+        if !experimental {
+            return self.mode_switch_error(
+                mode,
+                "experimental_required",
+                "Software mode switching is experimental; re-run with --experimental to try it",
+            );
+        }
 
-            self.console.print("");
-            self.console.print("[bold cyan]ARROW KEYS[/]");
-            self.console
-                .print(&format!("  [green]{}[/]", arrow_keys.join("  ")));
+        self.verbose(&format!(
+            "Attempting experimental switch to {} mode",
+            mode.name()
+        ));
 
-            self.console.print("");
-            self.console.print("[bold cyan]PUNCTUATION[/]");
-            for k in &punctuation {
-                // Avoid markup interpretation issues with backslash
-                let display = k.names.join(", ");
-                if display.contains('\\') {
-                    // Print without markup for backslash
-                    println!("  {}", display);
-                } else {
-                    self.console.print(&format!("  [green]{}[/]", display));
-                }
+        let devices = self
+            .session
+            .usb_devices()
+            .context("Failed to list USB devices via libusb (try running with sudo)")?;
+        let found = devices.into_iter().find_map(|device| {
+            let desc = device.device_descriptor().ok()?;
+            if desc.vendor_id() != KINESIS_VID {
+                return None;
             }
-
-            self.console.print("");
-            self.console.print("[bold cyan]EXAMPLES[/]");
-            self.console.print("  [dim]Copy/Paste workflow:[/]");
-            self.console
-                .print("  [yellow]savant program --left cmd+c --middle cmd+a --right cmd+v[/]");
-            self.console.print("");
-            self.console.print("  [dim]Multi-modifier combo:[/]");
-            self.console.print(
-                "  [yellow]savant program --left ctrl+shift+f1 --middle f5 --right enter[/]",
+            let current = PedalMode::from_pid(desc.product_id())?;
+            self.matches_unit_filter(&device)
+                .then_some((device, current))
+        });
+
+        let Some((device, current)) = found else {
+            self.log_event(LogEvent::Device, "No Savant Elite found");
+            return self.mode_switch_error(
+                mode,
+                "device_not_found",
+                "Savant Elite not found. Make sure it's connected.",
             );
-        }
-
-        Ok(())
-    }
+        };
+        self.log_event(
+            LogEvent::Device,
+            &format!(
+                "Found pedal in {} mode at Bus {:03} Device {:03}",
+                current.name(),
+                device.bus_number(),
+                device.address()
+            ),
+        );
 
-    fn preset(&self, name: Option<&str>, list: bool, show: bool, dry_run: bool) -> Result<()> {
-        // Handle --list flag
-        if list {
-            return self.list_presets();
-        }
+        let bus_number = device.bus_number();
+        let address = device.address();
+        let location = format!("Bus {:03} Device {:03}", bus_number, address);
 
-        // All other operations require a preset name
-        let Some(preset_name) = name else {
+        if current == mode {
             if self.json_output {
-                let output = JsonPresetListOutput {
-                    presets: PRESETS
-                        .iter()
-                        .map(|p| JsonPreset {
-                            name: p.name.to_string(),
-                            description: p.description.to_string(),
-                            left: p.left.to_string(),
-                            middle: p.middle.to_string(),
-                            right: p.right.to_string(),
-                        })
-                        .collect(),
+                let output = JsonModeSwitchOutput {
+                    success: true,
+                    from_mode: current.name().to_string(),
+                    to_mode: mode.name().to_string(),
+                    already_in_mode: true,
+                    method: None,
+                    reenumerated: false,
+                    location,
                 };
                 println!("{}", serde_json::to_string_pretty(&output)?);
-            } else {
-                self.console
-                    .print("[bold red]Error:[/] Missing preset name");
-                self.console.print("");
-                self.console
-                    .print("Usage: [bold yellow]savant preset <NAME>[/]");
-                self.console.print("");
-                self.console
-                    .print("Run [bold yellow]savant preset --list[/] to see available presets.");
-            }
-            return Ok(());
-        };
-
-        // Look up the preset
-        let Some(preset) = find_preset(preset_name) else {
-            if self.json_output {
-                let err = serde_json::json!({
-                    "error": "unknown_preset",
-                    "message": format!("Unknown preset: '{}'", preset_name),
-                    "available": PRESETS.iter().map(|p| p.name).collect::<Vec<_>>()
-                });
-                println!("{}", serde_json::to_string_pretty(&err)?);
             } else {
                 self.console.print(&format!(
-                    "[bold red]Error:[/] Unknown preset: '{}'",
-                    preset_name
+                    "[bold #2ecc71]✓[/] Device is already in {} mode [dim]({})[/]",
+                    mode.name(),
+                    location
                 ));
-                self.console.print("");
-                self.console.print("[bold cyan]Available presets:[/]");
-                for p in PRESETS {
-                    self.console
-                        .print(&format!("  [yellow]{}[/]  {}", p.name, p.description));
-                }
             }
-            return Err(anyhow!("Unknown preset: '{}'", preset_name));
-        };
-
-        // Handle --show flag
-        if show {
-            return self.show_preset(preset);
-        }
-
-        // Apply the preset (program the device)
-        self.verbose(&format!("Applying preset: {}", preset.name));
-        self.program(preset.left, preset.middle, preset.right, dry_run, false)
-    }
-
-    fn list_presets(&self) -> Result<()> {
-        if self.json_output {
-            let output = JsonPresetListOutput {
-                presets: PRESETS
-                    .iter()
-                    .map(|p| JsonPreset {
-                        name: p.name.to_string(),
-                        description: p.description.to_string(),
-                        left: p.left.to_string(),
-                        middle: p.middle.to_string(),
-                        right: p.right.to_string(),
-                    })
-                    .collect(),
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
             return Ok(());
         }
 
-        self.print_banner();
+        // Without the port chain the re-enumerated device can't be recognized, so the switch
+        // could never be confirmed; refuse before touching the firmware.
+        let port_numbers = match device.port_numbers() {
+            Ok(ports) => ports,
+            Err(e) => {
+                return self.mode_switch_error(
+                    mode,
+                    "port_unknown",
+                    &format!("Could not read the USB port chain for {}: {}", location, e),
+                );
+            }
+        };
+        let ports = port_numbers
+            .iter()
+            .map(u8::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
 
-        self.console.print(
-            "[bold #3498db]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
-        self.console.print(
-            "[bold #3498db]│[/]  [bold white]AVAILABLE PRESETS[/]                                           [bold #3498db]│[/]",
-        );
-        self.console.print(
-            "[bold #3498db]└─────────────────────────────────────────────────────────────────┘[/]",
-        );
-        self.console.print("");
+        if !self.json_output {
+            self.console.print(
+                "  [bold #f39c12]⚠[/]  [#f39c12]Sending undocumented SET_PID (0xCA) and REBOOT (0xCB) commands.[/]",
+            );
+            self.console.print(
+                "     [dim]If the pedal stops responding, unplug it and use the recessed switch.[/]",
+            );
+            self.console.print("");
+        }
 
-        for preset in PRESETS {
-            self.console
-                .print(&format!("  [bold yellow]{}[/]", preset.name));
-            self.console
-                .print(&format!("    [dim]{}[/]", preset.description));
+        if !yes {
+            if self.json_output || !std::io::stdin().is_terminal() {
+                return self.mode_switch_error(
+                    mode,
+                    "confirmation_required",
+                    "Not a terminal, so not asking; re-run with --yes to switch modes",
+                );
+            }
             self.console.print(&format!(
-                "    Left: [cyan]{}[/]  Middle: [cyan]{}[/]  Right: [cyan]{}[/]",
-                preset.left, preset.middle, preset.right
+                "  Switch the pedal at {} (port {}) from {} to {} mode? (y/N) ",
+                location,
+                ports,
+                current.name(),
+                mode.name()
             ));
-            self.console.print("");
+            let mut answer = String::new();
+            std::io::stdin().lock().read_line(&mut answer)?;
+            if !matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+                return self.mode_switch_error(mode, "cancelled", "Mode switch cancelled");
+            }
         }
 
-        self.console.print("[bold #2ecc71]Usage:[/]");
-        self.console
-            .print("  [yellow]savant preset copy-paste[/]        Apply the copy-paste preset");
-        self.console
-            .print("  [yellow]savant preset browser --dry-run[/] Preview browser preset");
-        self.console
-            .print("  [yellow]savant preset zoom --show[/]       Show zoom preset details");
+        let method = match self.send_mode_switch(&device, mode) {
+            Ok(method) => method,
+            Err(e) => {
+                return self.mode_switch_error(
+                    mode,
+                    "mode_switch_failed",
+                    &format!("Firmware refused the mode switch: {}", e),
+                );
+            }
+        };
 
-        Ok(())
-    }
+        self.verbose(&format!(
+            "Waiting up to {}s for PID 0x{:04X} to re-enumerate on bus {:03}",
+            REENUMERATION_TIMEOUT.as_secs(),
+            mode.pid(),
+            bus_number
+        ));
+        let Some(new_device) = self.session.wait_for_reenumeration(
+            bus_number,
+            &port_numbers,
+            address,
+            mode.pid(),
+            REENUMERATION_TIMEOUT,
+        ) else {
+            return self.mode_switch_error(
+                mode,
+                "mode_switch_failed",
+                &format!(
+                    "Device did not re-enumerate in {} mode (PID 0x{:04X})",
+                    mode.name(),
+                    mode.pid()
+                ),
+            );
+        };
+
+        let new_location = format!(
+            "Bus {:03} Device {:03}",
+            new_device.bus_number(),
+            new_device.address()
+        );
 
-    fn show_preset(&self, preset: &Preset) -> Result<()> {
         if self.json_output {
-            let output = JsonPreset {
-                name: preset.name.to_string(),
-                description: preset.description.to_string(),
-                left: preset.left.to_string(),
-                middle: preset.middle.to_string(),
-                right: preset.right.to_string(),
+            let output = JsonModeSwitchOutput {
+                success: true,
+                from_mode: current.name().to_string(),
+                to_mode: mode.name().to_string(),
+                already_in_mode: false,
+                method: Some(method.to_string()),
+                reenumerated: true,
+                location: new_location,
             };
             println!("{}", serde_json::to_string_pretty(&output)?);
             return Ok(());
         }
 
-        self.print_banner();
-
-        self.console.print(
-            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
         self.console.print(&format!(
-            "[bold #9b59b6]│[/]  [bold white]PRESET: {}[/]{}[bold #9b59b6]│[/]",
-            preset.name.to_uppercase(),
-            " ".repeat(53_usize.saturating_sub(preset.name.len()))
+            "[bold #2ecc71]✓[/] Switched to {} mode [dim]({}, re-enumerated as {})[/]",
+            mode.name(),
+            method,
+            new_location
         ));
         self.console.print(
-            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
+            "  [dim]If the pedal reverts after a replug, use the recessed switch instead.[/]",
         );
-        self.console.print("");
-
-        self.console
-            .print(&format!("  [dim]{}[/]", preset.description));
-        self.console.print("");
-
-        // Show the pedal visualization
-        self.print_pedal_visualization(preset.left, preset.middle, preset.right);
-
-        self.console.print("");
-        self.console.print(&format!(
-            "To apply: [bold yellow]savant preset {}[/]",
-            preset.name
-        ));
-        self.console.print(&format!(
-            "Preview:  [bold yellow]savant preset {} --dry-run[/]",
-            preset.name
-        ));
 
         Ok(())
     }
 
     // =========================================================================
-    // Config Profile Commands
+    // Doctor Command - System Diagnostics
     // =========================================================================
 
-    fn config(&self, command: ConfigCommand) -> Result<()> {
-        match command {
-            ConfigCommand::Save { name, force } => self.config_save(&name, force),
-            ConfigCommand::Load { name, dry_run } => self.config_load(&name, dry_run),
-            ConfigCommand::List => self.config_list(),
-            ConfigCommand::Show { name } => self.config_show(&name),
-            ConfigCommand::Delete { name, force } => self.config_delete(&name, force),
-            ConfigCommand::Check { file } => self.config_check(file.as_deref()),
-            ConfigCommand::History => self.config_history(),
-            ConfigCommand::Restore { number, apply } => self.config_restore(number, apply),
-        }
-    }
-
-    fn config_save(&self, name: &str, force: bool) -> Result<()> {
-        // Validate profile name
-        validate_profile_name(name)?;
-
-        self.verbose(&format!("Saving profile: {}", name));
+    fn udev_rules(&self, group: &str, output: Option<&str>) -> Result<()> {
+        let rules = linux_access::udev_rules(group);
 
-        // Check if current config exists
-        let current_config = PedalConfig::load();
-        let Some(config) = current_config else {
+        let Some(output) = output else {
             if self.json_output {
-                let err = serde_json::json!({
-                    "error": "no_current_config",
-                    "message": "No current configuration to save. Run 'savant program' first."
-                });
-                println!("{}", serde_json::to_string_pretty(&err)?);
+                let output = serde_json::json!({ "file": null, "rules": rules });
+                println!("{}", serde_json::to_string_pretty(&output)?);
             } else {
-                self.console
-                    .print("[bold red]Error:[/] No current configuration to save.");
-                self.console.print("");
-                self.console
-                    .print("Run [bold yellow]savant program[/] first to create a configuration.");
+                print!("{}", rules);
             }
-            return Err(anyhow!("No current configuration to save"));
+            return Ok(());
         };
+        let path = PathBuf::from(output);
+        write_atomic(&path, &rules)
+            .with_context(|| format!("Failed to write udev rule to {}", path.display()))?;
 
-        let path = profile_path(name);
+        if self.json_output {
+            let output = serde_json::json!({ "file": path.display().to_string(), "rules": rules });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            self.console.print(&format!(
+                "[bold #2ecc71]✓[/] Wrote udev rule to [bold]{}[/]",
+                path.display()
+            ));
+            self.console.print("");
+            self.console.print(&format!(
+                "Install it with: [bold yellow]sudo cp {} /etc/udev/rules.d/{}[/]",
+                path.display(),
+                linux_access::RULES_FILE_NAME
+            ));
+            self.console.print(
+                "Then run [bold yellow]sudo udevadm control --reload-rules && sudo udevadm trigger[/] and replug the pedal.",
+            );
+        }
+        Ok(())
+    }
 
-        // Check if profile already exists
-        if path.exists() && !force {
-            if self.json_output {
-                let err = serde_json::json!({
-                    "error": "profile_exists",
-                    "message": format!("Profile '{}' already exists. Use --force to overwrite.", name),
-                    "path": path.display().to_string()
-                });
-                println!("{}", serde_json::to_string_pretty(&err)?);
-            } else {
-                self.console.print(&format!(
-                    "[bold red]Error:[/] Profile '{}' already exists.",
-                    name
-                ));
-                self.console.print("");
-                self.console.print(&format!(
-                    "Use [bold yellow]savant config save {} --force[/] to overwrite.",
-                    name
+    fn show_log(&self, tail: Option<usize>) -> Result<()> {
+        let mut records = self.oplog.read();
+        if let Some(tail) = tail {
+            records.drain(..records.len().saturating_sub(tail));
+        }
+
+        if self.json_output {
+            let output = serde_json::json!({
+                "path": self.oplog.path.display().to_string(),
+                "records": records,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            return Ok(());
+        }
+
+        if records.is_empty() {
+            self.console.print(&format!(
+                "[dim]The operation log is empty ({}).[/]",
+                self.oplog.path.display()
+            ));
+            return Ok(());
+        }
+        for record in &records {
+            let time = chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M:%S%.3f")
+                        .to_string()
+                })
+                .unwrap_or_else(|_| record.timestamp.clone());
+            let color = match record.event {
+                LogEvent::Command => "bold #3498db",
+                LogEvent::Disconnect => "bold #e74c3c",
+                LogEvent::Interrupt => "bold #f39c12",
+                LogEvent::Result if record.message != "ok" => "bold #e74c3c",
+                LogEvent::Result => "#2ecc71",
+                LogEvent::Eeprom | LogEvent::Pedal => "#f1c40f",
+                LogEvent::Device | LogEvent::Transfer => "#95a5a6",
+            };
+            self.console.print(&format!(
+                "[dim]{} {:>7}[/] [{}]{:<10}[/] {}",
+                time,
+                record.pid,
+                color,
+                record.event.name(),
+                markup::escape(&record.message)
+            ));
+        }
+        Ok(())
+    }
+
+    fn doctor(&self, fix: bool, dry_run: bool, bundle: Option<&str>) -> Result<()> {
+        // Refuse a bad bundle target before spending time on the checks
+        if let Some(dir) = bundle.filter(|path| !path.ends_with(".tar")) {
+            if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some()) {
+                return Err(anyhow!(
+                    "{} already exists and is not empty; choose a new directory or a .tar file",
+                    dir
                 ));
             }
-            return Err(anyhow!("Profile '{}' already exists", name));
         }
 
-        // Create profiles directory if needed
-        let dir = profiles_dir();
-        if !dir.exists() {
-            self.verbose(&format!("Creating profiles directory: {}", dir.display()));
-            fs::create_dir_all(&dir).context("Failed to create profiles directory")?;
-        }
+        self.verbose("Running system diagnostics");
+
+        // Collect all check results
+        let mut checks: Vec<JsonDoctorCheck> = Vec::new();
+
+        // Get system info
+        let version = env!("CARGO_PKG_VERSION").to_string();
+        let platform = std::env::consts::OS.to_string();
+        let arch = std::env::consts::ARCH.to_string();
+
+        if !self.json_output {
+            self.print_banner();
+
+            self.console.print(
+                "[bold #3498db]┌─────────────────────────────────────────────────────────────────┐[/]",
+            );
+            self.console.print(
+                "[bold #3498db]│[/]  [bold white]SYSTEM DIAGNOSTICS[/]                                          [bold #3498db]│[/]",
+            );
+            self.console.print(
+                "[bold #3498db]└─────────────────────────────────────────────────────────────────┘[/]",
+            );
+            self.console.print("");
+        }
+
+        // Check 1: Binary/System info
+        checks.push(self.doctor_check_binary(&version, &platform, &arch));
+
+        // Check 2: Platform compatibility
+        checks.push(self.doctor_check_platform(&platform));
+
+        // Check 3: Device detection
+        checks.push(self.doctor_check_device());
 
-        // Save the profile
-        config.save_to(&path).context("Failed to save profile")?;
+        // Check 4: Config file
+        checks.push(self.doctor_check_config());
 
-        if self.json_output {
-            let output = JsonProfileSaveOutput {
-                success: true,
-                name: name.to_string(),
-                path: path.display().to_string(),
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
+        // Check 5: Profiles directory
+        checks.push(self.doctor_check_profiles());
+
+        if platform == "linux" {
+            // Linux: device-node permissions instead of Input Monitoring
+            checks.push(self.doctor_check_udev_rule());
+            checks.push(self.doctor_check_device_nodes());
+            checks.push(self.doctor_check_groups());
+            checks.push(self.doctor_check_kernel_driver());
         } else {
-            self.console.print(&format!(
-                "[bold #2ecc71]✓[/] Saved profile '[bold yellow]{}[/]'",
-                name
-            ));
-            self.console
-                .print(&format!("  Path: [dim]{}[/]", path.display()));
-            self.console.print("");
-            self.console.print(&format!(
-                "To load: [bold yellow]savant config load {}[/]",
-                name
-            ));
+            // Check 6: Input Monitoring (attempt to detect)
+            checks.push(self.doctor_check_input_monitoring());
         }
 
-        Ok(())
-    }
-
-    fn config_load(&self, name: &str, dry_run: bool) -> Result<()> {
-        // Validate profile name
-        validate_profile_name(name)?;
+        // Calculate summary
+        let passed = checks.iter().filter(|c| c.status == "pass").count();
+        let warnings = checks.iter().filter(|c| c.status == "warn").count();
+        let failed = checks.iter().filter(|c| c.status == "fail").count();
+        let total = checks.len();
+        let healthy = failed == 0;
 
-        self.verbose(&format!("Loading profile: {}", name));
+        let fixes = if fix {
+            let udev_dirs: Option<Vec<PathBuf>> = (platform == "linux")
+                .then(|| linux_access::RULES_DIRS.iter().map(PathBuf::from).collect());
+            let steps = plan_doctor_fixes(
+                &savant_config_dir(),
+                udev_dirs.as_deref(),
+                chrono::Utc::now(),
+            );
+            Some(self.doctor_apply_fixes(&steps, dry_run)?)
+        } else {
+            None
+        };
+        let fixes_failed = fixes
+            .iter()
+            .flatten()
+            .filter(|f| f.status == "failed")
+            .count();
+
+        let mut output = JsonDoctorOutput {
+            version,
+            platform,
+            arch,
+            checks,
+            summary: JsonDoctorSummary {
+                total,
+                passed,
+                warnings,
+                failed,
+                healthy,
+            },
+            fixes,
+            bundle: None,
+        };
+        if let Some(path) = bundle {
+            output.bundle = Some(self.write_support_bundle(&output, path)?);
+        }
 
-        let path = profile_path(name);
+        if self.json_output {
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else {
+            // Print summary
+            self.console.print("");
+            self.console.print(
+                "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
+            );
+            self.console.print(
+                "[bold #9b59b6]│[/]  [bold white]SUMMARY[/]                                                       [bold #9b59b6]│[/]",
+            );
+            self.console.print(
+                "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
+            );
+            self.console.print("");
 
-        // Check if profile exists
-        if !path.exists() {
-            if self.json_output {
-                let err = serde_json::json!({
-                    "error": "profile_not_found",
-                    "message": format!("Profile '{}' not found.", name),
-                    "path": path.display().to_string()
-                });
-                println!("{}", serde_json::to_string_pretty(&err)?);
+            if healthy {
+                self.console.print(&format!(
+                    "  [bold #2ecc71]✓[/] All {} checks passed{}",
+                    total,
+                    if warnings > 0 {
+                        format!(
+                            " ({} warning{})",
+                            warnings,
+                            if warnings == 1 { "" } else { "s" }
+                        )
+                    } else {
+                        String::new()
+                    }
+                ));
+                self.console.print("");
+                self.console
+                    .print("  [bold #2ecc71]Your system is ready to use savant-elite![/]");
             } else {
                 self.console.print(&format!(
-                    "[bold red]Error:[/] Profile '{}' not found.",
-                    name
+                    "  [bold #e74c3c]✗[/] {} issue{} found ({} passed, {} warning{}, {} failed)",
+                    failed + warnings,
+                    if failed + warnings == 1 { "" } else { "s" },
+                    passed,
+                    warnings,
+                    if warnings == 1 { "" } else { "s" },
+                    failed
                 ));
                 self.console.print("");
                 self.console
-                    .print("Run [bold yellow]savant config list[/] to see available profiles.");
+                    .print("  [dim]Fix the issues above and run 'savant doctor' again.[/]");
             }
-            return Err(anyhow!("Profile '{}' not found", name));
-        }
 
-        // Load the profile
-        let config = PedalConfig::load_from(&path)
-            .ok_or_else(|| anyhow!("Failed to parse profile '{}'", name))?;
-
-        self.verbose(&format!(
-            "Profile contents: left={}, middle={}, right={}",
-            config.left, config.middle, config.right
-        ));
+            if let Some(fixes) = &output.fixes {
+                self.print_doctor_fixes(fixes, dry_run);
+            }
+            if let Some(bundle) = &output.bundle {
+                self.console.print("");
+                self.console.print(&format!(
+                    "  [bold #2ecc71]✓[/] Wrote support bundle to [bold]{}[/] ({} files)",
+                    bundle.path,
+                    bundle.files.len()
+                ));
+                self.console.print(
+                    "    [dim]Home paths and your username are redacted; attach it to your support request.[/]",
+                );
+            }
+        }
 
-        // Program the device using the profile's configuration
-        self.program(&config.left, &config.middle, &config.right, dry_run, false)
+        if fixes_failed > 0 {
+            return Err(anyhow!(
+                "{} fix{} could not be applied",
+                fixes_failed,
+                if fixes_failed == 1 { "" } else { "es" }
+            ));
+        }
+        Ok(())
     }
 
-    fn config_list(&self) -> Result<()> {
-        self.verbose("Listing profiles");
-
-        let dir = profiles_dir();
-
-        // Collect profiles
-        let mut profiles: Vec<(String, PedalConfig)> = Vec::new();
+    /// Carry out the planned fixes (or, with `dry_run`, only describe them). A step is
+    /// skipped once an earlier step on the same file failed, so a failed backup never
+    /// lets the original be overwritten.
+    fn doctor_apply_fixes(
+        &self,
+        steps: &[DoctorFixStep],
+        dry_run: bool,
+    ) -> Result<Vec<JsonDoctorFix>> {
+        let _lock = if dry_run || steps.is_empty() {
+            None
+        } else {
+            Some(lock_config_dir(CONFIG_LOCK_WAIT)?)
+        };
 
-        if dir.exists() {
-            for entry in fs::read_dir(&dir).context("Failed to read profiles directory")? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.extension().is_some_and(|ext| ext == "conf") {
-                    if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
-                        if let Some(config) = PedalConfig::load_from(&path) {
-                            profiles.push((name.to_string(), config));
-                        }
+        let mut failed_paths: Vec<&PathBuf> = Vec::new();
+        let mut results = Vec::new();
+        for step in steps {
+            let (status, error) = if dry_run {
+                ("planned", None)
+            } else if failed_paths.contains(&&step.path) {
+                ("skipped", None)
+            } else {
+                self.verbose(&format!("{} {}", step.action.name(), step.path.display()));
+                match step.apply() {
+                    Ok(()) => ("applied", None),
+                    Err(e) => {
+                        failed_paths.push(&step.path);
+                        ("failed", Some(format!("{:#}", e)))
                     }
                 }
-            }
-        }
-
-        // Sort by name
-        profiles.sort_by(|a, b| a.0.cmp(&b.0));
-
-        if self.json_output {
-            let output = JsonProfileListOutput {
-                profiles: profiles
-                    .iter()
-                    .map(|(name, config)| JsonProfile {
-                        name: name.clone(),
-                        left: config.left.clone(),
-                        middle: config.middle.clone(),
-                        right: config.right.clone(),
-                    })
-                    .collect(),
-                profiles_dir: dir.display().to_string(),
             };
-            println!("{}", serde_json::to_string_pretty(&output)?);
-            return Ok(());
+            let to = match &step.action {
+                DoctorFixAction::Backup(to) | DoctorFixAction::Move(to) => {
+                    Some(to.display().to_string())
+                }
+                _ => None,
+            };
+            results.push(JsonDoctorFix {
+                action: step.action.name().to_string(),
+                path: step.path.display().to_string(),
+                to,
+                reason: step.reason.clone(),
+                status: status.to_string(),
+                error,
+            });
         }
+        Ok(results)
+    }
 
-        self.print_banner();
-
-        self.console.print(
-            "[bold #3498db]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
+    fn print_doctor_fixes(&self, fixes: &[JsonDoctorFix], dry_run: bool) {
+        self.console.print("");
         self.console.print(
-            "[bold #3498db]│[/]  [bold white]SAVED PROFILES[/]                                              [bold #3498db]│[/]",
+            "[bold #e67e22]┌─────────────────────────────────────────────────────────────────┐[/]",
         );
+        self.console.print(if dry_run {
+            "[bold #e67e22]│[/]  [bold white]FIXES[/] [dim](dry run)[/]                                               [bold #e67e22]│[/]"
+        } else {
+            "[bold #e67e22]│[/]  [bold white]FIXES[/]                                                         [bold #e67e22]│[/]"
+        });
         self.console.print(
-            "[bold #3498db]└─────────────────────────────────────────────────────────────────┘[/]",
+            "[bold #e67e22]└─────────────────────────────────────────────────────────────────┘[/]",
         );
         self.console.print("");
 
-        if profiles.is_empty() {
-            self.console.print("  [dim]No saved profiles yet.[/]");
-            self.console.print("");
-            self.console
-                .print("  Create one with: [bold yellow]savant config save <name>[/]");
-        } else {
-            for (name, config) in &profiles {
-                self.console.print(&format!("  [bold yellow]{}[/]", name));
-                self.console.print(&format!(
-                    "    Left: [cyan]{}[/]  Middle: [cyan]{}[/]  Right: [cyan]{}[/]",
-                    config.left, config.middle, config.right
-                ));
-                self.console.print("");
-            }
+        if fixes.is_empty() {
+            self.console.print("  [bold #2ecc71]✓[/] Nothing to fix");
+            return;
         }
 
-        self.console
-            .print(&format!("[dim]Profiles directory: {}[/]", dir.display()));
+        for fix in fixes {
+            let what = match (fix.action.as_str(), &fix.to) {
+                ("create_dir", _) => format!("Create directory {}", fix.path),
+                ("backup", Some(to)) => format!("Back up {} to {}", fix.path, to),
+                ("move", Some(to)) => format!("Move {} to {}", fix.path, to),
+                ("write", _) => format!("Write {}", fix.path),
+                _ => format!("Remove {}", fix.path),
+            };
+            let marker = match fix.status.as_str() {
+                "planned" => "[bold #3498db]•[/]",
+                "applied" => "[bold #2ecc71]✓[/]",
+                "skipped" => "[bold #f39c12]-[/]",
+                _ => "[bold #e74c3c]✗[/]",
+            };
+            self.console.print(&format!("  {} {}", marker, what));
+            self.console.print(&format!("    [dim]{}[/]", fix.reason));
+            if let Some(error) = &fix.error {
+                self.console
+                    .print(&format!("    [bold #e74c3c]{}[/]", error));
+            }
+        }
 
-        Ok(())
+        self.console.print("");
+        if dry_run {
+            self.console
+                .print("  [dim]Nothing was changed. Run 'savant doctor --fix' to apply.[/]");
+        } else {
+            self.console
+                .print("  [dim]Run 'savant doctor' again to confirm.[/]");
+        }
     }
 
-    fn config_show(&self, name: &str) -> Result<()> {
-        // Validate profile name
-        validate_profile_name(name)?;
-
-        self.verbose(&format!("Showing profile: {}", name));
-
-        let path = profile_path(name);
+    /// Write a support bundle for `doctor --bundle`: the doctor report, USB and HID
+    /// enumeration, probe results and the config directory, redacted with [`Redactor`].
+    /// `path` ending in `.tar` produces an archive, anything else a directory.
+    fn write_support_bundle(
+        &self,
+        doctor: &JsonDoctorOutput,
+        path: &str,
+    ) -> Result<JsonDoctorBundle> {
+        let redactor = Redactor::current();
+        let now = chrono::Utc::now();
+        let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+        let mut add_json = |name: &str, value: &serde_json::Value| -> Result<()> {
+            let json = serde_json::to_string_pretty(value)? + "\n";
+            files.push((name.to_string(), redactor.redact(&json).into_bytes()));
+            Ok(())
+        };
 
-        // Check if profile exists
-        if !path.exists() {
-            if self.json_output {
-                let err = serde_json::json!({
-                    "error": "profile_not_found",
-                    "message": format!("Profile '{}' not found.", name),
-                    "path": path.display().to_string()
-                });
-                println!("{}", serde_json::to_string_pretty(&err)?);
-            } else {
-                self.console.print(&format!(
-                    "[bold red]Error:[/] Profile '{}' not found.",
-                    name
-                ));
-                self.console.print("");
-                self.console
-                    .print("Run [bold yellow]savant config list[/] to see available profiles.");
+        self.verbose("Bundle: enumerating USB devices");
+        let usb = self
+            .session
+            .usb_devices()
+            .map(|devices| usb_enumeration(&devices));
+        let firmware_revision = usb.as_ref().ok().and_then(|devices| {
+            devices
+                .iter()
+                .find(|d| d.vid == format!("0x{:04X}", KINESIS_VID))
+                .map(|d| d.revision.clone())
+        });
+        add_json(
+            "usb.json",
+            &match &usb {
+                Ok(devices) => serde_json::json!({ "devices": devices }),
+                Err(e) => serde_json::json!({ "error": e.to_string() }),
+            },
+        )?;
+
+        self.verbose("Bundle: enumerating HID devices and probing the pedal");
+        match self.session.hid() {
+            Ok(api) => {
+                add_json(
+                    "hid.json",
+                    &serde_json::json!({ "devices": hid_enumeration(&api) }),
+                )?;
+                add_json(
+                    "probe.json",
+                    &serde_json::json!({ "devices": self.probe_devices(&api, true) }),
+                )?;
+            }
+            Err(e) => {
+                let error = serde_json::json!({ "error": e.to_string() });
+                add_json("hid.json", &error)?;
+                add_json("probe.json", &error)?;
             }
-            return Err(anyhow!("Profile '{}' not found", name));
         }
+        add_json("doctor.json", &serde_json::to_value(doctor)?)?;
 
-        // Load the profile
-        let config = PedalConfig::load_from(&path)
-            .ok_or_else(|| anyhow!("Failed to parse profile '{}'", name))?;
-
-        if self.json_output {
-            let output = JsonProfile {
-                name: name.to_string(),
-                left: config.left.clone(),
-                middle: config.middle.clone(),
-                right: config.right.clone(),
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
-            return Ok(());
+        // The config directory as-is, minus lock files
+        let config_dir = savant_config_dir();
+        let mut pending = vec![config_dir.clone()];
+        let mut saved_files = Vec::new();
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir)
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e.ok())
+            {
+                let entry_path = entry.path();
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.starts_with('.') || name == "locks" {
+                    continue;
+                }
+                if entry_path.is_dir() {
+                    pending.push(entry_path);
+                } else if let Ok(content) = fs::read(&entry_path) {
+                    let relative = entry_path
+                        .strip_prefix(&config_dir)
+                        .unwrap_or(&entry_path)
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    saved_files.push((format!("config/{}", relative), content));
+                }
+            }
         }
-
-        self.print_banner();
-
-        self.console.print(
-            "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
-        );
-        self.console.print(&format!(
-            "[bold #9b59b6]│[/]  [bold white]PROFILE: {}[/]{}[bold #9b59b6]│[/]",
-            name.to_uppercase(),
-            " ".repeat(55_usize.saturating_sub(name.len()))
-        ));
-        self.console.print(
-            "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
+        saved_files.sort();
+        // The operation log, oldest rotation first
+        for path in (1..=LOG_ROTATIONS)
+            .rev()
+            .map(|n| self.oplog.rotated_path(n))
+            .chain([self.oplog.path.clone()])
+        {
+            if let (Ok(content), Some(name)) = (fs::read(&path), path.file_name()) {
+                saved_files.push((format!("log/{}", name.to_string_lossy()), content));
+            }
+        }
+        for (name, content) in saved_files {
+            let content = match String::from_utf8(content) {
+                Ok(text) => redactor.redact(&text).into_bytes(),
+                Err(e) => e.into_bytes(),
+            };
+            files.push((name, content));
+        }
+
+        let mut names: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+        names.insert(0, "manifest.json".to_string());
+        let manifest = serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "platform": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "created": now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "firmware_revision": firmware_revision,
+            "config_dir": config_dir.display().to_string(),
+            "redacted": ["home directory", "username"],
+            "files": names,
+        });
+        let manifest = serde_json::to_string_pretty(&manifest)? + "\n";
+        files.insert(
+            0,
+            (
+                "manifest.json".to_string(),
+                redactor.redact(&manifest).into_bytes(),
+            ),
         );
-        self.console.print("");
-
-        // Show the pedal visualization
-        self.print_pedal_visualization(&config.left, &config.middle, &config.right);
 
-        self.console.print("");
-        self.console.print(&format!(
-            "To load: [bold yellow]savant config load {}[/]",
-            name
-        ));
-        self.console.print(&format!(
-            "Preview: [bold yellow]savant config load {} --dry-run[/]",
-            name
-        ));
+        let out = PathBuf::from(path);
+        if path.ends_with(".tar") {
+            let root = format!("savant-bundle-{}", now.format("%Y%m%dT%H%M%SZ"));
+            let mut archive = Vec::new();
+            for (name, content) in &files {
+                let mtime = now.timestamp().max(0) as u64;
+                tar_archive::append(&mut archive, &format!("{}/{}", root, name), content, mtime)
+                    .context("Write the bundle to a directory instead (a path without .tar)")?;
+            }
+            tar_archive::finish(&mut archive);
+            if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&out, archive)
+                .with_context(|| format!("Failed to write {}", out.display()))?;
+        } else {
+            for (name, content) in &files {
+                let file = out.join(name);
+                if let Some(parent) = file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&file, content)
+                    .with_context(|| format!("Failed to write {}", file.display()))?;
+            }
+        }
 
-        Ok(())
+        Ok(JsonDoctorBundle {
+            path: out.display().to_string(),
+            files: names,
+        })
     }
 
-    fn config_delete(&self, name: &str, force: bool) -> Result<()> {
-        // Validate profile name
-        validate_profile_name(name)?;
+    fn doctor_check_binary(&self, version: &str, platform: &str, arch: &str) -> JsonDoctorCheck {
+        if !self.json_output {
+            self.console.print("[bold cyan]Binary:[/]");
+            self.console.print(&format!(
+                "  [bold #2ecc71]✓[/] savant-elite version {}",
+                version
+            ));
+            self.console
+                .print(&format!("  [bold #2ecc71]✓[/] {} ({})", platform, arch));
+            self.console.print("");
+        }
 
-        self.verbose(&format!("Deleting profile: {}", name));
+        JsonDoctorCheck {
+            name: "binary".to_string(),
+            status: "pass".to_string(),
+            message: format!("savant-elite v{} on {} ({})", version, platform, arch),
+            details: None,
+            suggestions: vec![],
+        }
+    }
 
-        let path = profile_path(name);
+    fn doctor_check_platform(&self, platform: &str) -> JsonDoctorCheck {
+        let name = if platform == "macos" {
+            "macOS"
+        } else {
+            platform
+        };
+        let supported = matches!(platform, "macos" | "linux");
 
-        // Check if profile exists
-        if !path.exists() {
-            if self.json_output {
-                let err = serde_json::json!({
-                    "error": "profile_not_found",
-                    "message": format!("Profile '{}' not found.", name),
-                    "path": path.display().to_string()
-                });
-                println!("{}", serde_json::to_string_pretty(&err)?);
+        if !self.json_output {
+            self.console.print("[bold cyan]Platform:[/]");
+            if supported {
+                self.console.print(&format!(
+                    "  [bold #2ecc71]✓[/] {} detected (supported)",
+                    name
+                ));
             } else {
                 self.console.print(&format!(
-                    "[bold red]Error:[/] Profile '{}' not found.",
+                    "  [bold #e74c3c]✗[/] {} detected (unsupported)",
                     name
                 ));
+                self.console
+                    .print("    [dim]→ savant-elite requires macOS or Linux[/]");
             }
-            return Err(anyhow!("Profile '{}' not found", name));
-        }
-
-        // In JSON mode or with --force, just delete
-        // Without --force in interactive mode, we'd ideally prompt - but CLI tools
-        // typically use --force for this. We'll just require --force for safety.
-        if !force && !self.json_output {
-            self.console.print(&format!(
-                "[bold #f39c12]Warning:[/] About to delete profile '[bold yellow]{}[/]'",
-                name
-            ));
             self.console.print("");
-            self.console.print(&format!(
-                "Use [bold yellow]savant config delete {} --force[/] to confirm.",
-                name
-            ));
-            return Ok(());
         }
 
-        // Delete the profile
-        fs::remove_file(&path).context("Failed to delete profile")?;
-
-        if self.json_output {
-            let output = JsonProfileDeleteOutput {
-                success: true,
-                name: name.to_string(),
-                path: path.display().to_string(),
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
+        if supported {
+            JsonDoctorCheck {
+                name: "platform".to_string(),
+                status: "pass".to_string(),
+                message: format!("{} detected (supported)", name),
+                details: None,
+                suggestions: vec![],
+            }
         } else {
-            self.console.print(&format!(
-                "[bold #2ecc71]✓[/] Deleted profile '[bold yellow]{}[/]'",
-                name
-            ));
+            JsonDoctorCheck {
+                name: "platform".to_string(),
+                status: "fail".to_string(),
+                message: format!("{} detected (unsupported)", name),
+                details: None,
+                suggestions: vec!["savant-elite requires macOS or Linux".to_string()],
+            }
         }
-
-        Ok(())
     }
 
-    fn config_check(&self, file: Option<&str>) -> Result<()> {
-        self.verbose(&format!(
-            "Checking config file: {}",
-            file.unwrap_or("(default)")
-        ));
+    /// Print a finished check under `heading` and hand it back for the summary
+    fn doctor_report(&self, heading: &str, check: JsonDoctorCheck) -> JsonDoctorCheck {
+        if !self.json_output {
+            let marker = match check.status.as_str() {
+                "pass" => "[bold #2ecc71]✓[/]",
+                "warn" => "[bold #f39c12]⚠[/]",
+                _ => "[bold #e74c3c]✗[/]",
+            };
+            self.console.print(&format!("[bold cyan]{}:[/]", heading));
+            self.console
+                .print(&format!("  {} {}", marker, check.message));
+            if let Some(details) = &check.details {
+                for line in details.lines() {
+                    self.console.print(&format!("    [dim]{}[/]", line));
+                }
+            }
+            for suggestion in &check.suggestions {
+                self.console.print(&format!("    [dim]→ {}[/]", suggestion));
+            }
+            self.console.print("");
+        }
+        check
+    }
 
-        // Determine which file to check
-        let config_path = match file {
-            Some(path) => std::path::PathBuf::from(path),
-            None => PedalConfig::config_path(),
+    fn doctor_check_udev_rule(&self) -> JsonDoctorCheck {
+        let dirs: Vec<PathBuf> = linux_access::RULES_DIRS.iter().map(PathBuf::from).collect();
+        let check = match linux_access::find_installed_rule(&dirs) {
+            Some(path) => JsonDoctorCheck {
+                name: "udev_rule".to_string(),
+                status: "pass".to_string(),
+                message: "udev rule for the pedal is installed".to_string(),
+                details: Some(path.display().to_string()),
+                suggestions: vec![],
+            },
+            None => JsonDoctorCheck {
+                name: "udev_rule".to_string(),
+                status: "warn".to_string(),
+                message: "No udev rule for the pedal found".to_string(),
+                details: Some(format!("Searched {}", linux_access::RULES_DIRS.join(", "))),
+                suggestions: vec![
+                    format!(
+                        "Install one: savant udev-rules | sudo tee /etc/udev/rules.d/{}",
+                        linux_access::RULES_FILE_NAME
+                    ),
+                    "Then run 'sudo udevadm control --reload-rules && sudo udevadm trigger' and replug the pedal".to_string(),
+                ],
+            },
         };
+        self.doctor_report("udev rule", check)
+    }
 
-        let path_display = config_path.display().to_string();
+    fn doctor_check_device_nodes(&self) -> JsonDoctorCheck {
+        let mut nodes: Vec<PathBuf> = self
+            .session
+            .usb_devices()
+            .map(|devices| {
+                devices
+                    .iter()
+                    .filter(|d| {
+                        d.device_descriptor().is_ok_and(|desc| {
+                            linux_access::is_pedal(desc.vendor_id(), desc.product_id())
+                        })
+                    })
+                    .map(|d| {
+                        PathBuf::from(format!(
+                            "/dev/bus/usb/{:03}/{:03}",
+                            d.bus_number(),
+                            d.address()
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        nodes.extend(linux_access::hidraw_nodes(std::path::Path::new(
+            "/sys/class/hidraw",
+        )));
+
+        if nodes.is_empty() {
+            return self.doctor_report(
+                "Device access",
+                JsonDoctorCheck {
+                    name: "device_access".to_string(),
+                    status: "warn".to_string(),
+                    message: "No pedal connected; device permissions not checked".to_string(),
+                    details: None,
+                    suggestions: vec![],
+                },
+            );
+        }
 
-        // Check if file exists
-        if !config_path.exists() {
-            let error = JsonConfigCheckError {
-                line: None,
-                field: None,
-                value: None,
-                error: "File not found".to_string(),
+        let mut lines = Vec::new();
+        let mut denied = Vec::new();
+        for node in &nodes {
+            let result = fs::OpenOptions::new().read(true).write(true).open(node);
+            let state = match &result {
+                Ok(_) => "read/write OK".to_string(),
+                Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                    denied.push(node.display().to_string());
+                    "permission denied".to_string()
+                }
+                Err(e) => e.to_string(),
             };
+            lines.push(format!("{}: {}", node.display(), state));
+        }
 
-            if self.json_output {
-                let output = JsonConfigCheckOutput {
-                    valid: false,
-                    file: path_display.clone(),
-                    left: None,
-                    middle: None,
-                    right: None,
-                    errors: vec![error],
-                };
-                println!("{}", serde_json::to_string_pretty(&output)?);
-            } else {
-                self.console.print("[bold red]✗[/] Configuration invalid");
-                self.console.print("");
-                self.console.print(&format!(
-                    "  [bold red]Error:[/] File not found: {}",
-                    path_display
-                ));
-                self.console.print("");
-                self.console
-                    .print("  [dim]Run 'savant program' to create a configuration.[/]");
+        let check = if denied.is_empty() {
+            JsonDoctorCheck {
+                name: "device_access".to_string(),
+                status: "pass".to_string(),
+                message: format!("Read/write access to {} device node(s)", nodes.len()),
+                details: Some(lines.join("\n")),
+                suggestions: vec![],
             }
-            return Err(anyhow!("Config file not found: {}", path_display));
+        } else {
+            JsonDoctorCheck {
+                name: "device_access".to_string(),
+                status: "fail".to_string(),
+                message: format!("No read/write access to {}", denied.join(", ")),
+                details: Some(lines.join("\n")),
+                suggestions: vec![
+                    "Install the udev rule (see 'savant udev-rules') and replug the pedal"
+                        .to_string(),
+                    "Or run savant with sudo".to_string(),
+                ],
+            }
+        };
+        self.doctor_report("Device access", check)
+    }
+
+    fn doctor_check_groups(&self) -> JsonDoctorCheck {
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        if linux_access::process_uid(&status) == Some(0) {
+            return self.doctor_report(
+                "Groups",
+                JsonDoctorCheck {
+                    name: "groups".to_string(),
+                    status: "pass".to_string(),
+                    message: "Running as root; group membership not needed".to_string(),
+                    details: None,
+                    suggestions: vec![],
+                },
+            );
         }
+        let gids = linux_access::process_groups(&status);
+        let names = fs::read_to_string("/etc/group")
+            .map(|groups| linux_access::group_names(&gids, &groups))
+            .unwrap_or_default();
+
+        // An installed rule decides which group matters; otherwise check the usual ones
+        let dirs: Vec<PathBuf> = linux_access::RULES_DIRS.iter().map(PathBuf::from).collect();
+        let rule_group = linux_access::find_installed_rule(&dirs)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| linux_access::rule_group(&content));
+        let groups: Vec<&str> = match &rule_group {
+            Some(group) => vec![group.as_str()],
+            None => linux_access::ACCESS_GROUPS.to_vec(),
+        };
+        let member_of: Vec<&str> = groups
+            .iter()
+            .copied()
+            .filter(|group| names.iter().any(|name| name == group))
+            .collect();
 
-        // Read file content
-        let content = match fs::read_to_string(&config_path) {
-            Ok(c) => c,
-            Err(e) => {
-                let error = JsonConfigCheckError {
-                    line: None,
-                    field: None,
-                    value: None,
-                    error: format!("Cannot read file: {}", e),
-                };
+        let check = if member_of.is_empty() {
+            JsonDoctorCheck {
+                name: "groups".to_string(),
+                status: "warn".to_string(),
+                message: format!("Not a member of {}", groups.join(" or ")),
+                details: Some(
+                    "Local desktop logins get access through the rule's uaccess tag; SSH and headless sessions need the group".to_string(),
+                ),
+                suggestions: vec![format!(
+                    "sudo usermod -aG {} $USER, then log out and back in",
+                    rule_group.as_deref().unwrap_or(linux_access::DEFAULT_GROUP)
+                )],
+            }
+        } else {
+            JsonDoctorCheck {
+                name: "groups".to_string(),
+                status: "pass".to_string(),
+                message: format!("Member of {}", member_of.join(", ")),
+                details: None,
+                suggestions: vec![],
+            }
+        };
+        self.doctor_report("Groups", check)
+    }
 
-                if self.json_output {
-                    let output = JsonConfigCheckOutput {
-                        valid: false,
-                        file: path_display.clone(),
-                        left: None,
-                        middle: None,
-                        right: None,
-                        errors: vec![error],
-                    };
-                    println!("{}", serde_json::to_string_pretty(&output)?);
-                } else {
-                    self.console.print("[bold red]✗[/] Configuration invalid");
+    fn doctor_check_kernel_driver(&self) -> JsonDoctorCheck {
+        let interfaces =
+            linux_access::interface_drivers(std::path::Path::new("/sys/bus/usb/devices"));
+        if interfaces.is_empty() {
+            return self.doctor_report(
+                "Kernel driver",
+                JsonDoctorCheck {
+                    name: "kernel_driver".to_string(),
+                    status: "warn".to_string(),
+                    message: "No pedal connected; kernel driver not checked".to_string(),
+                    details: None,
+                    suggestions: vec![],
+                },
+            );
+        }
+
+        let details = interfaces
+            .iter()
+            .map(|i| {
+                format!(
+                    "{} (PID 0x{:04X}): {}",
+                    i.interface,
+                    i.product_id,
+                    i.driver.as_deref().unwrap_or("no driver")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        // In play mode the pedal types through usbhid; in programming mode savant detaches
+        // whatever is bound, so any driver is fine there
+        let unbound: Vec<&str> = interfaces
+            .iter()
+            .filter(|i| i.product_id == SAVANT_ELITE_PID && i.driver.as_deref() != Some("usbhid"))
+            .map(|i| i.interface.as_str())
+            .collect();
+
+        let check = if unbound.is_empty() {
+            JsonDoctorCheck {
+                name: "kernel_driver".to_string(),
+                status: "pass".to_string(),
+                message: "Kernel drivers look right".to_string(),
+                details: Some(details),
+                suggestions: vec![],
+            }
+        } else {
+            JsonDoctorCheck {
+                name: "kernel_driver".to_string(),
+                status: "warn".to_string(),
+                message: format!("usbhid is not bound to {}", unbound.join(", ")),
+                details: Some(details),
+                suggestions: vec![
+                    "Replug the pedal; if a previous savant run was killed, the driver may not have been reattached".to_string(),
+                ],
+            }
+        };
+        self.doctor_report("Kernel driver", check)
+    }
+
+    fn doctor_check_device(&self) -> JsonDoctorCheck {
+        if !self.json_output {
+            self.console.print("[bold cyan]Device:[/]");
+        }
+
+        // Try to detect device via libusb
+        let devices = match self.session.usb_devices() {
+            Ok(d) => d,
+            Err(e) => {
+                if !self.json_output {
+                    self.console.print(&format!(
+                        "  [bold #e74c3c]✗[/] Failed to enumerate USB devices: {}",
+                        e
+                    ));
+                    self.console.print("    [dim]→ Try running with sudo[/]");
                     self.console.print("");
-                    self.console
-                        .print(&format!("  [bold red]Error:[/] Cannot read file: {}", e));
                 }
-                return Err(anyhow!("Cannot read config file: {}", e));
+                return JsonDoctorCheck {
+                    name: "device".to_string(),
+                    status: "fail".to_string(),
+                    message: format!("Failed to enumerate USB devices: {}", e),
+                    details: None,
+                    suggestions: vec!["Try running with sudo".to_string()],
+                };
             }
         };
 
-        // Parse and validate config
-        let mut errors: Vec<JsonConfigCheckError> = Vec::new();
-        let mut left_value: Option<String> = None;
-        let mut middle_value: Option<String> = None;
-        let mut right_value: Option<String> = None;
-        let mut left_parsed: Option<KeyAction> = None;
-        let mut middle_parsed: Option<KeyAction> = None;
-        let mut right_parsed: Option<KeyAction> = None;
-
-        // Parse each line
-        for (line_num, line) in content.lines().enumerate() {
-            let line_display = line_num + 1; // 1-indexed for display
-            let line = line.trim();
+        let mut play_mode = false;
+        let mut program_mode = false;
 
-            // Skip empty lines
-            if line.is_empty() {
-                continue;
+        for device in devices.iter() {
+            if let Ok(desc) = device.device_descriptor() {
+                if desc.vendor_id() == KINESIS_VID {
+                    match desc.product_id() {
+                        SAVANT_ELITE_PID => play_mode = true,
+                        PROGRAMMING_PID => program_mode = true,
+                        _ => {}
+                    }
+                }
             }
+        }
 
-            // Check for proper key=value format
-            let Some((key, value)) = line.split_once('=') else {
-                errors.push(JsonConfigCheckError {
-                    line: Some(line_display),
-                    field: None,
-                    value: Some(line.to_string()),
-                    error: "Invalid syntax: expected 'key=value' format".to_string(),
-                });
-                continue;
-            };
+        if program_mode {
+            if !self.json_output {
+                self.console
+                    .print("  [bold #2ecc71]✓[/] Savant Elite detected");
+                self.console.print(&format!(
+                    "  [bold #2ecc71]✓[/] Mode: [bold #f39c12]PROGRAMMING[/] (PID 0x{:04X})",
+                    PROGRAMMING_PID
+                ));
+                self.console.print("    [dim]Ready to program![/]");
+                self.console.print("");
+            }
+            JsonDoctorCheck {
+                name: "device".to_string(),
+                status: "pass".to_string(),
+                message: "Savant Elite detected in PROGRAMMING mode".to_string(),
+                details: Some(format!(
+                    "VID=0x{:04X}, PID=0x{:04X}",
+                    KINESIS_VID, PROGRAMMING_PID
+                )),
+                suggestions: vec![],
+            }
+        } else if play_mode {
+            if !self.json_output {
+                self.console
+                    .print("  [bold #2ecc71]✓[/] Savant Elite detected");
+                self.console.print(&format!(
+                    "  [bold #f39c12]⚠[/] Mode: [bold #2ecc71]PLAY[/] (PID 0x{:04X})",
+                    SAVANT_ELITE_PID
+                ));
+                self.console
+                    .print("    [dim]→ To program: flip switch to Program, replug USB[/]");
+                self.console
+                    .print("    [dim]→ To monitor: this mode is correct[/]");
+                self.console.print("");
+            }
+            JsonDoctorCheck {
+                name: "device".to_string(),
+                status: "warn".to_string(),
+                message: "Savant Elite detected in PLAY mode".to_string(),
+                details: Some(format!(
+                    "VID=0x{:04X}, PID=0x{:04X}",
+                    KINESIS_VID, SAVANT_ELITE_PID
+                )),
+                suggestions: vec![
+                    "To program: flip switch to Program, replug USB".to_string(),
+                    "To monitor: this mode is correct".to_string(),
+                ],
+            }
+        } else {
+            if !self.json_output {
+                self.console
+                    .print("  [bold #e74c3c]✗[/] No Savant Elite device found");
+                self.console
+                    .print("    [dim]→ Connect the device via USB[/]");
+                self.console.print("    [dim]→ Try a different USB port[/]");
+                self.console
+                    .print("    [dim]→ Check if another app has the device open[/]");
+                self.console.print("");
+            }
+            JsonDoctorCheck {
+                name: "device".to_string(),
+                status: "fail".to_string(),
+                message: "No Savant Elite device found".to_string(),
+                details: None,
+                suggestions: vec![
+                    "Connect the device via USB".to_string(),
+                    "Try a different USB port".to_string(),
+                    "Check if another app has the device open".to_string(),
+                ],
+            }
+        }
+    }
 
-            let key = key.trim();
-            let value = value.trim();
+    fn doctor_check_config(&self) -> JsonDoctorCheck {
+        if !self.json_output {
+            self.console.print("[bold cyan]Config:[/]");
+        }
 
-            // Check for valid field name
-            match key {
-                "left" => {
-                    left_value = Some(value.to_string());
-                    match KeyAction::from_string(value) {
-                        Ok(action) => left_parsed = Some(action),
-                        Err(e) => {
-                            errors.push(JsonConfigCheckError {
-                                line: Some(line_display),
-                                field: Some("left".to_string()),
-                                value: Some(value.to_string()),
-                                error: e.to_string(),
-                            });
-                        }
+        let path = PedalConfig::config_path();
+
+        if path.exists() {
+            match PedalConfig::load() {
+                Some(config) => {
+                    if !self.json_output {
+                        self.console
+                            .print("  [bold #2ecc71]✓[/] Config file exists and is valid");
+                        self.console
+                            .print(&format!("    [dim]Path: {}[/]", path.display()));
+                        self.console.print(&format!(
+                            "    [dim]Config: left={}, middle={}, right={}[/]",
+                            config.left, config.middle, config.right
+                        ));
+                        self.console.print("");
                     }
-                }
-                "middle" => {
-                    middle_value = Some(value.to_string());
-                    match KeyAction::from_string(value) {
-                        Ok(action) => middle_parsed = Some(action),
-                        Err(e) => {
-                            errors.push(JsonConfigCheckError {
-                                line: Some(line_display),
-                                field: Some("middle".to_string()),
-                                value: Some(value.to_string()),
-                                error: e.to_string(),
-                            });
-                        }
+                    JsonDoctorCheck {
+                        name: "config".to_string(),
+                        status: "pass".to_string(),
+                        message: "Config file exists and is valid".to_string(),
+                        details: Some(format!(
+                            "left={}, middle={}, right={}",
+                            config.left, config.middle, config.right
+                        )),
+                        suggestions: vec![],
                     }
                 }
-                "right" => {
-                    right_value = Some(value.to_string());
-                    match KeyAction::from_string(value) {
-                        Ok(action) => right_parsed = Some(action),
-                        Err(e) => {
-                            errors.push(JsonConfigCheckError {
-                                line: Some(line_display),
-                                field: Some("right".to_string()),
-                                value: Some(value.to_string()),
-                                error: e.to_string(),
-                            });
-                        }
+                None => {
+                    if !self.json_output {
+                        self.console
+                            .print("  [bold #f39c12]⚠[/] Config file exists but is invalid");
+                        self.console
+                            .print(&format!("    [dim]Path: {}[/]", path.display()));
+                        self.console
+                            .print("    [dim]→ Run 'savant config check' to see what is wrong[/]");
+                        self.console.print("");
+                    }
+                    JsonDoctorCheck {
+                        name: "config".to_string(),
+                        status: "warn".to_string(),
+                        message: "Config file exists but is invalid".to_string(),
+                        details: Some(path.display().to_string()),
+                        suggestions: vec![
+                            "Run 'savant config check' to see what is wrong".to_string()
+                        ],
                     }
                 }
-                _ => {
-                    // Unknown key - warning, not error (for future compatibility)
-                    self.verbose(&format!(
-                        "Unknown key '{}' at line {} (ignored)",
-                        key, line_display
-                    ));
-                }
+            }
+        } else {
+            if !self.json_output {
+                self.console
+                    .print("  [bold #f39c12]⚠[/] Config file not found (OK for first-time use)");
+                self.console
+                    .print(&format!("    [dim]Path: {}[/]", path.display()));
+                self.console
+                    .print("    [dim]→ Run 'savant program' to create a configuration[/]");
+                self.console.print("");
+            }
+            JsonDoctorCheck {
+                name: "config".to_string(),
+                status: "warn".to_string(),
+                message: "Config file not found (OK for first-time use)".to_string(),
+                details: Some(path.display().to_string()),
+                suggestions: vec!["Run 'savant program' to create a configuration".to_string()],
             }
         }
+    }
 
-        // Check for missing required fields
-        if left_value.is_none() {
-            errors.push(JsonConfigCheckError {
-                line: None,
-                field: Some("left".to_string()),
-                value: None,
-                error: "Missing required field: left".to_string(),
-            });
-        }
-        if middle_value.is_none() {
-            errors.push(JsonConfigCheckError {
-                line: None,
-                field: Some("middle".to_string()),
-                value: None,
-                error: "Missing required field: middle".to_string(),
-            });
-        }
-        if right_value.is_none() {
-            errors.push(JsonConfigCheckError {
-                line: None,
-                field: Some("right".to_string()),
-                value: None,
-                error: "Missing required field: right".to_string(),
-            });
+    fn doctor_check_profiles(&self) -> JsonDoctorCheck {
+        if !self.json_output {
+            self.console.print("[bold cyan]Profiles:[/]");
         }
 
-        // Build output
-        let is_valid = errors.is_empty();
-        let error_count = errors.len();
+        let dir = profiles_dir();
 
-        if self.json_output {
-            let output = JsonConfigCheckOutput {
-                valid: is_valid,
-                file: path_display,
-                left: left_parsed.as_ref().map(|a| JsonConfigCheckParsedKey {
-                    action: left_value.clone().unwrap_or_default(),
-                    modifier_hex: format!("0x{:02X}", a.modifiers),
-                    key_hex: format!("0x{:02X}", a.key),
-                }),
-                middle: middle_parsed.as_ref().map(|a| JsonConfigCheckParsedKey {
-                    action: middle_value.clone().unwrap_or_default(),
-                    modifier_hex: format!("0x{:02X}", a.modifiers),
-                    key_hex: format!("0x{:02X}", a.key),
-                }),
-                right: right_parsed.as_ref().map(|a| JsonConfigCheckParsedKey {
-                    action: right_value.clone().unwrap_or_default(),
-                    modifier_hex: format!("0x{:02X}", a.modifiers),
-                    key_hex: format!("0x{:02X}", a.key),
-                }),
-                errors,
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
-        } else if is_valid {
-            self.console.print("[bold #2ecc71]✓[/] Configuration valid");
-            self.console.print("");
+        if dir.exists() {
+            // Count profiles
+            let count = fs::read_dir(&dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok())
+                        .filter(|e| e.path().extension().is_some_and(|ext| ext == "conf"))
+                        .count()
+                })
+                .unwrap_or(0);
 
-            if self.verbose {
+            if !self.json_output {
+                self.console.print(&format!(
+                    "  [bold #2ecc71]✓[/] Profiles directory exists ({} profile{})",
+                    count,
+                    if count == 1 { "" } else { "s" }
+                ));
+                self.console
+                    .print(&format!("    [dim]Path: {}[/]", dir.display()));
+                self.console.print("");
+            }
+            JsonDoctorCheck {
+                name: "profiles".to_string(),
+                status: "pass".to_string(),
+                message: format!(
+                    "Profiles directory exists ({} profile{})",
+                    count,
+                    if count == 1 { "" } else { "s" }
+                ),
+                details: Some(dir.display().to_string()),
+                suggestions: vec![],
+            }
+        } else {
+            if !self.json_output {
+                self.console.print(
+                    "  [bold #f39c12]⚠[/] Profiles directory not found (OK for first-time use)",
+                );
                 self.console
-                    .print(&format!("  [dim]File:[/] {}", path_display));
+                    .print(&format!("    [dim]Path: {}[/]", dir.display()));
+                self.console
+                    .print("    [dim]→ Run 'savant config save <name>' to create a profile[/]");
                 self.console.print("");
             }
+            JsonDoctorCheck {
+                name: "profiles".to_string(),
+                status: "warn".to_string(),
+                message: "Profiles directory not found (OK for first-time use)".to_string(),
+                details: Some(dir.display().to_string()),
+                suggestions: vec!["Run 'savant config save <name>' to create a profile".to_string()],
+            }
+        }
+    }
 
-            // Show parsed configuration
-            if let (Some(left), Some(left_a)) = (&left_value, &left_parsed) {
-                self.console
-                    .print(&format!("  [bold #e74c3c]Left:[/]   {}", left));
-                if self.verbose {
-                    self.console.print(&format!(
-                        "          [dim]Parsed: modifier=0x{:02X}, key=0x{:02X}[/]",
-                        left_a.modifiers, left_a.key
-                    ));
+    fn doctor_check_input_monitoring(&self) -> JsonDoctorCheck {
+        if !self.json_output {
+            self.console.print("[bold cyan]Permissions:[/]");
+        }
+
+        // Try to initialize HID API and open a device to check permissions
+        // This is a heuristic - if we can enumerate HID devices, permissions are likely OK
+        let hid_api = match self.session.hid() {
+            Ok(api) => api,
+            Err(e) => {
+                let err_str = e.to_string().to_lowercase();
+                let is_permission_error = err_str.contains("privilege")
+                    || err_str.contains("permission")
+                    || err_str.contains("access");
+
+                if is_permission_error {
+                    if !self.json_output {
+                        self.console.print(
+                            "  [bold #e74c3c]✗[/] Input Monitoring permission may be required",
+                        );
+                        self.console.print("    [dim]→ Open System Settings → Privacy & Security → Input Monitoring[/]");
+                        self.console
+                            .print("    [dim]→ Add your terminal app (Terminal, iTerm2, etc.)[/]");
+                        self.console.print("    [dim]→ Restart your terminal[/]");
+                        self.console.print("");
+                    }
+                    return JsonDoctorCheck {
+                        name: "permissions".to_string(),
+                        status: "fail".to_string(),
+                        message: "Input Monitoring permission may be required".to_string(),
+                        details: Some(e.to_string()),
+                        suggestions: vec![
+                            "Open System Settings → Privacy & Security → Input Monitoring"
+                                .to_string(),
+                            "Add your terminal app (Terminal, iTerm2, etc.)".to_string(),
+                            "Restart your terminal".to_string(),
+                        ],
+                    };
                 }
-            }
-            if let (Some(middle), Some(middle_a)) = (&middle_value, &middle_parsed) {
-                self.console
-                    .print(&format!("  [bold #f39c12]Middle:[/] {}", middle));
-                if self.verbose {
+
+                if !self.json_output {
                     self.console.print(&format!(
-                        "          [dim]Parsed: modifier=0x{:02X}, key=0x{:02X}[/]",
-                        middle_a.modifiers, middle_a.key
+                        "  [bold #f39c12]⚠[/] Could not initialize HID API: {}",
+                        e
                     ));
+                    self.console.print("");
                 }
+                return JsonDoctorCheck {
+                    name: "permissions".to_string(),
+                    status: "warn".to_string(),
+                    message: format!("Could not initialize HID API: {}", e),
+                    details: None,
+                    suggestions: vec![],
+                };
             }
-            if let (Some(right), Some(right_a)) = (&right_value, &right_parsed) {
+        };
+
+        // Check if we can enumerate devices with our VID
+        let devices: Vec<_> = hid_api
+            .device_list()
+            .filter(|d| d.vendor_id() == KINESIS_VID)
+            .collect();
+
+        if !devices.is_empty() {
+            if !self.json_output {
                 self.console
-                    .print(&format!("  [bold #2ecc71]Right:[/]  {}", right));
-                if self.verbose {
-                    self.console.print(&format!(
-                        "          [dim]Parsed: modifier=0x{:02X}, key=0x{:02X}[/]",
-                        right_a.modifiers, right_a.key
-                    ));
-                }
+                    .print("  [bold #2ecc71]✓[/] HID API accessible (Input Monitoring OK)");
+                self.console.print("");
+            }
+            JsonDoctorCheck {
+                name: "permissions".to_string(),
+                status: "pass".to_string(),
+                message: "HID API accessible (Input Monitoring OK)".to_string(),
+                details: None,
+                suggestions: vec![],
             }
         } else {
-            self.console.print("[bold red]✗[/] Configuration invalid");
-            self.console.print("");
-
-            for error in &errors {
-                let mut msg = String::new();
-                if let Some(line) = error.line {
-                    msg.push_str(&format!("Line {}: ", line));
-                }
-                // Only show "field=value" when we have both field and value
-                // For missing field errors, the error message already explains what's missing
-                if let (Some(ref field), Some(ref value)) = (&error.field, &error.value) {
-                    msg.push_str(&format!("{}={}", field, value));
-                }
-                if !msg.is_empty() {
-                    self.console.print(&format!("  [dim]{}[/]", msg));
-                }
+            // Device not found via HID, but we already checked via libusb
+            // This might just mean device is in program mode (not HID)
+            if !self.json_output {
                 self.console
-                    .print(&format!("  [bold red]Error:[/] {}", error.error));
+                    .print("  [bold #2ecc71]✓[/] HID API initialized successfully");
+                self.console
+                    .print("    [dim]Note: Device monitoring requires device in PLAY mode[/]");
                 self.console.print("");
             }
-
-            self.console
-                .print(&format!("[dim]{} error(s) found[/]", errors.len()));
-            self.console.print("");
-            self.console
-                .print("Run [bold yellow]savant keys[/] for a complete list of valid key names.");
-        }
-
-        if is_valid {
-            Ok(())
-        } else {
-            Err(anyhow!("Configuration has {} error(s)", error_count))
+            JsonDoctorCheck {
+                name: "permissions".to_string(),
+                status: "pass".to_string(),
+                message: "HID API initialized successfully".to_string(),
+                details: Some("Device monitoring requires device in PLAY mode".to_string()),
+                suggestions: vec![],
+            }
         }
     }
+}
 
-    fn config_history(&self) -> Result<()> {
-        self.verbose("Listing config history");
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let timeout_ms = cli.timeout.unwrap_or(DEFAULT_USB_TIMEOUT_MS);
+    let mut retry = RetryPolicy::default();
+    if let Some(retries) = cli.retries {
+        retry.retries = retries;
+    }
+    if let Some(backoff) = cli.retry_backoff {
+        retry.backoff = Duration::from_millis(backoff);
+    }
+    if !cli.retry_on.is_empty() {
+        retry.retry_on = cli.retry_on;
+    }
+    let savant = SavantElite::new(
+        cli.verbose,
+        cli.json,
+        timeout_ms,
+        cli.unit,
+        cli.allow,
+        cli.target_os,
+        retry,
+    )?;
 
-        let backups = PedalConfig::list_backups();
+    if cli.verbose {
+        eprintln!("[verbose] Verbose mode enabled");
+        eprintln!("[verbose] USB timeout: {}ms", timeout_ms);
+        eprintln!("[verbose] USB retries: {}", savant.retry.describe());
+        if let Some(unit) = cli.unit {
+            eprintln!("[verbose] Unit ID filter: {}", unit);
+        }
+    }
+    if cli.json {
+        savant.verbose("JSON output mode enabled");
+    }
 
-        if self.json_output {
-            let history: Vec<serde_json::Value> = backups
-                .iter()
-                .enumerate()
-                .map(|(i, (path, datetime, config))| {
-                    let mut entry = serde_json::json!({
-                        "number": i + 1,
-                        "timestamp": datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
-                        "path": path.display().to_string(),
-                    });
-                    if let Some(cfg) = config {
-                        entry["left"] = serde_json::json!(cfg.left);
-                        entry["middle"] = serde_json::json!(cfg.middle);
-                        entry["right"] = serde_json::json!(cfg.right);
-                    }
-                    entry
-                })
-                .collect();
+    // Reading the log isn't itself worth logging
+    if matches!(cli.command, Commands::Log { .. }) {
+        return run(&savant, cli.command);
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    savant
+        .oplog
+        .append(LogEvent::Command, &format!("savant {}", args.join(" ")));
+    let result = run(&savant, cli.command);
+    match &result {
+        Ok(()) => savant.oplog.append(LogEvent::Result, "ok"),
+        Err(e) => savant
+            .oplog
+            .append(LogEvent::Result, &format!("error: {:#}", e)),
+    }
+    result
+}
 
-            let output = serde_json::json!({
-                "history": history,
-                "count": backups.len(),
-                "history_dir": PedalConfig::history_dir().display().to_string(),
-            });
-            println!("{}", serde_json::to_string_pretty(&output)?);
-            return Ok(());
+fn run(savant: &SavantElite, command: Commands) -> Result<()> {
+    match command {
+        Commands::Info => {
+            savant.find_device()?;
         }
-
-        if backups.is_empty() {
-            self.console
-                .print("[bold yellow]No configuration history found.[/]");
-            self.console.print("");
-            self.console
-                .print("[dim]History is created automatically when you program the device[/]");
-            self.console
-                .print("[dim]or load a profile. Each change creates a backup.[/]");
-            return Ok(());
+        Commands::Monitor { duration } => {
+            savant.monitor(duration)?;
         }
-
-        self.console
-            .print("[bold #3498db]CONFIG HISTORY[/] [dim](most recent first)[/]");
-        self.console.print("");
-
-        for (i, (_path, datetime, config)) in backups.iter().enumerate() {
-            let num = format!("{:>3}.", i + 1);
-            let timestamp = datetime.format("%Y-%m-%d %H:%M:%S").to_string();
-
-            let summary = if let Some(cfg) = config {
-                format!("{}, {}, {}", cfg.left, cfg.middle, cfg.right)
+        Commands::Probe => {
+            savant.probe()?;
+        }
+        Commands::RawCmd {
+            cmd,
+            data,
+            interface,
+        } => {
+            savant.raw_cmd(&cmd, &data, interface)?;
+        }
+        Commands::Status => {
+            savant.status()?;
+        }
+        Commands::Program {
+            left,
+            middle,
+            right,
+            only,
+            dry_run,
+            monitor,
+            reboot,
+            on_interrupt,
+            interface,
+            transport,
+            force,
+        } => {
+            let saved = if only.is_empty() {
+                None
             } else {
-                "[dim]<unable to parse>[/]".to_string()
+                PedalConfig::load()
             };
-
-            self.console.print(&format!(
-                "  [bold #3498db]{}[/]  [#95a5a6]{}[/]  {}",
-                num, timestamp, summary
-            ));
+            let [left, middle, right] =
+                resolve_program_pedals([left, middle, right], &only, saved.as_ref())?;
+            savant.program(
+                left.as_deref(),
+                middle.as_deref(),
+                right.as_deref(),
+                ProgramOptions {
+                    dry_run,
+                    auto_monitor: monitor,
+                    reboot,
+                    on_interrupt,
+                    interface,
+                    transport,
+                    force,
+                    ..Default::default()
+                },
+            )?;
+        }
+        Commands::Keys { json } => {
+            savant.list_keys(json)?;
+        }
+        Commands::UdevRules { group, output } => {
+            savant.udev_rules(&group, output.as_deref())?;
+        }
+        Commands::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        Commands::Preset {
+            command: Some(PresetCommand::Check { file }),
+            ..
+        } => {
+            savant.preset_check(file.as_deref())?;
+        }
+        Commands::Preset {
+            command: None,
+            name,
+            list,
+            show,
+            dry_run,
+            force,
+        } => {
+            savant.preset(name.as_deref(), list, show, dry_run, force)?;
+        }
+        Commands::Config { command } => {
+            savant.config(command)?;
+        }
+        Commands::Doctor {
+            fix,
+            dry_run,
+            bundle,
+        } => {
+            savant.doctor(fix, dry_run, bundle.as_deref())?;
+        }
+        Commands::UnitId { command } => {
+            savant.unit_id(command)?;
+        }
+        Commands::Undo { dry_run } => {
+            savant.history_step(HistoryStep::Undo, dry_run)?;
+        }
+        Commands::Redo { dry_run } => {
+            savant.history_step(HistoryStep::Redo, dry_run)?;
+        }
+        Commands::Reset {
+            factory: _,
+            dry_run,
+        } => {
+            savant.reset_factory(dry_run)?;
+        }
+        Commands::Mode {
+            mode,
+            experimental,
+            yes,
+        } => {
+            savant.switch_mode(mode, experimental, yes)?;
+        }
+        Commands::Log { tail } => {
+            savant.show_log(tail)?;
         }
-
-        self.console.print("");
-        self.console
-            .print("[dim]Use 'savant config restore <N>' to restore a previous config.[/]");
-        self.console.print(
-            "[dim]Use 'savant config restore <N> --apply' to restore and program device.[/]",
-        );
-
-        Ok(())
     }
 
-    fn config_restore(&self, number: usize, apply: bool) -> Result<()> {
-        self.verbose(&format!("Restoring backup #{}", number));
-
-        let config = PedalConfig::restore_backup(number)?;
-
-        // JSON output only when NOT applying (program() doesn't support JSON mode)
-        if self.json_output && !apply {
-            let output = serde_json::json!({
-                "restored": true,
-                "backup_number": number,
-                "left": config.left,
-                "middle": config.middle,
-                "right": config.right,
-            });
-            println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
 
-            // Save the restored config
-            config.save()?;
-            return Ok(());
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        self.console.print(&format!(
-            "[bold #2ecc71]Restoring configuration from backup #{}...[/]",
-            number
-        ));
-        self.console.print("");
-        self.console
-            .print(&format!("  [bold]Left:[/]   {}", config.left));
-        self.console
-            .print(&format!("  [bold]Middle:[/] {}", config.middle));
-        self.console
-            .print(&format!("  [bold]Right:[/]  {}", config.right));
-        self.console.print("");
+    #[test]
+    fn parse_key_action_cmd_c() {
+        let action = KeyAction::from_string("cmd+c").unwrap();
+        assert_eq!(action.modifiers, usb_hid::MOD_LEFT_GUI);
+        assert_eq!(action.key, usb_hid::KEY_C);
+    }
 
-        if apply {
-            self.console
-                .print("[bold #3498db]Programming device with restored config...[/]");
-            self.console.print("");
-            self.program(&config.left, &config.middle, &config.right, false, false)
-        } else {
-            // Save the restored config
-            config.save()?;
-            self.console
-                .print("[bold #2ecc71]✓[/] Config restored to [bold]pedals.conf[/]");
-            self.console.print("");
-            self.console
-                .print("[dim]Run 'savant program' to apply to device, or[/]");
-            self.console.print("[dim]use 'savant config restore <N> --apply' to restore and program in one step.[/]");
-            Ok(())
-        }
+    #[test]
+    fn parse_key_action_multi_modifiers() {
+        let action = KeyAction::from_string("ctrl+shift+alt+f12").unwrap();
+        assert_eq!(
+            action.modifiers,
+            usb_hid::MOD_LEFT_CTRL | usb_hid::MOD_LEFT_SHIFT | usb_hid::MOD_LEFT_ALT
+        );
+        assert_eq!(action.key, usb_hid::KEY_F12);
     }
 
-    // =========================================================================
-    // Doctor Command - System Diagnostics
-    // =========================================================================
+    #[test]
+    fn parse_key_action_aliases() {
+        let a1 = KeyAction::from_string("option+a").unwrap();
+        let a2 = KeyAction::from_string("opt+a").unwrap();
+        let a3 = KeyAction::from_string("alt+a").unwrap();
 
-    fn doctor(&self) -> Result<()> {
-        self.verbose("Running system diagnostics");
+        assert_eq!(a1.modifiers, usb_hid::MOD_LEFT_ALT);
+        assert_eq!(a2.modifiers, usb_hid::MOD_LEFT_ALT);
+        assert_eq!(a3.modifiers, usb_hid::MOD_LEFT_ALT);
 
-        // Collect all check results
-        let mut checks: Vec<JsonDoctorCheck> = Vec::new();
+        assert_eq!(a1.key, usb_hid::KEY_A);
+        assert_eq!(a2.key, usb_hid::KEY_A);
+        assert_eq!(a3.key, usb_hid::KEY_A);
+    }
 
-        // Get system info
-        let version = env!("CARGO_PKG_VERSION").to_string();
-        let platform = std::env::consts::OS.to_string();
-        let arch = std::env::consts::ARCH.to_string();
+    #[test]
+    fn parse_key_action_rejects_unknown_modifier() {
+        let err = KeyAction::from_string("hyper+a").unwrap_err();
+        assert!(err.to_string().to_lowercase().contains("unknown modifier"));
+    }
 
-        if !self.json_output {
-            self.print_banner();
+    #[test]
+    fn parse_key_action_rejects_unknown_key() {
+        let err = KeyAction::from_string("cmd+notakey").unwrap_err();
+        assert!(err.to_string().to_lowercase().contains("unknown key"));
+    }
 
-            self.console.print(
-                "[bold #3498db]┌─────────────────────────────────────────────────────────────────┐[/]",
-            );
-            self.console.print(
-                "[bold #3498db]│[/]  [bold white]SYSTEM DIAGNOSTICS[/]                                          [bold #3498db]│[/]",
-            );
-            self.console.print(
-                "[bold #3498db]└─────────────────────────────────────────────────────────────────┘[/]",
-            );
-            self.console.print("");
-        }
+    #[test]
+    fn parse_key_action_rejects_empty() {
+        let err = KeyAction::from_string("").unwrap_err();
+        assert!(err.to_string().contains("cannot be empty"));
+    }
 
-        // Check 1: Binary/System info
-        checks.push(self.doctor_check_binary(&version, &platform, &arch));
+    #[test]
+    fn parse_key_action_rejects_whitespace_only() {
+        let err = KeyAction::from_string("   ").unwrap_err();
+        assert!(err.to_string().contains("cannot be empty"));
+    }
 
-        // Check 2: Platform compatibility
-        checks.push(self.doctor_check_platform(&platform));
+    #[test]
+    fn parse_key_action_rejects_leading_plus() {
+        let err = KeyAction::from_string("+c").unwrap_err();
+        assert!(err.to_string().contains("cannot start or end with"));
+    }
 
-        // Check 3: Device detection
-        checks.push(self.doctor_check_device());
+    #[test]
+    fn parse_key_action_rejects_trailing_plus() {
+        let err = KeyAction::from_string("cmd+").unwrap_err();
+        assert!(err.to_string().contains("cannot start or end with"));
+    }
 
-        // Check 4: Config file
-        checks.push(self.doctor_check_config());
+    #[test]
+    fn parse_key_action_rejects_just_plus() {
+        let err = KeyAction::from_string("+").unwrap_err();
+        assert!(err.to_string().contains("cannot start or end with"));
+    }
 
-        // Check 5: Profiles directory
-        checks.push(self.doctor_check_profiles());
+    #[test]
+    fn parse_key_action_rejects_double_plus() {
+        let err = KeyAction::from_string("cmd++c").unwrap_err();
+        assert!(err.to_string().contains("consecutive"));
+    }
 
-        // Check 6: Input Monitoring (attempt to detect)
-        checks.push(self.doctor_check_input_monitoring());
+    #[test]
+    fn parse_key_action_suggests_close_names() {
+        let err = KeyAction::from_string("cmd+escp").unwrap_err();
+        assert_eq!(err.suggestion, Some("esc"));
+        assert_eq!(
+            err.to_string(),
+            "Unknown key: \"escp\" (did you mean \"esc\"?)\n  cmd+escp\n      ^^^^"
+        );
 
-        // Calculate summary
-        let passed = checks.iter().filter(|c| c.status == "pass").count();
-        let warnings = checks.iter().filter(|c| c.status == "warn").count();
-        let failed = checks.iter().filter(|c| c.status == "fail").count();
-        let total = checks.len();
-        let healthy = failed == 0;
+        let err = KeyAction::from_string("comand+shfit+c").unwrap_err();
+        assert_eq!(err.suggestion, Some("command"));
+        assert_eq!((err.column, err.width), (0, 6));
+        let err = KeyAction::from_string("cmd+shfit+c").unwrap_err();
+        assert_eq!(err.suggestion, Some("shift"));
 
-        if self.json_output {
-            let output = JsonDoctorOutput {
-                version,
-                platform,
-                arch,
-                checks,
-                summary: JsonDoctorSummary {
-                    total,
-                    passed,
-                    warnings,
-                    failed,
-                    healthy,
-                },
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
-        } else {
-            // Print summary
-            self.console.print("");
-            self.console.print(
-                "[bold #9b59b6]┌─────────────────────────────────────────────────────────────────┐[/]",
-            );
-            self.console.print(
-                "[bold #9b59b6]│[/]  [bold white]SUMMARY[/]                                                       [bold #9b59b6]│[/]",
-            );
-            self.console.print(
-                "[bold #9b59b6]└─────────────────────────────────────────────────────────────────┘[/]",
-            );
-            self.console.print("");
+        // Nothing plausible to suggest
+        let err = KeyAction::from_string("cmd+xyzzy").unwrap_err();
+        assert_eq!(err.suggestion, None);
+        assert_eq!(err.summary(), "Unknown key: \"xyzzy\"");
+    }
 
-            if healthy {
-                self.console.print(&format!(
-                    "  [bold #2ecc71]✓[/] All {} checks passed{}",
-                    total,
-                    if warnings > 0 {
-                        format!(
-                            " ({} warning{})",
-                            warnings,
-                            if warnings == 1 { "" } else { "s" }
-                        )
-                    } else {
-                        String::new()
-                    }
-                ));
-                self.console.print("");
-                self.console
-                    .print("  [bold #2ecc71]Your system is ready to use savant-elite![/]");
-            } else {
-                self.console.print(&format!(
-                    "  [bold #e74c3c]✗[/] {} issue{} found ({} passed, {} warning{}, {} failed)",
-                    failed + warnings,
-                    if failed + warnings == 1 { "" } else { "s" },
-                    passed,
-                    warnings,
-                    if warnings == 1 { "" } else { "s" },
-                    failed
-                ));
-                self.console.print("");
-                self.console
-                    .print("  [dim]Fix the issues above and run 'savant doctor' again.[/]");
-            }
-        }
+    #[test]
+    fn parse_key_action_points_into_the_original_string() {
+        let err = KeyAction::from_string("  cmd + nope").unwrap_err();
+        assert_eq!((err.column, err.width), (8, 4));
+        let err = KeyAction::from_string("ctrl++c").unwrap_err();
+        assert_eq!((err.column, err.width), (5, 1));
+        let err = KeyAction::from_string("").unwrap_err();
+        assert_eq!(err.width, 0);
+        assert_eq!(err.to_string(), "Key action cannot be empty");
+    }
 
-        Ok(())
+    #[test]
+    fn parse_key_action_explains_misplaced_modifiers_and_keys() {
+        let err = KeyAction::from_string("cmd+shift").unwrap_err();
+        assert!(err
+            .summary()
+            .contains("is a modifier and needs a key after it"));
+        let err = KeyAction::from_string("c+cmd").unwrap_err();
+        assert!(err.summary().contains("the key must come last"));
     }
 
-    fn doctor_check_binary(&self, version: &str, platform: &str, arch: &str) -> JsonDoctorCheck {
-        if !self.json_output {
-            self.console.print("[bold cyan]Binary:[/]");
-            self.console.print(&format!(
-                "  [bold #2ecc71]✓[/] savant-elite version {}",
-                version
-            ));
-            self.console
-                .print(&format!("  [bold #2ecc71]✓[/] {} ({})", platform, arch));
-            self.console.print("");
-        }
+    #[test]
+    fn parse_key_action_resolves_portable_modifiers() {
+        let on = |os| {
+            KeyAction::parse_for("primary+shift+z", os)
+                .unwrap()
+                .modifiers
+        };
+        assert_eq!(
+            on(TargetOs::Macos),
+            usb_hid::MOD_LEFT_GUI | usb_hid::MOD_LEFT_SHIFT
+        );
+        assert_eq!(
+            on(TargetOs::Windows),
+            usb_hid::MOD_LEFT_CTRL | usb_hid::MOD_LEFT_SHIFT
+        );
+
+        let on = |os| {
+            KeyAction::parse_for("secondary+left", os)
+                .unwrap()
+                .modifiers
+        };
+        assert_eq!(on(TargetOs::Macos), usb_hid::MOD_LEFT_CTRL);
+        assert_eq!(on(TargetOs::Linux), usb_hid::MOD_LEFT_GUI);
 
-        JsonDoctorCheck {
-            name: "binary".to_string(),
-            status: "pass".to_string(),
-            message: format!("savant-elite v{} on {} ({})", version, platform, arch),
-            details: None,
-            suggestions: vec![],
-        }
+        let err = KeyAction::from_string("primry+c").unwrap_err();
+        assert_eq!(err.suggestion, Some("primary"));
     }
 
-    fn doctor_check_platform(&self, platform: &str) -> JsonDoctorCheck {
-        let is_macos = platform == "macos";
-
-        if !self.json_output {
-            self.console.print("[bold cyan]Platform:[/]");
-            if is_macos {
-                self.console
-                    .print("  [bold #2ecc71]✓[/] macOS detected (supported)");
-            } else {
-                self.console.print(&format!(
-                    "  [bold #e74c3c]✗[/] {} detected (unsupported)",
-                    platform
-                ));
-                self.console
-                    .print("    [dim]→ savant-elite requires macOS[/]");
+    #[test]
+    fn built_in_presets_avoid_platform_specific_modifiers() {
+        // Everything except the macOS-only presets should work for any --target-os
+        for preset in PRESETS.iter().filter(|p| !p.tags.contains(&"macos")) {
+            for action in [preset.left, preset.middle, preset.right] {
+                assert!(!action.contains("cmd"), "{}: {}", preset.name, action);
             }
-            self.console.print("");
         }
+    }
 
-        if is_macos {
-            JsonDoctorCheck {
-                name: "platform".to_string(),
-                status: "pass".to_string(),
-                message: "macOS detected (supported)".to_string(),
-                details: None,
-                suggestions: vec![],
-            }
-        } else {
-            JsonDoctorCheck {
-                name: "platform".to_string(),
-                status: "fail".to_string(),
-                message: format!("{} detected (unsupported)", platform),
-                details: None,
-                suggestions: vec!["savant-elite requires macOS".to_string()],
-            }
-        }
+    #[test]
+    fn browser_preset_picks_back_and_forward_per_os() {
+        let browser = find_preset("browser").unwrap();
+        assert_eq!(
+            browser.actions(TargetOs::Linux),
+            ["alt+left", "primary+t", "alt+right"]
+        );
+        assert_eq!(
+            browser.actions(TargetOs::Windows),
+            browser.actions(TargetOs::Linux)
+        );
+        assert_eq!(
+            browser.actions(TargetOs::Macos),
+            ["cmd+[", "primary+t", "cmd+]"]
+        );
+        let catalog = PresetCatalog::load_from(
+            std::path::Path::new("/nonexistent"),
+            std::path::Path::new("/nonexistent"),
+            TargetOs::Macos,
+        );
+        assert_eq!(catalog.find("browser").unwrap().left, "cmd+[");
     }
 
-    fn doctor_check_device(&self) -> JsonDoctorCheck {
-        if !self.json_output {
-            self.console.print("[bold cyan]Device:[/]");
+    #[test]
+    fn key_and_modifier_name_lists_match_the_parsers() {
+        for name in usb_hid::KEY_NAMES {
+            assert!(usb_hid::parse_key_name(name).is_some(), "{}", name);
         }
+        for name in usb_hid::MODIFIER_NAMES {
+            assert!(usb_hid::parse_modifier_name(name).is_some(), "{}", name);
+        }
+    }
 
-        // Try to detect device via libusb
-        let devices = match rusb::devices() {
-            Ok(d) => d,
-            Err(e) => {
-                if !self.json_output {
-                    self.console.print(&format!(
-                        "  [bold #e74c3c]✗[/] Failed to enumerate USB devices: {}",
-                        e
-                    ));
-                    self.console.print("    [dim]→ Try running with sudo[/]");
-                    self.console.print("");
-                }
-                return JsonDoctorCheck {
-                    name: "device".to_string(),
-                    status: "fail".to_string(),
-                    message: format!("Failed to enumerate USB devices: {}", e),
-                    details: None,
-                    suggestions: vec!["Try running with sudo".to_string()],
-                };
-            }
-        };
-
-        let mut play_mode = false;
-        let mut program_mode = false;
+    #[test]
+    fn parse_key_name_punctuation() {
+        assert_eq!(usb_hid::parse_key_name("-"), Some(0x2D));
+        assert_eq!(usb_hid::parse_key_name("="), Some(0x2E));
+        assert_eq!(usb_hid::parse_key_name("escape"), Some(usb_hid::KEY_ESC));
+    }
 
-        for device in devices.iter() {
-            if let Ok(desc) = device.device_descriptor() {
-                if desc.vendor_id() == KINESIS_VID {
-                    match desc.product_id() {
-                        SAVANT_ELITE_PID => play_mode = true,
-                        PROGRAMMING_PID => program_mode = true,
-                        _ => {}
-                    }
-                }
-            }
-        }
+    #[test]
+    fn pedal_config_rejects_newline_in_value() {
+        let config = PedalConfig {
+            left: "cmd+c\nright=evil".to_string(),
+            middle: "cmd+a".to_string(),
+            right: "cmd+v".to_string(),
+            ..Default::default()
+        };
+        let err = config.save(&HistoryMeta::command("program")).unwrap_err();
+        assert!(err.to_string().contains("newline"));
+    }
 
-        if program_mode {
-            if !self.json_output {
-                self.console
-                    .print("  [bold #2ecc71]✓[/] Savant Elite detected");
-                self.console.print(&format!(
-                    "  [bold #2ecc71]✓[/] Mode: [bold #f39c12]PROGRAMMING[/] (PID 0x{:04X})",
-                    PROGRAMMING_PID
-                ));
-                self.console.print("    [dim]Ready to program![/]");
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "device".to_string(),
-                status: "pass".to_string(),
-                message: "Savant Elite detected in PROGRAMMING mode".to_string(),
-                details: Some(format!(
-                    "VID=0x{:04X}, PID=0x{:04X}",
-                    KINESIS_VID, PROGRAMMING_PID
-                )),
-                suggestions: vec![],
-            }
-        } else if play_mode {
-            if !self.json_output {
-                self.console
-                    .print("  [bold #2ecc71]✓[/] Savant Elite detected");
-                self.console.print(&format!(
-                    "  [bold #f39c12]⚠[/] Mode: [bold #2ecc71]PLAY[/] (PID 0x{:04X})",
-                    SAVANT_ELITE_PID
-                ));
-                self.console
-                    .print("    [dim]→ To program: flip switch to Program, replug USB[/]");
-                self.console
-                    .print("    [dim]→ To monitor: this mode is correct[/]");
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "device".to_string(),
-                status: "warn".to_string(),
-                message: "Savant Elite detected in PLAY mode".to_string(),
-                details: Some(format!(
-                    "VID=0x{:04X}, PID=0x{:04X}",
-                    KINESIS_VID, SAVANT_ELITE_PID
-                )),
-                suggestions: vec![
-                    "To program: flip switch to Program, replug USB".to_string(),
-                    "To monitor: this mode is correct".to_string(),
-                ],
-            }
-        } else {
-            if !self.json_output {
-                self.console
-                    .print("  [bold #e74c3c]✗[/] No Savant Elite device found");
-                self.console
-                    .print("    [dim]→ Connect the device via USB[/]");
-                self.console.print("    [dim]→ Try a different USB port[/]");
-                self.console
-                    .print("    [dim]→ Check if another app has the device open[/]");
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "device".to_string(),
-                status: "fail".to_string(),
-                message: "No Savant Elite device found".to_string(),
-                details: None,
-                suggestions: vec![
-                    "Connect the device via USB".to_string(),
-                    "Try a different USB port".to_string(),
-                    "Check if another app has the device open".to_string(),
-                ],
-            }
-        }
+    #[test]
+    fn pedal_config_rejects_carriage_return_in_value() {
+        let config = PedalConfig {
+            left: "cmd+c".to_string(),
+            middle: "cmd+a\rright=evil".to_string(),
+            right: "cmd+v".to_string(),
+            ..Default::default()
+        };
+        let err = config.save(&HistoryMeta::command("program")).unwrap_err();
+        assert!(err.to_string().contains("newline"));
     }
 
-    fn doctor_check_config(&self) -> JsonDoctorCheck {
-        if !self.json_output {
-            self.console.print("[bold cyan]Config:[/]");
-        }
+    #[test]
+    fn pedal_config_roundtrip() {
+        let config = PedalConfig {
+            left: "cmd+c".to_string(),
+            middle: "cmd+a".to_string(),
+            right: "cmd+v".to_string(),
+            ..Default::default()
+        };
 
-        let path = PedalConfig::config_path();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("roundtrip.conf");
+        config.save_to(&path).unwrap();
 
-        if path.exists() {
-            match PedalConfig::load() {
-                Some(config) => {
-                    if !self.json_output {
-                        self.console
-                            .print("  [bold #2ecc71]✓[/] Config file exists and is valid");
-                        self.console
-                            .print(&format!("    [dim]Path: {}[/]", path.display()));
-                        self.console.print(&format!(
-                            "    [dim]Config: left={}, middle={}, right={}[/]",
-                            config.left, config.middle, config.right
-                        ));
-                        self.console.print("");
-                    }
-                    JsonDoctorCheck {
-                        name: "config".to_string(),
-                        status: "pass".to_string(),
-                        message: "Config file exists and is valid".to_string(),
-                        details: Some(format!(
-                            "left={}, middle={}, right={}",
-                            config.left, config.middle, config.right
-                        )),
-                        suggestions: vec![],
-                    }
-                }
-                None => {
-                    if !self.json_output {
-                        self.console
-                            .print("  [bold #f39c12]⚠[/] Config file exists but is invalid");
-                        self.console
-                            .print(&format!("    [dim]Path: {}[/]", path.display()));
-                        self.console
-                            .print("    [dim]→ Check file format (left=X, middle=Y, right=Z)[/]");
-                        self.console.print("");
-                    }
-                    JsonDoctorCheck {
-                        name: "config".to_string(),
-                        status: "warn".to_string(),
-                        message: "Config file exists but is invalid".to_string(),
-                        details: Some(path.display().to_string()),
-                        suggestions: vec![
-                            "Check file format (left=X, middle=Y, right=Z)".to_string()
-                        ],
-                    }
-                }
-            }
-        } else {
-            if !self.json_output {
-                self.console
-                    .print("  [bold #f39c12]⚠[/] Config file not found (OK for first-time use)");
-                self.console
-                    .print(&format!("    [dim]Path: {}[/]", path.display()));
-                self.console
-                    .print("    [dim]→ Run 'savant program' to create a configuration[/]");
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "config".to_string(),
-                status: "warn".to_string(),
-                message: "Config file not found (OK for first-time use)".to_string(),
-                details: Some(path.display().to_string()),
-                suggestions: vec!["Run 'savant program' to create a configuration".to_string()],
-            }
-        }
+        let loaded = PedalConfig::load_from(&path).unwrap();
+        assert_eq!(loaded.left, config.left);
+        assert_eq!(loaded.middle, config.middle);
+        assert_eq!(loaded.right, config.right);
     }
 
-    fn doctor_check_profiles(&self) -> JsonDoctorCheck {
-        if !self.json_output {
-            self.console.print("[bold cyan]Profiles:[/]");
-        }
+    #[test]
+    fn pedal_config_roundtrip_keeps_metadata() {
+        let config = PedalConfig {
+            left: "cmd+c".to_string(),
+            middle: "cmd+a".to_string(),
+            right: "cmd+v".to_string(),
+            details: [
+                PedalDetails {
+                    label: Some("Copy".to_string()),
+                    notes: Some("Transcription".to_string()),
+                },
+                PedalDetails::default(),
+                PedalDetails {
+                    label: Some("Paste".to_string()),
+                    notes: None,
+                },
+            ],
+            device: Some(DeviceBinding {
+                serial: None,
+                unit_id: Some(2),
+            }),
+            last_method: Some("feat-rid0-cmd".to_string()),
+            target_os: Some(TargetOs::Windows),
+        };
 
-        let dir = profiles_dir();
+        let content = config.serialize().unwrap();
+        assert!(content.contains("\"version\": 1"));
 
-        if dir.exists() {
-            // Count profiles
-            let count = fs::read_dir(&dir)
-                .map(|entries| {
-                    entries
-                        .filter_map(|e| e.ok())
-                        .filter(|e| e.path().extension().is_some_and(|ext| ext == "conf"))
-                        .count()
-                })
-                .unwrap_or(0);
+        let loaded = PedalConfig::parse(&content).unwrap();
+        assert_eq!(loaded.left, "cmd+c");
+        assert!(loaded.details == config.details);
+        assert!(loaded.device == config.device);
+        assert_eq!(loaded.last_method.as_deref(), Some("feat-rid0-cmd"));
+        assert!(content.contains("\"target_os\": \"windows\""));
+        assert_eq!(loaded.target_os, Some(TargetOs::Windows));
+    }
 
-            if !self.json_output {
-                self.console.print(&format!(
-                    "  [bold #2ecc71]✓[/] Profiles directory exists ({} profile{})",
-                    count,
-                    if count == 1 { "" } else { "s" }
-                ));
-                self.console
-                    .print(&format!("    [dim]Path: {}[/]", dir.display()));
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "profiles".to_string(),
-                status: "pass".to_string(),
-                message: format!(
-                    "Profiles directory exists ({} profile{})",
-                    count,
-                    if count == 1 { "" } else { "s" }
-                ),
-                details: Some(dir.display().to_string()),
-                suggestions: vec![],
-            }
-        } else {
-            if !self.json_output {
-                self.console.print(
-                    "  [bold #f39c12]⚠[/] Profiles directory not found (OK for first-time use)",
-                );
-                self.console
-                    .print(&format!("    [dim]Path: {}[/]", dir.display()));
-                self.console
-                    .print("    [dim]→ Run 'savant config save <name>' to create a profile[/]");
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "profiles".to_string(),
-                status: "warn".to_string(),
-                message: "Profiles directory not found (OK for first-time use)".to_string(),
-                details: Some(dir.display().to_string()),
-                suggestions: vec!["Run 'savant config save <name>' to create a profile".to_string()],
-            }
-        }
+    #[test]
+    fn pedal_config_parses_legacy_format() {
+        let content = "left=cmd+c\nmiddle=cmd+a\nright=cmd+v\n";
+        assert!(PedalConfig::is_legacy(content));
+        let config = PedalConfig::parse(content).unwrap();
+        assert_eq!(config.middle, "cmd+a");
+        assert!(config.device.is_none());
     }
 
-    fn doctor_check_input_monitoring(&self) -> JsonDoctorCheck {
-        if !self.json_output {
-            self.console.print("[bold cyan]Permissions:[/]");
-        }
+    #[test]
+    fn pedal_config_versioned_rejects_unknown_fields() {
+        let content = r#"{"version": 1, "pedals": {
+            "left": {"action": "cmd+c"},
+            "middle": {"action": "cmd+a", "colour": "red"},
+            "right": {"action": "cmd+v"}}}"#;
+        assert!(PedalConfig::parse_versioned(content).is_err());
+    }
 
-        // Try to initialize HID API and open a device to check permissions
-        // This is a heuristic - if we can enumerate HID devices, permissions are likely OK
-        let hid_api = match HidApi::new() {
-            Ok(api) => api,
-            Err(e) => {
-                let err_str = e.to_string().to_lowercase();
-                let is_permission_error = err_str.contains("privilege")
-                    || err_str.contains("permission")
-                    || err_str.contains("access");
+    #[test]
+    fn pedal_config_versioned_rejects_newer_version() {
+        let content = r#"{"version": 99, "pedals": {}}"#;
+        let Err(err) = PedalConfig::parse_versioned(content) else {
+            panic!("version 99 should be rejected");
+        };
+        assert!(err.to_string().contains("Unsupported config version 99"));
+    }
 
-                if is_permission_error {
-                    if !self.json_output {
-                        self.console.print(
-                            "  [bold #e74c3c]✗[/] Input Monitoring permission may be required",
-                        );
-                        self.console.print("    [dim]→ Open System Settings → Privacy & Security → Input Monitoring[/]");
-                        self.console
-                            .print("    [dim]→ Add your terminal app (Terminal, iTerm2, etc.)[/]");
-                        self.console.print("    [dim]→ Restart your terminal[/]");
-                        self.console.print("");
-                    }
-                    return JsonDoctorCheck {
-                        name: "permissions".to_string(),
-                        status: "fail".to_string(),
-                        message: "Input Monitoring permission may be required".to_string(),
-                        details: Some(e.to_string()),
-                        suggestions: vec![
-                            "Open System Settings → Privacy & Security → Input Monitoring"
-                                .to_string(),
-                            "Add your terminal app (Terminal, iTerm2, etc.)".to_string(),
-                            "Restart your terminal".to_string(),
-                        ],
-                    };
-                }
+    #[test]
+    fn check_versioned_config_reports_each_bad_pedal() {
+        let content = r#"{"version": 1, "pedals": {
+            "left": {"action": "cmd+nope"},
+            "middle": {"action": "cmd+a"}}}"#;
+        let result = check_versioned_config(content, TargetOs::Macos);
+        assert_eq!(result.errors.len(), 2);
+        assert!(result.parsed[1].is_some());
+        assert_eq!(result.errors[0].field.as_deref(), Some("left"));
+        assert!(result.errors[1].error.contains("pedals.right.action"));
+    }
 
-                if !self.json_output {
-                    self.console.print(&format!(
-                        "  [bold #f39c12]⚠[/] Could not initialize HID API: {}",
-                        e
-                    ));
-                    self.console.print("");
-                }
-                return JsonDoctorCheck {
-                    name: "permissions".to_string(),
-                    status: "warn".to_string(),
-                    message: format!("Could not initialize HID API: {}", e),
-                    details: None,
-                    suggestions: vec![],
-                };
-            }
-        };
+    #[test]
+    fn check_versioned_config_reports_json_syntax_line() {
+        let result = check_versioned_config("{\n\"version\": 1,\n}", TargetOs::Macos);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].line, Some(3));
+    }
 
-        // Check if we can enumerate devices with our VID
-        let devices: Vec<_> = hid_api
-            .device_list()
-            .filter(|d| d.vendor_id() == KINESIS_VID)
-            .collect();
+    #[test]
+    fn check_legacy_config_flags_missing_pedal() {
+        let result = check_legacy_config("left=cmd+c\nmiddle=cmd+a\nextra=1\n", TargetOs::Macos);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].field.as_deref(), Some("right"));
+        assert_eq!(result.warnings.len(), 1);
+    }
 
-        if !devices.is_empty() {
-            if !self.json_output {
-                self.console
-                    .print("  [bold #2ecc71]✓[/] HID API accessible (Input Monitoring OK)");
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "permissions".to_string(),
-                status: "pass".to_string(),
-                message: "HID API accessible (Input Monitoring OK)".to_string(),
-                details: None,
-                suggestions: vec![],
-            }
-        } else {
-            // Device not found via HID, but we already checked via libusb
-            // This might just mean device is in program mode (not HID)
-            if !self.json_output {
-                self.console
-                    .print("  [bold #2ecc71]✓[/] HID API initialized successfully");
-                self.console
-                    .print("    [dim]Note: Device monitoring requires device in PLAY mode[/]");
-                self.console.print("");
-            }
-            JsonDoctorCheck {
-                name: "permissions".to_string(),
-                status: "pass".to_string(),
-                message: "HID API initialized successfully".to_string(),
-                details: Some("Device monitoring requires device in PLAY mode".to_string()),
-                suggestions: vec![],
-            }
-        }
+    #[test]
+    fn write_atomic_replaces_file_without_leaving_temp_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("nested").join("pedals.conf");
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        let entries: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
     }
-}
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let timeout_ms = cli.timeout.unwrap_or(DEFAULT_USB_TIMEOUT_MS);
-    let savant = SavantElite::new(cli.verbose, cli.json, timeout_ms)?;
+    #[test]
+    fn file_lock_is_exclusive_until_dropped() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(".lock");
 
-    if cli.verbose {
-        eprintln!("[verbose] Verbose mode enabled");
-        eprintln!("[verbose] USB timeout: {}ms", timeout_ms);
+        let held = FileLock::acquire(&path, Duration::ZERO).unwrap();
+        assert!(FileLock::acquire(&path, Duration::from_millis(200)).is_err());
+
+        drop(held);
+        assert!(FileLock::acquire(&path, Duration::ZERO).is_ok());
     }
-    if cli.json {
-        savant.verbose("JSON output mode enabled");
+
+    #[test]
+    fn preset_catalog_layers_user_over_system_over_built_in() {
+        let system = tempfile::tempdir().unwrap();
+        let user = tempfile::tempdir().unwrap();
+        fs::write(
+            system.path().join("daw.json"),
+            r#"{"left": "r", "middle": "space", "right": "enter"}"#,
+        )
+        .unwrap();
+        fs::write(
+            system.path().join("team-zoom.json"),
+            r#"{"name": "zoom", "left": "f1", "middle": "f2", "right": "f3"}"#,
+        )
+        .unwrap();
+        fs::write(
+            user.path().join("daw.json"),
+            r#"{"left": "f5", "middle": "space", "right": "enter"}"#,
+        )
+        .unwrap();
+
+        let catalog = PresetCatalog::load_from(system.path(), user.path(), TargetOs::Linux);
+        assert!(catalog.problems.is_empty());
+        assert_eq!(catalog.presets.len(), PRESETS.len() + 1);
+
+        let daw = catalog.find("DAW").unwrap();
+        assert_eq!(daw.left, "f5");
+        assert_eq!(daw.source.label(), "user");
+        let zoom = catalog.find("zoom").unwrap();
+        assert_eq!(zoom.left, "f1");
+        assert_eq!(zoom.source.label(), "system");
+        assert_eq!(
+            catalog.find("copy-paste").unwrap().source.label(),
+            "built-in"
+        );
     }
 
-    match cli.command {
-        Commands::Info => {
-            savant.find_device()?;
-        }
-        Commands::Monitor { duration } => {
-            savant.monitor(duration)?;
-        }
-        Commands::Probe => {
-            savant.probe()?;
-        }
-        Commands::RawCmd {
-            cmd,
-            data,
-            interface,
-        } => {
-            savant.raw_cmd(&cmd, &data, interface)?;
-        }
-        Commands::Status => {
-            savant.status()?;
-        }
-        Commands::Program {
-            left,
-            middle,
-            right,
-            dry_run,
-            monitor,
-        } => {
-            savant.program(&left, &middle, &right, dry_run, monitor)?;
-        }
-        Commands::Keys { json } => {
-            savant.list_keys(json)?;
-        }
-        Commands::Completions { shell } => {
-            let mut cmd = Cli::command();
-            let name = cmd.get_name().to_string();
-            generate(shell, &mut cmd, name, &mut std::io::stdout());
-        }
-        Commands::Preset {
-            name,
-            list,
-            show,
-            dry_run,
-        } => {
-            savant.preset(name.as_deref(), list, show, dry_run)?;
-        }
-        Commands::Config { command } => {
-            savant.config(command)?;
-        }
-        Commands::Doctor => {
-            savant.doctor()?;
+    #[test]
+    fn preset_catalog_reports_invalid_and_duplicate_files() {
+        let system = tempfile::tempdir().unwrap();
+        let user = tempfile::tempdir().unwrap();
+        fs::write(
+            user.path().join("a.json"),
+            r#"{"name": "dup", "left": "f1", "middle": "f2", "right": "f3"}"#,
+        )
+        .unwrap();
+        fs::write(
+            user.path().join("b.json"),
+            r#"{"name": "dup", "left": "f1", "middle": "f2", "right": "f3"}"#,
+        )
+        .unwrap();
+        fs::write(
+            user.path().join("check.json"),
+            r#"{"left": "cmd+bogus", "middle": "f2", "right": "f3"}"#,
+        )
+        .unwrap();
+
+        let catalog = PresetCatalog::load_from(system.path(), user.path(), TargetOs::Linux);
+        assert_eq!(
+            catalog.find("dup").unwrap().source.path(),
+            Some(user.path().join("a.json").as_path())
+        );
+        assert_eq!(catalog.problems.len(), 2);
+        assert!(catalog.problems[0].1[0].contains("Duplicate preset name"));
+        // Reserved name and the bad action are both reported
+        assert_eq!(catalog.problems[1].1.len(), 2);
+    }
+
+    fn bundle(profiles: &[(&str, &str)]) -> String {
+        let profiles: Vec<String> = profiles
+            .iter()
+            .map(|(name, left)| {
+                format!(
+                    r#"{{"name": "{}", "config": {{"version": 1, "pedals": {{"left": {{"action": "{}"}}, "middle": {{"action": "f2"}}, "right": {{"action": "f3"}}}}}}}}"#,
+                    name, left
+                )
+            })
+            .collect();
+        format!(r#"{{"version": 1, "profiles": [{}]}}"#, profiles.join(", "))
+    }
+
+    #[test]
+    fn plan_import_resolves_conflicts() {
+        let content = bundle(&[("work", "f1"), ("home", "f4")]);
+        let exists = |name: &str| name == "work" || name == "work-2";
+
+        let Err(err) = plan_import(&content, exists, ImportConflict::Fail) else {
+            panic!("conflicting import should fail");
+        };
+        assert!(err
+            .to_string()
+            .contains("'work': a profile with this name already exists"));
+
+        let steps = plan_import(&content, exists, ImportConflict::Rename).unwrap();
+        assert_eq!(steps[0].name, "work-3");
+        assert_eq!(steps[0].action, ImportAction::Rename);
+        assert_eq!(steps[1].name, "home");
+        assert_eq!(steps[1].action, ImportAction::Create);
+
+        let steps = plan_import(&content, exists, ImportConflict::Skip).unwrap();
+        assert_eq!(steps[0].action, ImportAction::Skip);
+        let steps = plan_import(&content, exists, ImportConflict::Overwrite).unwrap();
+        assert_eq!(steps[0].action, ImportAction::Overwrite);
+    }
+
+    #[test]
+    fn tar_archive_writes_valid_ustar_headers() {
+        let mut archive = Vec::new();
+        tar_archive::append(&mut archive, "bundle/doctor.json", b"{}\n", 1_700_000_000).unwrap();
+        let long = format!("bundle/config/profiles/{}.conf", "p".repeat(90));
+        tar_archive::append(&mut archive, &long, b"x", 0).unwrap();
+        tar_archive::finish(&mut archive);
+        assert_eq!(archive.len(), 512 * 6);
+
+        let header = &archive[..512];
+        assert!(header.starts_with(b"bundle/doctor.json\0"));
+        assert_eq!(&header[124..136], b"00000000003\0");
+        assert_eq!(&header[257..263], b"ustar\0");
+        let stored =
+            u32::from_str_radix(std::str::from_utf8(&header[148..154]).unwrap(), 8).unwrap();
+        let sum: u32 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    b' ' as u32
+                } else {
+                    b as u32
+                }
+            })
+            .sum();
+        assert_eq!(stored, sum);
+        assert_eq!(&archive[512..515], b"{}\n");
+
+        // Paths over 100 bytes are split into prefix and name
+        let header = &archive[1024..1536];
+        assert!(header.starts_with(format!("{}.conf\0", "p".repeat(90)).as_bytes()));
+        assert!(header[345..].starts_with(b"bundle/config/profiles\0"));
+    }
+
+    #[test]
+    fn tar_archive_rejects_paths_that_cannot_be_split() {
+        // Byte 100 falls inside a multibyte character and there's no `/` to split at
+        let long = format!("bundle/{}", "é".repeat(60));
+        let mut archive = Vec::new();
+        assert!(tar_archive::append(&mut archive, &long, b"x", 0).is_err());
+        assert!(archive.is_empty());
+    }
+
+    #[test]
+    fn redactor_hides_home_and_username() {
+        let redactor = Redactor::new(Some("/home/alice".to_string()), Some("alice".to_string()));
+        assert_eq!(
+            redactor.redact("/home/alice/.config/savant-elite/pedals.conf"),
+            "~/.config/savant-elite/pedals.conf"
+        );
+        assert_eq!(
+            redactor.redact("owner alice, not malice or alice_b"),
+            "owner <user>, not malice or alice_b"
+        );
+        // Too short to redact safely
+        assert_eq!(
+            Redactor::new(Some("/".to_string()), Some("a".to_string())).redact("/a b"),
+            "/a b"
+        );
+    }
+
+    #[test]
+    fn operation_log_rotates_by_size_and_reads_oldest_first() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log = OperationLog {
+            path: temp_dir.path().join("savant.log"),
+            max_bytes: 300,
+        };
+        for i in 0..40 {
+            log.append(LogEvent::Transfer, &format!("attempt {}", i));
         }
+
+        assert!(log.rotated_path(LOG_ROTATIONS).exists());
+        assert!(!log.rotated_path(LOG_ROTATIONS + 1).exists());
+        let records = log.read();
+        assert!(records.len() < 40);
+        assert_eq!(records.last().unwrap().message, "attempt 39");
+        let numbers: Vec<usize> = records
+            .iter()
+            .map(|r| r.message["attempt ".len()..].parse().unwrap())
+            .collect();
+        assert!(numbers.windows(2).all(|w| w[1] == w[0] + 1));
     }
 
-    Ok(())
-}
+    #[test]
+    fn device_session_caches_usb_enumeration_until_refreshed() {
+        let session = DeviceSession::default();
+        let Ok(first) = session.usb_devices() else {
+            return; // No usable libusb backend here
+        };
+        assert!(session.usb.borrow().is_some());
+        assert_eq!(session.usb_devices().unwrap().len(), first.len());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        session.refresh_usb();
+        assert!(session.usb.borrow().is_none());
+        assert!(session.usb_devices().is_ok());
+    }
 
     #[test]
-    fn parse_key_action_cmd_c() {
-        let action = KeyAction::from_string("cmd+c").unwrap();
-        assert_eq!(action.modifiers, usb_hid::MOD_LEFT_GUI);
-        assert_eq!(action.key, usb_hid::KEY_C);
+    fn interrupt_guard_records_a_signal_instead_of_exiting() {
+        let guard = InterruptGuard::install();
+        assert!(!guard.interrupted());
+        signal_hook::low_level::raise(signal_hook::consts::SIGTERM).unwrap();
+        assert!(guard.interrupted());
+        drop(guard);
+
+        // A fresh guard starts clear
+        let guard = InterruptGuard::install();
+        assert!(!guard.interrupted());
     }
 
     #[test]
-    fn parse_key_action_multi_modifiers() {
-        let action = KeyAction::from_string("ctrl+shift+alt+f12").unwrap();
+    fn interrupt_report_puts_command_first_and_pads_to_packet_size() {
+        let report = interrupt_report(xkeys_protocol::CMD_SET_KEY_MACRO, &[1, 0x08, 0x06], 36);
+        assert_eq!(report.len(), 36);
+        assert_eq!(&report[..5], &[0xCC, 1, 0x08, 0x06, 0]);
+        assert!(report[4..].iter().all(|&b| b == 0));
+
+        // Tiny (or unreported) packet sizes still get an 8-byte report
         assert_eq!(
-            action.modifiers,
-            usb_hid::MOD_LEFT_CTRL | usb_hid::MOD_LEFT_SHIFT | usb_hid::MOD_LEFT_ALT
+            interrupt_report(xkeys_protocol::CMD_SAVE_TO_EEPROM, &[], 0),
+            vec![0xCE, 0, 0, 0, 0, 0, 0, 0]
         );
-        assert_eq!(action.key, usb_hid::KEY_F12);
     }
 
     #[test]
-    fn parse_key_action_aliases() {
-        let a1 = KeyAction::from_string("option+a").unwrap();
-        let a2 = KeyAction::from_string("opt+a").unwrap();
-        let a3 = KeyAction::from_string("alt+a").unwrap();
+    fn retry_policy_limits_attempts_kinds_and_doubles_backoff() {
+        let policy = RetryPolicy::default();
+        assert!(!policy.should_retry(&rusb::Error::Pipe, 1));
+        assert!(policy.should_retry(&rusb::Error::Timeout, 2));
+        assert!(!policy.should_retry(&rusb::Error::Timeout, 3));
+        assert!(!policy.should_retry(&rusb::Error::NoDevice, 1));
+        assert_eq!(policy.delay(1), Duration::from_millis(20));
+        assert_eq!(policy.delay(3), Duration::from_millis(80));
+
+        let timeouts_only = RetryPolicy {
+            retry_on: vec![RetryKind::Timeout],
+            ..RetryPolicy::default()
+        };
+        assert!(!timeouts_only.should_retry(&rusb::Error::Pipe, 1));
+        let with_pipe = RetryPolicy {
+            retry_on: vec![RetryKind::Pipe],
+            ..RetryPolicy::default()
+        };
+        assert!(with_pipe.should_retry(&rusb::Error::Pipe, 1));
+        let off = RetryPolicy {
+            retries: 0,
+            ..RetryPolicy::default()
+        };
+        assert!(!off.should_retry(&rusb::Error::Timeout, 1));
+        assert_eq!(off.describe(), "off");
+    }
 
-        assert_eq!(a1.modifiers, usb_hid::MOD_LEFT_ALT);
-        assert_eq!(a2.modifiers, usb_hid::MOD_LEFT_ALT);
-        assert_eq!(a3.modifiers, usb_hid::MOD_LEFT_ALT);
+    #[test]
+    fn salvage_recovers_actions_from_a_rejected_config() {
+        let extra_field = r#"{"version": 1, "pedals": {"left": {"action": "cmd+c"}, "middle": {"action": "cmd+a"}, "right": {"action": "cmd+v"}}, "colour": "red"}"#;
+        assert!(PedalConfig::parse(extra_field).is_none());
+        let config = PedalConfig::salvage(extra_field).unwrap();
+        assert_eq!(
+            (config.left.as_str(), config.right.as_str()),
+            ("cmd+c", "cmd+v")
+        );
 
-        assert_eq!(a1.key, usb_hid::KEY_A);
-        assert_eq!(a2.key, usb_hid::KEY_A);
-        assert_eq!(a3.key, usb_hid::KEY_A);
+        // Missing or invalid pedals, and newer schema versions, can't be salvaged
+        assert!(PedalConfig::salvage("left=cmd+c\nmiddle=cmd+a\n").is_none());
+        assert!(PedalConfig::salvage("left=cmd+c\nmiddle=cmd+a\nright=cmd+qq\n").is_none());
+        assert!(
+            PedalConfig::salvage(&extra_field.replace("\"version\": 1", "\"version\": 9"))
+                .is_none()
+        );
     }
 
     #[test]
-    fn parse_key_action_rejects_unknown_modifier() {
-        let err = KeyAction::from_string("hyper+a").unwrap_err();
-        assert!(err.to_string().to_lowercase().contains("unknown modifier"));
+    fn plan_doctor_fixes_covers_dirs_config_history_and_udev() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_dir = temp_dir.path().join("savant-elite");
+        let rules_dir = temp_dir.path().join("rules.d");
+        let now = chrono::Utc::now();
+
+        let steps = plan_doctor_fixes(&config_dir, Some(std::slice::from_ref(&rules_dir)), now);
+        let actions: Vec<&str> = steps.iter().map(|s| s.action.name()).collect();
+        assert_eq!(actions, ["create_dir", "create_dir", "create_dir", "write"]);
+        assert!(plan_doctor_fixes(&config_dir, None, now).len() == 3);
+
+        fs::create_dir_all(config_dir.join("history")).unwrap();
+        fs::create_dir_all(config_dir.join("profiles")).unwrap();
+        fs::create_dir_all(&rules_dir).unwrap();
+        fs::write(
+            rules_dir.join("70-savant-elite.rules"),
+            linux_access::udev_rules("plugdev"),
+        )
+        .unwrap();
+        fs::write(config_dir.join("pedals.conf"), "left=cmd+c\n").unwrap();
+        fs::write(config_dir.join("history/bad.json"), "{").unwrap();
+        let raw = HistoryFile {
+            timestamp: now.to_rfc3339(),
+            meta: HistoryMeta::command("program"),
+            config: HistoryConfig::Raw("not a config".to_string()),
+        };
+        fs::write(
+            config_dir.join("history/raw.json"),
+            serde_json::to_string(&raw).unwrap(),
+        )
+        .unwrap();
+
+        let steps = plan_doctor_fixes(&config_dir, Some(&[rules_dir]), now);
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(&steps[0].action, DoctorFixAction::Move(to)
+            if to.to_string_lossy().contains("pedals.conf.broken-")));
+        assert_eq!(steps[1].action, DoctorFixAction::Remove);
+        assert!(steps[1].path.ends_with("bad.json"));
+
+        fs::write(
+            config_dir.join("pedals.conf"),
+            "left=f1\nmiddle=f2\nright=f3\n",
+        )
+        .unwrap();
+        let steps = plan_doctor_fixes(&config_dir, None, now);
+        assert_eq!(
+            steps[0].action,
+            DoctorFixAction::Backup(config_dir.join("pedals.conf.legacy"))
+        );
+        assert!(
+            matches!(&steps[1].action, DoctorFixAction::Write(c) if c.contains("\"version\": 1"))
+        );
     }
 
     #[test]
-    fn parse_key_action_rejects_unknown_key() {
-        let err = KeyAction::from_string("cmd+notakey").unwrap_err();
-        assert!(err.to_string().to_lowercase().contains("unknown key"));
+    fn plan_import_rejects_whole_bundle_on_any_problem() {
+        let content = bundle(&[
+            ("ok", "f1"),
+            ("bad", "cmd+bogus"),
+            ("ok", "f1"),
+            ("../x", "f1"),
+        ]);
+        let Err(err) = plan_import(&content, |_| false, ImportConflict::Overwrite) else {
+            panic!("invalid bundle should fail");
+        };
+        let message = err.to_string();
+        assert!(message.contains("3 problem(s)"));
+        assert!(message.contains("'bad': left pedal"));
+        assert!(message.contains("more than once"));
+        assert!(message.contains("'../x'"));
+
+        let newer = r#"{"version": 2, "profiles": []}"#;
+        let Err(err) = plan_import(newer, |_| false, ImportConflict::Fail) else {
+            panic!("newer bundle version should fail");
+        };
+        assert!(err.to_string().contains("Unsupported bundle version 2"));
     }
 
     #[test]
-    fn parse_key_action_rejects_empty() {
-        let err = KeyAction::from_string("").unwrap_err();
-        assert!(err.to_string().contains("cannot be empty"));
+    fn history_entry_reads_metadata_and_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("20260102T030405.678Z.json");
+        let entry = HistoryFile {
+            timestamp: "2026-01-02T03:04:05.678Z".to_string(),
+            meta: HistoryMeta {
+                command: "preset browser".to_string(),
+                device: Some(HistoryDevice {
+                    serial: None,
+                    revision: Some("1.0.0".to_string()),
+                }),
+                method: Some("feat-rid0-cmd".to_string()),
+                success: Some(true),
+                retries: None,
+                interrupted: None,
+            },
+            config: HistoryConfig::Versioned(Box::new(saved_config().to_file())),
+        };
+        fs::write(&path, serde_json::to_string(&entry).unwrap()).unwrap();
+
+        let read = HistoryEntry::read(&path);
+        let meta = read.meta.unwrap();
+        assert_eq!(meta.command, "preset browser");
+        assert_eq!(meta.success, Some(true));
+        assert_eq!(read.config.unwrap().left, "cmd+z");
+        assert_eq!(
+            read.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "2026-01-02T03:04:05.678Z"
+        );
     }
 
     #[test]
-    fn parse_key_action_rejects_whitespace_only() {
-        let err = KeyAction::from_string("   ").unwrap_err();
-        assert!(err.to_string().contains("cannot be empty"));
+    fn history_entry_keeps_unparseable_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let legacy = temp_dir.path().join("2025-06-01_120000.conf");
+        fs::write(&legacy, "left=cmd+c\nmiddle=cmd+a\nright=cmd+v\n").unwrap();
+        let renamed = temp_dir.path().join("copy of backup.conf");
+        fs::write(&renamed, "left=cmd+c\n").unwrap();
+
+        let legacy = HistoryEntry::read(&legacy);
+        assert!(legacy.meta.is_none());
+        assert_eq!(legacy.config.unwrap().right, "cmd+v");
+
+        // Falls back to the file's mtime instead of being dropped
+        let renamed = HistoryEntry::read(&renamed);
+        assert!(renamed.config.is_none());
+        assert!(renamed.timestamp > chrono::DateTime::<chrono::Utc>::default());
     }
 
     #[test]
-    fn parse_key_action_rejects_leading_plus() {
-        let err = KeyAction::from_string("+c").unwrap_err();
-        assert!(err.to_string().contains("cannot start or end with"));
+    fn history_entries_in_the_same_millisecond_sort_by_counter() {
+        let stem = "20250601T120000.123Z";
+        let entry = |name: String| HistoryEntry {
+            path: PathBuf::from(name),
+            timestamp: chrono::DateTime::parse_from_rfc3339("2025-06-01T12:00:00.123Z")
+                .unwrap()
+                .to_utc(),
+            meta: None,
+            config: None,
+        };
+        let mut entries: Vec<HistoryEntry> = ["", "-1", "-2", "-10"]
+            .iter()
+            .map(|suffix| entry(format!("{}{}.json", stem, suffix)))
+            .collect();
+        entries.push(entry("20250601T115959.999Z-3.json".to_string()));
+        entries[4].timestamp -= chrono::Duration::milliseconds(124);
+        entries.sort_by(HistoryEntry::newest_first);
+
+        let names: Vec<String> = entries
+            .iter()
+            .map(|e| e.path.display().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                format!("{}-10.json", stem),
+                format!("{}-2.json", stem),
+                format!("{}-1.json", stem),
+                format!("{}.json", stem),
+                "20250601T115959.999Z-3.json".to_string(),
+            ]
+        );
     }
 
     #[test]
-    fn parse_key_action_rejects_trailing_plus() {
-        let err = KeyAction::from_string("cmd+").unwrap_err();
-        assert!(err.to_string().contains("cannot start or end with"));
+    fn history_step_undo_walks_back_past_undone_entries() {
+        // S0 -> S1 -> S2, then undo: live is S1
+        let history = [Some("undo"), Some("program"), None];
+        assert_eq!(history_step_target(history, HistoryStep::Undo), Some(2));
+        assert_eq!(history_step_target(history, HistoryStep::Redo), Some(0));
+
+        // ...then redo (live S2) and undo again (live S1)
+        let history = [
+            Some("undo"),
+            Some("redo"),
+            Some("undo"),
+            Some("program"),
+            None,
+        ];
+        assert_eq!(history_step_target(history, HistoryStep::Undo), Some(4));
+        assert_eq!(history_step_target(history, HistoryStep::Redo), Some(0));
     }
 
     #[test]
-    fn parse_key_action_rejects_just_plus() {
-        let err = KeyAction::from_string("+").unwrap_err();
-        assert!(err.to_string().contains("cannot start or end with"));
+    fn history_step_redo_consumes_undo_entries_in_order() {
+        // S0 -> S1 -> S2, undo twice (live S0): redo goes to S1, then S2
+        let history = [Some("undo"), Some("undo"), Some("program"), None];
+        assert_eq!(history_step_target(history, HistoryStep::Redo), Some(0));
+        let history = [Some("redo"), Some("undo"), Some("undo"), Some("program")];
+        assert_eq!(history_step_target(history, HistoryStep::Redo), Some(2));
+        assert_eq!(history_step_target(history, HistoryStep::Undo), Some(0));
     }
 
     #[test]
-    fn parse_key_action_rejects_double_plus() {
-        let err = KeyAction::from_string("cmd++c").unwrap_err();
-        assert!(err.to_string().contains("consecutive"));
+    fn history_step_redo_is_cleared_by_new_changes() {
+        let history = [Some("preset browser"), Some("undo"), Some("program")];
+        assert_eq!(history_step_target(history, HistoryStep::Redo), None);
+        assert_eq!(history_step_target(history, HistoryStep::Undo), Some(0));
+        assert_eq!(history_step_target([], HistoryStep::Undo), None);
     }
 
     #[test]
-    fn parse_key_name_punctuation() {
-        assert_eq!(usb_hid::parse_key_name("-"), Some(0x2D));
-        assert_eq!(usb_hid::parse_key_name("="), Some(0x2E));
-        assert_eq!(usb_hid::parse_key_name("escape"), Some(usb_hid::KEY_ESC));
+    fn history_step_undo_after_interrupted_unsaved_program() {
+        // S0 -> S1 via program, then another program run either completes or is interrupted
+        // before the EEPROM save; undo must step back from whatever the pedal holds
+        for (interrupted, eeprom_skipped, expected) in [(true, true, "S0"), (false, false, "S1")] {
+            let mut history = vec![(Some("program"), "S0")];
+            if program_updates_config(interrupted, eeprom_skipped) {
+                history.insert(0, (Some("program"), "S1"));
+            }
+            let target = history_step_target(history.iter().map(|e| e.0), HistoryStep::Undo);
+            assert_eq!(target.map(|i| history[i].1), Some(expected));
+        }
+        assert!(program_updates_config(true, false));
+    }
+
+    fn lint_codes(
+        actions: [&str; 3],
+        os: TargetOs,
+    ) -> Vec<(&'static str, &'static str, &'static str)> {
+        let actions = actions.map(|a| Some(KeyAction::from_string(a).unwrap()));
+        lint_pedals(&actions, os)
+            .iter()
+            .map(|f| (f.pedal.name(), f.code.name(), f.severity.name()))
+            .collect()
     }
 
     #[test]
-    fn pedal_config_rejects_newline_in_value() {
-        let config = PedalConfig {
-            left: "cmd+c\nright=evil".to_string(),
-            middle: "cmd+a".to_string(),
-            right: "cmd+v".to_string(),
+    fn lint_flags_destructive_chords_per_platform() {
+        let actions = ["cmd+q", "alt+f4", "ctrl+shift+z"];
+        assert_eq!(
+            lint_codes(actions, TargetOs::Macos),
+            [("left", "destructive", "error")]
+        );
+        assert_eq!(
+            lint_codes(actions, TargetOs::Windows),
+            [("middle", "destructive", "error")]
+        );
+        assert_eq!(
+            lint_codes(["ctrl+alt+del", "ctrl+alt+f2", "ctrl+c"], TargetOs::Linux),
+            [
+                ("left", "os-reserved", "error"),
+                ("middle", "os-reserved", "warning")
+            ]
+        );
+
+        // Right-hand modifiers (as read back from a device) count the same as left-hand ones
+        let right_hand = KeyAction {
+            modifiers: usb_hid::MOD_RIGHT_ALT,
+            key: usb_hid::KEY_F4,
         };
-        let err = config.save().unwrap_err();
-        assert!(err.to_string().contains("newline"));
+        let findings = lint_pedals(&[None, Some(right_hand), None], TargetOs::Windows);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].code, LintCode::Destructive);
     }
 
     #[test]
-    fn pedal_config_rejects_carriage_return_in_value() {
-        let config = PedalConfig {
-            left: "cmd+c".to_string(),
-            middle: "cmd+a\rright=evil".to_string(),
-            right: "cmd+v".to_string(),
-        };
-        let err = config.save().unwrap_err();
-        assert!(err.to_string().contains("newline"));
+    fn lint_flags_bare_keys_and_duplicates() {
+        assert_eq!(
+            lint_codes(["a", "backspace", "f5"], TargetOs::Macos),
+            [
+                ("left", "bare-key", "warning"),
+                ("middle", "destructive", "warning")
+            ]
+        );
+        assert_eq!(
+            lint_codes(["cmd+c", "command+C", "cmd+c"], TargetOs::Macos),
+            [
+                ("middle", "duplicate", "warning"),
+                ("right", "duplicate", "warning")
+            ]
+        );
     }
 
     #[test]
-    fn pedal_config_roundtrip() {
-        let config = PedalConfig {
-            left: "cmd+c".to_string(),
-            middle: "cmd+a".to_string(),
-            right: "cmd+v".to_string(),
-        };
+    fn udev_rules_cover_both_modes_and_are_recognised() {
+        let rules = linux_access::udev_rules("plugdev");
+        for pid in ["030c", "0232"] {
+            assert!(rules.contains(&format!("ATTRS{{idProduct}}==\"{}\"", pid)));
+        }
+        assert!(rules.contains("SUBSYSTEM==\"hidraw\""));
+        assert!(linux_access::rules_match_pedal(&rules));
+        assert_eq!(linux_access::rule_group(&rules).as_deref(), Some("plugdev"));
+        assert!(!linux_access::rules_match_pedal(
+            "# SUBSYSTEM==\"usb\", ATTRS{idVendor}==\"05f3\", ATTRS{idProduct}==\"030c\"\n"
+        ));
+        // A rule for some other Kinesis device isn't a pedal rule
+        let keyboard = "SUBSYSTEM==\"usb\", ATTRS{idVendor}==\"05f3\", ATTRS{idProduct}==\"0007\", GROUP=\"input\"\n";
+        assert!(!linux_access::rules_match_pedal(keyboard));
+        assert_eq!(linux_access::rule_group(keyboard), None);
+        assert_eq!(
+            linux_access::rule_group(&linux_access::udev_rules("dialout")).as_deref(),
+            Some("dialout")
+        );
 
-        let temp_dir = tempfile::tempdir().unwrap();
-        let path = temp_dir.path().join("roundtrip.conf");
-        config.save_to(&path).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("60-other.rules"), "# nothing here\n").unwrap();
+        assert!(linux_access::find_installed_rule(&[dir.path().to_path_buf()]).is_none());
+        let path = dir.path().join(linux_access::RULES_FILE_NAME);
+        fs::write(&path, &rules).unwrap();
+        assert_eq!(
+            linux_access::find_installed_rule(&[dir.path().to_path_buf()]),
+            Some(path)
+        );
+    }
 
-        let loaded = PedalConfig::load_from(&path).unwrap();
-        assert_eq!(loaded.left, config.left);
-        assert_eq!(loaded.middle, config.middle);
-        assert_eq!(loaded.right, config.right);
+    #[test]
+    fn linux_sysfs_lookups_find_the_pedal() {
+        let uevent = "DRIVER=hid-generic\nHID_ID=0003:000005F3:0000030C\nHID_NAME=Kinesis\n";
+        assert_eq!(linux_access::parse_hid_id(uevent), Some((0x05F3, 0x030C)));
+        assert_eq!(linux_access::parse_hid_id("DRIVER=x\n"), None);
+
+        let sys = tempfile::tempdir().unwrap();
+        for (node, id) in [
+            ("hidraw0", "000046D:0000C52B"),
+            ("hidraw3", "000005F3:0000030C"),
+            // Another X-keys product under the same vendor ID
+            ("hidraw5", "000005F3:00000405"),
+        ] {
+            let device = sys.path().join("hidraw").join(node).join("device");
+            fs::create_dir_all(&device).unwrap();
+            fs::write(device.join("uevent"), format!("HID_ID=0003:{}\n", id)).unwrap();
+        }
+        assert_eq!(
+            linux_access::hidraw_nodes(&sys.path().join("hidraw")),
+            [PathBuf::from("/dev/hidraw3")]
+        );
+
+        let devices = sys.path().join("usb");
+        let pedal = devices.join("1-2");
+        fs::create_dir_all(pedal.join("1-2:1.0")).unwrap();
+        fs::write(pedal.join("idVendor"), "05f3\n").unwrap();
+        fs::write(pedal.join("idProduct"), "0232\n").unwrap();
+        let xkeys = devices.join("1-3");
+        fs::create_dir_all(xkeys.join("1-3:1.0")).unwrap();
+        fs::write(xkeys.join("idVendor"), "05f3\n").unwrap();
+        fs::write(xkeys.join("idProduct"), "0405\n").unwrap();
+        let drivers = linux_access::interface_drivers(&devices);
+        assert_eq!(drivers.len(), 1);
+        assert_eq!(drivers[0].interface, "1-2:1.0");
+        assert_eq!(drivers[0].product_id, PROGRAMMING_PID);
+        assert!(drivers[0].driver.is_none());
+    }
+
+    #[test]
+    fn linux_group_lookup_maps_ids_to_names() {
+        let status = "Name:\tsavant\nUid:\t1000\t1000\t1000\t1000\nGroups:\t4 24 46 1000 \n";
+        assert_eq!(linux_access::process_uid(status), Some(1000));
+        let gids = linux_access::process_groups(status);
+        assert_eq!(gids, [4, 24, 46, 1000]);
+        let groups = "root:x:0:\nadm:x:4:alice\nplugdev:x:46:alice\nalice:x:1000:\n";
+        assert_eq!(
+            linux_access::group_names(&gids, groups),
+            ["adm", "plugdev", "alice"]
+        );
+    }
+
+    #[test]
+    fn diff_configs_treats_equivalent_spellings_as_unchanged() {
+        let a = saved_config();
+        let b = PedalConfig {
+            left: "command+Z".to_string(),
+            right: "cmd+v".to_string(),
+            ..saved_config()
+        };
+        let diff = diff_configs(&a, &b);
+        assert_eq!(
+            diff.iter().map(|p| p.changed).collect::<Vec<_>>(),
+            [false, false, true]
+        );
     }
 
     #[test]
@@ -4935,6 +12840,65 @@ mod tests {
         assert_eq!(loaded.right, "cmd+v");
     }
 
+    fn saved_config() -> PedalConfig {
+        PedalConfig {
+            left: "cmd+z".to_string(),
+            middle: "cmd+s".to_string(),
+            right: "shift+cmd+z".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolve_program_pedals_defaults_when_nothing_given() {
+        let resolved = resolve_program_pedals([None, None, None], &[], None).unwrap();
+        assert_eq!(resolved, DEFAULT_PEDAL_ACTIONS.map(|a| Some(a.to_string())));
+    }
+
+    #[test]
+    fn resolve_program_pedals_leaves_unmentioned_pedals_untouched() {
+        let resolved =
+            resolve_program_pedals([None, Some("f5".to_string()), None], &[], None).unwrap();
+        assert_eq!(resolved, [None, Some("f5".to_string()), None]);
+    }
+
+    #[test]
+    fn resolve_program_pedals_only_fills_from_saved_config() {
+        let saved = saved_config();
+        let resolved = resolve_program_pedals(
+            [Some("f1".to_string()), None, None],
+            &[PedalPosition::Left, PedalPosition::Right],
+            Some(&saved),
+        )
+        .unwrap();
+        assert_eq!(
+            resolved,
+            [
+                Some("f1".to_string()),
+                None,
+                Some("shift+cmd+z".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_program_pedals_only_requires_an_action() {
+        let err =
+            resolve_program_pedals([None, None, None], &[PedalPosition::Middle], None).unwrap_err();
+        assert!(err.to_string().contains("--middle"));
+    }
+
+    #[test]
+    fn resolve_program_pedals_rejects_action_outside_only() {
+        let err = resolve_program_pedals(
+            [None, None, Some("cmd+v".to_string())],
+            &[PedalPosition::Left],
+            Some(&saved_config()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not selected by --only"));
+    }
+
     #[test]
     fn normalize_boot_keyboard_report_too_short() {
         let data = [0u8; 7]; // Less than 8 bytes
@@ -4986,6 +12950,91 @@ mod tests {
         assert_eq!(report, [0, 0, usb_hid::KEY_A, 0, 0, 0, 0, 0]);
     }
 
+    #[test]
+    fn xkeys_command_layouts_match_report_id_naming() {
+        let cmd = xkeys_protocol::CMD_GENERATE_DATA;
+        let layouts = xkeys_command_layouts(cmd, &[]);
+        assert_eq!(layouts.len(), 12);
+        for (name, _, data) in &layouts {
+            if xkeys_protocol::layout_has_report_id(name) {
+                assert_eq!(&data[..2], &[0, cmd], "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_key_macro_report_accepts_echoed_command_and_pedal() {
+        let cmd = xkeys_protocol::CMD_GET_KEY_MACRO;
+        let data = [cmd, 1, usb_hid::MOD_LEFT_GUI, usb_hid::KEY_C, 0, 0, 0, 0];
+        assert_eq!(
+            xkeys_protocol::parse_key_macro_report(&data, 1),
+            Some((usb_hid::MOD_LEFT_GUI, usb_hid::KEY_C))
+        );
+        let data = [0, cmd, 2, 0, usb_hid::KEY_A, 0, 0, 0];
+        assert_eq!(
+            xkeys_protocol::parse_key_macro_report(&data, 2),
+            Some((0, usb_hid::KEY_A))
+        );
+        // Another pedal's answer doesn't count
+        assert_eq!(xkeys_protocol::parse_key_macro_report(&data, 0), None);
+    }
+
+    #[test]
+    fn parse_key_macro_report_rejects_reports_without_the_command() {
+        // Looked like [pedal 0, mod, key] to the old fallback format
+        let data = [0, usb_hid::MOD_LEFT_GUI, usb_hid::KEY_C, 0, 0, 0, 0, 0];
+        assert_eq!(xkeys_protocol::parse_key_macro_report(&data, 0), None);
+        assert_eq!(xkeys_protocol::parse_key_macro_report(&[0; 8], 0), None);
+        let short = [xkeys_protocol::CMD_GET_KEY_MACRO, 0, 0];
+        assert_eq!(xkeys_protocol::parse_key_macro_report(&short, 0), None);
+    }
+
+    #[test]
+    fn parse_unit_id_report_plain() {
+        let data = [7, xkeys_protocol::GENERATED_DATA_FLAG, 0, 0, 0, 0, 0, 0];
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, false), Some(7));
+    }
+
+    #[test]
+    fn parse_unit_id_report_prefixed() {
+        let data = [0, 7, xkeys_protocol::GENERATED_DATA_FLAG, 0, 0, 0, 0, 0];
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, true), Some(7));
+        // The layout fixes the position: a prefixed report is not read as unit 0
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, false), None);
+    }
+
+    #[test]
+    fn parse_unit_id_report_unit_zero() {
+        let data = [
+            0,
+            xkeys_protocol::GENERATED_DATA_FLAG | xkeys_protocol::PROGRAM_SWITCH_FLAG,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ];
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, false), Some(0));
+    }
+
+    #[test]
+    fn parse_unit_id_report_rejects_key_reports() {
+        // A plain boot keyboard report has no generated-data flag
+        let data = [usb_hid::MOD_LEFT_GUI, 0, usb_hid::KEY_C, 0, 0, 0, 0, 0];
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, false), None);
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, true), None);
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&[3], false), None);
+    }
+
+    #[test]
+    fn parse_unit_id_report_rejects_keyboard_report_in_either_layout() {
+        // [0, 0, KEY_C, ...] used to parse as unit ID 0 via the prefixed branch
+        let data = [0, 0, usb_hid::KEY_C, 0, 0, 0, 0, 0];
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, false), None);
+        assert_eq!(xkeys_protocol::parse_unit_id_report(&data, true), None);
+    }
+
     // ============================================================================
     // USB HID Key Code Coverage Tests (BEAD-107)
     // ============================================================================
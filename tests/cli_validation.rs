@@ -6,9 +6,34 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 
-/// Helper to get the savant command
+/// Helper to get the savant command. The operation log goes to the build's temp dir so
+/// test runs never touch the developer's real log.
 fn savant() -> Command {
-    Command::new(env!("CARGO_BIN_EXE_savant"))
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_savant"));
+    cmd.env(
+        "SAVANT_LOG_PATH",
+        std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("savant.log"),
+    );
+    cmd
+}
+
+/// Helper to run savant against an empty config directory instead of the user's own
+fn savant_in(home: &std::path::Path) -> Command {
+    let mut cmd = savant();
+    cmd.env_remove("SAVANT_LOG_PATH")
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home)
+        .env("XDG_STATE_HOME", home);
+    cmd
+}
+
+/// Where savant keeps its config when run through `savant_in(home)`
+fn savant_config_dir_in(home: &std::path::Path) -> std::path::PathBuf {
+    if cfg!(target_os = "macos") {
+        home.join("Library/Application Support/savant-elite")
+    } else {
+        home.join("savant-elite")
+    }
 }
 
 // ============================================================================
@@ -45,7 +70,8 @@ fn cli_shows_subcommand_help() {
         .stdout(predicate::str::contains("--left"))
         .stdout(predicate::str::contains("--middle"))
         .stdout(predicate::str::contains("--right"))
-        .stdout(predicate::str::contains("--dry-run"));
+        .stdout(predicate::str::contains("--dry-run"))
+        .stdout(predicate::str::contains("--reboot"));
 }
 
 // ============================================================================
@@ -61,6 +87,159 @@ fn cli_accepts_valid_single_key() {
         .success();
 }
 
+#[test]
+fn cli_program_leaves_unmentioned_pedals_unchanged() {
+    savant()
+        .args(["program", "--middle", "f5", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Leaving unchanged on the device"))
+        .stdout(predicate::str::contains("Left, Right"));
+}
+
+#[test]
+fn cli_program_only_rejects_conflicting_pedal() {
+    savant()
+        .args(["program", "--only", "left", "--right", "cmd+v", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not selected by --only"));
+}
+
+#[test]
+fn cli_program_only_rejects_unknown_pedal() {
+    savant()
+        .args(["program", "--only", "center", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+#[test]
+fn cli_program_only_is_repeatable() {
+    savant()
+        .args([
+            "program",
+            "--only",
+            "left",
+            "--only",
+            "right",
+            "--left",
+            "f1",
+            "--right",
+            "f2",
+            "--dry-run",
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn cli_program_accepts_on_interrupt_choice() {
+    savant()
+        .args([
+            "program",
+            "--left",
+            "cmd+c",
+            "--on-interrupt",
+            "save",
+            "--dry-run",
+        ])
+        .assert()
+        .success();
+    savant()
+        .args([
+            "program",
+            "--left",
+            "cmd+c",
+            "--on-interrupt",
+            "later",
+            "--dry-run",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'later'"));
+}
+
+#[test]
+fn cli_program_verbose_shows_transport_and_interface() {
+    savant()
+        .args([
+            "--verbose",
+            "program",
+            "--left",
+            "cmd+c",
+            "--transport",
+            "interrupt",
+            "--interface",
+            "1",
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Transport: interrupt, interface 1",
+        ));
+}
+
+#[test]
+fn cli_program_rejects_unknown_transport() {
+    savant()
+        .args([
+            "program",
+            "--left",
+            "cmd+c",
+            "--transport",
+            "bulk",
+            "--dry-run",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'bulk'"));
+}
+
+#[test]
+fn cli_program_help_shows_force() {
+    savant()
+        .args(["program", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--force"))
+        .stdout(predicate::str::contains("already holds these keys"));
+}
+
+#[test]
+fn cli_program_json_keeps_progress_off_stdout() {
+    let output = savant()
+        .args(["--json", "program", "--left", "cmd+c", "--dry-run"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("PEDAL PROGRAMMING"))
+        .get_output()
+        .stdout
+        .clone();
+
+    let json: serde_json::Value = serde_json::from_slice(&output)
+        .expect("program --json --dry-run should print only the JSON result");
+    assert_eq!(json["dry_run"], true);
+    assert_eq!(json["eeprom"], "planned");
+    let pedals = json["pedals"].as_array().unwrap();
+    assert_eq!(pedals.len(), 3);
+    assert_eq!(pedals[0]["pedal"], "left");
+    assert_eq!(pedals[0]["action"], "cmd+c");
+    assert_eq!(pedals[0]["status"], "planned");
+    assert_eq!(pedals[1]["status"], "unchanged");
+    assert_eq!(pedals[2]["status"], "unchanged");
+}
+
+#[test]
+fn cli_program_accepts_reboot_with_dry_run() {
+    savant()
+        .args(["program", "--left", "cmd+c", "--reboot", "--dry-run"])
+        .assert()
+        .success();
+}
+
 #[test]
 fn cli_accepts_valid_modifier_key() {
     // Test modifier+key combo
@@ -685,7 +864,7 @@ fn cli_preset_list_json_is_valid() {
     assert!(presets.is_array(), "presets should be an array");
 
     let presets_arr = presets.as_array().unwrap();
-    assert!(presets_arr.len() >= 4, "should have at least 4 presets");
+    assert!(presets_arr.len() >= 5, "should have at least 5 presets");
 
     // Verify first preset has required fields
     let first = &presets_arr[0];
@@ -726,9 +905,9 @@ fn cli_preset_show_json_is_valid() {
         serde_json::from_slice(&output).expect("preset --show --json should produce valid JSON");
 
     assert_eq!(json.get("name").unwrap(), "copy-paste");
-    assert_eq!(json.get("left").unwrap(), "cmd+c");
-    assert_eq!(json.get("middle").unwrap(), "cmd+a");
-    assert_eq!(json.get("right").unwrap(), "cmd+v");
+    assert_eq!(json.get("left").unwrap(), "primary+c");
+    assert_eq!(json.get("middle").unwrap(), "primary+a");
+    assert_eq!(json.get("right").unwrap(), "primary+v");
 }
 
 #[test]
@@ -763,6 +942,14 @@ fn cli_preset_help() {
         .stdout(predicate::str::contains("preset configuration"));
 }
 
+#[test]
+fn cli_preset_accepts_force() {
+    savant()
+        .args(["preset", "browser", "--force", "--dry-run"])
+        .assert()
+        .success();
+}
+
 #[test]
 fn cli_preset_dry_run_works() {
     // --dry-run should show configuration without error (device mode check happens later)
@@ -774,6 +961,75 @@ fn cli_preset_dry_run_works() {
         .stdout(predicate::str::contains("YOUR PEDAL CONFIGURATION"));
 }
 
+#[test]
+fn cli_preset_check_accepts_valid_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("transcription.json");
+    std::fs::write(
+        &path,
+        r#"{"description": "Rewind/play/forward", "tags": ["audio"], "left": "f7", "middle": "f8", "right": "f9"}"#,
+    )
+    .unwrap();
+
+    savant()
+        .args(["preset", "check", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("transcription"));
+}
+
+#[test]
+fn cli_preset_check_reports_invalid_actions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.json");
+    std::fs::write(
+        &path,
+        r#"{"left": "cmd+notakey", "middle": "f8", "right": "f9"}"#,
+    )
+    .unwrap();
+
+    let output = savant()
+        .args(["--json", "preset", "check", path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["valid"], false);
+    assert!(json["files"][0]["errors"][0]
+        .as_str()
+        .unwrap()
+        .contains("left"));
+}
+
+#[test]
+fn cli_preset_list_includes_preset_files() {
+    let home = tempfile::tempdir().unwrap();
+    let system = tempfile::tempdir().unwrap();
+    std::fs::write(
+        system.path().join("daw.json"),
+        r#"{"description": "Record/play/stop", "left": "r", "middle": "space", "right": "enter"}"#,
+    )
+    .unwrap();
+
+    let output = savant_in(home.path())
+        .env("SAVANT_PRESET_PATH", system.path())
+        .args(["--json", "preset", "--list"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let presets = json["presets"].as_array().unwrap();
+    let daw = presets.iter().find(|p| p["name"] == "daw").unwrap();
+    assert_eq!(daw["source"], "system");
+    assert!(presets
+        .iter()
+        .any(|p| p["name"] == "copy-paste" && p["source"] == "built-in"));
+}
+
 // ============================================================================
 // Config Profile Command Tests
 // ============================================================================
@@ -900,7 +1156,8 @@ fn cli_config_load_help() {
         .assert()
         .success()
         .stdout(predicate::str::contains("--dry-run"))
-        .stdout(predicate::str::contains("Preview"));
+        .stdout(predicate::str::contains("Preview"))
+        .stdout(predicate::str::contains("--force"));
 }
 
 #[test]
@@ -913,6 +1170,190 @@ fn cli_config_delete_help() {
         .stdout(predicate::str::contains("confirmation"));
 }
 
+#[test]
+fn cli_config_list_shows_system_profiles() {
+    let home = tempfile::tempdir().unwrap();
+    let system = tempfile::tempdir().unwrap();
+    std::fs::write(
+        system.path().join("team.conf"),
+        "left=f1\nmiddle=f2\nright=f3\n",
+    )
+    .unwrap();
+
+    let output = savant_in(home.path())
+        .env("SAVANT_PROFILE_PATH", system.path())
+        .args(["--json", "config", "list"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["profiles"][0]["name"], "team");
+    assert_eq!(json["profiles"][0]["source"], "system");
+
+    savant_in(home.path())
+        .env("SAVANT_PROFILE_PATH", system.path())
+        .args(["config", "show", "team"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Source: system"));
+}
+
+#[test]
+fn cli_config_delete_refuses_system_profile() {
+    let home = tempfile::tempdir().unwrap();
+    let system = tempfile::tempdir().unwrap();
+    let path = system.path().join("team.conf");
+    std::fs::write(&path, "left=f1\nmiddle=f2\nright=f3\n").unwrap();
+
+    savant_in(home.path())
+        .env("SAVANT_PROFILE_PATH", system.path())
+        .args(["config", "delete", "team", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("system profile"));
+    assert!(path.exists());
+}
+
+#[test]
+fn cli_config_export_import_round_trip() {
+    let source = tempfile::tempdir().unwrap();
+    let target = tempfile::tempdir().unwrap();
+    let bundle = source.path().join("bundle.json");
+    let profile = source.path().join("profile.conf");
+    std::fs::write(&profile, "left=f1\nmiddle=f2\nright=f3\n").unwrap();
+
+    // Put a profile in place through the system store so no device is needed
+    savant_in(source.path())
+        .env("SAVANT_PROFILE_PATH", source.path())
+        .args([
+            "config",
+            "export",
+            "profile",
+            "-o",
+            bundle.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 1 profile(s)"));
+
+    savant_in(target.path())
+        .args(["config", "import", bundle.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 of 1"));
+
+    // A second import clashes unless a conflict strategy is given
+    savant_in(target.path())
+        .args(["config", "import", bundle.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+    savant_in(target.path())
+        .args([
+            "--json",
+            "config",
+            "import",
+            bundle.to_str().unwrap(),
+            "--rename",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("profile-2"));
+}
+
+#[test]
+fn cli_config_import_conflict_flags_are_exclusive() {
+    savant()
+        .args(["config", "import", "bundle.json", "--rename", "--skip"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn cli_config_new_set_copy_rename() {
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| savant_in(home.path()).args(args).assert();
+
+    run(&[
+        "config", "new", "work", "--left", "cmd+c", "--middle", "cmd+a", "--right", "cmd+v",
+    ])
+    .success()
+    .stdout(predicate::str::contains("Created profile 'work'"));
+    run(&[
+        "config", "new", "work", "--left", "f1", "--middle", "f2", "--right", "f3",
+    ])
+    .failure()
+    .stderr(predicate::str::contains("already exists"));
+    run(&["config", "set", "work", "left", "cmd+z"]).success();
+    run(&["config", "copy", "work", "home"]).success();
+    run(&["config", "rename", "home", "house"]).success();
+
+    let output = run(&["--json", "config", "show", "house"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["left"], "cmd+z");
+    run(&["config", "show", "home"]).failure();
+}
+
+#[test]
+fn cli_config_new_and_set_validate_actions() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args([
+            "config",
+            "new",
+            "bad",
+            "-l",
+            "cmd+bogus",
+            "-m",
+            "f2",
+            "-r",
+            "f3",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown key"));
+    savant_in(home.path())
+        .args([
+            "config", "new", "../bad", "-l", "f1", "-m", "f2", "-r", "f3",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Profile name can only contain"));
+    savant_in(home.path())
+        .args(["config", "set", "missing", "left", "f1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn cli_config_edit_reports_editor_outcome() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args(["config", "new", "work", "-l", "f1", "-m", "f2", "-r", "f3"])
+        .assert()
+        .success();
+    savant_in(home.path())
+        .env("VISUAL", "true")
+        .args(["config", "edit", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes"));
+    savant_in(home.path())
+        .env("VISUAL", "false")
+        .args(["config", "edit", "work"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("profile left unchanged"));
+}
+
 // ============================================================================
 // Doctor Command Tests
 // ============================================================================
@@ -1103,6 +1544,59 @@ fn cli_timeout_verbose_shows_value() {
         .stderr(predicate::str::contains("USB timeout: 5000ms"));
 }
 
+// ============================================================================
+// Retry Tests
+// ============================================================================
+
+#[test]
+fn cli_retries_verbose_shows_policy() {
+    savant()
+        .args([
+            "--verbose",
+            "--retries",
+            "4",
+            "--retry-backoff",
+            "10",
+            "--retry-on",
+            "timeout",
+            "--retry-on",
+            "busy",
+            "status",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "USB retries: 4 (backoff 10ms, on timeout,busy)",
+        ));
+}
+
+#[test]
+fn cli_retries_zero_disables_retrying() {
+    savant()
+        .args(["--verbose", "--retries", "0", "status"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("USB retries: off"));
+}
+
+#[test]
+fn cli_retries_rejects_too_many() {
+    savant()
+        .args(["--retries", "11", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("11 is not in 0..=10"));
+}
+
+#[test]
+fn cli_retry_on_rejects_unknown_kind() {
+    savant()
+        .args(["--retry-on", "no-device", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'no-device'"));
+}
+
 // ============================================================================
 // Config Check Tests
 // ============================================================================
@@ -1203,9 +1697,107 @@ fn cli_config_check_invalid_file() {
 }
 
 #[test]
-fn cli_config_check_verbose() {
-    savant()
-        .args(["--verbose", "config", "check"])
+fn cli_config_check_json_points_at_typo() {
+    use std::io::Write;
+    let mut temp = tempfile::NamedTempFile::new().unwrap();
+    writeln!(temp, "left=cmd+escp").unwrap();
+    writeln!(temp, "middle=cmd+a").unwrap();
+    writeln!(temp, "right=cmd+v").unwrap();
+
+    let output = savant()
+        .args(["--json", "config", "check", temp.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let error = &json["errors"][0];
+    assert_eq!(error["field"], "left");
+    assert_eq!(error["column"], 5);
+    assert_eq!(error["suggestion"], "esc");
+}
+
+#[test]
+fn cli_program_shows_suggestion_and_caret() {
+    savant()
+        .args(["program", "--left", "comand+c", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("did you mean \"command\""))
+        .stderr(predicate::str::contains("  ^^^^^^"));
+}
+
+#[test]
+fn cli_config_check_versioned_file() {
+    use std::io::Write;
+    let mut temp = tempfile::NamedTempFile::new().unwrap();
+    write!(
+        temp,
+        r#"{{"version": 1, "pedals": {{
+            "left": {{"action": "cmd+c", "label": "Copy"}},
+            "middle": {{"action": "cmd+a"}},
+            "right": {{"action": "cmd+v", "notes": "paste into the EHR"}}}},
+          "device": {{"unit_id": 3}}}}"#
+    )
+    .unwrap();
+
+    let output = savant()
+        .args(["--json", "config", "check", temp.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["valid"], true);
+    assert_eq!(json["format"], "v1");
+    assert_eq!(json["left"]["action"], "cmd+c");
+}
+
+#[test]
+fn cli_config_check_versioned_rejects_unknown_field() {
+    use std::io::Write;
+    let mut temp = tempfile::NamedTempFile::new().unwrap();
+    write!(
+        temp,
+        r#"{{"version": 1, "colour": "red", "pedals": {{
+            "left": {{"action": "cmd+c"}},
+            "middle": {{"action": "cmd+a"}},
+            "right": {{"action": "cmd+v"}}}}}}"#
+    )
+    .unwrap();
+
+    savant()
+        .args(["config", "check", temp.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("unknown field"));
+}
+
+#[test]
+fn cli_config_check_reports_legacy_format() {
+    use std::io::Write;
+    let mut temp = tempfile::NamedTempFile::new().unwrap();
+    writeln!(temp, "left=cmd+c\nmiddle=cmd+a\nright=cmd+v").unwrap();
+
+    let output = savant()
+        .args(["--json", "config", "check", temp.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["format"], "legacy");
+}
+
+#[test]
+fn cli_config_check_verbose() {
+    savant()
+        .args(["--verbose", "config", "check"])
         .assert()
         .stderr(predicate::str::contains("Checking config file"));
 }
@@ -1297,3 +1889,697 @@ fn cli_config_restore_rejects_out_of_range() {
             )),
         );
 }
+
+#[test]
+fn cli_config_diff_shows_help() {
+    savant()
+        .args(["config", "diff", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("current"))
+        .stdout(predicate::str::contains("history:3"));
+}
+
+#[test]
+fn cli_config_diff_requires_two_operands() {
+    savant()
+        .args(["config", "diff", "current"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("<B>"));
+}
+
+#[test]
+fn cli_config_diff_rejects_invalid_profile_name() {
+    savant()
+        .args(["config", "diff", "profile:../etc", "current"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Profile name can only contain"));
+}
+
+#[test]
+fn cli_config_diff_rejects_bad_history_reference() {
+    savant()
+        .args(["config", "diff", "history:latest", "current"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid history number"));
+}
+
+// ============================================================================
+// Unit ID Tests
+// ============================================================================
+
+#[test]
+fn cli_unit_id_help() {
+    savant()
+        .args(["unit-id", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("get"))
+        .stdout(predicate::str::contains("set"));
+}
+
+#[test]
+fn cli_unit_id_set_rejects_out_of_range() {
+    savant()
+        .args(["unit-id", "set", "256"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("256"));
+}
+
+#[test]
+fn cli_unit_id_set_requires_id() {
+    savant()
+        .args(["unit-id", "set"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ID"));
+}
+
+#[test]
+fn cli_unit_id_get_json_is_valid() {
+    let output = savant()
+        .args(["--json", "unit-id", "get"])
+        .assert()
+        .get_output()
+        .stdout
+        .clone();
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output).expect("unit-id get --json should produce valid JSON");
+    assert!(json.get("devices").is_some_and(|d| d.is_array()));
+}
+
+#[test]
+fn cli_unit_selector_accepted_globally() {
+    savant()
+        .args(["--unit", "3", "program", "--left", "cmd+c", "--dry-run"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn cli_unit_selector_rejects_non_numeric() {
+    savant()
+        .args(["--unit", "left", "status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+// ============================================================================
+// Mode Switch Tests
+// ============================================================================
+
+#[test]
+fn cli_mode_help() {
+    savant()
+        .args(["mode", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--experimental"))
+        .stdout(predicate::str::contains("--yes"))
+        .stdout(predicate::str::contains("program"))
+        .stdout(predicate::str::contains("play"));
+}
+
+#[test]
+fn cli_mode_rejects_unknown_mode() {
+    savant()
+        .args(["mode", "turbo", "--experimental"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+#[test]
+fn cli_mode_requires_experimental_flag() {
+    savant()
+        .args(["mode", "program"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("paperclip"))
+        .stderr(predicate::str::contains("--experimental"));
+}
+
+#[test]
+fn cli_mode_requires_experimental_flag_json() {
+    let output = savant()
+        .args(["--json", "mode", "play"])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output).expect("mode --json should produce valid JSON");
+    assert_eq!(json["error"], "experimental_required");
+    assert_eq!(json["target_mode"], "play");
+}
+
+// ============================================================================
+// Factory Reset Tests
+// ============================================================================
+
+#[test]
+fn cli_reset_requires_factory_flag() {
+    savant()
+        .args(["reset"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--factory"));
+}
+
+#[test]
+fn cli_reset_factory_dry_run_works() {
+    savant()
+        .args(["reset", "--factory", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("⌃⌥4"))
+        .stdout(predicate::str::contains("⌃⌥6"));
+}
+
+#[test]
+fn cli_preset_factory_is_listed() {
+    let output = savant()
+        .args(["--json", "preset", "factory", "--show"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&output).expect("preset --show --json should produce valid JSON");
+    assert_eq!(json["left"], "ctrl+alt+4");
+    assert_eq!(json["middle"], "ctrl+alt+5");
+    assert_eq!(json["right"], "ctrl+alt+6");
+}
+
+// ============================================================================
+// Undo / Redo Tests
+// ============================================================================
+
+#[test]
+fn cli_undo_and_redo_show_help() {
+    savant()
+        .args(["undo", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("before the last change"));
+    savant()
+        .args(["redo", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("savant undo"));
+}
+
+#[test]
+fn cli_undo_fails_with_empty_history() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args(["undo", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to undo"));
+}
+
+#[test]
+fn cli_undo_dry_run_uses_previous_config() {
+    let home = tempfile::tempdir().unwrap();
+    let output = savant_in(home.path())
+        .args(["--json", "config", "history"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let history_dir = std::path::PathBuf::from(json["history_dir"].as_str().unwrap());
+    std::fs::create_dir_all(&history_dir).unwrap();
+    std::fs::write(
+        history_dir.join("2025-01-01_120000.conf"),
+        "left=cmd+z\nmiddle=cmd+a\nright=cmd+v\n",
+    )
+    .unwrap();
+
+    savant_in(home.path())
+        .args(["undo", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reverting"))
+        .stdout(predicate::str::contains("⌘Z"));
+
+    // Nothing has been undone yet, so there is nothing to redo
+    savant_in(home.path())
+        .args(["redo", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to redo"));
+}
+
+// ============================================================================
+// Shortcut Lint Tests
+// ============================================================================
+
+#[test]
+fn cli_program_refuses_destructive_shortcut() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args([
+            "--target-os",
+            "windows",
+            "program",
+            "--left",
+            "alt+f4",
+            "--dry-run",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("--allow destructive"))
+        .stderr(predicate::str::contains("Refusing to program"));
+}
+
+#[test]
+fn cli_program_allow_overrides_lint() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args([
+            "--target-os",
+            "macos",
+            "program",
+            "--left",
+            "cmd+q",
+            "--dry-run",
+        ])
+        .args(["--allow", "destructive"])
+        .assert()
+        .success();
+    savant_in(home.path())
+        .args([
+            "--target-os",
+            "linux",
+            "program",
+            "--left",
+            "ctrl+q",
+            "--dry-run",
+        ])
+        .args(["--allow", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Shortcut check").not());
+}
+
+#[test]
+fn cli_program_warns_about_bare_keys_without_blocking() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args(["program", "--left", "a", "--middle", "a", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bare-key"))
+        .stdout(predicate::str::contains("same action as the left pedal"));
+}
+
+#[test]
+fn cli_config_check_reports_lint_in_json() {
+    let home = tempfile::tempdir().unwrap();
+    let file = home.path().join("pedals.conf");
+    std::fs::write(&file, "left=a\nmiddle=cmd+a\nright=cmd+v\n").unwrap();
+
+    let output = savant_in(home.path())
+        .args(["--json", "config", "check"])
+        .arg(&file)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["valid"], true);
+    assert_eq!(json["lint"][0]["code"], "bare-key");
+    assert_eq!(json["lint"][0]["pedal"], "left");
+
+    let output = savant_in(home.path())
+        .args(["--json", "--allow", "bare-key", "config", "check"])
+        .arg(&file)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert!(json.get("lint").is_none());
+}
+
+#[test]
+fn cli_rejects_unknown_allow_value() {
+    savant()
+        .args(["--allow", "everything", "keys"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+// ============================================================================
+// Target OS Tests
+// ============================================================================
+
+#[test]
+fn cli_primary_modifier_resolves_per_target_os() {
+    let home = tempfile::tempdir().unwrap();
+    for (os, action, modifier) in [
+        ("macos", "primary+c", "0x08"),
+        ("linux", "primary+c", "0x01"),
+        ("windows", "primary+c", "0x01"),
+        ("windows", "secondary+c", "0x08"),
+    ] {
+        savant_in(home.path())
+            .args(["-v", "--target-os", os, "program", "--left", action])
+            .arg("--dry-run")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(format!("modifiers={}", modifier)));
+    }
+}
+
+#[test]
+fn cli_config_check_uses_target_os_from_file() {
+    let home = tempfile::tempdir().unwrap();
+    let file = home.path().join("pedals.conf");
+    std::fs::write(
+        &file,
+        r#"{
+  "version": 1,
+  "pedals": {
+    "left": {"action": "primary+c"},
+    "middle": {"action": "primary+a"},
+    "right": {"action": "primary+v"}
+  },
+  "target_os": "macos"
+}"#,
+    )
+    .unwrap();
+
+    let output = savant_in(home.path())
+        .args(["--json", "config", "check"])
+        .arg(&file)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["target_os"], "macos");
+    assert_eq!(json["left"]["modifier_hex"], "0x08");
+
+    // The flag wins over the file
+    let output = savant_in(home.path())
+        .args(["--json", "--target-os", "linux", "config", "check"])
+        .arg(&file)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["left"]["modifier_hex"], "0x01");
+}
+
+#[test]
+fn cli_config_new_records_target_os() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args(["--target-os", "windows", "config", "new", "win"])
+        .args([
+            "--left",
+            "primary+c",
+            "--middle",
+            "primary+a",
+            "--right",
+            "primary+v",
+        ])
+        .assert()
+        .success();
+    let output = savant_in(home.path())
+        .args(["--json", "config", "show", "win"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["target_os"], "windows");
+}
+
+#[test]
+fn cli_rejects_unknown_target_os() {
+    savant()
+        .args(["--target-os", "beos", "keys"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+// ============================================================================
+// udev Rule Tests
+// ============================================================================
+
+#[test]
+fn cli_udev_rules_cover_both_product_ids() {
+    savant()
+        .args(["udev-rules"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ATTRS{idVendor}==\"05f3\""))
+        .stdout(predicate::str::contains("ATTRS{idProduct}==\"030c\""))
+        .stdout(predicate::str::contains("ATTRS{idProduct}==\"0232\""))
+        .stdout(predicate::str::contains("GROUP=\"plugdev\""));
+}
+
+#[test]
+fn cli_udev_rules_writes_file_with_custom_group() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("70-savant-elite.rules");
+    savant()
+        .args(["udev-rules", "--group", "input", "-o"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote udev rule"));
+    let rules = std::fs::read_to_string(&path).unwrap();
+    assert!(rules.contains("GROUP=\"input\""));
+}
+
+#[test]
+fn cli_doctor_runs_linux_checks_on_linux() {
+    let output = savant()
+        .args(["--json", "doctor"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let names: Vec<&str> = json["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["name"].as_str().unwrap())
+        .collect();
+    if cfg!(target_os = "linux") {
+        for name in ["udev_rule", "device_access", "groups", "kernel_driver"] {
+            assert!(names.contains(&name), "missing {} in {:?}", name, names);
+        }
+        assert!(!names.contains(&"permissions"));
+    } else {
+        assert!(!names.contains(&"udev_rule"));
+    }
+}
+
+// ============================================================================
+// Doctor Fix Tests
+// ============================================================================
+
+#[test]
+fn cli_doctor_dry_run_requires_fix() {
+    savant()
+        .args(["doctor", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--fix"));
+}
+
+#[test]
+fn cli_doctor_fix_dry_run_changes_nothing() {
+    let home = tempfile::tempdir().unwrap();
+    let config_dir = savant_config_dir_in(home.path());
+    std::fs::create_dir_all(config_dir.join("history")).unwrap();
+    std::fs::write(config_dir.join("pedals.conf"), "{ not json").unwrap();
+    std::fs::write(config_dir.join("history/broken.json"), "{").unwrap();
+
+    let output = savant_in(home.path())
+        .args(["--json", "doctor", "--fix", "--dry-run"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let fixes = json["fixes"].as_array().unwrap();
+    assert!(fixes.iter().all(|f| f["status"] == "planned"));
+    assert!(fixes
+        .iter()
+        .any(|f| f["action"] == "move" && f["path"].as_str().unwrap().ends_with("pedals.conf")));
+    assert!(fixes.iter().any(|f| f["action"] == "remove"));
+
+    assert!(config_dir.join("pedals.conf").exists());
+    assert!(config_dir.join("history/broken.json").exists());
+    assert!(!config_dir.join("profiles").exists());
+}
+
+#[test]
+fn cli_doctor_fix_repairs_config_and_history() {
+    let home = tempfile::tempdir().unwrap();
+    let config_dir = savant_config_dir_in(home.path());
+    std::fs::create_dir_all(config_dir.join("history")).unwrap();
+    std::fs::write(
+        config_dir.join("pedals.conf"),
+        r#"{"version": 1, "pedals": {"left": {"action": "f1"}, "middle": {"action": "f2"}, "right": {"action": "f3"}}, "extra": true}"#,
+    )
+    .unwrap();
+    std::fs::write(config_dir.join("history/broken.json"), "{").unwrap();
+
+    savant_in(home.path())
+        .args(["doctor", "--fix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("FIXES"))
+        .stdout(predicate::str::contains("Create directory"));
+
+    let repaired = std::fs::read_to_string(config_dir.join("pedals.conf")).unwrap();
+    assert!(!repaired.contains("extra"));
+    assert!(repaired.contains("\"f3\""));
+    let backups = std::fs::read_dir(&config_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with("pedals.conf.broken-")
+        })
+        .count();
+    assert_eq!(backups, 1);
+    assert!(!config_dir.join("history/broken.json").exists());
+    assert!(config_dir.join("profiles").is_dir());
+}
+
+#[test]
+fn cli_doctor_bundle_collects_redacted_files() {
+    let home = tempfile::tempdir().unwrap();
+    let config_dir = savant_config_dir_in(home.path());
+    std::fs::create_dir_all(config_dir.join("profiles")).unwrap();
+    std::fs::write(
+        config_dir.join("profiles/work.conf"),
+        r#"{"version": 1, "pedals": {"left": {"action": "f1"}, "middle": {"action": "f2"}, "right": {"action": "f3"}}}"#,
+    )
+    .unwrap();
+    let out = home.path().join("bundle");
+
+    savant_in(home.path())
+        .args(["doctor", "--bundle"])
+        .arg(&out)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote support bundle"));
+
+    for name in [
+        "manifest.json",
+        "doctor.json",
+        "usb.json",
+        "hid.json",
+        "probe.json",
+        "config/profiles/work.conf",
+    ] {
+        assert!(out.join(name).is_file(), "missing {}", name);
+    }
+    let home_path = home.path().to_string_lossy().to_string();
+    let doctor = std::fs::read_to_string(out.join("doctor.json")).unwrap();
+    assert!(!doctor.contains(&home_path));
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(out.join("manifest.json")).unwrap()).unwrap();
+    assert_eq!(manifest["version"], env!("CARGO_PKG_VERSION"));
+    assert!(manifest["config_dir"].as_str().unwrap().starts_with('~'));
+}
+
+#[test]
+fn cli_doctor_bundle_writes_tar_archive() {
+    let home = tempfile::tempdir().unwrap();
+    let out = home.path().join("support.tar");
+    savant_in(home.path())
+        .args(["--json", "doctor", "--bundle"])
+        .arg(&out)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"bundle\""));
+    let archive = std::fs::read(&out).unwrap();
+    assert_eq!(archive.len() % 512, 0);
+    assert_eq!(&archive[257..262], b"ustar");
+    assert!(String::from_utf8_lossy(&archive[..100]).contains("/manifest.json"));
+}
+
+#[test]
+fn cli_doctor_bundle_refuses_non_empty_directory() {
+    let home = tempfile::tempdir().unwrap();
+    std::fs::write(home.path().join("keep.txt"), "x").unwrap();
+    savant_in(home.path())
+        .args(["doctor", "--bundle"])
+        .arg(home.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not empty"));
+}
+
+// ============================================================================
+// Operation Log Tests
+// ============================================================================
+
+#[test]
+fn cli_log_records_commands_and_results() {
+    let home = tempfile::tempdir().unwrap();
+    savant_in(home.path())
+        .args(["config", "load", "missing"])
+        .assert()
+        .failure();
+    savant_in(home.path()).args(["keys"]).assert().success();
+
+    let output = savant_in(home.path())
+        .args(["log", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let records = json["records"].as_array().unwrap();
+    let events: Vec<(&str, &str)> = records
+        .iter()
+        .map(|r| (r["event"].as_str().unwrap(), r["message"].as_str().unwrap()))
+        .collect();
+    assert_eq!(events.len(), 4, "{:?}", events);
+    assert_eq!(events[0], ("command", "savant config load missing"));
+    assert!(events[1].0 == "result" && events[1].1.starts_with("error: "));
+    assert_eq!(events[3], ("result", "ok"));
+
+    savant_in(home.path())
+        .args(["log", "--tail", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("result"))
+        .stdout(predicate::str::contains("savant keys").not());
+}